@@ -7,7 +7,7 @@ use rusqlite::Connection;
 pub type Pool = std::sync::Arc<r2d2::Pool<r2d2_sqlite::SqliteConnectionManager>>;
 
 #[allow(dead_code)]
-const SCHEMA_VERSION: i64 = 30;
+const SCHEMA_VERSION: i64 = 54;
 
 fn table_has_column(conn: &Connection, table_name: &str, column_name: &str) -> Result<bool, String> {
     let pragma_sql = format!("PRAGMA table_info({table_name})");
@@ -36,6 +36,158 @@ fn add_column_if_missing(
     Ok(())
 }
 
+/// Rebuilds `table` with the given REAL columns declared INTEGER and their values multiplied by
+/// `scale` (major -> minor units). Keeps column order (backups copy with `SELECT *`), constraints
+/// and indexes. The caller disables foreign keys around it and runs it inside a transaction.
+fn convert_money_columns(conn: &Connection, table: &str, columns: &[&str], scale: i64) -> Result<(), String> {
+    let create_sql: String = conn
+        .query_row(
+            "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = ?1",
+            [table],
+            |r| r.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    let mut new_sql = create_sql.clone();
+    for column in columns {
+        new_sql = retype_column(&new_sql, column);
+    }
+    let header_end = new_sql.find('(').ok_or_else(|| format!("Unexpected schema for {table}"))?;
+    let new_sql = format!("CREATE TABLE {table}_new {}", &new_sql[header_end..]);
+
+    let mut stmt = conn
+        .prepare("SELECT sql FROM sqlite_master WHERE type = 'index' AND tbl_name = ?1 AND sql IS NOT NULL")
+        .map_err(|e| e.to_string())?;
+    let indexes: Vec<String> = stmt
+        .query_map([table], |r| r.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({table})")).map_err(|e| e.to_string())?;
+    let all_columns: Vec<String> = stmt
+        .query_map([], |r| r.get(1))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+    let select: Vec<String> = all_columns
+        .iter()
+        .map(|c| {
+            if columns.contains(&c.as_str()) {
+                format!("CAST(ROUND({c} * {scale}) AS INTEGER)")
+            } else {
+                c.clone()
+            }
+        })
+        .collect();
+
+    conn.execute(&new_sql, []).map_err(|e| e.to_string())?;
+    conn.execute(
+        &format!(
+            "INSERT INTO {table}_new ({}) SELECT {} FROM {table}",
+            all_columns.join(", "),
+            select.join(", ")
+        ),
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute(&format!("DROP TABLE {table}"), []).map_err(|e| e.to_string())?;
+    conn.execute(&format!("ALTER TABLE {table}_new RENAME TO {table}"), [])
+        .map_err(|e| e.to_string())?;
+    for index in indexes {
+        conn.execute(&index, []).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// `<column> REAL` -> `<column> INTEGER` in a CREATE TABLE statement, leaving longer names that
+/// end the same way (`paid_amount` vs `amount`) alone.
+fn retype_column(sql: &str, column: &str) -> String {
+    let needle = format!("{column} REAL");
+    let mut out = String::with_capacity(sql.len());
+    let mut rest = sql;
+    while let Some(pos) = rest.find(&needle) {
+        let prev = rest[..pos].chars().next_back().or_else(|| out.chars().next_back());
+        let next = rest[pos + needle.len()..].chars().next();
+        let bounded = !prev.is_some_and(|c| c.is_ascii_alphanumeric() || c == '_')
+            && !next.is_some_and(|c| c.is_ascii_alphanumeric() || c == '_');
+        out.push_str(&rest[..pos]);
+        if bounded {
+            out.push_str(&format!("{column} INTEGER"));
+        } else {
+            out.push_str(&needle);
+        }
+        rest = &rest[pos + needle.len()..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Snapshot amounts (see `custom_tariffs::TariffSchedule`) from major to minor units.
+fn convert_snapshot_amounts(json: &str, scale: i64) -> Option<String> {
+    let mut value: serde_json::Value = serde_json::from_str(json).ok()?;
+    let to_minor = |v: &mut serde_json::Value| {
+        if let Some(major) = v.as_f64() {
+            *v = serde_json::json!((major * scale as f64).round() as i64);
+        }
+    };
+    if let Some(base) = value.get_mut("base") {
+        for key in ["basePrice", "additionalHourPrice"] {
+            if let Some(v) = base.get_mut(key) {
+                to_minor(v);
+            }
+        }
+    }
+    for key in ["dailyMaxAmount", "lostTicketAmount"] {
+        if let Some(v) = value.get_mut(key) {
+            to_minor(v);
+        }
+    }
+    if let Some(rules) = value.get_mut("rules").and_then(|r| r.as_array_mut()) {
+        for rule in rules {
+            if let Some(v) = rule.get_mut("amount") {
+                to_minor(v);
+            }
+        }
+    }
+    serde_json::to_string(&value).ok()
+}
+
+/// Reads a key from the `drive_config` key/value table (shared app settings).
+pub fn get_config_value(conn: &Connection, key: &str) -> Result<Option<String>, String> {
+    let mut stmt = conn
+        .prepare("SELECT value FROM drive_config WHERE key = ?1")
+        .map_err(|e| e.to_string())?;
+    let mut rows = stmt.query([key]).map_err(|e| e.to_string())?;
+    let row = rows.next().map_err(|e| e.to_string())?;
+    Ok(row.and_then(|r| r.get::<_, String>(0).ok()))
+}
+
+pub fn set_config_value(conn: &Connection, key: &str, value: &str) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO drive_config (key, value) VALUES (?1, ?2) ON CONFLICT(key) DO UPDATE SET value = ?2",
+        rusqlite::params![key, value],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Runs `f` inside `BEGIN IMMEDIATE` / `COMMIT`. Any error from `f` (or from the commit)
+/// rolls everything back, so multi-statement writes are all-or-nothing.
+pub fn with_transaction<T, F>(conn: &Connection, f: F) -> Result<T, String>
+where
+    F: FnOnce(&Connection) -> Result<T, String>,
+{
+    conn.execute("BEGIN IMMEDIATE", []).map_err(|e| e.to_string())?;
+    let result = f(conn).and_then(|value| {
+        conn.execute("COMMIT", []).map_err(|e| e.to_string())?;
+        Ok(value)
+    });
+    if result.is_err() {
+        let _ = conn.execute("ROLLBACK", []);
+    }
+    result
+}
+
 pub fn run_migrations(conn: &Connection) -> Result<(), String> {
     conn.execute_batch(
         r#"
@@ -672,6 +824,599 @@ pub fn run_migrations(conn: &Connection) -> Result<(), String> {
             .map_err(|e| e.to_string())?;
     }
 
+    if current < 31 {
+        // Capacidad del parqueadero por tipo de vehículo; zone = '' es la capacidad general del tipo.
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS lot_capacity (
+                vehicle_type TEXT NOT NULL,
+                zone TEXT NOT NULL DEFAULT '',
+                spaces INTEGER NOT NULL CHECK (spaces >= 0),
+                updated_at TEXT NOT NULL,
+                PRIMARY KEY (vehicle_type, zone)
+            );
+            "#,
+        )
+        .map_err(|e| e.to_string())?;
+        conn.execute("INSERT INTO schema_version (version) VALUES (31)", [])
+            .map_err(|e| e.to_string())?;
+    }
+
+    if current < 32 {
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS spaces (
+                id TEXT PRIMARY KEY,
+                code TEXT NOT NULL UNIQUE,
+                zone TEXT NOT NULL DEFAULT '',
+                allowed_vehicle_types TEXT NOT NULL,
+                status TEXT NOT NULL DEFAULT 'available' CHECK (status IN ('available', 'occupied', 'out_of_service')),
+                vehicle_id TEXT,
+                created_at TEXT NOT NULL,
+                updated_at TEXT
+            );
+            CREATE INDEX IF NOT EXISTS idx_spaces_vehicle ON spaces(vehicle_id);
+            "#,
+        )
+        .map_err(|e| e.to_string())?;
+        add_column_if_missing(conn, "vehicles", "space_id", "space_id TEXT")?;
+        conn.execute("INSERT INTO schema_version (version) VALUES (32)", [])
+            .map_err(|e| e.to_string())?;
+    }
+
+    if current < 33 {
+        // Franjas horarias por tarifa (nocturna, fin de semana, festivos) y calendario de festivos.
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS tariff_rules (
+                id TEXT PRIMARY KEY,
+                tariff_id TEXT NOT NULL,
+                name TEXT,
+                weekdays TEXT NOT NULL DEFAULT '',
+                start_time TEXT NOT NULL,
+                end_time TEXT NOT NULL,
+                holidays TEXT NOT NULL DEFAULT 'any' CHECK (holidays IN ('any', 'only', 'exclude')),
+                pricing TEXT NOT NULL CHECK (pricing IN ('flat', 'per_block')),
+                amount REAL NOT NULL CHECK (amount >= 0),
+                block_minutes INTEGER CHECK (block_minutes IS NULL OR block_minutes > 0),
+                priority INTEGER NOT NULL DEFAULT 0,
+                created_at TEXT NOT NULL,
+                FOREIGN KEY (tariff_id) REFERENCES custom_tariffs(id)
+            );
+            CREATE INDEX IF NOT EXISTS idx_tariff_rules_tariff ON tariff_rules(tariff_id);
+
+            CREATE TABLE IF NOT EXISTS holidays (
+                date TEXT PRIMARY KEY,
+                name TEXT
+            );
+            "#,
+        )
+        .map_err(|e| e.to_string())?;
+        conn.execute("INSERT INTO schema_version (version) VALUES (33)", [])
+            .map_err(|e| e.to_string())?;
+    }
+
+    if current < 34 {
+        add_column_if_missing(
+            conn,
+            "custom_tariffs",
+            "grace_minutes",
+            "grace_minutes INTEGER CHECK (grace_minutes IS NULL OR grace_minutes >= 0)",
+        )?;
+        add_column_if_missing(
+            conn,
+            "custom_tariffs",
+            "daily_max_amount",
+            "daily_max_amount REAL CHECK (daily_max_amount IS NULL OR daily_max_amount >= 0)",
+        )?;
+        add_column_if_missing(
+            conn,
+            "custom_tariffs",
+            "lost_ticket_amount",
+            "lost_ticket_amount REAL CHECK (lost_ticket_amount IS NULL OR lost_ticket_amount >= 0)",
+        )?;
+        conn.execute("INSERT INTO schema_version (version) VALUES (34)", [])
+            .map_err(|e| e.to_string())?;
+    }
+
+    if current < 35 {
+        // Tarifa congelada al ingreso (JSON de TariffSchedule) para cobrar la salida con ella.
+        add_column_if_missing(conn, "vehicles", "tariff_snapshot", "tariff_snapshot TEXT")?;
+        conn.execute("INSERT INTO schema_version (version) VALUES (35)", [])
+            .map_err(|e| e.to_string())?;
+    }
+
+    if current < 36 {
+        // Vales de validación de comercios vecinos y registro de cada canje (facturación a comercios).
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS vouchers (
+                id TEXT PRIMARY KEY,
+                code TEXT NOT NULL UNIQUE,
+                merchant TEXT NOT NULL,
+                kind TEXT NOT NULL CHECK (kind IN ('percentage', 'fixed', 'free_minutes')),
+                value REAL NOT NULL CHECK (value >= 0),
+                expires_on TEXT,
+                max_uses INTEGER CHECK (max_uses IS NULL OR max_uses > 0),
+                uses_count INTEGER NOT NULL DEFAULT 0,
+                active INTEGER NOT NULL DEFAULT 1,
+                created_at TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS voucher_redemptions (
+                id TEXT PRIMARY KEY,
+                voucher_id TEXT NOT NULL,
+                vehicle_id TEXT NOT NULL,
+                merchant TEXT NOT NULL,
+                discount_amount REAL NOT NULL,
+                redeemed_at TEXT NOT NULL,
+                operator_user_id TEXT,
+                FOREIGN KEY (voucher_id) REFERENCES vouchers(id),
+                FOREIGN KEY (vehicle_id) REFERENCES vehicles(id)
+            );
+            CREATE INDEX IF NOT EXISTS idx_voucher_redemptions_redeemed_at ON voucher_redemptions(redeemed_at);
+            CREATE INDEX IF NOT EXISTS idx_voucher_redemptions_merchant ON voucher_redemptions(merchant);
+            "#,
+        )
+        .map_err(|e| e.to_string())?;
+        conn.execute("INSERT INTO schema_version (version) VALUES (36)", [])
+            .map_err(|e| e.to_string())?;
+    }
+
+    if current < 37 {
+        // Auditoría de precios digitados a mano: precio calculado, precio cobrado y motivo.
+        add_column_if_missing(conn, "transactions", "computed_amount", "computed_amount REAL")?;
+        add_column_if_missing(conn, "transactions", "override_amount", "override_amount REAL")?;
+        add_column_if_missing(conn, "transactions", "override_reason", "override_reason TEXT")?;
+        conn.execute("INSERT INTO schema_version (version) VALUES (37)", [])
+            .map_err(|e| e.to_string())?;
+    }
+
+    if current < 38 {
+        // Libro de deudas: reemplaza la copia de vehicles.debt en cada ingreso.
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS debts (
+                id TEXT PRIMARY KEY,
+                plate_upper TEXT NOT NULL,
+                vehicle_id TEXT NOT NULL,
+                amount REAL NOT NULL CHECK (amount > 0),
+                paid_amount REAL NOT NULL DEFAULT 0 CHECK (paid_amount >= 0),
+                status TEXT NOT NULL DEFAULT 'open' CHECK (status IN ('open', 'settled', 'written_off')),
+                created_at TEXT NOT NULL,
+                closed_at TEXT,
+                write_off_reason TEXT,
+                operator_user_id TEXT
+            );
+            CREATE INDEX IF NOT EXISTS idx_debts_plate_status ON debts(plate_upper, status);
+            CREATE INDEX IF NOT EXISTS idx_debts_created_at ON debts(created_at);
+            "#,
+        )
+        .map_err(|e| e.to_string())?;
+        // Con placa, la última salida ya acumula lo arrastrado de turnos anteriores: solo esa
+        // pasa al libro. Sin placa no había arrastre, cada turno con saldo es una deuda.
+        let mut stmt = conn
+            .prepare(
+                r#"
+                SELECT v.id, COALESCE(v.plate_upper, ''), v.debt, COALESCE(v.exit_time, v.entry_time), v.operator_user_id
+                FROM vehicles v
+                WHERE v.status = 'completed' AND COALESCE(v.debt, 0) > 0
+                  AND (COALESCE(v.plate_upper, '') = ''
+                       OR v.exit_time = (SELECT MAX(w.exit_time) FROM vehicles w
+                                         WHERE w.plate_upper = v.plate_upper AND w.status = 'completed'))
+                "#,
+            )
+            .map_err(|e| e.to_string())?;
+        let outstanding: Vec<(String, String, f64, String, Option<String>)> = stmt
+            .query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?)))
+            .map_err(|e| e.to_string())?
+            .collect::<Result<_, _>>()
+            .map_err(|e| e.to_string())?;
+        drop(stmt);
+        for (vehicle_id, plate_upper, amount, created_at, operator_user_id) in outstanding {
+            conn.execute(
+                "INSERT INTO debts (id, plate_upper, vehicle_id, amount, paid_amount, status, created_at, operator_user_id) VALUES (?1, ?2, ?3, ?4, 0, 'open', ?5, ?6)",
+                rusqlite::params![
+                    crate::id_gen::generate_id(crate::id_gen::PREFIX_DEBT),
+                    plate_upper,
+                    vehicle_id,
+                    amount,
+                    created_at,
+                    operator_user_id
+                ],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+        // Los turnos activos ya no guardan la deuda arrastrada.
+        conn.execute("UPDATE vehicles SET debt = 0 WHERE status = 'active'", [])
+            .map_err(|e| e.to_string())?;
+        conn.execute("INSERT INTO schema_version (version) VALUES (38)", [])
+            .map_err(|e| e.to_string())?;
+    }
+
+    if current < 39 {
+        // Tipo de transacción: 'parking' (salida) o 'debt_payment' (abono de deuda sin salida).
+        add_column_if_missing(
+            conn,
+            "transactions",
+            "kind",
+            "kind TEXT NOT NULL DEFAULT 'parking'",
+        )?;
+        add_column_if_missing(conn, "shift_closures", "debt_payments_total", "debt_payments_total REAL")?;
+        conn.execute("INSERT INTO schema_version (version) VALUES (39)", [])
+            .map_err(|e| e.to_string())?;
+    }
+
+    if current < 40 {
+        // Pagos de contratos como línea aparte del cierre de turno.
+        add_column_if_missing(
+            conn,
+            "shift_closures",
+            "contract_payments_total",
+            "contract_payments_total REAL",
+        )?;
+        conn.execute("INSERT INTO schema_version (version) VALUES (40)", [])
+            .map_err(|e| e.to_string())?;
+    }
+
+    if current < 41 {
+        // Arqueo real: fondo inicial y retiros del cajón, conteo por denominación y
+        // aprobación de supervisor cuando el descuadre supera la tolerancia.
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS cash_movements (
+                id TEXT PRIMARY KEY,
+                kind TEXT NOT NULL,
+                amount REAL NOT NULL CHECK (amount > 0),
+                reason TEXT,
+                created_at TEXT NOT NULL,
+                operator_user_id TEXT
+            );
+            CREATE INDEX IF NOT EXISTS idx_cash_movements_created_at ON cash_movements(created_at);
+            "#,
+        )
+        .map_err(|e| e.to_string())?;
+        add_column_if_missing(conn, "shift_closures", "opening_float", "opening_float REAL")?;
+        add_column_if_missing(conn, "shift_closures", "cash_drops", "cash_drops REAL")?;
+        add_column_if_missing(
+            conn,
+            "shift_closures",
+            "expected_drawer_cash",
+            "expected_drawer_cash REAL",
+        )?;
+        add_column_if_missing(conn, "shift_closures", "denominations", "denominations TEXT")?;
+        add_column_if_missing(
+            conn,
+            "shift_closures",
+            "status",
+            "status TEXT NOT NULL DEFAULT 'closed'",
+        )?;
+        add_column_if_missing(conn, "shift_closures", "approved_by", "approved_by TEXT")?;
+        add_column_if_missing(conn, "shift_closures", "approved_at", "approved_at TEXT")?;
+        add_column_if_missing(conn, "shift_closures", "approval_note", "approval_note TEXT")?;
+        conn.execute("INSERT INTO schema_version (version) VALUES (41)", [])
+            .map_err(|e| e.to_string())?;
+    }
+
+    if current < 42 {
+        // Turnos explícitos por operador y caja: cada cobro, deuda y movimiento de cajón queda
+        // ligado al turno abierto, y el cierre se calcula por turno y no por ventana de tiempo.
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS shifts (
+                id TEXT PRIMARY KEY,
+                operator_user_id TEXT,
+                booth TEXT NOT NULL,
+                opened_at TEXT NOT NULL,
+                closed_at TEXT,
+                status TEXT NOT NULL DEFAULT 'open' CHECK (status IN ('open', 'closed')),
+                closure_id TEXT
+            );
+            CREATE UNIQUE INDEX IF NOT EXISTS idx_shifts_open_operator ON shifts(operator_user_id) WHERE status = 'open';
+            CREATE UNIQUE INDEX IF NOT EXISTS idx_shifts_open_booth ON shifts(booth) WHERE status = 'open';
+            "#,
+        )
+        .map_err(|e| e.to_string())?;
+        add_column_if_missing(conn, "transactions", "shift_id", "shift_id TEXT")?;
+        add_column_if_missing(conn, "debts", "shift_id", "shift_id TEXT")?;
+        add_column_if_missing(conn, "cash_movements", "shift_id", "shift_id TEXT")?;
+        add_column_if_missing(conn, "contract_payments", "shift_id", "shift_id TEXT")?;
+        add_column_if_missing(conn, "vehicles", "exit_shift_id", "exit_shift_id TEXT")?;
+        add_column_if_missing(conn, "shift_closures", "shift_id", "shift_id TEXT")?;
+        conn.execute_batch(
+            r#"
+            CREATE INDEX IF NOT EXISTS idx_transactions_shift ON transactions(shift_id);
+            CREATE INDEX IF NOT EXISTS idx_debts_shift ON debts(shift_id);
+            CREATE INDEX IF NOT EXISTS idx_cash_movements_shift ON cash_movements(shift_id);
+            CREATE INDEX IF NOT EXISTS idx_contract_payments_shift ON contract_payments(shift_id);
+            CREATE INDEX IF NOT EXISTS idx_vehicles_exit_shift ON vehicles(exit_shift_id);
+            "#,
+        )
+        .map_err(|e| e.to_string())?;
+        conn.execute("INSERT INTO schema_version (version) VALUES (42)", [])
+            .map_err(|e| e.to_string())?;
+    }
+
+    if current < 43 {
+        // Gastos menores y depósitos al cajón como líneas del cierre de turno.
+        add_column_if_missing(conn, "shift_closures", "cash_expenses", "cash_expenses REAL")?;
+        add_column_if_missing(conn, "shift_closures", "cash_deposits", "cash_deposits REAL")?;
+        conn.execute("INSERT INTO schema_version (version) VALUES (43)", [])
+            .map_err(|e| e.to_string())?;
+    }
+
+    if current < 44 {
+        // Devoluciones: transacción negativa (kind 'refund') ligada al cobro original.
+        add_column_if_missing(conn, "transactions", "refund_of", "refund_of TEXT")?;
+        add_column_if_missing(conn, "transactions", "refund_reason", "refund_reason TEXT")?;
+        add_column_if_missing(conn, "shift_closures", "refunds_total", "refunds_total REAL")?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_transactions_refund_of ON transactions(refund_of)",
+            [],
+        )
+        .map_err(|e| e.to_string())?;
+        conn.execute("INSERT INTO schema_version (version) VALUES (44)", [])
+            .map_err(|e| e.to_string())?;
+    }
+
+    if current < 45 {
+        // Antes redondeaba los montos REAL a los decimales de la moneda; la conversión a
+        // unidades mínimas de la versión 54 ya redondea, así que solo queda el número de versión.
+        conn.execute("INSERT INTO schema_version (version) VALUES (45)", [])
+            .map_err(|e| e.to_string())?;
+    }
+
+    if current < 46 {
+        // Facturas de contratos: una por periodo pagado, con numeración consecutiva y copia
+        // de los datos del emisor y del cliente al momento de emitirla.
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS contract_invoices (
+                id TEXT PRIMARY KEY,
+                number INTEGER NOT NULL UNIQUE,
+                invoice_number TEXT NOT NULL UNIQUE,
+                contract_id TEXT NOT NULL,
+                payment_id TEXT NOT NULL UNIQUE,
+                issuer_name TEXT NOT NULL,
+                issuer_tax_id TEXT NOT NULL,
+                issuer_address TEXT NOT NULL,
+                issuer_phone TEXT NOT NULL,
+                client_name TEXT NOT NULL,
+                client_phone TEXT,
+                plate TEXT NOT NULL,
+                period_from TEXT NOT NULL,
+                period_to TEXT NOT NULL,
+                method TEXT NOT NULL,
+                currency TEXT NOT NULL,
+                subtotal REAL NOT NULL,
+                tax_rate REAL NOT NULL,
+                tax_amount REAL NOT NULL,
+                total REAL NOT NULL,
+                issued_at TEXT NOT NULL,
+                operator_user_id TEXT,
+                FOREIGN KEY (contract_id) REFERENCES contracts(id),
+                FOREIGN KEY (payment_id) REFERENCES contract_payments(id)
+            );
+            CREATE INDEX IF NOT EXISTS idx_contract_invoices_contract ON contract_invoices(contract_id);
+            CREATE INDEX IF NOT EXISTS idx_contract_invoices_issued ON contract_invoices(issued_at);
+            "#,
+        )
+        .map_err(|e| e.to_string())?;
+        conn.execute("INSERT INTO schema_version (version) VALUES (46)", [])
+            .map_err(|e| e.to_string())?;
+    }
+
+    if current < 47 {
+        // Renovación automática de contratos e historial de cambios de estado
+        // (pagos, mora, vencimiento, renovaciones); changed_by NULL = proceso automático.
+        add_column_if_missing(conn, "contracts", "auto_renew", "auto_renew INTEGER NOT NULL DEFAULT 0")?;
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS contract_history (
+                id TEXT PRIMARY KEY,
+                contract_id TEXT NOT NULL,
+                from_status TEXT,
+                to_status TEXT NOT NULL,
+                event TEXT NOT NULL,
+                reason TEXT,
+                changed_at TEXT NOT NULL,
+                changed_by TEXT,
+                FOREIGN KEY (contract_id) REFERENCES contracts(id)
+            );
+            CREATE INDEX IF NOT EXISTS idx_contract_history_contract ON contract_history(contract_id, changed_at);
+            "#,
+        )
+        .map_err(|e| e.to_string())?;
+        conn.execute("INSERT INTO schema_version (version) VALUES (47)", [])
+            .map_err(|e| e.to_string())?;
+    }
+
+    if current < 48 {
+        // Historial versionado de contratos: cada entrada lleva el número de versión y, en
+        // las ediciones, los valores anteriores y nuevos (JSON). Los contratos existentes
+        // reciben una entrada inicial "created" como versión 1.
+        add_column_if_missing(conn, "contract_history", "version", "version INTEGER NOT NULL DEFAULT 0")?;
+        add_column_if_missing(conn, "contract_history", "changes", "changes TEXT")?;
+        conn.execute_batch(
+            r#"
+            INSERT INTO contract_history (id, contract_id, from_status, to_status, event, reason, changed_at, changed_by)
+            SELECT 'CH' || substr(c.id, 3), c.id, NULL, 'active', 'created', 'Contract created', c.created_at, NULL
+            FROM contracts c
+            WHERE NOT EXISTS (
+                SELECT 1 FROM contract_history h WHERE h.contract_id = c.id AND h.event = 'created'
+            );
+            UPDATE contract_history SET version = (
+                SELECT COUNT(*) FROM contract_history h2
+                WHERE h2.contract_id = contract_history.contract_id
+                  AND (h2.changed_at < contract_history.changed_at
+                       OR (h2.changed_at = contract_history.changed_at AND h2.rowid <= contract_history.rowid))
+            );
+            CREATE UNIQUE INDEX IF NOT EXISTS idx_contract_history_version ON contract_history(contract_id, version);
+            "#,
+        )
+        .map_err(|e| e.to_string())?;
+        conn.execute("INSERT INTO schema_version (version) VALUES (48)", [])
+            .map_err(|e| e.to_string())?;
+    }
+
+    if current < 49 {
+        // Contratos de flota/familia: un contrato cubre varias placas con un cupo de vehículos
+        // simultáneos. La placa del contrato pasa a ser la principal del conjunto.
+        add_column_if_missing(
+            conn,
+            "contracts",
+            "max_simultaneous_vehicles",
+            "max_simultaneous_vehicles INTEGER NOT NULL DEFAULT 1",
+        )?;
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS contract_plates (
+                contract_id TEXT NOT NULL,
+                plate_upper TEXT NOT NULL,
+                added_at TEXT NOT NULL,
+                PRIMARY KEY (contract_id, plate_upper),
+                FOREIGN KEY (contract_id) REFERENCES contracts(id)
+            );
+            CREATE INDEX IF NOT EXISTS idx_contract_plates_plate ON contract_plates(plate_upper);
+            INSERT OR IGNORE INTO contract_plates (contract_id, plate_upper, added_at)
+            SELECT id, plate_upper, created_at FROM contracts;
+            "#,
+        )
+        .map_err(|e| e.to_string())?;
+        conn.execute("INSERT INTO schema_version (version) VALUES (49)", [])
+            .map_err(|e| e.to_string())?;
+    }
+
+    if current < 50 {
+        // Pagos parciales y prorrateo: saldo a favor del contrato, fin del próximo período
+        // cuando es más corto (primer período prorrateado) y cambio de plan programado para
+        // el siguiente corte.
+        add_column_if_missing(conn, "contracts", "credit_balance", "credit_balance REAL NOT NULL DEFAULT 0")?;
+        add_column_if_missing(conn, "contracts", "next_period_end", "next_period_end TEXT")?;
+        add_column_if_missing(conn, "contracts", "pending_monthly_amount", "pending_monthly_amount REAL")?;
+        add_column_if_missing(conn, "contracts", "pending_billing_period_days", "pending_billing_period_days INTEGER")?;
+        add_column_if_missing(conn, "contracts", "pending_effective_date", "pending_effective_date TEXT")?;
+        conn.execute("INSERT INTO schema_version (version) VALUES (50)", [])
+            .map_err(|e| e.to_string())?;
+    }
+
+    if current < 51 {
+        use crate::permissions;
+        // El rol operador solo se siembra en instalaciones nuevas: en bases actualizadas se le
+        // otorgan los permisos que se le agregaron después (abrir turno, movimientos de caja,
+        // capacidad, espacios y lectura de ajustes).
+        for perm in [
+            permissions::CAJA_SHIFT_OPEN,
+            permissions::CAJA_CASH_MOVEMENTS_CREATE,
+            permissions::CAPACITY_READ,
+            permissions::SPACES_READ,
+            permissions::SETTINGS_READ,
+        ] {
+            conn.execute(
+                "INSERT OR IGNORE INTO role_permissions (role_id, permission) SELECT id, ?1 FROM roles WHERE id = 'role_operator'",
+                [perm],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+        conn.execute("INSERT INTO schema_version (version) VALUES (51)", [])
+            .map_err(|e| e.to_string())?;
+    }
+
+    if current < 52 {
+        // Parte del cobro de salida que saldó deudas anteriores de la placa: no se devuelve
+        // con la salida.
+        add_column_if_missing(conn, "vehicles", "paid_to_previous", "paid_to_previous REAL NOT NULL DEFAULT 0")?;
+        conn.execute("INSERT INTO schema_version (version) VALUES (52)", [])
+            .map_err(|e| e.to_string())?;
+    }
+
+    if current < 53 {
+        // Pagos de contrato que no completan un período: abonos al saldo a favor, sin
+        // período cubierto ('credit'); el resto cubre uno o más períodos ('period').
+        add_column_if_missing(conn, "contract_payments", "kind", "kind TEXT NOT NULL DEFAULT 'period'")?;
+        add_column_if_missing(conn, "contract_invoices", "kind", "kind TEXT NOT NULL DEFAULT 'period'")?;
+        conn.execute("INSERT INTO schema_version (version) VALUES (53)", [])
+            .map_err(|e| e.to_string())?;
+    }
+
+    if current < 54 {
+        // Montos como INTEGER en unidades mínimas de la moneda configurada (ver money.rs).
+        // vouchers.value sigue REAL (porcentaje, monto o minutos según el tipo) y los valores
+        // de drive_config siguen en unidades mayores.
+        let scale = crate::money::currency(conn)?.minor_per_major();
+        let money_columns: &[(&str, &[&str])] = &[
+            ("vehicles", &["total_amount", "debt", "special_rate", "paid_to_previous"]),
+            ("transactions", &["amount", "computed_amount", "override_amount"]),
+            ("debts", &["amount", "paid_amount"]),
+            ("custom_tariffs", &["amount", "additional_hour_price", "daily_max_amount", "lost_ticket_amount"]),
+            ("tariff_rules", &["amount"]),
+            ("contracts", &[
+                "monthly_amount",
+                "extra_charge_first",
+                "extra_charge_repeat",
+                "extra_charge_per_interval",
+                "credit_balance",
+                "pending_monthly_amount",
+            ]),
+            ("contract_payments", &["amount"]),
+            ("contract_invoices", &["subtotal", "tax_amount", "total"]),
+            ("cash_movements", &["amount"]),
+            ("voucher_redemptions", &["discount_amount"]),
+            ("shift_closures", &[
+                "expected_total",
+                "cash_total",
+                "card_total",
+                "transfer_total",
+                "arqueo_cash",
+                "discrepancy",
+                "debt_total",
+                "debt_payments_total",
+                "contract_payments_total",
+                "opening_float",
+                "cash_drops",
+                "expected_drawer_cash",
+                "cash_expenses",
+                "cash_deposits",
+                "refunds_total",
+            ]),
+        ];
+        // Todo en una transacción: si algo falla, ninguna tabla queda convertida y volver a
+        // correr la migración no multiplica dos veces las que ya lo estaban. foreign_keys no se
+        // puede cambiar dentro de una transacción, así que se desactiva alrededor.
+        conn.execute("PRAGMA foreign_keys = OFF", [])
+            .map_err(|e| e.to_string())?;
+        let converted = with_transaction(conn, |conn| {
+            for (table, columns) in money_columns {
+                convert_money_columns(conn, table, columns, scale)?;
+            }
+
+            let snapshots: Vec<(String, String)> = {
+                let mut stmt = conn
+                    .prepare("SELECT id, tariff_snapshot FROM vehicles WHERE tariff_snapshot IS NOT NULL")
+                    .map_err(|e| e.to_string())?;
+                let rows = stmt
+                    .query_map([], |r| Ok((r.get(0)?, r.get(1)?)))
+                    .map_err(|e| e.to_string())?;
+                rows.collect::<Result<_, _>>().map_err(|e| e.to_string())?
+            };
+            for (id, json) in snapshots {
+                if let Some(converted) = convert_snapshot_amounts(&json, scale) {
+                    conn.execute(
+                        "UPDATE vehicles SET tariff_snapshot = ?1 WHERE id = ?2",
+                        rusqlite::params![converted, id],
+                    )
+                    .map_err(|e| e.to_string())?;
+                }
+            }
+            conn.execute("INSERT INTO schema_version (version) VALUES (54)", [])
+                .map_err(|e| e.to_string())?;
+            Ok(())
+        });
+        conn.execute("PRAGMA foreign_keys = ON", [])
+            .map_err(|e| e.to_string())?;
+        converted?;
+    }
+
     sync_role_permissions_from_code(conn)?;
     seed_developer_role_and_user(conn)?;
     Ok(())
@@ -892,6 +1637,96 @@ pub fn open_pool(db_path: &std::path::Path) -> Result<r2d2::Pool<r2d2_sqlite::Sq
 mod tests {
     use super::*;
 
+    #[test]
+    fn upgraded_operator_role_gets_shift_open_permission() {
+        let conn = Connection::open_in_memory().unwrap();
+        run_migrations(&conn).unwrap();
+        // Base anterior a la versión 51: el operador no tenía el permiso.
+        conn.execute_batch(
+            "DELETE FROM role_permissions WHERE role_id = 'role_operator' AND permission = 'caja:shift:open';
+             DELETE FROM schema_version WHERE version >= 51;",
+        )
+        .unwrap();
+        run_migrations(&conn).unwrap();
+        let granted: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM role_permissions WHERE role_id = 'role_operator' AND permission = 'caja:shift:open'",
+                [],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!(granted, 1);
+    }
+
+    #[test]
+    fn money_columns_move_to_integer_minor_units() {
+        let conn = Connection::open_in_memory().unwrap();
+        run_migrations(&conn).unwrap();
+        // Base anterior a la versión 54, en dólares: montos REAL en unidades mayores.
+        conn.execute_batch(
+            "INSERT INTO drive_config (key, value) VALUES ('currency_code', 'USD');
+             INSERT INTO contracts (id, client_name, plate, plate_upper, vehicle_type, tariff_kind, monthly_amount, included_hours_per_day, date_from, date_to, status, created_at, credit_balance)
+             VALUES ('CO1', 'Ana', 'ABC123', 'ABC123', 'car', 'none', 99.99, 6, '2025-01-01', '2025-01-31', 'active', '2025-01-01T00:00:00+00:00', 12.5);
+             INSERT INTO vehicles (id, ticket_code, plate, plate_upper, vehicle_type, entry_time, status, tariff_kind, tariff_snapshot)
+             VALUES ('v1', 'T1', 'ABC123', 'ABC123', 'car', '2025-01-01T00:00:00+00:00', 'active', 'regular', '{\"base\":{\"basePrice\":2.5},\"lostTicketAmount\":10,\"rules\":[{\"amount\":0.75}]}');
+             DELETE FROM schema_version WHERE version >= 54;",
+        )
+        .unwrap();
+        run_migrations(&conn).unwrap();
+
+        let (monthly, credit): (i64, i64) = conn
+            .query_row("SELECT monthly_amount, credit_balance FROM contracts WHERE id = 'CO1'", [], |r| {
+                Ok((r.get(0)?, r.get(1)?))
+            })
+            .unwrap();
+        assert_eq!((monthly, credit), (9999, 1250));
+        let column_type: String = conn
+            .query_row("SELECT type FROM pragma_table_info('contracts') WHERE name = 'credit_balance'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(column_type, "INTEGER");
+        let snapshot: serde_json::Value = conn
+            .query_row("SELECT tariff_snapshot FROM vehicles WHERE id = 'v1'", [], |r| r.get::<_, String>(0))
+            .map(|s| serde_json::from_str(&s).unwrap())
+            .unwrap();
+        assert_eq!(snapshot["base"]["basePrice"], 250);
+        assert_eq!(snapshot["lostTicketAmount"], 1000);
+        assert_eq!(snapshot["rules"][0]["amount"], 75);
+        // Los índices de las tablas reconstruidas se conservan.
+        let indexes: i64 = conn
+            .query_row("SELECT COUNT(*) FROM sqlite_master WHERE type = 'index' AND tbl_name = 'vehicles' AND sql IS NOT NULL", [], |r| r.get(0))
+            .unwrap();
+        assert!(indexes > 0);
+    }
+
+
+    #[test]
+    fn money_column_conversion_is_all_or_nothing() {
+        let conn = Connection::open_in_memory().unwrap();
+        run_migrations(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO drive_config (key, value) VALUES ('currency_code', 'USD');
+             INSERT INTO contracts (id, client_name, plate, plate_upper, vehicle_type, tariff_kind, monthly_amount, included_hours_per_day, date_from, date_to, status, created_at, credit_balance)
+             VALUES ('CO1', 'Ana', 'ABC123', 'ABC123', 'car', 'none', 99.99, 6, '2025-01-01', '2025-01-31', 'active', '2025-01-01T00:00:00+00:00', 12.5);
+             DELETE FROM schema_version WHERE version >= 54;
+             CREATE TABLE cash_movements_new (id TEXT);",
+        )
+        .unwrap();
+        // La reconstrucción de cash_movements falla después de convertir contracts.
+        assert!(run_migrations(&conn).is_err());
+        let credit: f64 = conn
+            .query_row("SELECT credit_balance FROM contracts WHERE id = 'CO1'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(credit, 12.5);
+
+        conn.execute("DROP TABLE cash_movements_new", []).unwrap();
+        run_migrations(&conn).unwrap();
+        let credit: i64 = conn
+            .query_row("SELECT credit_balance FROM contracts WHERE id = 'CO1'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(credit, 1250);
+        let foreign_keys: i64 = conn.query_row("PRAGMA foreign_keys", [], |r| r.get(0)).unwrap();
+        assert_eq!(foreign_keys, 1);
+    }
     #[test]
     fn test_open_pool_con_ruta_valida() {
         let dir = std::env::temp_dir().join("coco_parking_smoke_test");
@@ -1,6 +1,7 @@
 use serde::Serialize;
 use tauri::State;
 
+use crate::money::Money;
 use crate::permissions;
 use crate::state::AppState;
 
@@ -101,6 +102,7 @@ pub fn dev_set_current_user(state: State<AppState>, user_id: String) -> Result<S
 pub fn dev_get_db_snapshot(state: State<AppState>) -> Result<DbSnapshot, String> {
     require_dev_console(&state)?;
     let conn = state.db.get().map_err(|e| e.to_string())?;
+    let currency = crate::money::currency(&conn)?;
 
     let vehicles_count: u32 = conn
         .query_row("SELECT COUNT(*) FROM vehicles", [], |r| r.get(0))
@@ -122,7 +124,7 @@ pub fn dev_get_db_snapshot(state: State<AppState>) -> Result<DbSnapshot, String>
                 ticket_code: r.get(1)?,
                 plate: r.get(2)?,
                 status: r.get(3)?,
-                total_amount: r.get(4)?,
+                total_amount: r.get::<_, Option<Money>>(4)?.map(|m| currency.major(m)),
                 entry_time: r.get(5)?,
                 exit_time: r.get(6)?,
             })
@@ -143,7 +145,7 @@ pub fn dev_get_db_snapshot(state: State<AppState>) -> Result<DbSnapshot, String>
             Ok(TransactionRow {
                 id: r.get(0)?,
                 vehicle_id: r.get(1)?,
-                amount: r.get(2)?,
+                amount: currency.major(r.get(2)?),
                 method: r.get(3)?,
                 created_at: r.get(4)?,
             })
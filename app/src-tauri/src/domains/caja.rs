@@ -1,20 +1,36 @@
 //! Caja (tesorería) lee del mismo almacén que vehículos: AppState.db (SQLite).
 //! Evita duplicación y desalineación front/back; no hay cálculo de tesorería solo en frontend.
 
-use rusqlite::params;
-use serde::Serialize;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
 use tauri::State;
 
+use crate::db;
+use crate::domains::settings;
 use crate::id_gen;
+use crate::money::{self, Currency, Money};
 use crate::permissions;
 use crate::state::AppState;
 
+/// Movimientos de cajón que no son cobros. Entran: fondo inicial y depósitos (sencillo);
+/// salen: depósitos a bóveda (drop), retiros y gastos menores.
+const CASH_MOVEMENT_KINDS: &[&str] = &["opening_float", "deposit", "drop", "withdrawal", "expense"];
+
+/// Salidas hacia la bóveda: las registra un supervisor, no el operador del turno.
+const SAFE_MOVEMENT_KINDS: &[&str] = &["drop", "withdrawal"];
+
+/// drive_config: descuadre máximo (valor absoluto) que se cierra sin aprobación de supervisor.
+const CONFIG_KEY_DISCREPANCY_TOLERANCE: &str = "cash_discrepancy_tolerance";
+
 /// Dev-only: verifica qué lee caja_get_treasury (today, conteos, suma del día, muestra de transacciones).
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CajaDebug {
-    pub today_utc: String,
-    pub today_prefix: String,
+    /// Día de negocio (YYYY-MM-DD) en la zona del negocio.
+    pub today: String,
+    /// Rango UTC [inicio, fin) del día de negocio con que se filtran las transacciones.
+    pub day_start: String,
+    pub day_end: String,
     pub total_transactions_all: u32,
     pub total_transactions_today: u32,
     /// Sum of amounts for today's transactions (to verify expected_cash).
@@ -27,8 +43,9 @@ pub fn caja_get_debug(state: State<AppState>) -> Result<CajaDebug, String> {
     state.check_permission(permissions::CAJA_TREASURY_READ)?;
     let conn = state.db.get().map_err(|e| e.to_string())?;
 
-    let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
-    let today_prefix = format!("{}%", today);
+    let tz = settings::business_tz(&conn)?;
+    let today = settings::today_in(tz);
+    let (day_start, day_end) = settings::day_bounds_utc(tz, today);
 
     let total_transactions_all: u32 = conn
         .query_row("SELECT COUNT(*) FROM transactions", [], |r| r.get(0))
@@ -36,16 +53,17 @@ pub fn caja_get_debug(state: State<AppState>) -> Result<CajaDebug, String> {
 
     let total_transactions_today: u32 = conn
         .query_row(
-            "SELECT COUNT(*) FROM transactions WHERE created_at LIKE ?1",
-            params![&today_prefix],
+            "SELECT COUNT(*) FROM transactions WHERE created_at >= ?1 AND created_at < ?2",
+            params![&day_start, &day_end],
             |r| r.get(0),
         )
         .map_err(|e| e.to_string())?;
 
-    let sum_today: f64 = conn
+    let currency = money::currency(&conn)?;
+    let sum_today: Money = conn
         .query_row(
-            "SELECT COALESCE(SUM(amount), 0) FROM transactions WHERE created_at LIKE ?1",
-            params![&today_prefix],
+            "SELECT COALESCE(SUM(amount), 0) FROM transactions WHERE created_at >= ?1 AND created_at < ?2",
+            params![&day_start, &day_end],
             |r| r.get(0),
         )
         .map_err(|e| e.to_string())?;
@@ -57,18 +75,21 @@ pub fn caja_get_debug(state: State<AppState>) -> Result<CajaDebug, String> {
         )
         .map_err(|e| e.to_string())?;
     let rows = stmt
-        .query_map([], |r| Ok((r.get::<_, String>(0)?, r.get::<_, f64>(1)?, r.get::<_, String>(2)?, r.get::<_, String>(3)?)))
+        .query_map([], |r| {
+            Ok((r.get::<_, String>(0)?, currency.major(r.get(1)?), r.get::<_, String>(2)?, r.get::<_, String>(3)?))
+        })
         .map_err(|e| e.to_string())?;
     for row in rows {
         last_transactions.push(row.map_err(|e| e.to_string())?);
     }
 
     Ok(CajaDebug {
-        today_utc: today.clone(),
-        today_prefix: today_prefix.clone(),
+        today: today.format("%Y-%m-%d").to_string(),
+        day_start,
+        day_end,
         total_transactions_all,
         total_transactions_today,
-        sum_today,
+        sum_today: currency.major(sum_today),
         last_transactions,
     })
 }
@@ -77,11 +98,30 @@ pub fn caja_get_debug(state: State<AppState>) -> Result<CajaDebug, String> {
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TreasuryData {
+    /// Turno abierto del operador; None para fechas históricas o sin turno.
+    pub shift_id: Option<String>,
     pub expected_cash: f64,
-    pub actual_cash: f64,
-    pub discrepancy: f64,
+    /// Arqueo registrado en los cierres del alcance; None mientras no haya cierre con conteo.
+    pub actual_cash: Option<f64>,
+    /// Contado menos efectivo esperado según esos cierres; None sin conteo registrado.
+    pub discrepancy: Option<f64>,
     pub total_transactions: u32,
     pub payment_breakdown: PaymentBreakdown,
+    pub opening_float: f64,
+    /// Depósitos a bóveda y retiros del cajón.
+    pub cash_drops: f64,
+    /// Gastos menores pagados del cajón.
+    pub cash_expenses: f64,
+    /// Efectivo ingresado al cajón que no es cobro (sencillo, reposición).
+    pub cash_deposits: f64,
+    /// Fondo inicial + depósitos + cobros en efectivo - retiros y gastos.
+    pub expected_drawer_cash: f64,
+    /// Abonos de deuda (ya incluidos en el desglose por medio de pago).
+    pub debt_payments_total: f64,
+    /// Pagos de mensualidades de contratos (ya incluidos en el desglose por medio de pago).
+    pub contract_payments_total: f64,
+    /// Devoluciones de cobros (ya descontadas del desglose por medio de pago).
+    pub refunds_total: f64,
     pub debt_total: f64,
     pub vehicles_attended: u32,
     pub vehicles_with_debt: u32,
@@ -95,22 +135,13 @@ pub struct PaymentBreakdown {
     pub transfer: f64,
 }
 
-fn date_prefix_for_treasury(date_override: Option<&str>) -> String {
-    let date_str = match date_override.and_then(|s| {
-        let s = s.trim();
-        if s.len() == 10
-            && s.as_bytes().get(4) == Some(&b'-')
-            && s.as_bytes().get(7) == Some(&b'-')
-        {
-            Some(s.to_string())
-        } else {
-            None
-        }
-    }) {
-        Some(d) => d,
-        None => chrono::Utc::now().format("%Y-%m-%d").to_string(),
-    };
-    format!("{}%", date_str)
+/// Día de negocio pedido (YYYY-MM-DD); hoy en la zona del negocio si no viene o es inválido.
+fn date_for_treasury(date_override: Option<&str>, tz: chrono_tz::Tz) -> chrono::NaiveDate {
+    date_override
+        .map(str::trim)
+        .filter(|s| s.len() == 10)
+        .and_then(|s| settings::parse_date(s).ok())
+        .unwrap_or_else(|| settings::today_in(tz))
 }
 
 #[tauri::command]
@@ -120,92 +151,222 @@ pub fn caja_get_treasury(state: State<AppState>, date: Option<String>) -> Result
 
     let now = chrono::Utc::now();
     let now_rfc = now.to_rfc3339();
-    let today_str = now.format("%Y-%m-%d").to_string();
-    let is_today = date.is_none();
 
-    // For today: query only the current shift (since last closure).
-    // For historical dates: query the full day.
-    let (since_str, until_str) = if is_today {
-        let since = conn
-            .query_row(
-                "SELECT closed_at FROM shift_closures WHERE closed_at LIKE ?1 AND closed_at < ?2 ORDER BY closed_at DESC LIMIT 1",
-                params![&format!("{}%", today_str), &now_rfc],
-                |row| row.get::<_, String>(0),
-            )
-            .ok()
-            .unwrap_or_else(|| format!("{}T00:00:00.000Z", today_str));
-        (since, now_rfc.clone())
+    // Hoy: el turno abierto del operador; sin turno, lo transcurrido desde el último cierre.
+    // Fechas históricas: el día completo.
+    let shift = match date {
+        None => open_shift_for(&conn, state.get_current_user_id().as_deref())?,
+        Some(_) => None,
+    };
+    let tz = settings::business_tz(&conn)?;
+    let (since_str, until_str) = if date.is_none() {
+        (current_shift_since(&conn, tz, &now), now_rfc.clone())
     } else {
-        let today_prefix = date_prefix_for_treasury(date.as_deref());
-        let date_str = today_prefix.trim_end_matches('%');
-        (
-            format!("{}T00:00:00.000Z", date_str),
-            format!("{}T23:59:59.999Z", date_str),
-        )
+        settings::day_bounds_utc(tz, date_for_treasury(date.as_deref(), tz))
     };
+    let scope = match &shift {
+        Some(s) => Scope::Shift(&s.id),
+        None => Scope::Window(&since_str, &until_str),
+    };
+    let totals = cash_totals(&conn, &scope)?;
+    let currency = money::currency(&conn)?;
+    let major = |m: Money| currency.major(m);
+
+    let expected_cash = major(totals.expected_total());
+    let expected_drawer_cash = major(totals.expected_drawer_cash());
+    // El conteo real se registra al cerrar el turno: el de su cierre, o el de los cierres del día.
+    let counted = counted_cash(&conn, &scope)?;
+
+    Ok(TreasuryData {
+        shift_id: shift.map(|s| s.id),
+        expected_cash,
+        actual_cash: counted.map(|(arqueo, _)| major(arqueo)),
+        discrepancy: counted.map(|(_, discrepancy)| major(discrepancy)),
+        total_transactions: totals.total_transactions,
+        payment_breakdown: PaymentBreakdown {
+            cash: major(totals.cash),
+            card: major(totals.card),
+            transfer: major(totals.transfer),
+        },
+        opening_float: major(totals.opening_float),
+        cash_drops: major(totals.cash_drops),
+        cash_expenses: major(totals.cash_expenses),
+        cash_deposits: major(totals.cash_deposits),
+        expected_drawer_cash,
+        debt_payments_total: major(totals.debt_payments_total),
+        contract_payments_total: major(totals.contract_payments_total),
+        refunds_total: major(totals.refunds_total),
+        debt_total: major(totals.debt_total),
+        vehicles_attended: totals.vehicles_attended,
+        vehicles_with_debt: totals.vehicles_with_debt,
+        vehicles_removed: totals.vehicles_removed,
+    })
+}
+
+/// Inicio del periodo sin turno abierto: último cierre de hoy o medianoche en la zona del negocio.
+fn current_shift_since(conn: &Connection, tz: chrono_tz::Tz, now: &chrono::DateTime<chrono::Utc>) -> String {
+    let (midnight, _) = settings::day_bounds_utc(tz, now.with_timezone(&tz).date_naive());
+    conn.query_row(
+        "SELECT closed_at FROM shift_closures WHERE closed_at >= ?1 AND closed_at < ?2 ORDER BY closed_at DESC LIMIT 1",
+        params![&midnight, &now.to_rfc3339()],
+        |row| row.get::<_, String>(0),
+    )
+    .ok()
+    .unwrap_or(midnight)
+}
+
+/// Alcance de los totales de caja: un turno explícito, o una ventana de tiempo para días
+/// históricos y para la operación sin turno abierto.
+enum Scope<'a> {
+    Shift(&'a str),
+    Window(&'a str, &'a str),
+}
+
+impl Scope<'_> {
+    /// Condición SQL del alcance: por `shift_col` en un turno, por `time_col` en una ventana.
+    fn clause(&self, time_col: &str, shift_col: &str) -> String {
+        match self {
+            Scope::Shift(_) => format!("{shift_col} = ?1"),
+            Scope::Window(..) => format!("{time_col} > ?1 AND {time_col} <= ?2"),
+        }
+    }
+
+    fn args(&self) -> Vec<&str> {
+        match self {
+            Scope::Shift(id) => vec![id],
+            Scope::Window(since, until) => vec![since, until],
+        }
+    }
+}
+
+/// Totales de caja de un alcance. El desglose por medio de pago ya incluye los pagos de
+/// contratos, que viven en `contract_payments` y no en `transactions`.
+struct CashTotals {
+    total_transactions: u32,
+    cash: Money,
+    card: Money,
+    transfer: Money,
+    debt_payments_total: Money,
+    contract_payments_total: Money,
+    refunds_total: Money,
+    opening_float: Money,
+    cash_drops: Money,
+    cash_expenses: Money,
+    cash_deposits: Money,
+    debt_total: Money,
+    vehicles_attended: u32,
+    vehicles_with_debt: u32,
+    vehicles_removed: u32,
+}
 
-    let (total_transactions, cash, card, transfer): (u32, f64, f64, f64) = conn
+impl CashTotals {
+    /// Fondo inicial + depósitos + cobros en efectivo - retiros y gastos.
+    fn expected_drawer_cash(&self) -> Money {
+        self.opening_float + self.cash_deposits + self.cash - self.cash_drops - self.cash_expenses
+    }
+
+    /// Efectivo + tarjeta + transferencia.
+    fn expected_total(&self) -> Money {
+        self.cash + self.card + self.transfer
+    }
+}
+
+fn cash_totals(conn: &Connection, scope: &Scope) -> Result<CashTotals, String> {
+    let args = scope.args();
+    let args = rusqlite::params_from_iter(args.iter());
+
+    let (total_transactions, cash, card, transfer, debt_payments_total, refunds_total): (u32, Money, Money, Money, Money, Money) = conn
         .query_row(
-            r#"
-            SELECT
-                COUNT(CASE WHEN LOWER(method) NOT IN ('removed', 'debt') THEN 1 END),
-                COALESCE(SUM(CASE WHEN LOWER(method) = 'cash' THEN amount ELSE 0 END), 0),
-                COALESCE(SUM(CASE WHEN LOWER(method) = 'card' THEN amount ELSE 0 END), 0),
-                COALESCE(SUM(CASE WHEN LOWER(method) = 'transfer' THEN amount ELSE 0 END), 0)
-            FROM transactions
-            WHERE created_at > ?1 AND created_at <= ?2
-            "#,
-            params![&since_str, &until_str],
-            |row| Ok((row.get::<_, i64>(0)? as u32, row.get(1)?, row.get(2)?, row.get(3)?)),
+            &format!(
+                r#"
+                SELECT
+                    COUNT(CASE WHEN LOWER(method) NOT IN ('removed', 'debt') AND kind != 'refund' THEN 1 END),
+                    COALESCE(SUM(CASE WHEN LOWER(method) = 'cash' THEN amount ELSE 0 END), 0),
+                    COALESCE(SUM(CASE WHEN LOWER(method) = 'card' THEN amount ELSE 0 END), 0),
+                    COALESCE(SUM(CASE WHEN LOWER(method) = 'transfer' THEN amount ELSE 0 END), 0),
+                    COALESCE(SUM(CASE WHEN kind = 'debt_payment' THEN amount ELSE 0 END), 0),
+                    COALESCE(SUM(CASE WHEN kind = 'refund' THEN -amount ELSE 0 END), 0)
+                FROM transactions
+                WHERE {}
+                "#,
+                scope.clause("created_at", "shift_id")
+            ),
+            args.clone(),
+            |row| Ok((row.get::<_, i64>(0)? as u32, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?)),
         )
         .map_err(|e| e.to_string())?;
 
-    let expected_cash = cash + card + transfer;
-    let actual_cash = expected_cash;
-    let discrepancy = 0.0;
-
-    let debt_total: f64 = conn
+    let (contract_cash, contract_card, contract_transfer): (Money, Money, Money) = conn
         .query_row(
-            "SELECT COALESCE(SUM(debt), 0) FROM vehicles WHERE exit_time > ?1 AND exit_time <= ?2 AND status = 'completed' AND debt > 0",
-            params![&since_str, &until_str],
-            |row| row.get(0),
+            &format!(
+                r#"
+                SELECT
+                    COALESCE(SUM(CASE WHEN LOWER(method) = 'cash' THEN amount ELSE 0 END), 0),
+                    COALESCE(SUM(CASE WHEN LOWER(method) = 'card' THEN amount ELSE 0 END), 0),
+                    COALESCE(SUM(CASE WHEN LOWER(method) = 'transfer' THEN amount ELSE 0 END), 0)
+                FROM contract_payments
+                WHERE {}
+                "#,
+                scope.clause("created_at", "shift_id")
+            ),
+            args.clone(),
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
         )
-        .unwrap_or(0.0);
+        .map_err(|e| e.to_string())?;
 
-    let vehicles_attended: u32 = conn
+    let (opening_float, cash_drops, cash_expenses, cash_deposits): (Money, Money, Money, Money) = conn
         .query_row(
-            "SELECT COUNT(*) FROM vehicles WHERE exit_time > ?1 AND exit_time <= ?2 AND status IN ('completed', 'removed')",
-            params![&since_str, &until_str],
-            |row| row.get(0),
+            &format!(
+                r#"
+                SELECT
+                    COALESCE(SUM(CASE WHEN kind = 'opening_float' THEN amount ELSE 0 END), 0),
+                    COALESCE(SUM(CASE WHEN kind IN ('drop', 'withdrawal') THEN amount ELSE 0 END), 0),
+                    COALESCE(SUM(CASE WHEN kind = 'expense' THEN amount ELSE 0 END), 0),
+                    COALESCE(SUM(CASE WHEN kind = 'deposit' THEN amount ELSE 0 END), 0)
+                FROM cash_movements
+                WHERE {}
+                "#,
+                scope.clause("created_at", "shift_id")
+            ),
+            args.clone(),
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
         )
-        .unwrap_or(0);
+        .map_err(|e| e.to_string())?;
 
-    let vehicles_with_debt: u32 = conn
+    let (debt_total, vehicles_with_debt): (Money, u32) = conn
         .query_row(
-            "SELECT COUNT(*) FROM vehicles WHERE exit_time > ?1 AND exit_time <= ?2 AND status = 'completed' AND debt > 0",
-            params![&since_str, &until_str],
-            |row| row.get(0),
+            &format!(
+                "SELECT COALESCE(SUM(amount), 0), COUNT(DISTINCT vehicle_id) FROM debts WHERE {}",
+                scope.clause("created_at", "shift_id")
+            ),
+            args.clone(),
+            |row| Ok((row.get(0)?, row.get::<_, i64>(1)? as u32)),
         )
-        .unwrap_or(0);
+        .map_err(|e| e.to_string())?;
 
-    let vehicles_removed: u32 = conn
+    let (vehicles_attended, vehicles_removed): (u32, u32) = conn
         .query_row(
-            "SELECT COUNT(*) FROM vehicles WHERE exit_time > ?1 AND exit_time <= ?2 AND status = 'removed'",
-            params![&since_str, &until_str],
-            |row| row.get(0),
+            &format!(
+                "SELECT COUNT(CASE WHEN status IN ('completed', 'removed') THEN 1 END), COUNT(CASE WHEN status = 'removed' THEN 1 END) FROM vehicles WHERE {}",
+                scope.clause("exit_time", "exit_shift_id")
+            ),
+            args,
+            |row| Ok((row.get::<_, i64>(0)? as u32, row.get::<_, i64>(1)? as u32)),
         )
-        .unwrap_or(0);
+        .map_err(|e| e.to_string())?;
 
-    Ok(TreasuryData {
-        expected_cash,
-        actual_cash,
-        discrepancy,
+    Ok(CashTotals {
         total_transactions,
-        payment_breakdown: PaymentBreakdown {
-            cash,
-            card,
-            transfer,
-        },
+        cash: cash + contract_cash,
+        card: card + contract_card,
+        transfer: transfer + contract_transfer,
+        debt_payments_total,
+        contract_payments_total: contract_cash + contract_card + contract_transfer,
+        refunds_total,
+        opening_float,
+        cash_drops,
+        cash_expenses,
+        cash_deposits,
         debt_total,
         vehicles_attended,
         vehicles_with_debt,
@@ -213,6 +374,168 @@ pub fn caja_get_treasury(state: State<AppState>, date: Option<String>) -> Result
     })
 }
 
+/// Arqueo y descuadre registrados en los cierres del alcance (sumados si son varios). None si
+/// no hay cierre o ninguno registró conteo.
+fn counted_cash(conn: &Connection, scope: &Scope) -> Result<Option<(Money, Money)>, String> {
+    let (counted_closures, arqueo, discrepancy): (i64, Money, Money) = conn
+        .query_row(
+            &format!(
+                "SELECT COUNT(arqueo_cash), COALESCE(SUM(arqueo_cash), 0), COALESCE(SUM(CASE WHEN arqueo_cash IS NOT NULL THEN discrepancy ELSE 0 END), 0) FROM shift_closures WHERE {}",
+                scope.clause("closed_at", "shift_id")
+            ),
+            rusqlite::params_from_iter(scope.args().iter()),
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .map_err(|e| e.to_string())?;
+    Ok((counted_closures > 0).then_some((arqueo, discrepancy)))
+}
+
+/// Turno de caja de un operador en una caja (booth). Los cobros, deudas y movimientos de
+/// cajón se ligan al turno abierto de quien los registra.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Shift {
+    pub id: String,
+    pub operator_user_id: Option<String>,
+    pub booth: String,
+    pub opened_at: String,
+    pub closed_at: Option<String>,
+    /// 'open' o 'closed'.
+    pub status: String,
+    pub closure_id: Option<String>,
+}
+
+const SHIFT_COLS: &str = "id, operator_user_id, booth, opened_at, closed_at, status, closure_id";
+
+fn shift_from_row(row: &rusqlite::Row) -> rusqlite::Result<Shift> {
+    Ok(Shift {
+        id: row.get(0)?,
+        operator_user_id: row.get(1)?,
+        booth: row.get(2)?,
+        opened_at: row.get(3)?,
+        closed_at: row.get(4)?,
+        status: row.get(5)?,
+        closure_id: row.get(6)?,
+    })
+}
+
+/// Turno abierto del operador. None sin sesión o sin turno abierto.
+pub(crate) fn open_shift_for(conn: &Connection, operator_user_id: Option<&str>) -> Result<Option<Shift>, String> {
+    let Some(operator_user_id) = operator_user_id else {
+        return Ok(None);
+    };
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {SHIFT_COLS} FROM shifts WHERE operator_user_id = ?1 AND status = 'open'"
+        ))
+        .map_err(|e| e.to_string())?;
+    let mut rows = stmt
+        .query_map(params![operator_user_id], shift_from_row)
+        .map_err(|e| e.to_string())?;
+    rows.next().transpose().map_err(|e| e.to_string())
+}
+
+/// Id del turno abierto del operador, para ligar cobros y deudas al registrarlos. Sin turno
+/// abierto no se reciben ni devuelven pagos: quedarían fuera de todo cierre de caja.
+pub(crate) fn open_shift_id(conn: &Connection, operator_user_id: Option<&str>) -> Result<String, String> {
+    open_shift_for(conn, operator_user_id)?
+        .map(|s| s.id)
+        .ok_or_else(|| "Abra un turno de caja antes de registrar cobros".to_string())
+}
+
+fn open_shift_on(
+    conn: &Connection,
+    operator_user_id: &str,
+    booth: &str,
+    opening_float: Option<Money>,
+    opened_at: &str,
+) -> Result<Shift, String> {
+    let booth = booth.trim();
+    if booth.is_empty() {
+        return Err("Indique la caja del turno".to_string());
+    }
+    let id = id_gen::generate_id(id_gen::PREFIX_SHIFT);
+    // Turno abierto, caja ocupada y cierre pendiente se leen bajo el mismo bloqueo que el INSERT.
+    db::with_transaction(conn, |conn| {
+        if let Some(open) = open_shift_for(conn, Some(operator_user_id))? {
+            return Err(format!("Ya tiene un turno abierto en la caja {}", open.booth));
+        }
+        let booth_busy: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM shifts WHERE booth = ?1 AND status = 'open'",
+                params![booth],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+        if booth_busy > 0 {
+            return Err(format!("La caja {} ya tiene un turno abierto", booth));
+        }
+        // Un cierre con descuadre sobre la tolerancia deja al cajero y a la caja en espera hasta
+        // que un supervisor lo apruebe.
+        let pending: Option<String> = conn
+            .query_row(
+                "SELECT sh.booth FROM shift_closures c JOIN shifts sh ON sh.id = c.shift_id WHERE c.status = 'pending_approval' AND (sh.operator_user_id = ?1 OR sh.booth = ?2) LIMIT 1",
+                params![operator_user_id, booth],
+                |row| row.get(0),
+            )
+            .ok();
+        if let Some(pending_booth) = pending {
+            return Err(format!(
+                "El cierre anterior de la caja {} está pendiente de aprobación",
+                pending_booth
+            ));
+        }
+
+        conn.execute(
+            "INSERT INTO shifts (id, operator_user_id, booth, opened_at, status) VALUES (?1, ?2, ?3, ?4, 'open')",
+            params![&id, operator_user_id, booth, opened_at],
+        )
+        .map_err(|e| e.to_string())?;
+        if let Some(amount) = opening_float.filter(|a| *a > Money::ZERO) {
+            record_cash_movement(conn, &id, "opening_float", amount, None, opened_at, Some(operator_user_id))?;
+        }
+        Ok(())
+    })?;
+    Ok(Shift {
+        id,
+        operator_user_id: Some(operator_user_id.to_string()),
+        booth: booth.to_string(),
+        opened_at: opened_at.to_string(),
+        closed_at: None,
+        status: "open".to_string(),
+        closure_id: None,
+    })
+}
+
+/// Abre el turno del operador en sesión en una caja, con fondo inicial opcional.
+#[tauri::command]
+pub fn caja_open_shift(
+    state: State<AppState>,
+    booth: String,
+    opening_float: Option<f64>,
+) -> Result<Shift, String> {
+    state.check_permission(permissions::CAJA_SHIFT_OPEN)?;
+    let conn = state.db.get().map_err(|e| e.to_string())?;
+    let operator_user_id = state
+        .get_current_user_id()
+        .ok_or_else(|| "Inicie sesión para abrir un turno".to_string())?;
+    let currency = money::currency(&conn)?;
+    open_shift_on(
+        &conn,
+        &operator_user_id,
+        &booth,
+        opening_float.map(|a| currency.money(a)),
+        &chrono::Utc::now().to_rfc3339(),
+    )
+}
+
+#[tauri::command]
+pub fn caja_get_current_shift(state: State<AppState>) -> Result<Option<Shift>, String> {
+    state.check_permission(permissions::CAJA_TREASURY_READ)?;
+    let conn = state.db.get().map_err(|e| e.to_string())?;
+    open_shift_for(&conn, state.get_current_user_id().as_deref())
+}
+
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ShiftClosure {
@@ -223,187 +546,509 @@ pub struct ShiftClosure {
     pub card_total: f64,
     pub transfer_total: f64,
     pub arqueo_cash: Option<f64>,
+    /// Contado menos efectivo esperado en el cajón (positivo = sobrante, negativo = faltante).
     pub discrepancy: f64,
     pub total_transactions: u32,
     pub notes: Option<String>,
     pub operator_user_id: Option<String>,
+    pub debt_payments_total: f64,
+    pub contract_payments_total: f64,
+    pub refunds_total: f64,
     pub debt_total: f64,
     pub vehicles_attended: u32,
     pub vehicles_with_debt: u32,
     pub vehicles_removed: u32,
+    pub opening_float: f64,
+    pub cash_drops: f64,
+    pub cash_expenses: f64,
+    pub cash_deposits: f64,
+    pub expected_drawer_cash: f64,
+    /// Conteo de billetes y monedas; vacío si el arqueo se registró como un solo monto.
+    pub denominations: Vec<DenominationCount>,
+    /// 'closed', 'pending_approval' (descuadre sobre la tolerancia) o 'approved'.
+    pub status: String,
+    pub approved_by: Option<String>,
+    pub approved_at: Option<String>,
+    pub approval_note: Option<String>,
+    /// Turno cerrado; None en cierres anteriores a los turnos explícitos.
+    pub shift_id: Option<String>,
+    pub booth: Option<String>,
+    pub opened_at: Option<String>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DenominationCount {
+    pub denomination: f64,
+    pub quantity: i64,
+}
+
+/// Total contado a partir del desglose por denominación.
+fn counted_from_denominations(currency: &Currency, denominations: &[DenominationCount]) -> Result<Money, String> {
+    let mut total = Money::ZERO;
+    for d in denominations {
+        if d.denomination <= 0.0 || !d.denomination.is_finite() {
+            return Err("La denominación debe ser mayor a 0".to_string());
+        }
+        if d.quantity < 0 {
+            return Err("La cantidad por denominación no puede ser negativa".to_string());
+        }
+        total += Money::from_minor(currency.money(d.denomination).minor() * d.quantity);
+    }
+    Ok(total)
+}
+
+fn close_shift_on(
+    conn: &Connection,
+    shift: &Shift,
+    now: chrono::DateTime<chrono::Utc>,
+    arqueo_cash: Option<Money>,
+    denominations: Option<Vec<DenominationCount>>,
+    notes: Option<String>,
+    operator_user_id: Option<String>,
+) -> Result<ShiftClosure, String> {
+    let now_rfc = now.to_rfc3339();
+
+    // Con desglose, el arqueo es la suma de billetes y monedas; si no, el monto digitado. Si
+    // llegan ambos deben coincidir.
+    let currency = money::currency(conn)?;
+    let denominations = denominations.unwrap_or_default();
+    let arqueo_cash = if denominations.is_empty() {
+        arqueo_cash
+    } else {
+        let counted = counted_from_denominations(&currency, &denominations)?;
+        if let Some(typed) = arqueo_cash {
+            if typed != counted {
+                return Err(format!(
+                    "El arqueo digitado ({:.2}) no coincide con el desglose por denominación ({:.2})",
+                    currency.major(typed),
+                    currency.major(counted)
+                ));
+            }
+        }
+        Some(counted)
+    };
+    if arqueo_cash.is_some_and(|a| a < Money::ZERO) {
+        return Err("El arqueo no puede ser negativo".to_string());
+    }
+
+    let id = id_gen::generate_id(id_gen::PREFIX_SHIFT_CLOSURE);
+    let closed_at = now_rfc.clone();
+    let denominations_json = if denominations.is_empty() {
+        None
+    } else {
+        Some(serde_json::to_string(&denominations).map_err(|e| e.to_string())?)
+    };
+
+    let (totals, expected_total, expected_drawer_cash, discrepancy, status) = db::with_transaction(conn, |conn| {
+        let n = conn
+            .execute(
+                "UPDATE shifts SET status = 'closed', closed_at = ?1, closure_id = ?2 WHERE id = ?3 AND status = 'open'",
+                params![&closed_at, &id, &shift.id],
+            )
+            .map_err(|e| e.to_string())?;
+        if n == 0 {
+            return Err("El turno ya fue cerrado".to_string());
+        }
+        // Los totales se leen ya con el turno cerrado y bajo el mismo bloqueo: ningún cobro
+        // puede ligarse al turno después de sumarlo.
+        let totals = cash_totals(conn, &Scope::Shift(&shift.id))?;
+        let expected_total = totals.expected_total();
+        let expected_drawer_cash = totals.expected_drawer_cash();
+        // Sobrante/faltante solo contra efectivo: tarjeta y transferencia no pasan por el cajón.
+        let discrepancy = arqueo_cash.map(|a| a - expected_drawer_cash).unwrap_or(Money::ZERO);
+        let status = match get_discrepancy_tolerance(conn)? {
+            Some(tolerance) if arqueo_cash.is_some() && discrepancy.abs() > currency.money(tolerance) => {
+                "pending_approval"
+            }
+            _ => "closed",
+        };
+        conn.execute(
+            "INSERT INTO shift_closures (id, closed_at, expected_total, cash_total, card_total, transfer_total, arqueo_cash, discrepancy, total_transactions, notes, operator_user_id, debt_total, vehicles_attended, vehicles_with_debt, vehicles_removed, debt_payments_total, contract_payments_total, opening_float, cash_drops, expected_drawer_cash, denominations, status, shift_id, cash_expenses, cash_deposits, refunds_total) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24, ?25, ?26)",
+            params![
+                &id,
+                &closed_at,
+                expected_total,
+                totals.cash,
+                totals.card,
+                totals.transfer,
+                arqueo_cash,
+                discrepancy,
+                totals.total_transactions as i64,
+                notes.as_deref(),
+                operator_user_id,
+                totals.debt_total,
+                totals.vehicles_attended as i64,
+                totals.vehicles_with_debt as i64,
+                totals.vehicles_removed as i64,
+                totals.debt_payments_total,
+                totals.contract_payments_total,
+                totals.opening_float,
+                totals.cash_drops,
+                expected_drawer_cash,
+                denominations_json,
+                status,
+                &shift.id,
+                totals.cash_expenses,
+                totals.cash_deposits,
+                totals.refunds_total,
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+        Ok((totals, expected_total, expected_drawer_cash, discrepancy, status))
+    })?;
+
+    let major = |m: Money| currency.major(m);
+    Ok(ShiftClosure {
+        id: id.clone(),
+        closed_at: closed_at.clone(),
+        expected_total: major(expected_total),
+        cash_total: major(totals.cash),
+        card_total: major(totals.card),
+        transfer_total: major(totals.transfer),
+        arqueo_cash: arqueo_cash.map(major),
+        discrepancy: major(discrepancy),
+        total_transactions: totals.total_transactions,
+        notes,
+        operator_user_id,
+        debt_payments_total: major(totals.debt_payments_total),
+        contract_payments_total: major(totals.contract_payments_total),
+        refunds_total: major(totals.refunds_total),
+        debt_total: major(totals.debt_total),
+        vehicles_attended: totals.vehicles_attended,
+        vehicles_with_debt: totals.vehicles_with_debt,
+        vehicles_removed: totals.vehicles_removed,
+        opening_float: major(totals.opening_float),
+        cash_drops: major(totals.cash_drops),
+        cash_expenses: major(totals.cash_expenses),
+        cash_deposits: major(totals.cash_deposits),
+        expected_drawer_cash: major(expected_drawer_cash),
+        denominations,
+        status: status.to_string(),
+        approved_by: None,
+        approved_at: None,
+        approval_note: None,
+        shift_id: Some(shift.id.clone()),
+        booth: Some(shift.booth.clone()),
+        opened_at: Some(shift.opened_at.clone()),
+    })
+}
+
+/// Cierra el turno abierto del operador en sesión.
 #[tauri::command]
 pub fn caja_close_shift(
     state: State<AppState>,
     arqueo_cash: Option<f64>,
+    denominations: Option<Vec<DenominationCount>>,
     notes: Option<String>,
 ) -> Result<ShiftClosure, String> {
     state.check_permission(permissions::CAJA_SHIFT_CLOSE)?;
     let conn = state.db.get().map_err(|e| e.to_string())?;
+    let operator_user_id = state.get_current_user_id();
+    let shift = open_shift_for(&conn, operator_user_id.as_deref())?
+        .ok_or_else(|| "No hay un turno abierto para cerrar".to_string())?;
+    let currency = money::currency(&conn)?;
+    let arqueo_cash = arqueo_cash.map(|a| currency.money(a));
+    close_shift_on(&conn, &shift, chrono::Utc::now(), arqueo_cash, denominations, notes, operator_user_id)
+}
 
-    let now = chrono::Utc::now();
-    let now_rfc = now.to_rfc3339();
-    let today_str = now.format("%Y-%m-%d").to_string();
-    let today_prefix = format!("{}%", today_str);
-    let since_str = conn
-        .query_row(
-            "SELECT closed_at FROM shift_closures WHERE closed_at LIKE ?1 AND closed_at < ?2 ORDER BY closed_at DESC LIMIT 1",
-            params![&today_prefix, &now_rfc],
-            |row| row.get::<_, String>(0),
-        )
-        .ok()
-        .unwrap_or_else(|| format!("{}T00:00:00.000Z", today_str));
+const SHIFT_CLOSURE_SELECT: &str = "SELECT s.id, s.closed_at, s.expected_total, s.cash_total, s.card_total, s.transfer_total, s.arqueo_cash, s.discrepancy, s.total_transactions, s.notes, COALESCE(u.display_name, s.operator_user_id), COALESCE(s.debt_total, 0), COALESCE(s.vehicles_attended, 0), COALESCE(s.vehicles_with_debt, 0), COALESCE(s.vehicles_removed, 0), COALESCE(s.debt_payments_total, 0), COALESCE(s.contract_payments_total, 0), COALESCE(s.opening_float, 0), COALESCE(s.cash_drops, 0), COALESCE(s.expected_drawer_cash, s.cash_total), s.denominations, s.status, COALESCE(a.display_name, s.approved_by), s.approved_at, s.approval_note, s.shift_id, sh.booth, sh.opened_at, COALESCE(s.cash_expenses, 0), COALESCE(s.cash_deposits, 0), COALESCE(s.refunds_total, 0) FROM shift_closures s LEFT JOIN users u ON s.operator_user_id = u.id LEFT JOIN users a ON s.approved_by = a.id LEFT JOIN shifts sh ON sh.id = s.shift_id";
 
-    let total_transactions: u32 = conn
-        .query_row(
-            "SELECT COUNT(*) FROM transactions WHERE created_at > ?1 AND created_at <= ?2 AND LOWER(method) NOT IN ('removed', 'debt')",
-            params![&since_str, &now_rfc],
-            |row| row.get(0),
-        )
-        .map_err(|e| e.to_string())?;
+fn shift_closure_from_row(row: &rusqlite::Row, currency: &Currency) -> rusqlite::Result<ShiftClosure> {
+    let denominations: Option<String> = row.get(20)?;
+    let major = |idx: usize| row.get::<_, Money>(idx).map(|m| currency.major(m));
+    Ok(ShiftClosure {
+        id: row.get(0)?,
+        closed_at: row.get(1)?,
+        expected_total: major(2)?,
+        cash_total: major(3)?,
+        card_total: major(4)?,
+        transfer_total: major(5)?,
+        arqueo_cash: row.get::<_, Option<Money>>(6)?.map(|m| currency.major(m)),
+        discrepancy: major(7)?,
+        total_transactions: row.get::<_, i64>(8)? as u32,
+        notes: row.get(9)?,
+        operator_user_id: row.get(10)?,
+        debt_payments_total: major(15)?,
+        contract_payments_total: major(16)?,
+        refunds_total: major(30)?,
+        debt_total: major(11)?,
+        vehicles_attended: row.get::<_, i64>(12)? as u32,
+        vehicles_with_debt: row.get::<_, i64>(13)? as u32,
+        vehicles_removed: row.get::<_, i64>(14)? as u32,
+        opening_float: major(17)?,
+        cash_drops: major(18)?,
+        cash_expenses: major(28)?,
+        cash_deposits: major(29)?,
+        expected_drawer_cash: major(19)?,
+        denominations: denominations
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default(),
+        status: row.get(21)?,
+        approved_by: row.get(22)?,
+        approved_at: row.get(23)?,
+        approval_note: row.get(24)?,
+        shift_id: row.get(25)?,
+        booth: row.get(26)?,
+        opened_at: row.get(27)?,
+    })
+}
 
-    let (cash_total, card_total, transfer_total) = if total_transactions > 0 {
-        let cash: f64 = conn
-            .query_row(
-                "SELECT COALESCE(SUM(amount), 0) FROM transactions WHERE created_at > ?1 AND created_at <= ?2 AND LOWER(method) = 'cash'",
-                params![&since_str, &now_rfc],
-                |row| row.get(0),
-            )
-            .unwrap_or(0.0);
-        let card: f64 = conn
-            .query_row(
-                "SELECT COALESCE(SUM(amount), 0) FROM transactions WHERE created_at > ?1 AND created_at <= ?2 AND LOWER(method) = 'card'",
-                params![&since_str, &now_rfc],
-                |row| row.get(0),
-            )
-            .unwrap_or(0.0);
-        let transfer: f64 = conn
-            .query_row(
-                "SELECT COALESCE(SUM(amount), 0) FROM transactions WHERE created_at > ?1 AND created_at <= ?2 AND LOWER(method) = 'transfer'",
-                params![&since_str, &now_rfc],
-                |row| row.get(0),
-            )
-            .unwrap_or(0.0);
-        (cash, card, transfer)
-    } else {
-        (0.0, 0.0, 0.0)
-    };
+#[tauri::command]
+pub fn caja_list_shift_closures(
+    state: State<AppState>,
+    limit: Option<u32>,
+) -> Result<Vec<ShiftClosure>, String> {
+    state.check_permission(permissions::CAJA_TREASURY_READ)?;
+    let conn = state.db.get().map_err(|e| e.to_string())?;
+    let limit = limit.unwrap_or(50).min(200) as i64;
+    let currency = money::currency(&conn)?;
 
-    let expected_total = cash_total + card_total + transfer_total;
-    let discrepancy = arqueo_cash
-        .map(|a| a - cash_total)
-        .unwrap_or(0.0);
+    let mut stmt = conn
+        .prepare(&format!("{SHIFT_CLOSURE_SELECT} ORDER BY s.closed_at DESC LIMIT ?1"))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![limit], |row| shift_closure_from_row(row, &currency))
+        .map_err(|e| e.to_string())?;
 
-    let debt_total: f64 = conn
-        .query_row(
-            "SELECT COALESCE(SUM(debt), 0) FROM vehicles WHERE exit_time > ?1 AND exit_time <= ?2 AND status = 'completed' AND debt > 0",
-            params![&since_str, &now_rfc],
-            |row| row.get(0),
-        )
-        .unwrap_or(0.0);
+    let mut list = Vec::new();
+    for row in rows {
+        list.push(row.map_err(|e| e.to_string())?);
+    }
+    Ok(list)
+}
 
-    let vehicles_attended: u32 = conn
+fn approve_shift_closure_on(
+    conn: &Connection,
+    closure_id: &str,
+    note: Option<&str>,
+    approver_user_id: Option<&str>,
+    approved_at: &str,
+) -> Result<ShiftClosure, String> {
+    let (status, closed_by): (String, Option<String>) = conn
         .query_row(
-            "SELECT COUNT(*) FROM vehicles WHERE exit_time > ?1 AND exit_time <= ?2 AND status IN ('completed', 'removed')",
-            params![&since_str, &now_rfc],
-            |row| row.get(0),
+            "SELECT status, operator_user_id FROM shift_closures WHERE id = ?1",
+            params![closure_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
         )
-        .unwrap_or(0);
+        .map_err(|_| "Cierre de turno no encontrado".to_string())?;
+    if status != "pending_approval" {
+        return Err("El cierre de turno no está pendiente de aprobación".to_string());
+    }
+    if approver_user_id.is_some() && approver_user_id == closed_by.as_deref() {
+        return Err("El cierre debe aprobarlo un usuario distinto a quien lo cerró".to_string());
+    }
+    conn.execute(
+        "UPDATE shift_closures SET status = 'approved', approved_by = ?2, approved_at = ?3, approval_note = ?4 WHERE id = ?1",
+        params![closure_id, approver_user_id, approved_at, note],
+    )
+    .map_err(|e| e.to_string())?;
+    let currency = money::currency(conn)?;
+    conn.query_row(
+        &format!("{SHIFT_CLOSURE_SELECT} WHERE s.id = ?1"),
+        params![closure_id],
+        |row| shift_closure_from_row(row, &currency),
+    )
+    .map_err(|e| e.to_string())
+}
 
-    let vehicles_with_debt: u32 = conn
-        .query_row(
-            "SELECT COUNT(*) FROM vehicles WHERE exit_time > ?1 AND exit_time <= ?2 AND status = 'completed' AND debt > 0",
-            params![&since_str, &now_rfc],
-            |row| row.get(0),
-        )
-        .unwrap_or(0);
+/// Aprobación de supervisor para un cierre cuyo descuadre superó la tolerancia.
+#[tauri::command]
+pub fn caja_approve_shift_closure(
+    state: State<AppState>,
+    closure_id: String,
+    note: Option<String>,
+) -> Result<ShiftClosure, String> {
+    state.check_permission(permissions::CAJA_SHIFT_APPROVE)?;
+    let conn = state.db.get().map_err(|e| e.to_string())?;
+    let approver = state.get_current_user_id();
+    let note = note.as_deref().map(str::trim).filter(|n| !n.is_empty());
+    approve_shift_closure_on(
+        &conn,
+        closure_id.trim(),
+        note,
+        approver.as_deref(),
+        &chrono::Utc::now().to_rfc3339(),
+    )
+}
 
-    let vehicles_removed: u32 = conn
-        .query_row(
-            "SELECT COUNT(*) FROM vehicles WHERE exit_time > ?1 AND exit_time <= ?2 AND status = 'removed'",
-            params![&since_str, &now_rfc],
-            |row| row.get(0),
-        )
-        .unwrap_or(0);
+/// Tolerancia de descuadre (valor absoluto) sin aprobación. None = nunca se exige.
+fn get_discrepancy_tolerance(conn: &Connection) -> Result<Option<f64>, String> {
+    Ok(db::get_config_value(conn, CONFIG_KEY_DISCREPANCY_TOLERANCE)?
+        .and_then(|v| v.trim().parse::<f64>().ok()))
+}
 
-    let id = id_gen::generate_id(id_gen::PREFIX_SHIFT_CLOSURE);
-    let closed_at = now_rfc.clone();
-    let operator_user_id = state.get_current_user_id();
+#[tauri::command]
+pub fn caja_get_discrepancy_tolerance(state: State<AppState>) -> Result<Option<f64>, String> {
+    state.check_permission(permissions::CAJA_TREASURY_READ)?;
+    let conn = state.db.get().map_err(|e| e.to_string())?;
+    get_discrepancy_tolerance(&conn)
+}
+
+#[tauri::command]
+pub fn caja_set_discrepancy_tolerance(
+    state: State<AppState>,
+    tolerance: Option<f64>,
+) -> Result<Option<f64>, String> {
+    state.check_permission(permissions::CAJA_SHIFT_APPROVE)?;
+    if tolerance.is_some_and(|t| t < 0.0 || !t.is_finite()) {
+        return Err("La tolerancia debe ser un número mayor o igual a 0".to_string());
+    }
+    let conn = state.db.get().map_err(|e| e.to_string())?;
+    let value = tolerance.map(|t| t.to_string()).unwrap_or_default();
+    db::set_config_value(&conn, CONFIG_KEY_DISCREPANCY_TOLERANCE, &value)?;
+    Ok(tolerance)
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CashMovement {
+    pub id: String,
+    /// 'opening_float', 'deposit', 'drop', 'withdrawal' o 'expense'.
+    pub kind: String,
+    pub amount: f64,
+    pub reason: Option<String>,
+    pub created_at: String,
+    pub operator_user_id: Option<String>,
+    pub shift_id: Option<String>,
+}
 
+fn record_cash_movement(
+    conn: &Connection,
+    shift_id: &str,
+    kind: &str,
+    amount: Money,
+    reason: Option<&str>,
+    created_at: &str,
+    operator_user_id: Option<&str>,
+) -> Result<CashMovement, String> {
+    if !CASH_MOVEMENT_KINDS.contains(&kind) {
+        return Err(format!(
+            "Tipo de movimiento inválido: {}. Use opening_float, deposit, drop, withdrawal o expense",
+            kind
+        ));
+    }
+    if amount <= Money::ZERO {
+        return Err("El monto debe ser mayor a 0".to_string());
+    }
+    if kind == "expense" && reason.is_none() {
+        return Err("Indique el motivo del gasto".to_string());
+    }
+    let status: Option<String> = conn
+        .query_row("SELECT status FROM shifts WHERE id = ?1", params![shift_id], |row| row.get(0))
+        .ok();
+    match status.as_deref() {
+        Some("open") => {}
+        Some(_) => return Err("El turno de caja ya está cerrado".to_string()),
+        None => return Err("Turno de caja no encontrado".to_string()),
+    }
+    // Un solo fondo inicial por turno: repetirlo inflaría el efectivo esperado en el cajón.
+    if kind == "opening_float" {
+        let floats: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM cash_movements WHERE shift_id = ?1 AND kind = 'opening_float'",
+                params![shift_id],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+        if floats > 0 {
+            return Err("El turno ya tiene fondo inicial".to_string());
+        }
+    }
+    let id = id_gen::generate_id(id_gen::PREFIX_CASH_MOVEMENT);
     conn.execute(
-        "INSERT INTO shift_closures (id, closed_at, expected_total, cash_total, card_total, transfer_total, arqueo_cash, discrepancy, total_transactions, notes, operator_user_id, debt_total, vehicles_attended, vehicles_with_debt, vehicles_removed) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
-        params![
-            &id,
-            &closed_at,
-            expected_total,
-            cash_total,
-            card_total,
-            transfer_total,
-            arqueo_cash,
-            discrepancy,
-            total_transactions as i64,
-            notes.as_deref(),
-            operator_user_id,
-            debt_total,
-            vehicles_attended as i64,
-            vehicles_with_debt as i64,
-            vehicles_removed as i64,
-        ],
+        "INSERT INTO cash_movements (id, kind, amount, reason, created_at, operator_user_id, shift_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![&id, kind, amount, reason, created_at, operator_user_id, shift_id],
     )
     .map_err(|e| e.to_string())?;
+    Ok(CashMovement {
+        id,
+        kind: kind.to_string(),
+        amount: money::currency(conn)?.major(amount),
+        reason: reason.map(str::to_string),
+        created_at: created_at.to_string(),
+        operator_user_id: operator_user_id.map(str::to_string),
+        shift_id: Some(shift_id.to_string()),
+    })
+}
 
-    Ok(ShiftClosure {
-        id: id.clone(),
-        closed_at: closed_at.clone(),
-        expected_total,
-        cash_total,
-        card_total,
-        transfer_total,
-        arqueo_cash,
-        discrepancy,
-        total_transactions,
-        notes,
-        operator_user_id,
-        debt_total,
-        vehicles_attended,
-        vehicles_with_debt,
-        vehicles_removed,
+/// Movimiento de cajón: fondo, depósito o gasto en el turno abierto del operador, o salida a
+/// bóveda (drop/withdrawal, con permiso de supervisor) del turno abierto indicado en
+/// `shift_id`, que suele ser el de otro cajero.
+#[tauri::command]
+pub fn caja_record_cash_movement(
+    state: State<AppState>,
+    kind: String,
+    amount: f64,
+    reason: Option<String>,
+    shift_id: Option<String>,
+) -> Result<CashMovement, String> {
+    let kind = kind.trim().to_lowercase();
+    let safe_movement = SAFE_MOVEMENT_KINDS.contains(&kind.as_str());
+    if safe_movement {
+        state.check_permission(permissions::CAJA_CASH_WITHDRAW)?;
+    } else {
+        state.check_permission(permissions::CAJA_CASH_MOVEMENTS_CREATE)?;
+    }
+    let conn = state.db.get().map_err(|e| e.to_string())?;
+    let operator_user_id = state.get_current_user_id();
+    let reason = reason.as_deref().map(str::trim).filter(|r| !r.is_empty());
+    let amount = money::currency(&conn)?.money(amount);
+    // El turno se resuelve y se verifica abierto bajo el mismo bloqueo que el INSERT: el
+    // movimiento no puede quedar en un turno que se cierra en paralelo.
+    db::with_transaction(&conn, |conn| {
+        let shift_id = if safe_movement {
+            shift_id
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .ok_or_else(|| "Indique el turno del que sale el efectivo".to_string())?
+        } else {
+            open_shift_id(conn, operator_user_id.as_deref())?
+        };
+        record_cash_movement(
+            conn,
+            &shift_id,
+            &kind,
+            amount,
+            reason,
+            &chrono::Utc::now().to_rfc3339(),
+            operator_user_id.as_deref(),
+        )
     })
 }
 
+/// Movimientos de cajón de un turno; por defecto el turno abierto del operador.
 #[tauri::command]
-pub fn caja_list_shift_closures(
+pub fn caja_list_cash_movements(
     state: State<AppState>,
-    limit: Option<u32>,
-) -> Result<Vec<ShiftClosure>, String> {
+    shift_id: Option<String>,
+) -> Result<Vec<CashMovement>, String> {
     state.check_permission(permissions::CAJA_TREASURY_READ)?;
     let conn = state.db.get().map_err(|e| e.to_string())?;
-    let limit = limit.unwrap_or(50).min(200) as i64;
+    let shift_id = match shift_id.map(|s| s.trim().to_string()).filter(|s| !s.is_empty()) {
+        Some(id) => id,
+        None => match open_shift_for(&conn, state.get_current_user_id().as_deref())? {
+            Some(shift) => shift.id,
+            None => return Ok(Vec::new()),
+        },
+    };
+    let currency = money::currency(&conn)?;
 
     let mut stmt = conn
         .prepare(
-            "SELECT s.id, s.closed_at, s.expected_total, s.cash_total, s.card_total, s.transfer_total, s.arqueo_cash, s.discrepancy, s.total_transactions, s.notes, COALESCE(u.display_name, s.operator_user_id), COALESCE(s.debt_total, 0), COALESCE(s.vehicles_attended, 0), COALESCE(s.vehicles_with_debt, 0), COALESCE(s.vehicles_removed, 0) FROM shift_closures s LEFT JOIN users u ON s.operator_user_id = u.id ORDER BY s.closed_at DESC LIMIT ?1",
+            "SELECT m.id, m.kind, m.amount, m.reason, m.created_at, COALESCE(u.display_name, m.operator_user_id), m.shift_id FROM cash_movements m LEFT JOIN users u ON m.operator_user_id = u.id WHERE m.shift_id = ?1 ORDER BY m.created_at",
         )
         .map_err(|e| e.to_string())?;
     let rows = stmt
-        .query_map(params![limit], |row| {
-            Ok(ShiftClosure {
+        .query_map(params![&shift_id], |row| {
+            Ok(CashMovement {
                 id: row.get(0)?,
-                closed_at: row.get(1)?,
-                expected_total: row.get(2)?,
-                cash_total: row.get(3)?,
-                card_total: row.get(4)?,
-                transfer_total: row.get(5)?,
-                arqueo_cash: row.get(6)?,
-                discrepancy: row.get(7)?,
-                total_transactions: row.get::<_, i64>(8)? as u32,
-                notes: row.get(9)?,
-                operator_user_id: row.get(10)?,
-                debt_total: row.get(11)?,
-                vehicles_attended: row.get::<_, i64>(12)? as u32,
-                vehicles_with_debt: row.get::<_, i64>(13)? as u32,
-                vehicles_removed: row.get::<_, i64>(14)? as u32,
+                kind: row.get(1)?,
+                amount: currency.major(row.get(2)?),
+                reason: row.get(3)?,
+                created_at: row.get(4)?,
+                operator_user_id: row.get(5)?,
+                shift_id: row.get(6)?,
             })
         })
         .map_err(|e| e.to_string())?;
@@ -414,3 +1059,171 @@ pub fn caja_list_shift_closures(
     }
     Ok(list)
 }
+
+/// Abono de deuda de una placa sin pasar un vehículo por la salida (oldest-first).
+#[tauri::command]
+pub fn caja_pay_debt(
+    state: State<AppState>,
+    plate: String,
+    amount: f64,
+    method: String,
+) -> Result<crate::domains::debts::DebtPaymentResult, String> {
+    state.check_permission(permissions::CAJA_DEBT_PAYMENT_CREATE)?;
+    let conn = state.db.get().map_err(|e| e.to_string())?;
+    let operator_user_id = state.get_current_user_id();
+    crate::domains::debts::pay_plate_debt(
+        &conn,
+        &plate,
+        money::currency(&conn)?.money(amount),
+        &method.trim().to_lowercase(),
+        operator_user_id.as_deref(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cop(amount: i64) -> Money {
+        Money::from_minor(amount)
+    }
+
+    fn at(ts: &str) -> chrono::DateTime<chrono::Utc> {
+        chrono::DateTime::parse_from_rfc3339(ts).unwrap().with_timezone(&chrono::Utc)
+    }
+
+    #[test]
+    fn close_shift_counts_drawer_and_requires_approval_over_tolerance() {
+        let conn = Connection::open_in_memory().unwrap();
+        db::run_migrations(&conn).unwrap();
+        let shift = open_shift_on(&conn, "cashier", "A", Some(cop(50000)), "2025-01-05T07:00:00+00:00").unwrap();
+        let other = open_shift_on(&conn, "other", "B", None, "2025-01-05T07:30:00+00:00").unwrap();
+        assert!(open_shift_on(&conn, "third", "A", None, "2025-01-05T08:00:00+00:00").is_err());
+        assert!(open_shift_on(&conn, "cashier", "C", None, "2025-01-05T08:00:00+00:00").is_err());
+        conn.execute_batch(&format!(
+            "INSERT INTO vehicles (id, ticket_code, plate, plate_upper, vehicle_type, entry_time, exit_time, status, tariff_kind, exit_shift_id) VALUES
+                ('v1', 'T1', 'ABC123', 'ABC123', 'car', '2025-01-05T08:00:00+00:00', '2025-01-05T09:00:00+00:00', 'completed', 'regular', '{a}'),
+                ('v2', 'T2', 'XYZ789', 'XYZ789', 'car', '2025-01-05T08:00:00+00:00', '2025-01-05T09:00:00+00:00', 'completed', 'regular', '{b}');
+             INSERT INTO transactions (id, vehicle_id, amount, method, created_at, shift_id) VALUES
+                ('tx1', 'v1', 10000, 'cash', '2025-01-05T09:00:00+00:00', '{a}'),
+                ('tx2', 'v1', 4000, 'card', '2025-01-05T09:05:00+00:00', '{a}'),
+                ('tx3', 'v2', 7000, 'cash', '2025-01-05T09:00:00+00:00', '{b}');",
+            a = shift.id,
+            b = other.id
+        ))
+        .unwrap();
+        record_cash_movement(&conn, &shift.id, "drop", cop(20000), Some("bóveda"), "2025-01-05T10:00:00+00:00", None).unwrap();
+        assert!(record_cash_movement(&conn, &shift.id, "tip", cop(1000), None, "2025-01-05T10:00:00+00:00", None).is_err());
+        assert!(
+            record_cash_movement(&conn, &shift.id, "opening_float", cop(1000), None, "2025-01-05T10:00:00+00:00", None)
+                .is_err()
+        );
+        record_cash_movement(&conn, &other.id, "opening_float", cop(1000), None, "2025-01-05T10:00:00+00:00", None).unwrap();
+        db::set_config_value(&conn, CONFIG_KEY_DISCREPANCY_TOLERANCE, "500").unwrap();
+
+        // 50000 + 10000 - 20000 = 40000 esperados; se cuentan 39000. El cobro de la caja B no cuenta.
+        let counted = vec![
+            DenominationCount { denomination: 10000.0, quantity: 3 },
+            DenominationCount { denomination: 1000.0, quantity: 9 },
+        ];
+        assert_eq!(counted_cash(&conn, &Scope::Shift(&shift.id)).unwrap(), None);
+        assert!(close_shift_on(
+            &conn,
+            &shift,
+            at("2025-01-06T01:00:00+00:00"),
+            Some(cop(1)),
+            Some(counted.clone()),
+            None,
+            Some("cashier".to_string()),
+        )
+        .is_err());
+        let closure = close_shift_on(
+            &conn,
+            &shift,
+            at("2025-01-06T01:00:00+00:00"),
+            Some(cop(39000)),
+            Some(counted),
+            None,
+            Some("cashier".to_string()),
+        )
+        .unwrap();
+        assert_eq!(closure.cash_total, 10000.0);
+        assert_eq!(closure.total_transactions, 2);
+        assert_eq!(closure.vehicles_attended, 1);
+        assert_eq!(closure.expected_drawer_cash, 40000.0);
+        assert_eq!(closure.arqueo_cash, Some(39000.0));
+        assert_eq!(closure.discrepancy, -1000.0);
+        assert_eq!(closure.status, "pending_approval");
+        assert_eq!(counted_cash(&conn, &Scope::Shift(&shift.id)).unwrap(), Some((cop(39000), cop(-1000))));
+        let (day_start, day_end) = ("2025-01-05T05:00:00.000Z", "2025-01-06T05:00:00.000Z");
+        assert_eq!(
+            counted_cash(&conn, &Scope::Window(day_start, day_end)).unwrap(),
+            Some((cop(39000), cop(-1000)))
+        );
+        assert!(open_shift_for(&conn, Some("cashier")).unwrap().is_none());
+        assert!(record_cash_movement(&conn, &shift.id, "drop", cop(1000), None, "2025-01-06T01:10:00+00:00", None).is_err());
+        assert!(open_shift_id(&conn, Some("cashier")).is_err());
+        assert_eq!(open_shift_id(&conn, Some("other")).unwrap(), other.id);
+
+        assert!(open_shift_on(&conn, "cashier", "C", None, "2025-01-06T01:02:00+00:00").is_err());
+        assert!(open_shift_on(&conn, "third", "A", None, "2025-01-06T01:02:00+00:00").is_err());
+        assert!(approve_shift_closure_on(&conn, &closure.id, None, Some("cashier"), "2025-01-06T01:05:00+00:00").is_err());
+        let approved =
+            approve_shift_closure_on(&conn, &closure.id, Some("faltante"), Some("supervisor"), "2025-01-06T01:05:00+00:00")
+                .unwrap();
+        assert_eq!(approved.status, "approved");
+        assert_eq!(approved.denominations.len(), 2);
+        assert_eq!(approved.approval_note.as_deref(), Some("faltante"));
+        assert_eq!(approved.booth.as_deref(), Some("A"));
+        assert!(open_shift_on(&conn, "cashier", "A", None, "2025-01-06T02:00:00+00:00").is_ok());
+    }
+
+    #[test]
+    fn expenses_and_deposits_move_expected_drawer_cash() {
+        let conn = Connection::open_in_memory().unwrap();
+        db::run_migrations(&conn).unwrap();
+        let shift = open_shift_on(&conn, "cashier", "A", Some(cop(20000)), "2025-01-05T07:00:00+00:00").unwrap();
+        record_cash_movement(&conn, &shift.id, "deposit", cop(5000), Some("sencillo"), "2025-01-05T08:00:00+00:00", None).unwrap();
+        record_cash_movement(&conn, &shift.id, "expense", cop(3000), Some("papelería"), "2025-01-05T09:00:00+00:00", None).unwrap();
+        record_cash_movement(&conn, &shift.id, "withdrawal", cop(10000), None, "2025-01-05T10:00:00+00:00", None).unwrap();
+        assert!(record_cash_movement(&conn, &shift.id, "expense", cop(1000), None, "2025-01-05T10:00:00+00:00", None).is_err());
+        assert!(record_cash_movement(&conn, "missing", "drop", cop(1000), None, "2025-01-05T10:00:00+00:00", None).is_err());
+
+        let totals = cash_totals(&conn, &Scope::Shift(&shift.id)).unwrap();
+        assert_eq!(totals.cash_deposits, cop(5000));
+        assert_eq!(totals.cash_expenses, cop(3000));
+        assert_eq!(totals.cash_drops, cop(10000));
+        // 20000 + 5000 - 3000 - 10000
+        assert_eq!(totals.expected_drawer_cash(), cop(12000));
+    }
+
+    #[test]
+    fn contract_payments_count_in_the_method_breakdown_of_their_shift() {
+        let conn = Connection::open_in_memory().unwrap();
+        db::run_migrations(&conn).unwrap();
+        let shift = open_shift_on(&conn, "cashier", "A", None, "2025-01-05T07:00:00+00:00").unwrap();
+        conn.execute_batch(&format!(
+            "INSERT INTO vehicles (id, ticket_code, plate, plate_upper, vehicle_type, entry_time, exit_time, status, tariff_kind, exit_shift_id) VALUES
+                ('v1', 'T1', 'ABC123', 'ABC123', 'car', '2025-01-05T08:00:00+00:00', '2025-01-05T09:00:00+00:00', 'completed', 'regular', '{s}');
+             INSERT INTO transactions (id, vehicle_id, amount, method, created_at, shift_id) VALUES
+                ('tx1', 'v1', 6000, 'cash', '2025-01-05T09:00:00+00:00', '{s}');
+             INSERT INTO contracts (id, client_name, plate, plate_upper, vehicle_type, tariff_kind, monthly_amount, included_hours_per_day, date_from, date_to, status, created_at)
+             VALUES ('CO1', 'A', 'AAA111', 'AAA111', 'car', 'none', 100000, 6, '2025-01-01', '2025-01-01', 'active', '2025-01-01T00:00:00+00:00');
+             INSERT INTO contract_payments (id, contract_id, amount, method, period_from, period_to, created_at, shift_id) VALUES
+                ('P1', 'CO1', 100000, 'transfer', '2025-01-01', '2025-01-31', '2025-01-05T10:00:00+00:00', '{s}'),
+                ('P2', 'CO1', 20000, 'cash', '2025-02-01', '2025-02-06', '2025-01-05T11:00:00+00:00', '{s}'),
+                ('P3', 'CO1', 50000, 'cash', '2025-02-07', '2025-02-20', '2025-01-04T11:00:00+00:00', NULL);",
+            s = shift.id
+        ))
+        .unwrap();
+
+        let totals = cash_totals(&conn, &Scope::Shift(&shift.id)).unwrap();
+        assert_eq!(totals.cash, cop(26000));
+        assert_eq!(totals.transfer, cop(100000));
+        assert_eq!(totals.contract_payments_total, cop(120000));
+        assert_eq!(totals.expected_total(), cop(126000));
+        assert_eq!(totals.expected_drawer_cash(), cop(26000));
+        // Los pagos de contrato no son transacciones de parqueo.
+        assert_eq!(totals.total_transactions, 1);
+    }
+}
@@ -0,0 +1,360 @@
+//! Lot capacity: number of spaces per vehicle type, optionally split by zone.
+//! Occupancy is derived from active vehicles (per zone, through the space they occupy);
+//! register_entry checks it (warn or reject, see `capacity_enforcement` in drive_config) and
+//! metrics use it for occupancy rates.
+
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::db::{get_config_value, set_config_value};
+use crate::permissions;
+use crate::state::AppState;
+
+const CONFIG_KEY_ENFORCEMENT: &str = "capacity_enforcement";
+const ENFORCEMENT_WARN: &str = "warn";
+const ENFORCEMENT_REJECT: &str = "reject";
+pub(crate) const VEHICLE_TYPES: &[&str] = &["car", "motorcycle", "truck", "bicycle"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CapacityZone {
+    pub vehicle_type: String,
+    /// Empty string = general capacity for the vehicle type (no zone).
+    pub zone: String,
+    pub spaces: i64,
+    /// Active vehicles of the type parked in a space of the zone (for '', those without a zone).
+    pub occupied: i64,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CapacityTypeStatus {
+    pub vehicle_type: String,
+    /// None when no capacity is configured for this type (unlimited).
+    pub capacity: Option<i64>,
+    pub occupied: i64,
+    pub available: Option<i64>,
+    /// Percentage 0–100; 0 when capacity is not configured.
+    pub occupancy_rate: f64,
+    pub is_full: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CapacityStatus {
+    pub enforcement: String,
+    pub total_capacity: i64,
+    pub total_occupied: i64,
+    pub occupancy_rate: f64,
+    pub by_vehicle_type: Vec<CapacityTypeStatus>,
+    pub zones: Vec<CapacityZone>,
+}
+
+/// Occupancy as a percentage of capacity (0 when capacity is 0 / not configured).
+pub fn occupancy_rate(occupied: f64, capacity: i64) -> f64 {
+    if capacity <= 0 {
+        return 0.0;
+    }
+    (occupied / capacity as f64 * 100.0 * 100.0).round() / 100.0
+}
+
+/// Sum of spaces for a vehicle type across all zones. None if the type has no capacity rows.
+pub fn capacity_for_type(conn: &Connection, vehicle_type: &str) -> Result<Option<i64>, String> {
+    conn.query_row(
+        "SELECT SUM(spaces) FROM lot_capacity WHERE vehicle_type = ?1",
+        params![vehicle_type],
+        |row| row.get::<_, Option<i64>>(0),
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Total configured spaces (all types, all zones).
+pub fn total_capacity(conn: &Connection) -> Result<i64, String> {
+    conn.query_row("SELECT COALESCE(SUM(spaces), 0) FROM lot_capacity", [], |row| row.get(0))
+        .map_err(|e| e.to_string())
+}
+
+fn occupied_for_type(conn: &Connection, vehicle_type: &str) -> Result<i64, String> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM vehicles WHERE status = 'active' AND vehicle_type = ?1",
+        params![vehicle_type],
+        |row| row.get(0),
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Spaces configured for a (vehicle type, zone) pair. None if the pair has no row.
+fn capacity_for_zone(conn: &Connection, vehicle_type: &str, zone: &str) -> Option<i64> {
+    conn.query_row(
+        "SELECT spaces FROM lot_capacity WHERE vehicle_type = ?1 AND zone = ?2",
+        params![vehicle_type, zone],
+        |row| row.get(0),
+    )
+    .ok()
+}
+
+/// Active vehicles of the type whose space is in `zone`; zone '' counts those parked without one.
+fn occupied_for_zone(conn: &Connection, vehicle_type: &str, zone: &str) -> Result<i64, String> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM vehicles v LEFT JOIN spaces s ON s.id = v.space_id
+         WHERE v.status = 'active' AND v.vehicle_type = ?1 AND COALESCE(s.zone, '') = ?2",
+        params![vehicle_type, zone],
+        |row| row.get(0),
+    )
+    .map_err(|e| e.to_string())
+}
+
+fn get_enforcement(conn: &Connection) -> Result<String, String> {
+    Ok(get_config_value(conn, CONFIG_KEY_ENFORCEMENT)?
+        .filter(|v| v == ENFORCEMENT_WARN || v == ENFORCEMENT_REJECT)
+        .unwrap_or_else(|| ENFORCEMENT_WARN.to_string()))
+}
+
+/// Called by register_entry (inside its transaction, before inserting) with the space the
+/// vehicle is about to take. Checks the type's total capacity and, when the space's zone has
+/// its own row, the zone's. Returns Err when full and enforcement is "reject";
+/// Ok(Some(warning)) when full and enforcement is "warn".
+pub fn check_entry_capacity(
+    conn: &Connection,
+    vehicle_type: &str,
+    space_id: Option<&str>,
+) -> Result<Option<String>, String> {
+    let mut message = None;
+    if let Some(capacity) = capacity_for_type(conn, vehicle_type)? {
+        let occupied = occupied_for_type(conn, vehicle_type)?;
+        if occupied >= capacity {
+            message = Some(format!(
+                "Capacidad completa para {}: {} de {} espacios ocupados",
+                vehicle_type, occupied, capacity
+            ));
+        }
+    }
+    let zone: Option<String> = match space_id {
+        Some(sid) => Some(
+            conn.query_row("SELECT zone FROM spaces WHERE id = ?1", params![sid], |row| row.get(0))
+                .map_err(|e| e.to_string())?,
+        ),
+        None => None,
+    };
+    if let Some(zone) = zone.filter(|z| !z.is_empty() && message.is_none()) {
+        if let Some(capacity) = capacity_for_zone(conn, vehicle_type, &zone) {
+            let occupied = occupied_for_zone(conn, vehicle_type, &zone)?;
+            if occupied >= capacity {
+                message = Some(format!(
+                    "Capacidad completa para {} en la zona {}: {} de {} espacios ocupados",
+                    vehicle_type, zone, occupied, capacity
+                ));
+            }
+        }
+    }
+    match message {
+        Some(m) if get_enforcement(conn)? == ENFORCEMENT_REJECT => Err(m),
+        other => Ok(other),
+    }
+}
+
+fn list_zones(conn: &Connection) -> Result<Vec<CapacityZone>, String> {
+    let mut stmt = conn
+        .prepare("SELECT vehicle_type, zone, spaces, updated_at FROM lot_capacity ORDER BY vehicle_type, zone")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(CapacityZone {
+                vehicle_type: row.get(0)?,
+                zone: row.get(1)?,
+                spaces: row.get(2)?,
+                occupied: 0,
+                updated_at: row.get(3)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    let mut zones = rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())?;
+    for zone in &mut zones {
+        zone.occupied = occupied_for_zone(conn, &zone.vehicle_type, &zone.zone)?;
+    }
+    Ok(zones)
+}
+
+#[tauri::command]
+pub fn capacity_get_status(state: State<AppState>) -> Result<CapacityStatus, String> {
+    state.check_permission(permissions::CAPACITY_READ)?;
+    let conn = state.db.get().map_err(|e| e.to_string())?;
+
+    let mut by_vehicle_type = Vec::with_capacity(VEHICLE_TYPES.len());
+    let mut total_occupied = 0_i64;
+    for vt in VEHICLE_TYPES {
+        let capacity = capacity_for_type(&conn, vt)?;
+        let occupied = occupied_for_type(&conn, vt)?;
+        total_occupied += occupied;
+        by_vehicle_type.push(CapacityTypeStatus {
+            vehicle_type: vt.to_string(),
+            capacity,
+            occupied,
+            available: capacity.map(|c| (c - occupied).max(0)),
+            occupancy_rate: occupancy_rate(occupied as f64, capacity.unwrap_or(0)),
+            is_full: capacity.map(|c| occupied >= c).unwrap_or(false),
+        });
+    }
+    let total_capacity = total_capacity(&conn)?;
+
+    Ok(CapacityStatus {
+        enforcement: get_enforcement(&conn)?,
+        total_capacity,
+        total_occupied,
+        occupancy_rate: occupancy_rate(total_occupied as f64, total_capacity),
+        by_vehicle_type,
+        zones: list_zones(&conn)?,
+    })
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CapacitySetZoneArgs {
+    pub vehicle_type: String,
+    pub zone: Option<String>,
+    pub spaces: i64,
+}
+
+/// Creates or replaces the capacity of a (vehicle type, zone) pair.
+#[tauri::command]
+pub fn capacity_set_zone(state: State<AppState>, args: CapacitySetZoneArgs) -> Result<CapacityZone, String> {
+    state.check_permission(permissions::CAPACITY_MODIFY)?;
+    let vehicle_type = args.vehicle_type.trim().to_lowercase();
+    if !VEHICLE_TYPES.contains(&vehicle_type.as_str()) {
+        return Err(format!("Invalid vehicle type: {}", args.vehicle_type));
+    }
+    if args.spaces < 0 {
+        return Err("Spaces must be >= 0".to_string());
+    }
+    let zone = args.zone.as_deref().map(str::trim).unwrap_or("").to_string();
+    let now = chrono::Utc::now().to_rfc3339();
+    let conn = state.db.get().map_err(|e| e.to_string())?;
+    let occupied = occupied_for_zone(&conn, &vehicle_type, &zone)?;
+    conn.execute(
+        "INSERT INTO lot_capacity (vehicle_type, zone, spaces, updated_at) VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(vehicle_type, zone) DO UPDATE SET spaces = ?3, updated_at = ?4",
+        params![vehicle_type, zone, args.spaces, now],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(CapacityZone {
+        vehicle_type,
+        zone,
+        spaces: args.spaces,
+        occupied,
+        updated_at: now,
+    })
+}
+
+#[tauri::command]
+pub fn capacity_delete_zone(
+    state: State<AppState>,
+    vehicle_type: String,
+    zone: Option<String>,
+) -> Result<(), String> {
+    state.check_permission(permissions::CAPACITY_MODIFY)?;
+    let conn = state.db.get().map_err(|e| e.to_string())?;
+    let zone = zone.as_deref().map(str::trim).unwrap_or("").to_string();
+    let n = conn
+        .execute(
+            "DELETE FROM lot_capacity WHERE vehicle_type = ?1 AND zone = ?2",
+            params![vehicle_type.trim().to_lowercase(), zone],
+        )
+        .map_err(|e| e.to_string())?;
+    if n == 0 {
+        return Err("Capacity zone not found".to_string());
+    }
+    Ok(())
+}
+
+/// Sets what happens when a vehicle type is full at entry: "warn" (default) or "reject".
+#[tauri::command]
+pub fn capacity_set_enforcement(state: State<AppState>, mode: String) -> Result<String, String> {
+    state.check_permission(permissions::CAPACITY_MODIFY)?;
+    let mode = mode.trim().to_lowercase();
+    if mode != ENFORCEMENT_WARN && mode != ENFORCEMENT_REJECT {
+        return Err("Enforcement must be 'warn' or 'reject'".to_string());
+    }
+    let conn = state.db.get().map_err(|e| e.to_string())?;
+    set_config_value(&conn, CONFIG_KEY_ENFORCEMENT, &mode)?;
+    Ok(mode)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn migrated_conn() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::run_migrations(&conn).unwrap();
+        conn
+    }
+
+    fn set_capacity(conn: &Connection, vehicle_type: &str, zone: &str, spaces: i64) {
+        conn.execute(
+            "INSERT INTO lot_capacity (vehicle_type, zone, spaces, updated_at) VALUES (?1, ?2, ?3, 'x')",
+            params![vehicle_type, zone, spaces],
+        )
+        .unwrap();
+    }
+
+    fn add_space(conn: &Connection, id: &str, zone: &str) {
+        conn.execute(
+            "INSERT INTO spaces (id, code, zone, allowed_vehicle_types, status, created_at) VALUES (?1, ?1, ?2, 'car', 'available', 'x')",
+            params![id, zone],
+        )
+        .unwrap();
+    }
+
+    fn park(conn: &Connection, id: &str, space_id: Option<&str>) {
+        conn.execute(
+            "INSERT INTO vehicles (id, ticket_code, plate, plate_upper, vehicle_type, entry_time, status, space_id) VALUES (?1, ?1, ?1, ?1, 'car', 'x', 'active', ?2)",
+            params![id, space_id],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn zones_count_the_vehicles_parked_in_their_spaces() {
+        let conn = migrated_conn();
+        set_capacity(&conn, "car", "A", 1);
+        set_capacity(&conn, "car", "B", 5);
+        add_space(&conn, "a1", "A");
+        add_space(&conn, "a2", "A");
+        add_space(&conn, "b1", "B");
+        park(&conn, "v1", Some("a1"));
+        park(&conn, "v2", None);
+
+        let zones = list_zones(&conn).unwrap();
+        let occupied: Vec<(&str, i64)> = zones.iter().map(|z| (z.zone.as_str(), z.occupied)).collect();
+        assert_eq!(occupied, vec![("A", 1), ("B", 0)]);
+        assert_eq!(occupied_for_zone(&conn, "car", "").unwrap(), 1);
+
+        // The type still has room (2 of 6) but zone A is full.
+        let warning = check_entry_capacity(&conn, "car", Some("a2")).unwrap();
+        assert!(warning.unwrap().contains("zona A"));
+        assert_eq!(check_entry_capacity(&conn, "car", Some("b1")).unwrap(), None);
+        assert_eq!(check_entry_capacity(&conn, "car", None).unwrap(), None);
+
+        set_config_value(&conn, CONFIG_KEY_ENFORCEMENT, ENFORCEMENT_REJECT).unwrap();
+        assert!(check_entry_capacity(&conn, "car", Some("a2")).is_err());
+        assert_eq!(check_entry_capacity(&conn, "car", Some("b1")).unwrap(), None);
+    }
+
+    #[test]
+    fn a_full_vehicle_type_warns_or_rejects() {
+        let conn = migrated_conn();
+        assert_eq!(check_entry_capacity(&conn, "car", None).unwrap(), None);
+        set_capacity(&conn, "car", "", 1);
+        park(&conn, "v1", None);
+        assert!(check_entry_capacity(&conn, "car", None).unwrap().is_some());
+        assert_eq!(check_entry_capacity(&conn, "motorcycle", None).unwrap(), None);
+
+        set_config_value(&conn, CONFIG_KEY_ENFORCEMENT, ENFORCEMENT_REJECT).unwrap();
+        assert_eq!(
+            check_entry_capacity(&conn, "car", None).unwrap_err(),
+            "Capacidad completa para car: 1 de 1 espacios ocupados"
+        );
+    }
+}
@@ -1,8 +1,12 @@
+use std::time::Duration;
+
 use rusqlite::params;
 use serde::{Deserialize, Serialize};
-use tauri::State;
+use tauri::{AppHandle, Manager, State};
 
+use crate::domains::settings;
 use crate::id_gen;
+use crate::money::{Currency, Money};
 use crate::permissions;
 use crate::state::AppState;
 
@@ -33,55 +37,95 @@ pub struct Contract {
     pub cancellation_reason: Option<String>,
     pub last_payment_date: Option<String>,
     pub end_date: Option<String>,
+    /// When the end date is reached, the term is extended one billing period instead of expiring.
+    pub auto_renew: bool,
+    /// Every plate covered by the contract (uppercase); `plate` is the main one.
+    pub plates: Vec<String>,
+    /// How many of `plates` may be parked at the same time under the contract.
+    pub max_simultaneous_vehicles: i64,
+    /// Amount paid but not yet applied to a period (partial payments).
+    pub credit_balance: f64,
+    /// End of the next period when it is shorter than a full one (prorated first period).
+    pub next_period_end: Option<String>,
+    /// Plan change scheduled for the period starting on `pending_effective_date`.
+    pub pending_monthly_amount: Option<f64>,
+    pub pending_billing_period_days: Option<i64>,
+    pub pending_effective_date: Option<String>,
+}
+
+/// Today's date (YYYY-MM-DD) in the business timezone; contract dates are business dates.
+fn business_today(conn: &rusqlite::Connection) -> Result<String, String> {
+    let tz = settings::business_tz(conn)?;
+    Ok(settings::today_in(tz).format("%Y-%m-%d").to_string())
 }
 
-fn is_in_arrears(status: &str, date_to: &str, end_date: Option<&str>) -> bool {
+fn is_in_arrears(status: &str, date_to: &str, end_date: Option<&str>, today: &str) -> bool {
     if status == "cancelled" {
         return false;
     }
     // If the contract has a culmination date and it has passed, billing stops — not arrears.
-    let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
     if let Some(ed) = end_date {
-        if !ed.is_empty() && ed <= today.as_str() {
+        if !ed.is_empty() && ed <= today {
             return false;
         }
     }
     if status == "arrears" {
         return true;
     }
-    date_to < today.as_str()
+    date_to < today
 }
 
-fn row_to_contract(row: &rusqlite::Row) -> rusqlite::Result<Contract> {
+fn row_to_contract(row: &rusqlite::Row, today: &str, currency: &Currency) -> rusqlite::Result<Contract> {
+    let major = |col: &str| -> rusqlite::Result<Option<f64>> {
+        Ok(row.get::<_, Option<Money>>(col)?.map(|m| currency.major(m)))
+    };
     let status: String = row.get("status")?;
     let date_to: String = row.get("date_to")?;
     let end_date: Option<String> = row.get("end_date").unwrap_or(None);
-    let arrears = is_in_arrears(&status, &date_to, end_date.as_deref());
+    let arrears = is_in_arrears(&status, &date_to, end_date.as_deref(), today);
+    let plate_upper: String = row.get("plate_upper")?;
+    let mut plates: Vec<String> = row
+        .get::<_, Option<String>>("plates")
+        .unwrap_or(None)
+        .map(|list| list.split(',').filter(|p| !p.is_empty()).map(String::from).collect())
+        .unwrap_or_default();
+    plates.sort_by_key(|p| (*p != plate_upper, p.clone()));
+    if plates.is_empty() {
+        plates.push(plate_upper.clone());
+    }
     Ok(Contract {
         id: row.get("id")?,
         client_name: row.get("client_name")?,
         client_phone: row.get("client_phone")?,
         plate: row.get("plate")?,
-        plate_upper: row.get("plate_upper")?,
+        plate_upper,
         vehicle_type: row.get("vehicle_type")?,
         tariff_kind: row.get("tariff_kind")?,
-        monthly_amount: row.get("monthly_amount")?,
+        monthly_amount: major("monthly_amount")?.unwrap_or(0.0),
         included_hours_per_day: row.get("included_hours_per_day")?,
         date_from: row.get("date_from")?,
         date_to,
         status,
         created_at: row.get("created_at")?,
         notes: row.get("notes")?,
-        extra_charge_first: row.get("extra_charge_first")?,
-        extra_charge_repeat: row.get("extra_charge_repeat")?,
+        extra_charge_first: major("extra_charge_first")?,
+        extra_charge_repeat: major("extra_charge_repeat")?,
         extra_interval: row.get("extra_interval")?,
         is_in_arrears: arrears,
         billing_period_days: row.get("billing_period_days").unwrap_or(30),
         cancelled_at: row.get("cancelled_at").unwrap_or(None),
         cancellation_reason: row.get("cancellation_reason").unwrap_or(None),
         last_payment_date: row.get("last_payment_date").unwrap_or(None),
-        extra_charge_per_interval: row.get("extra_charge_per_interval").unwrap_or(None),
+        extra_charge_per_interval: major("extra_charge_per_interval").unwrap_or(None),
         end_date,
+        auto_renew: row.get::<_, i64>("auto_renew").unwrap_or(0) != 0,
+        plates,
+        max_simultaneous_vehicles: row.get("max_simultaneous_vehicles").unwrap_or(1),
+        credit_balance: major("credit_balance").unwrap_or(None).unwrap_or(0.0),
+        next_period_end: row.get("next_period_end").unwrap_or(None),
+        pending_monthly_amount: major("pending_monthly_amount").unwrap_or(None),
+        pending_billing_period_days: row.get("pending_billing_period_days").unwrap_or(None),
+        pending_effective_date: row.get("pending_effective_date").unwrap_or(None),
     })
 }
 
@@ -91,19 +135,23 @@ const CONTRACT_COLS: &str = r#"
     date_from, date_to, status, created_at, notes,
     extra_charge_first, extra_charge_repeat, extra_interval,
     billing_period_days, cancelled_at, cancellation_reason, last_payment_date,
-    extra_charge_per_interval, end_date
+    extra_charge_per_interval, end_date, auto_renew, max_simultaneous_vehicles,
+    credit_balance, next_period_end,
+    pending_monthly_amount, pending_billing_period_days, pending_effective_date,
+    (SELECT GROUP_CONCAT(cp.plate_upper, ',') FROM contract_plates cp WHERE cp.contract_id = contracts.id) AS plates
 "#;
 
 pub fn find_active_contract_for_plate(
     conn: &rusqlite::Connection,
     plate_upper: &str,
 ) -> Option<Contract> {
-    let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
+    let today = business_today(conn).ok()?;
+    let currency = crate::money::currency(conn).ok()?;
     conn.query_row(
         &format!(
             r#"SELECT {CONTRACT_COLS}
                FROM contracts
-               WHERE plate_upper = ?1
+               WHERE id IN (SELECT contract_id FROM contract_plates WHERE plate_upper = ?1)
                  AND status = 'active'
                  AND date_from <= ?2
                  AND date_to >= ?2
@@ -111,7 +159,7 @@ pub fn find_active_contract_for_plate(
                LIMIT 1"#
         ),
         params![plate_upper, today],
-        |row| row_to_contract(row),
+        |row| row_to_contract(row, &today, &currency),
     )
     .ok()
 }
@@ -120,21 +168,106 @@ pub fn find_any_contract_for_plate(
     conn: &rusqlite::Connection,
     plate_upper: &str,
 ) -> Option<Contract> {
+    let today = business_today(conn).ok()?;
+    let currency = crate::money::currency(conn).ok()?;
     conn.query_row(
         &format!(
             r#"SELECT {CONTRACT_COLS}
                FROM contracts
-               WHERE plate_upper = ?1
+               WHERE id IN (SELECT contract_id FROM contract_plates WHERE plate_upper = ?1)
                  AND status IN ('active', 'arrears')
                ORDER BY date_to DESC
                LIMIT 1"#
         ),
         params![plate_upper],
-        |row| row_to_contract(row),
+        |row| row_to_contract(row, &today, &currency),
     )
     .ok()
 }
 
+/// Whether the vehicle `vehicle_id` (entered at `entry_time`) fits in the contract's quota of
+/// simultaneous vehicles: the slots go to the contract plates that entered first, so a vehicle
+/// that arrived while the quota was full is charged the regular tariff.
+pub fn has_free_slot(
+    conn: &rusqlite::Connection,
+    contract: &Contract,
+    vehicle_id: &str,
+    entry_time: &str,
+) -> Result<bool, String> {
+    let parked_before: i64 = conn
+        .query_row(
+            r#"SELECT COUNT(*) FROM vehicles
+               WHERE plate_upper IN (SELECT plate_upper FROM contract_plates WHERE contract_id = ?1)
+                 AND id != ?2
+                 AND status IN ('active', 'completed')
+                 AND (entry_time < ?3 OR (entry_time = ?3 AND id < ?2))
+                 AND (exit_time IS NULL OR exit_time > ?3)"#,
+            params![contract.id, vehicle_id, entry_time],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    Ok(parked_before < contract.max_simultaneous_vehicles.max(1))
+}
+
+/// Uppercased, de-duplicated plates with `main` first.
+fn normalize_plates(main: &str, others: &[String]) -> Vec<String> {
+    let mut plates = vec![main.to_string()];
+    for p in others.iter().map(|p| p.trim().to_uppercase()).filter(|p| !p.is_empty()) {
+        if !plates.contains(&p) {
+            plates.push(p);
+        }
+    }
+    plates
+}
+
+/// First plate already covered by another active or in-arrears contract.
+fn plate_in_other_contract(
+    conn: &rusqlite::Connection,
+    plates: &[String],
+    contract_id: Option<&str>,
+) -> Result<Option<String>, String> {
+    for plate in plates {
+        let taken: i64 = conn
+            .query_row(
+                r#"SELECT COUNT(*) FROM contract_plates cp
+                   JOIN contracts c ON c.id = cp.contract_id
+                   WHERE cp.plate_upper = ?1 AND c.status IN ('active', 'arrears')
+                     AND c.id != COALESCE(?2, '')"#,
+                params![plate, contract_id],
+                |r| r.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+        if taken > 0 {
+            return Ok(Some(plate.clone()));
+        }
+    }
+    Ok(None)
+}
+
+fn validate_quota(max_simultaneous: i64, plates: &[String]) -> Result<(), String> {
+    if max_simultaneous < 1 || max_simultaneous as usize > plates.len() {
+        return Err(format!(
+            "max_simultaneous_vehicles must be between 1 and the number of plates ({})",
+            plates.len()
+        ));
+    }
+    Ok(())
+}
+
+/// Replaces the plate set of a contract.
+fn save_plates(conn: &rusqlite::Connection, contract_id: &str, plates: &[String], at: &str) -> Result<(), String> {
+    conn.execute("DELETE FROM contract_plates WHERE contract_id = ?1", params![contract_id])
+        .map_err(|e| e.to_string())?;
+    for plate in plates {
+        conn.execute(
+            "INSERT INTO contract_plates (contract_id, plate_upper, added_at) VALUES (?1, ?2, ?3)",
+            params![contract_id, plate, at],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
 const VALID_VEHICLE_TYPES: &[&str] = &["car", "motorcycle", "truck", "bicycle"];
 const VALID_TARIFF_KINDS: &[&str] = &["none", "employee", "student"];
 const VALID_STATUSES: &[&str] = &["active", "expired", "cancelled", "arrears"];
@@ -145,6 +278,10 @@ pub struct CreateContractArgs {
     client_name: String,
     client_phone: Option<String>,
     plate: String,
+    /// Additional plates sharing the contract (fleet/family).
+    #[serde(default)]
+    plates: Vec<String>,
+    max_simultaneous_vehicles: Option<i64>,
     vehicle_type: String,
     tariff_kind: Option<String>,
     monthly_amount: Option<f64>,
@@ -155,6 +292,11 @@ pub struct CreateContractArgs {
     extra_interval: Option<i64>,
     billing_period_days: Option<i64>,
     end_date: Option<String>,
+    auto_renew: Option<bool>,
+    /// Ends the first period early (e.g. on the 1st of next month); the first payment is
+    /// then prorated and covers `date_from..first_period_end`. Without it the first period is
+    /// a full one. Either way it is due from `date_from` until paid.
+    first_period_end: Option<String>,
 }
 
 fn suggest_monthly_amount(
@@ -162,14 +304,16 @@ fn suggest_monthly_amount(
     vehicle_type: &str,
     tariff_kind: &str,
     days: i64,
-) -> f64 {
+) -> Result<Money, String> {
     let tariff = crate::domains::custom_tariffs::get_tariff_for_calculation(
         conn, vehicle_type, tariff_kind,
     );
-    match tariff {
-        Ok(t) => t.base_price * days as f64,
-        Err(_) => 0.0,
-    }
+    let amount = match tariff {
+        Ok(t) => t.base.base_price.times(days as f64),
+        Err(_) => Money::ZERO,
+    };
+    // Precio sugerido al cliente: redondeado al incremento de la moneda.
+    Ok(crate::money::currency(conn)?.round_charge(amount))
 }
 
 #[tauri::command]
@@ -188,7 +332,8 @@ pub fn contracts_suggest_monthly(
         .map(str::to_lowercase)
         .unwrap_or_else(|| "employee".to_string());
     let days = days.unwrap_or(31);
-    Ok(suggest_monthly_amount(&conn, &vt, &kind, days))
+    let amount = suggest_monthly_amount(&conn, &vt, &kind, days)?;
+    Ok(crate::money::currency(&conn)?.major(amount))
 }
 
 #[tauri::command]
@@ -231,52 +376,68 @@ pub fn contracts_create(
         }
     }
 
-    let existing: i64 = conn
-        .query_row(
-            "SELECT COUNT(*) FROM contracts WHERE plate_upper = ?1 AND status = 'active'",
-            params![&plate_upper],
-            |r| r.get(0),
-        )
-        .map_err(|e| e.to_string())?;
-    if existing > 0 {
-        return Err("An active contract already exists for this plate".to_string());
+    let plates = normalize_plates(&plate_upper, &args.plates);
+    let max_simultaneous_vehicles = args.max_simultaneous_vehicles.unwrap_or(1);
+    validate_quota(max_simultaneous_vehicles, &plates)?;
+    if let Some(taken) = plate_in_other_contract(&conn, &plates, None)? {
+        return Err(format!("An active contract already exists for plate {}", taken));
     }
 
     let included_hours = args.included_hours_per_day.unwrap_or(6.0);
     let billing_period_days = args.billing_period_days.unwrap_or(30).max(1);
     let date_from = args.date_from.trim().to_string();
-    let date_to = chrono::NaiveDate::parse_from_str(&date_from, "%Y-%m-%d")
-        .map(|d| (d + chrono::Duration::days(billing_period_days)).format("%Y-%m-%d").to_string())
-        .unwrap_or_else(|_| date_from.clone());
+    let first_period_end = args.first_period_end.as_deref().map(str::trim).filter(|s| !s.is_empty()).map(String::from);
+    chrono::NaiveDate::parse_from_str(&date_from, "%Y-%m-%d")
+        .map_err(|_| "date_from must be a YYYY-MM-DD date".to_string())?;
+    // El primer período, completo o prorrateado hasta first_period_end, queda pendiente de
+    // pago desde date_from: lo cubre el primer pago registrado.
+    if let Some(first_end) = &first_period_end {
+        let full_end = add_days(&date_from, billing_period_days)?;
+        if first_end.as_str() <= date_from.as_str() || *first_end > full_end {
+            return Err(format!("first_period_end must be after date_from and no later than {}", full_end));
+        }
+    }
+    let date_to = date_from.clone();
 
-    let monthly_amount = args.monthly_amount.unwrap_or_else(|| {
-        suggest_monthly_amount(&conn, &vehicle_type, &tariff_kind, 31)
-    });
+    let currency = crate::money::currency(&conn)?;
+    let monthly_amount = match args.monthly_amount {
+        Some(amount) => currency.money(amount),
+        None => suggest_monthly_amount(&conn, &vehicle_type, &tariff_kind, 31)?,
+    };
+    let extra_charge = args.extra_charge_per_interval.map(|v| currency.money(v));
 
     let id = id_gen::generate_id(id_gen::PREFIX_CONTRACT);
     let created_at = chrono::Utc::now().to_rfc3339();
 
     let end_date = args.end_date.as_deref().map(str::trim).filter(|s| !s.is_empty()).map(String::from);
+    let auto_renew = args.auto_renew.unwrap_or(false);
+    let created_by = state.get_current_user_id();
 
-    conn.execute(
-        r#"INSERT INTO contracts
-            (id, client_name, client_phone, plate, plate_upper, vehicle_type,
-             tariff_kind, monthly_amount, included_hours_per_day,
-             date_from, date_to, status, created_at, notes,
-             extra_charge_first, extra_charge_repeat, extra_interval, billing_period_days,
-             extra_charge_per_interval, end_date)
-           VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, 'active', ?12, ?13, ?14, ?14, ?15, ?16, ?14, ?17)"#,
-        params![
-            id, client_name, args.client_phone, plate, plate_upper,
-            vehicle_type, tariff_kind, monthly_amount, included_hours,
-            date_from, date_to, created_at, args.notes,
-            args.extra_charge_per_interval, args.extra_interval,
-            billing_period_days, end_date,
-        ],
-    )
-    .map_err(|e| e.to_string())?;
+    crate::db::with_transaction(&conn, |conn| {
+        conn.execute(
+            r#"INSERT INTO contracts
+                (id, client_name, client_phone, plate, plate_upper, vehicle_type,
+                 tariff_kind, monthly_amount, included_hours_per_day,
+                 date_from, date_to, status, created_at, notes,
+                 extra_charge_first, extra_charge_repeat, extra_interval, billing_period_days,
+                 extra_charge_per_interval, end_date, auto_renew, max_simultaneous_vehicles,
+                 next_period_end)
+               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, 'active', ?12, ?13, ?14, ?14, ?15, ?16, ?14, ?17, ?18, ?19, ?20)"#,
+            params![
+                id, client_name, args.client_phone, plate, plate_upper,
+                vehicle_type, tariff_kind, monthly_amount, included_hours,
+                date_from, date_to, created_at, args.notes,
+                extra_charge, args.extra_interval,
+                billing_period_days, end_date, auto_renew, max_simultaneous_vehicles,
+                first_period_end,
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+        save_plates(conn, &id, &plates, &created_at)?;
+        log_transition(conn, &id, None, "active", "created", Some("Contract created"), &created_at, created_by.as_deref())
+    })?;
 
-    let arrears = is_in_arrears("active", &date_to, end_date.as_deref());
+    let arrears = is_in_arrears("active", &date_to, end_date.as_deref(), &business_today(&conn)?);
     Ok(Contract {
         id,
         client_name,
@@ -285,23 +446,31 @@ pub fn contracts_create(
         plate_upper,
         vehicle_type,
         tariff_kind,
-        monthly_amount,
+        monthly_amount: currency.major(monthly_amount),
         included_hours_per_day: included_hours,
         date_from,
         date_to,
         status: "active".to_string(),
         created_at,
         notes: args.notes,
-        extra_charge_first: args.extra_charge_per_interval,
-        extra_charge_repeat: args.extra_charge_per_interval,
+        extra_charge_first: extra_charge.map(|m| currency.major(m)),
+        extra_charge_repeat: extra_charge.map(|m| currency.major(m)),
         extra_interval: args.extra_interval,
-        extra_charge_per_interval: args.extra_charge_per_interval,
+        extra_charge_per_interval: extra_charge.map(|m| currency.major(m)),
         is_in_arrears: arrears,
         billing_period_days,
         cancelled_at: None,
         cancellation_reason: None,
         last_payment_date: None,
         end_date,
+        auto_renew,
+        plates,
+        max_simultaneous_vehicles,
+        credit_balance: 0.0,
+        next_period_end: first_period_end,
+        pending_monthly_amount: None,
+        pending_billing_period_days: None,
+        pending_effective_date: None,
     })
 }
 
@@ -332,7 +501,7 @@ pub fn contracts_list(
     let (sql, search_param) = match (status_filter, search_filter) {
         (Some(st), Some(s)) => (
             format!(
-                "{base_select} WHERE status = '{st}' AND (client_name LIKE ?1 OR plate_upper LIKE ?1 OR client_phone LIKE ?1) ORDER BY created_at DESC LIMIT 100"
+                "{base_select} WHERE status = '{st}' AND (client_name LIKE ?1 OR id IN (SELECT contract_id FROM contract_plates WHERE plate_upper LIKE ?1) OR client_phone LIKE ?1) ORDER BY created_at DESC LIMIT 100"
             ),
             Some(format!("%{}%", s.to_uppercase())),
         ),
@@ -341,7 +510,7 @@ pub fn contracts_list(
             None,
         ),
         (None, Some(s)) => (
-            format!("{base_select} WHERE client_name LIKE ?1 OR plate_upper LIKE ?1 OR client_phone LIKE ?1 ORDER BY created_at DESC LIMIT 100"),
+            format!("{base_select} WHERE client_name LIKE ?1 OR id IN (SELECT contract_id FROM contract_plates WHERE plate_upper LIKE ?1) OR client_phone LIKE ?1 ORDER BY created_at DESC LIMIT 100"),
             Some(format!("%{}%", s.to_uppercase())),
         ),
         (None, None) => (
@@ -350,16 +519,18 @@ pub fn contracts_list(
         ),
     };
 
+    let today = business_today(&conn)?;
+    let currency = crate::money::currency(&conn)?;
     let items: Vec<Contract> = match &search_param {
         Some(p) => {
             let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
-            let rows = stmt.query_map(params![p], |row| row_to_contract(row))
+            let rows = stmt.query_map(params![p], |row| row_to_contract(row, &today, &currency))
                 .map_err(|e| e.to_string())?;
             rows.filter_map(|r| r.ok()).collect()
         }
         None => {
             let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
-            let rows = stmt.query_map([], |row| row_to_contract(row))
+            let rows = stmt.query_map([], |row| row_to_contract(row, &today, &currency))
                 .map_err(|e| e.to_string())?;
             rows.filter_map(|r| r.ok()).collect()
         }
@@ -393,6 +564,10 @@ pub struct UpdateContractArgs {
     extra_interval: Option<i64>,
     billing_period_days: Option<i64>,
     end_date: Option<String>,
+    auto_renew: Option<bool>,
+    /// Full set of plates; the main plate stays first when kept.
+    plates: Option<Vec<String>>,
+    max_simultaneous_vehicles: Option<i64>,
 }
 
 #[tauri::command]
@@ -403,29 +578,46 @@ pub fn contracts_update(
     state.check_permission(permissions::CONTRACTS_MODIFY)?;
     let conn = state.db.get().map_err(|e| e.to_string())?;
     let id = args.id.trim().to_string();
+    let today = business_today(&conn)?;
+    let currency = crate::money::currency(&conn)?;
 
     let existing = conn
         .query_row(
             &format!("SELECT {CONTRACT_COLS} FROM contracts WHERE id = ?1"),
             params![&id],
-            |row| row_to_contract(row),
+            |row| row_to_contract(row, &today, &currency),
         )
         .map_err(|_| "Contract not found".to_string())?;
+    if existing.status == "cancelled" {
+        return Err("Cannot modify a cancelled contract".to_string());
+    }
+    let before = existing.clone();
 
     let new_name = args.client_name
         .as_deref().map(str::trim).filter(|s| !s.is_empty()).map(String::from)
         .unwrap_or(existing.client_name);
     let new_phone = args.client_phone.or(existing.client_phone);
-    let new_amount = args.monthly_amount.unwrap_or(existing.monthly_amount);
+    let new_amount = currency.money(args.monthly_amount.unwrap_or(existing.monthly_amount));
     let new_hours = args.included_hours_per_day.unwrap_or(existing.included_hours_per_day);
     let new_from = args.date_from.unwrap_or(existing.date_from);
     let new_to = args.date_to.unwrap_or(existing.date_to);
     let new_notes = args.notes.or(existing.notes);
-    let new_extra = if args.extra_charge_per_interval.is_some() { args.extra_charge_per_interval } else { existing.extra_charge_per_interval };
+    let new_extra = if args.extra_charge_per_interval.is_some() { args.extra_charge_per_interval } else { existing.extra_charge_per_interval }
+        .map(|v| currency.money(v));
     let new_extra_interval = if args.extra_interval.is_some() { args.extra_interval } else { existing.extra_interval };
     let new_billing = if args.billing_period_days.is_some() { args.billing_period_days.unwrap_or(30) } else { existing.billing_period_days };
 
-    if new_billing != existing.billing_period_days {
+    if new_billing < 1 {
+        return Err("billing_period_days must be >= 1".to_string());
+    }
+    // Con pagos registrados, un cambio de plan (monto o frecuencia) no altera los períodos ya
+    // pagados: se programa para el siguiente corte (date_to actual).
+    let mut new_pending = (
+        before.pending_monthly_amount.map(|a| currency.money(a)),
+        before.pending_billing_period_days,
+        before.pending_effective_date.clone(),
+    );
+    let (new_amount, new_billing) = if new_amount != currency.money(before.monthly_amount) || new_billing != before.billing_period_days {
         let payments_count: i64 = conn
             .query_row(
                 "SELECT COUNT(*) FROM contract_payments WHERE contract_id = ?1",
@@ -434,53 +626,323 @@ pub fn contracts_update(
             )
             .unwrap_or(0);
         if payments_count > 0 {
-            return Err("No se puede cambiar la frecuencia de cobro de un contrato con pagos registrados. Esto podría extender la vigencia sin cobro.".to_string());
+            new_pending = (Some(new_amount), Some(new_billing), Some(before.date_to.clone()));
+            (currency.money(before.monthly_amount), before.billing_period_days)
+        } else {
+            (new_amount, new_billing)
         }
-    }
+    } else {
+        (new_amount, new_billing)
+    };
 
     let new_end_date = if args.end_date.is_some() {
         args.end_date.as_deref().map(str::trim).filter(|s| !s.is_empty()).map(String::from)
     } else {
         existing.end_date
     };
+    let new_auto_renew = args.auto_renew.unwrap_or(existing.auto_renew);
+    let new_plates = match args.plates.as_deref() {
+        Some(list) => {
+            let list: Vec<String> = list.iter().map(|p| p.trim().to_uppercase()).filter(|p| !p.is_empty()).collect();
+            let main = if list.contains(&existing.plate_upper) {
+                existing.plate_upper.clone()
+            } else {
+                list.first().cloned().ok_or_else(|| "Plate is required".to_string())?
+            };
+            normalize_plates(&main, &list)
+        }
+        None => existing.plates.clone(),
+    };
+    let new_max_simultaneous = args.max_simultaneous_vehicles.unwrap_or(existing.max_simultaneous_vehicles);
+    validate_quota(new_max_simultaneous, &new_plates)?;
+    if new_plates != existing.plates {
+        if let Some(taken) = plate_in_other_contract(&conn, &new_plates, Some(&id))? {
+            return Err(format!("An active contract already exists for plate {}", taken));
+        }
+    }
+
+    let mut changes = serde_json::Map::new();
+    diff_field(&mut changes, "clientName", &before.client_name, &new_name);
+    diff_field(&mut changes, "clientPhone", &before.client_phone, &new_phone);
+    diff_field(&mut changes, "monthlyAmount", &before.monthly_amount, &currency.major(new_amount));
+    diff_field(&mut changes, "includedHoursPerDay", &before.included_hours_per_day, &new_hours);
+    diff_field(&mut changes, "dateFrom", &before.date_from, &new_from);
+    diff_field(&mut changes, "dateTo", &before.date_to, &new_to);
+    diff_field(&mut changes, "notes", &before.notes, &new_notes);
+    diff_field(&mut changes, "extraChargePerInterval", &before.extra_charge_per_interval, &new_extra.map(|m| currency.major(m)));
+    diff_field(&mut changes, "extraInterval", &before.extra_interval, &new_extra_interval);
+    diff_field(&mut changes, "billingPeriodDays", &before.billing_period_days, &new_billing);
+    diff_field(&mut changes, "endDate", &before.end_date, &new_end_date);
+    diff_field(&mut changes, "autoRenew", &before.auto_renew, &new_auto_renew);
+    diff_field(&mut changes, "plates", &before.plates, &new_plates);
+    diff_field(&mut changes, "maxSimultaneousVehicles", &before.max_simultaneous_vehicles, &new_max_simultaneous);
+    diff_field(&mut changes, "pendingMonthlyAmount", &before.pending_monthly_amount, &new_pending.0.map(|m| currency.major(m)));
+    diff_field(&mut changes, "pendingBillingPeriodDays", &before.pending_billing_period_days, &new_pending.1);
+    diff_field(&mut changes, "pendingEffectiveDate", &before.pending_effective_date, &new_pending.2);
+    if changes.is_empty() {
+        return Ok(before);
+    }
 
     let updated_at = chrono::Utc::now().to_rfc3339();
     let updated_by = state.get_current_user_id();
 
-    conn.execute(
-        r#"UPDATE contracts SET client_name = ?1, client_phone = ?2, monthly_amount = ?3,
-           included_hours_per_day = ?4, date_from = ?5, date_to = ?6, notes = ?7,
-           extra_charge_first = ?8, extra_charge_repeat = ?8, extra_interval = ?9,
-           billing_period_days = ?10, updated_at = ?11, updated_by = ?12,
-           extra_charge_per_interval = ?8, end_date = ?14
-           WHERE id = ?13"#,
-        params![
-            new_name, new_phone, new_amount, new_hours, new_from, new_to, new_notes,
-            new_extra, new_extra_interval, new_billing,
-            updated_at, updated_by, &id, new_end_date
-        ],
-    )
-    .map_err(|e| e.to_string())?;
+    crate::db::with_transaction(&conn, |conn| {
+        conn.execute(
+            r#"UPDATE contracts SET client_name = ?1, client_phone = ?2, monthly_amount = ?3,
+               included_hours_per_day = ?4, date_from = ?5, date_to = ?6, notes = ?7,
+               extra_charge_first = ?8, extra_charge_repeat = ?8, extra_interval = ?9,
+               billing_period_days = ?10, updated_at = ?11, updated_by = ?12,
+               extra_charge_per_interval = ?8, end_date = ?14, auto_renew = ?15,
+               plate = ?16, plate_upper = ?16, max_simultaneous_vehicles = ?17,
+               pending_monthly_amount = ?18, pending_billing_period_days = ?19, pending_effective_date = ?20
+               WHERE id = ?13"#,
+            params![
+                new_name, new_phone, new_amount, new_hours, new_from, new_to, new_notes,
+                new_extra, new_extra_interval, new_billing,
+                updated_at, updated_by, &id, new_end_date, new_auto_renew,
+                new_plates[0], new_max_simultaneous,
+                new_pending.0, new_pending.1, new_pending.2
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+        if new_plates != before.plates {
+            save_plates(conn, &id, &new_plates, &updated_at)?;
+        }
+        log_changes(conn, &id, &before.status, &serde_json::Value::Object(changes), &updated_at, updated_by.as_deref())
+    })?;
 
     conn.query_row(
         &format!("SELECT {CONTRACT_COLS} FROM contracts WHERE id = ?1"),
         params![&id],
-        |row| row_to_contract(row),
+        |row| row_to_contract(row, &today, &currency),
     )
     .map_err(|e| e.to_string())
 }
 
+/// Cancels the contract; the row, its payments and invoices are kept.
+pub(crate) fn cancel_contract_on(
+    conn: &rusqlite::Connection,
+    id: &str,
+    reason: Option<&str>,
+    at: &str,
+    by: Option<&str>,
+) -> Result<(), String> {
+    let status: String = conn
+        .query_row("SELECT status FROM contracts WHERE id = ?1", params![id], |row| row.get(0))
+        .map_err(|_| "Contract not found".to_string())?;
+    if status == "cancelled" {
+        return Err("Contract is already cancelled".to_string());
+    }
+    let reason = reason.map(str::trim).filter(|s| !s.is_empty());
+    crate::db::with_transaction(conn, |conn| {
+        conn.execute(
+            r#"UPDATE contracts SET status = 'cancelled', cancelled_at = ?1, cancellation_reason = ?2,
+               updated_at = ?1, updated_by = ?3
+               WHERE id = ?4"#,
+            params![at, reason, by, id],
+        )
+        .map_err(|e| e.to_string())?;
+        log_transition(conn, id, Some(&status), "cancelled", "cancelled", reason, at, by)
+    })
+}
+
 #[tauri::command]
-pub fn contracts_delete(state: State<AppState>, id: String) -> Result<(), String> {
+pub fn contracts_delete(
+    state: State<AppState>,
+    id: String,
+    reason: Option<String>,
+) -> Result<(), String> {
     state.check_permission(permissions::CONTRACTS_DELETE)?;
     let conn = state.db.get().map_err(|e| e.to_string())?;
-    let n = conn
-        .execute("DELETE FROM contracts WHERE id = ?1", params![id.trim()])
+    let at = chrono::Utc::now().to_rfc3339();
+    let by = state.get_current_user_id();
+    cancel_contract_on(&conn, id.trim(), reason.as_deref(), &at, by.as_deref())
+}
+
+/// Drops the plan change scheduled for the next billing cut; the current plan stays.
+fn cancel_plan_change_on(
+    conn: &rusqlite::Connection,
+    id: &str,
+    at: &str,
+    by: Option<&str>,
+) -> Result<(), String> {
+    let (status, pending_amount, pending_days): (String, Option<Money>, Option<i64>) = conn
+        .query_row(
+            "SELECT status, pending_monthly_amount, pending_billing_period_days FROM contracts WHERE id = ?1",
+            params![id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .map_err(|_| "Contract not found".to_string())?;
+    if pending_amount.is_none() && pending_days.is_none() {
+        return Err("The contract has no pending plan change".to_string());
+    }
+    let currency = crate::money::currency(conn)?;
+    let changes = serde_json::json!({
+        "pendingMonthlyAmount": { "old": pending_amount.map(|m| currency.major(m)), "new": null },
+        "pendingBillingPeriodDays": { "old": pending_days, "new": null },
+    });
+    crate::db::with_transaction(conn, |conn| {
+        conn.execute(
+            r#"UPDATE contracts SET pending_monthly_amount = NULL, pending_billing_period_days = NULL,
+               pending_effective_date = NULL, updated_at = ?1, updated_by = ?2
+               WHERE id = ?3"#,
+            params![at, by, id],
+        )
         .map_err(|e| e.to_string())?;
-    if n == 0 {
-        return Err("Contract not found".to_string());
+        insert_history(
+            conn, id, Some(&status), &status, "plan_change_cancelled",
+            Some("Scheduled plan change cancelled"), Some(&changes), at, by,
+        )
+    })
+}
+
+#[tauri::command]
+pub fn contracts_cancel_plan_change(state: State<AppState>, id: String) -> Result<(), String> {
+    state.check_permission(permissions::CONTRACTS_MODIFY)?;
+    let conn = state.db.get().map_err(|e| e.to_string())?;
+    let at = chrono::Utc::now().to_rfc3339();
+    let by = state.get_current_user_id();
+    cancel_plan_change_on(&conn, id.trim(), &at, by.as_deref())
+}
+
+/// Billing position of a contract while a payment is applied period by period.
+#[derive(Debug, Clone)]
+struct BillingState {
+    date_to: chrono::NaiveDate,
+    monthly_amount: Money,
+    billing_period_days: i64,
+    end_date: Option<chrono::NaiveDate>,
+    next_period_end: Option<chrono::NaiveDate>,
+    pending: Option<(Money, i64, chrono::NaiveDate)>,
+}
+
+fn parse_opt_date(value: Option<&str>) -> Option<chrono::NaiveDate> {
+    value
+        .filter(|s| !s.is_empty())
+        .and_then(|s| chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d").ok())
+}
+
+impl BillingState {
+    fn of(contract: &Contract, currency: &Currency) -> Result<Self, String> {
+        let date_to = chrono::NaiveDate::parse_from_str(&contract.date_to, "%Y-%m-%d").map_err(|e| e.to_string())?;
+        let pending = match (
+            contract.pending_monthly_amount,
+            contract.pending_billing_period_days,
+            parse_opt_date(contract.pending_effective_date.as_deref()),
+        ) {
+            (Some(amount), Some(days), Some(from)) => Some((currency.money(amount), days.max(1), from)),
+            _ => None,
+        };
+        Ok(BillingState {
+            date_to,
+            monthly_amount: currency.money(contract.monthly_amount),
+            billing_period_days: contract.billing_period_days.max(1),
+            end_date: parse_opt_date(contract.end_date.as_deref()),
+            next_period_end: parse_opt_date(contract.next_period_end.as_deref()),
+            pending,
+        })
+    }
+
+    /// Switches to the scheduled plan once the next period starts on or after its date.
+    fn apply_due_plan_change(&mut self) -> bool {
+        match self.pending {
+            Some((amount, days, from)) if self.date_to >= from => {
+                self.monthly_amount = amount;
+                self.billing_period_days = days;
+                self.pending = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// End and price of the period starting at `date_to`; None once the end date is reached.
+    /// Periods shortened by `next_period_end` or `end_date` are prorated by day.
+    fn next_period(&self, currency: &Currency) -> Option<(chrono::NaiveDate, Money)> {
+        if self.end_date.is_some_and(|end| end <= self.date_to) {
+            return None;
+        }
+        let full_end = self.date_to + chrono::Duration::days(self.billing_period_days);
+        let mut to = self.next_period_end.filter(|d| *d > self.date_to).unwrap_or(full_end).min(full_end);
+        if let Some(end) = self.end_date {
+            to = to.min(end);
+        }
+        let days = (to - self.date_to).num_days();
+        let amount = if days == self.billing_period_days {
+            self.monthly_amount
+        } else {
+            currency.round_charge(self.monthly_amount.times(days as f64 / self.billing_period_days as f64))
+        };
+        Some((to, amount))
+    }
+
+    /// Applies `available` (payment plus credit) to whole periods; returns what is left over.
+    fn allocate(&mut self, currency: &Currency, mut available: Money) -> (Money, bool) {
+        let mut plan_changed = false;
+        loop {
+            plan_changed |= self.apply_due_plan_change();
+            match self.next_period(currency) {
+                Some((to, amount)) if available >= amount => {
+                    available -= amount;
+                    self.date_to = to;
+                    self.next_period_end = None;
+                    // Período sin costo: se cubre uno por pago.
+                    if amount <= Money::ZERO {
+                        break;
+                    }
+                }
+                _ => break,
+            }
+        }
+        (available, plan_changed)
     }
-    Ok(())
+}
+
+/// Amount due for the next period of a contract, net of its credit balance.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaymentQuote {
+    pub contract_id: String,
+    pub period_from: String,
+    pub period_to: String,
+    pub period_amount: f64,
+    pub prorated: bool,
+    pub credit_balance: f64,
+    pub amount_due: f64,
+}
+
+fn quote_next_payment(contract: &Contract, currency: &Currency) -> Result<Option<PaymentQuote>, String> {
+    let mut billing = BillingState::of(contract, currency)?;
+    let credit_balance = currency.money(contract.credit_balance);
+    billing.apply_due_plan_change();
+    Ok(billing.next_period(currency).map(|(to, amount)| PaymentQuote {
+        contract_id: contract.id.clone(),
+        period_from: billing.date_to.format("%Y-%m-%d").to_string(),
+        period_to: to.format("%Y-%m-%d").to_string(),
+        period_amount: currency.major(amount),
+        prorated: (to - billing.date_to).num_days() != billing.billing_period_days,
+        credit_balance: contract.credit_balance,
+        amount_due: currency.major((amount - credit_balance).max(Money::ZERO)),
+    }))
+}
+
+#[tauri::command]
+pub fn contracts_quote_payment(
+    state: State<AppState>,
+    contract_id: String,
+) -> Result<Option<PaymentQuote>, String> {
+    state.check_permission(permissions::CONTRACTS_READ)?;
+    let conn = state.db.get().map_err(|e| e.to_string())?;
+    let today = business_today(&conn)?;
+    let currency = crate::money::currency(&conn)?;
+    let contract = conn
+        .query_row(
+            &format!("SELECT {CONTRACT_COLS} FROM contracts WHERE id = ?1"),
+            params![contract_id.trim()],
+            |row| row_to_contract(row, &today, &currency),
+        )
+        .map_err(|_| "Contract not found".to_string())?;
+    quote_next_payment(&contract, &currency)
 }
 
 #[derive(Debug, Deserialize)]
@@ -500,10 +962,16 @@ pub struct ContractPayment {
     pub method: String,
     pub period_from: String,
     pub period_to: String,
+    /// "period" when it covers one or more periods; "credit" when it only adds to the credit
+    /// balance (then period_from == period_to).
+    pub kind: String,
     pub operator_user_id: Option<String>,
     pub created_at: String,
 }
 
+pub const PAYMENT_KIND_PERIOD: &str = "period";
+pub const PAYMENT_KIND_CREDIT: &str = "credit";
+
 #[tauri::command]
 pub fn contracts_record_payment(
     state: State<AppState>,
@@ -511,7 +979,14 @@ pub fn contracts_record_payment(
 ) -> Result<Contract, String> {
     state.check_permission(permissions::CONTRACTS_PAYMENT_CREATE)?;
     let conn = state.db.get().map_err(|e| e.to_string())?;
+    record_payment_on(&conn, args, state.get_current_user_id())
+}
 
+fn record_payment_on(
+    conn: &rusqlite::Connection,
+    args: RecordPaymentArgs,
+    operator_user_id: Option<String>,
+) -> Result<Contract, String> {
     let valid_methods = ["cash", "card", "transfer"];
     let method = args.method.trim().to_lowercase();
     if !valid_methods.contains(&method.as_str()) {
@@ -519,75 +994,116 @@ pub fn contracts_record_payment(
     }
 
     let contract_id = args.contract_id.trim().to_string();
-    let contract = conn
-        .query_row(
-            &format!("SELECT {CONTRACT_COLS} FROM contracts WHERE id = ?1"),
-            params![&contract_id],
-            |row| row_to_contract(row),
-        )
-        .map_err(|_| "Contract not found".to_string())?;
-
-    if contract.status == "cancelled" {
-        return Err("Cannot record payment for a cancelled contract".to_string());
-    }
-
-    let amount = args.amount.unwrap_or(contract.monthly_amount);
-    if amount < 0.0 {
-        return Err("Amount must be >= 0".to_string());
-    }
+    let today = business_today(conn)?;
+    let currency = crate::money::currency(conn)?;
+    // El contrato se lee y el pago se reparte dentro de la transacción: dos pagos simultáneos
+    // encadenan uno tras otro en vez de partir del mismo vencimiento y pisar el saldo a favor.
+    crate::db::with_transaction(conn, |conn| {
+        let contract = conn
+            .query_row(
+                &format!("SELECT {CONTRACT_COLS} FROM contracts WHERE id = ?1"),
+                params![&contract_id],
+                |row| row_to_contract(row, &today, &currency),
+            )
+            .map_err(|_| "Contract not found".to_string())?;
 
-    // Siempre encadenar desde el último vencimiento para mantener historial sin gaps.
-    // Si hay mora, el gap se refleja en is_in_arrears, no en los períodos.
-    let period_from = contract.date_to.clone();
+        if contract.status == "cancelled" {
+            return Err("Cannot record payment for a cancelled contract".to_string());
+        }
 
-    let period_to_date = chrono::NaiveDate::parse_from_str(&period_from, "%Y-%m-%d")
-        .map_err(|e| e.to_string())?;
-    let period_to = (period_to_date + chrono::Duration::days(contract.billing_period_days))
-        .format("%Y-%m-%d")
-        .to_string();
+        // Sin monto, se cobra lo que falta para cubrir el siguiente período.
+        let quote = quote_next_payment(&contract, &currency)?
+            .ok_or_else(|| "The contract has no period left to bill (end date reached)".to_string())?;
+        let amount = currency.money(args.amount.unwrap_or(quote.amount_due));
+        if amount < Money::ZERO {
+            return Err("Amount must be >= 0".to_string());
+        }
 
-    let payment_id = id_gen::generate_id("cpay");
-    let created_at = chrono::Utc::now().to_rfc3339();
-    let operator_user_id = state.get_current_user_id();
+        // Siempre encadenar desde el último vencimiento para mantener historial sin gaps.
+        // Si hay mora, el gap se refleja en is_in_arrears, no en los períodos. Lo que no alcanza
+        // para un período completo queda como saldo a favor.
+        let period_from = contract.date_to.clone();
+        let mut billing = BillingState::of(&contract, &currency)?;
+        let (credit_balance, plan_changed) =
+            billing.allocate(&currency, currency.money(contract.credit_balance) + amount);
+        let period_to = billing.date_to.format("%Y-%m-%d").to_string();
+        let next_period_end = billing.next_period_end.map(|d| d.format("%Y-%m-%d").to_string());
+        let new_status = if period_to.as_str() >= today.as_str() { "active" } else { contract.status.as_str() };
+        // Lo que no cubre ningún período es un abono al saldo a favor, no un período de 0 días.
+        let kind = if period_to == period_from { PAYMENT_KIND_CREDIT } else { PAYMENT_KIND_PERIOD };
+        if kind == PAYMENT_KIND_CREDIT && amount <= Money::ZERO {
+            return Err("Amount must be > 0".to_string());
+        }
 
-    conn.execute("BEGIN IMMEDIATE", []).map_err(|e| e.to_string())?;
+        let payment_id = id_gen::generate_id("cpay");
+        let created_at = chrono::Utc::now().to_rfc3339();
+        let shift_id = crate::domains::caja::open_shift_id(conn, operator_user_id.as_deref())?;
 
-    let result = (|| {
         conn.execute(
             r#"INSERT INTO contract_payments
-                (id, contract_id, amount, method, period_from, period_to, operator_user_id, created_at)
-               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)"#,
+                (id, contract_id, amount, method, period_from, period_to, operator_user_id, created_at, shift_id, kind)
+               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)"#,
             params![
                 payment_id, contract_id, amount, method,
-                period_from, period_to, operator_user_id, created_at
+                period_from, period_to, operator_user_id, created_at, shift_id, kind
             ],
         )
         .map_err(|e| e.to_string())?;
 
+        // Factura del periodo pagado, con el siguiente consecutivo.
+        let payment = ContractPayment {
+            id: payment_id.clone(),
+            contract_id: contract_id.clone(),
+            amount: currency.major(amount),
+            method: method.clone(),
+            period_from: period_from.clone(),
+            period_to: period_to.clone(),
+            kind: kind.to_string(),
+            operator_user_id: operator_user_id.clone(),
+            created_at: created_at.clone(),
+        };
+        crate::domains::invoices::issue_for_payment(conn, &contract, &payment)?;
+
+        let pending = billing.pending;
         conn.execute(
-            "UPDATE contracts SET date_to = ?1, status = 'active', last_payment_date = ?3 WHERE id = ?2",
-            params![period_to, contract_id, created_at],
+            r#"UPDATE contracts SET date_to = ?1, status = ?2, last_payment_date = ?3, credit_balance = ?4,
+               next_period_end = ?5, monthly_amount = ?6, billing_period_days = ?7,
+               pending_monthly_amount = ?8, pending_billing_period_days = ?9, pending_effective_date = ?10
+               WHERE id = ?11"#,
+            params![
+                period_to, new_status, created_at, credit_balance, next_period_end,
+                billing.monthly_amount, billing.billing_period_days,
+                pending.map(|p| p.0), pending.map(|p| p.1),
+                pending.map(|p| p.2.format("%Y-%m-%d").to_string()),
+                contract_id
+            ],
         )
         .map_err(|e| e.to_string())?;
-
-        conn.query_row(
-            &format!("SELECT {CONTRACT_COLS} FROM contracts WHERE id = ?1"),
-            params![&contract_id],
-            |row| row_to_contract(row),
-        )
-        .map_err(|e| e.to_string())
-    })();
-
-    match result {
-        Ok(updated) => {
-            conn.execute("COMMIT", []).map_err(|e| e.to_string())?;
-            Ok(updated)
+        if plan_changed {
+            let changes = serde_json::json!({
+                "monthlyAmount": { "old": contract.monthly_amount, "new": currency.major(billing.monthly_amount) },
+                "billingPeriodDays": { "old": contract.billing_period_days, "new": billing.billing_period_days },
+            });
+            insert_history(
+                conn, &contract_id, Some(&contract.status), &contract.status, "plan_changed",
+                Some("Scheduled plan change applied"), Some(&changes), &created_at, operator_user_id.as_deref(),
+            )?;
         }
-        Err(e) => {
-            let _ = conn.execute("ROLLBACK", []);
-            Err(e)
+        if contract.status != new_status {
+            log_transition(
+                conn, &contract_id, Some(&contract.status), new_status, "payment",
+                Some(&format!("Paid through {}", period_to)), &created_at, operator_user_id.as_deref(),
+            )?;
         }
-    }
+        Ok(())
+    })?;
+
+    conn.query_row(
+        &format!("SELECT {CONTRACT_COLS} FROM contracts WHERE id = ?1"),
+        params![&contract_id],
+        |row| row_to_contract(row, &today, &currency),
+    )
+    .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -598,11 +1114,12 @@ pub fn contracts_list_payments(
     state.check_permission(permissions::CONTRACTS_READ)?;
     let conn = state.db.get().map_err(|e| e.to_string())?;
     let cid = contract_id.trim().to_string();
+    let currency = crate::money::currency(&conn)?;
 
     let mut stmt = conn
         .prepare(
             r#"SELECT id, contract_id, amount, method, period_from, period_to,
-                      operator_user_id, created_at
+                      kind, operator_user_id, created_at
                FROM contract_payments
                WHERE contract_id = ?1
                ORDER BY created_at DESC"#,
@@ -614,10 +1131,11 @@ pub fn contracts_list_payments(
             Ok(ContractPayment {
                 id: row.get("id")?,
                 contract_id: row.get("contract_id")?,
-                amount: row.get("amount")?,
+                amount: currency.major(row.get("amount")?),
                 method: row.get("method")?,
                 period_from: row.get("period_from")?,
                 period_to: row.get("period_to")?,
+                kind: row.get("kind")?,
                 operator_user_id: row.get("operator_user_id")?,
                 created_at: row.get("created_at")?,
             })
@@ -637,3 +1155,480 @@ pub fn contracts_get_any_by_plate(
     let plate_upper = plate.trim().to_uppercase();
     Ok(find_any_contract_for_plate(&conn, &plate_upper))
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContractHistoryEntry {
+    pub id: String,
+    pub contract_id: String,
+    pub from_status: Option<String>,
+    pub to_status: String,
+    pub event: String,
+    pub reason: Option<String>,
+    pub changed_at: String,
+    /// None when the change was made by the status job.
+    pub changed_by: Option<String>,
+    /// Contract version after this change (1 = as created).
+    pub version: i64,
+    /// Edited fields as `{ field: { old, new } }`; None for status changes.
+    pub changes: Option<serde_json::Value>,
+}
+
+/// Appends a new version to contract_history (version = previous + 1).
+#[allow(clippy::too_many_arguments)]
+fn insert_history(
+    conn: &rusqlite::Connection,
+    contract_id: &str,
+    from: Option<&str>,
+    to: &str,
+    event: &str,
+    reason: Option<&str>,
+    changes: Option<&serde_json::Value>,
+    at: &str,
+    by: Option<&str>,
+) -> Result<(), String> {
+    let changes = changes.map(|c| c.to_string());
+    conn.execute(
+        r#"INSERT INTO contract_history
+            (id, contract_id, from_status, to_status, event, reason, changed_at, changed_by, version, changes)
+           VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8,
+                   (SELECT COALESCE(MAX(version), 0) + 1 FROM contract_history WHERE contract_id = ?2), ?9)"#,
+        params![
+            id_gen::generate_id(id_gen::PREFIX_CONTRACT_HISTORY),
+            contract_id, from, to, event, reason, at, by, changes
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Appends a status change to contract_history. `from` is None for a newly created contract.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn log_transition(
+    conn: &rusqlite::Connection,
+    contract_id: &str,
+    from: Option<&str>,
+    to: &str,
+    event: &str,
+    reason: Option<&str>,
+    at: &str,
+    by: Option<&str>,
+) -> Result<(), String> {
+    insert_history(conn, contract_id, from, to, event, reason, None, at, by)
+}
+
+/// Appends an edit of contract fields (status unchanged) with its old/new values.
+fn log_changes(
+    conn: &rusqlite::Connection,
+    contract_id: &str,
+    status: &str,
+    changes: &serde_json::Value,
+    at: &str,
+    by: Option<&str>,
+) -> Result<(), String> {
+    insert_history(conn, contract_id, Some(status), status, "updated", None, Some(changes), at, by)
+}
+
+/// Records `{ key: { old, new } }` when the value changed.
+fn diff_field<T: Serialize + PartialEq>(
+    changes: &mut serde_json::Map<String, serde_json::Value>,
+    key: &str,
+    old: &T,
+    new: &T,
+) {
+    if old != new {
+        changes.insert(key.to_string(), serde_json::json!({ "old": old, "new": new }));
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusJobSummary {
+    pub arrears: usize,
+    pub expired: usize,
+    pub renewed: usize,
+    pub reactivated: usize,
+}
+
+fn add_days(date: &str, days: i64) -> Result<String, String> {
+    let d = chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|e| e.to_string())?;
+    Ok((d + chrono::Duration::days(days)).format("%Y-%m-%d").to_string())
+}
+
+/// Brings the stored status of active/arrears contracts in line with `today` (YYYY-MM-DD):
+/// a reached end date renews the term (auto_renew) or expires the contract; an unpaid period
+/// moves it to arrears and a paid-up contract in arrears back to active. Each change is logged.
+pub(crate) fn run_status_transitions(
+    conn: &rusqlite::Connection,
+    today: &str,
+    now: &str,
+) -> Result<StatusJobSummary, String> {
+    let currency = crate::money::currency(conn)?;
+    let contracts: Vec<Contract> = {
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT {CONTRACT_COLS} FROM contracts WHERE status IN ('active', 'arrears')"
+            ))
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| row_to_contract(row, today, &currency))
+            .map_err(|e| e.to_string())?;
+        rows.filter_map(|r| r.ok()).collect()
+    };
+
+    let mut summary = StatusJobSummary::default();
+    crate::db::with_transaction(conn, |conn| {
+        for contract in contracts {
+            let status = contract.status.as_str();
+            if let Some(end) = contract.end_date.as_deref().filter(|e| !e.is_empty() && *e <= today) {
+                if contract.auto_renew && contract.billing_period_days > 0 {
+                    // Extiende la vigencia período a período hasta cubrir hoy.
+                    let mut new_end = end.to_string();
+                    while new_end.as_str() <= today {
+                        new_end = add_days(&new_end, contract.billing_period_days)?;
+                    }
+                    conn.execute(
+                        "UPDATE contracts SET end_date = ?1, updated_at = ?2 WHERE id = ?3",
+                        params![new_end, now, contract.id],
+                    )
+                    .map_err(|e| e.to_string())?;
+                    log_transition(
+                        conn, &contract.id, Some(status), status, "renewed",
+                        Some(&format!("End date {} extended to {}", end, new_end)), now, None,
+                    )?;
+                    summary.renewed += 1;
+                } else {
+                    conn.execute(
+                        "UPDATE contracts SET status = 'expired', updated_at = ?1 WHERE id = ?2",
+                        params![now, contract.id],
+                    )
+                    .map_err(|e| e.to_string())?;
+                    log_transition(
+                        conn, &contract.id, Some(status), "expired", "expired",
+                        Some(&format!("End date {} reached", end)), now, None,
+                    )?;
+                    summary.expired += 1;
+                    continue;
+                }
+            }
+
+            let unpaid = contract.date_to.as_str() < today;
+            let next = match (status, unpaid) {
+                ("active", true) => Some(("arrears", "arrears", format!("Unpaid since {}", contract.date_to))),
+                ("arrears", false) => Some(("active", "reactivated", format!("Paid through {}", contract.date_to))),
+                _ => None,
+            };
+            if let Some((to, event, reason)) = next {
+                conn.execute(
+                    "UPDATE contracts SET status = ?1, updated_at = ?2 WHERE id = ?3",
+                    params![to, now, contract.id],
+                )
+                .map_err(|e| e.to_string())?;
+                log_transition(conn, &contract.id, Some(status), to, event, Some(&reason), now, None)?;
+                if to == "arrears" {
+                    summary.arrears += 1;
+                } else {
+                    summary.reactivated += 1;
+                }
+            }
+        }
+        Ok(())
+    })?;
+    Ok(summary)
+}
+
+fn run_status_job(conn: &rusqlite::Connection) -> Result<StatusJobSummary, String> {
+    let today = business_today(conn)?;
+    let now = chrono::Utc::now().to_rfc3339();
+    run_status_transitions(conn, &today, &now)
+}
+
+const STATUS_JOB_INTERVAL_SECS: u64 = 3600;
+
+/// Runs the contract status job at startup and then every hour.
+pub fn spawn_contract_status_scheduler(app: AppHandle) {
+    std::thread::spawn(move || loop {
+        if let Some(state) = app.try_state::<AppState>() {
+            if let Ok(conn) = state.db.get() {
+                if let Err(e) = run_status_job(&conn) {
+                    log::warn!("Contract status job failed: {}", e);
+                }
+            }
+        }
+        std::thread::sleep(Duration::from_secs(STATUS_JOB_INTERVAL_SECS));
+    });
+}
+
+#[tauri::command]
+pub fn contracts_run_status_job(state: State<AppState>) -> Result<StatusJobSummary, String> {
+    state.check_permission(permissions::CONTRACTS_MODIFY)?;
+    let conn = state.db.get().map_err(|e| e.to_string())?;
+    run_status_job(&conn)
+}
+
+#[tauri::command]
+pub fn contracts_get_history(
+    state: State<AppState>,
+    contract_id: String,
+) -> Result<Vec<ContractHistoryEntry>, String> {
+    state.check_permission(permissions::CONTRACTS_READ)?;
+    let conn = state.db.get().map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(
+            r#"SELECT id, contract_id, from_status, to_status, event, reason, changed_at, changed_by,
+                      version, changes
+               FROM contract_history
+               WHERE contract_id = ?1
+               ORDER BY version DESC"#,
+        )
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map(params![contract_id.trim()], |row| {
+            Ok(ContractHistoryEntry {
+                id: row.get("id")?,
+                contract_id: row.get("contract_id")?,
+                from_status: row.get("from_status")?,
+                to_status: row.get("to_status")?,
+                event: row.get("event")?,
+                reason: row.get("reason")?,
+                changed_at: row.get("changed_at")?,
+                changed_by: row.get("changed_by")?,
+                version: row.get("version")?,
+                changes: row
+                    .get::<_, Option<String>>("changes")?
+                    .and_then(|json| serde_json::from_str(&json).ok()),
+            })
+        })
+        .map_err(|e| e.to_string())?;
+
+    Ok(rows.filter_map(|r| r.ok()).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+
+    fn status_of(conn: &Connection, id: &str) -> (String, Option<String>) {
+        conn.query_row(
+            "SELECT status, end_date FROM contracts WHERE id = ?1",
+            params![id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn status_job_moves_contracts_and_logs_history() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::run_migrations(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO contracts (id, client_name, plate, plate_upper, vehicle_type, tariff_kind, monthly_amount, included_hours_per_day, date_from, date_to, status, created_at, end_date, auto_renew)
+             VALUES ('LATE', 'A', 'AAA111', 'AAA111', 'car', 'none', 100000, 6, '2025-01-01', '2025-02-28', 'active', '2025-01-01T00:00:00+00:00', NULL, 0),
+                    ('ENDS', 'B', 'BBB222', 'BBB222', 'car', 'none', 100000, 6, '2025-01-01', '2025-03-31', 'active', '2025-01-01T00:00:00+00:00', '2025-03-10', 0),
+                    ('RENEW', 'C', 'CCC333', 'CCC333', 'car', 'none', 100000, 6, '2025-01-01', '2025-03-31', 'active', '2025-01-01T00:00:00+00:00', '2025-03-10', 1),
+                    ('PAID', 'D', 'DDD444', 'DDD444', 'car', 'none', 100000, 6, '2025-01-01', '2025-03-31', 'arrears', '2025-01-01T00:00:00+00:00', NULL, 0);",
+        )
+        .unwrap();
+
+        let summary = run_status_transitions(&conn, "2025-03-15", "2025-03-15T12:00:00+00:00").unwrap();
+        assert_eq!((summary.arrears, summary.expired, summary.renewed, summary.reactivated), (1, 1, 1, 1));
+        assert_eq!(status_of(&conn, "LATE").0, "arrears");
+        assert_eq!(status_of(&conn, "ENDS").0, "expired");
+        assert_eq!(status_of(&conn, "RENEW"), ("active".to_string(), Some("2025-04-09".to_string())));
+        assert_eq!(status_of(&conn, "PAID").0, "active");
+
+        let logged: i64 = conn
+            .query_row("SELECT COUNT(*) FROM contract_history WHERE changed_by IS NULL", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(logged, 4);
+
+        // Una segunda pasada no encuentra nada que cambiar.
+        let again = run_status_transitions(&conn, "2025-03-15", "2025-03-15T13:00:00+00:00").unwrap();
+        assert_eq!((again.arrears, again.expired, again.renewed, again.reactivated), (0, 0, 0, 0));
+    }
+
+    #[test]
+    fn cancelling_keeps_payments_and_versions_history() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::run_migrations(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO contracts (id, client_name, plate, plate_upper, vehicle_type, tariff_kind, monthly_amount, included_hours_per_day, date_from, date_to, status, created_at)
+             VALUES ('CO1', 'A', 'AAA111', 'AAA111', 'car', 'none', 100000, 6, '2025-01-01', '2025-02-28', 'active', '2025-01-01T00:00:00+00:00');
+             INSERT INTO contract_payments (id, contract_id, amount, method, period_from, period_to, created_at)
+             VALUES ('P1', 'CO1', 100000, 'cash', '2025-01-01', '2025-01-31', '2025-01-01T15:00:00+00:00');",
+        )
+        .unwrap();
+        log_transition(&conn, "CO1", None, "active", "created", None, "2025-01-01T00:00:00+00:00", None).unwrap();
+        let changes = serde_json::json!({ "monthlyAmount": { "old": 100000.0, "new": 120000.0 } });
+        log_changes(&conn, "CO1", "active", &changes, "2025-02-01T00:00:00+00:00", Some("US1")).unwrap();
+
+        cancel_contract_on(&conn, "CO1", Some(" Se mudó "), "2025-02-10T00:00:00+00:00", Some("US1")).unwrap();
+        assert!(cancel_contract_on(&conn, "CO1", None, "2025-02-11T00:00:00+00:00", None).is_err());
+
+        let (status, reason): (String, Option<String>) = conn
+            .query_row("SELECT status, cancellation_reason FROM contracts WHERE id = 'CO1'", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert_eq!((status.as_str(), reason.as_deref()), ("cancelled", Some("Se mudó")));
+        let payments: i64 = conn
+            .query_row("SELECT COUNT(*) FROM contract_payments WHERE contract_id = 'CO1'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(payments, 1);
+
+        let versions: Vec<(i64, String)> = conn
+            .prepare("SELECT version, event FROM contract_history WHERE contract_id = 'CO1' ORDER BY version")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(
+            versions,
+            vec![(1, "created".to_string()), (2, "updated".to_string()), (3, "cancelled".to_string())]
+        );
+    }
+
+    #[test]
+    fn pending_plan_change_can_be_cancelled() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::run_migrations(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO contracts (id, client_name, plate, plate_upper, vehicle_type, tariff_kind, monthly_amount, included_hours_per_day, date_from, date_to, status, created_at, pending_monthly_amount, pending_billing_period_days, pending_effective_date)
+             VALUES ('CO1', 'Ana', 'ABC123', 'ABC123', 'car', 'none', 90000, 6, '2025-01-01', '2025-01-31', 'active', '2025-01-01T00:00:00+00:00', 120000, 30, '2025-01-31')",
+            [],
+        )
+        .unwrap();
+
+        cancel_plan_change_on(&conn, "CO1", "2025-01-10T00:00:00+00:00", Some("admin")).unwrap();
+        let pending: (Option<Money>, Option<String>) = conn
+            .query_row("SELECT pending_monthly_amount, pending_effective_date FROM contracts WHERE id = 'CO1'", [], |r| {
+                Ok((r.get(0)?, r.get(1)?))
+            })
+            .unwrap();
+        assert_eq!(pending, (None, None));
+        let events: i64 = conn
+            .query_row("SELECT COUNT(*) FROM contract_history WHERE contract_id = 'CO1' AND event = 'plan_change_cancelled'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(events, 1);
+        assert!(cancel_plan_change_on(&conn, "CO1", "2025-01-11T00:00:00+00:00", Some("admin")).is_err());
+    }
+
+    #[test]
+    fn shared_plates_respect_the_simultaneous_quota() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::run_migrations(&conn).unwrap();
+        let today = business_today(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO contracts (id, client_name, plate, plate_upper, vehicle_type, tariff_kind, monthly_amount, included_hours_per_day, date_from, date_to, status, created_at, max_simultaneous_vehicles)
+             VALUES ('CO1', 'Familia', 'AAA111', 'AAA111', 'car', 'none', 100000, 6, '2025-01-01', ?1, 'active', '2025-01-01T00:00:00+00:00', 1)",
+            params![add_days(&today, 30).unwrap()],
+        )
+        .unwrap();
+        save_plates(&conn, "CO1", &normalize_plates("AAA111", &[" bbb222 ".to_string(), "AAA111".to_string()]), "2025-01-01T00:00:00+00:00").unwrap();
+
+        let contract = find_active_contract_for_plate(&conn, "BBB222").unwrap();
+        assert_eq!(contract.id, "CO1");
+        assert_eq!(contract.plates, vec!["AAA111".to_string(), "BBB222".to_string()]);
+        assert!(validate_quota(3, &contract.plates).is_err());
+
+        conn.execute_batch(
+            "INSERT INTO vehicles (id, ticket_code, plate, plate_upper, vehicle_type, entry_time, status) VALUES
+                ('v1', 'T1', 'AAA111', 'AAA111', 'car', '2025-03-01T08:00:00+00:00', 'active'),
+                ('v2', 'T2', 'BBB222', 'BBB222', 'car', '2025-03-01T09:00:00+00:00', 'active');",
+        )
+        .unwrap();
+        assert!(has_free_slot(&conn, &contract, "v1", "2025-03-01T08:00:00+00:00").unwrap());
+        assert!(!has_free_slot(&conn, &contract, "v2", "2025-03-01T09:00:00+00:00").unwrap());
+
+        // Si el primero ya había salido cuando entró el segundo, el cupo estaba libre.
+        conn.execute_batch(
+            "UPDATE vehicles SET status = 'completed', exit_time = '2025-03-01T08:30:00+00:00' WHERE id = 'v1'",
+        )
+        .unwrap();
+        assert!(has_free_slot(&conn, &contract, "v2", "2025-03-01T09:00:00+00:00").unwrap());
+    }
+
+    #[test]
+    fn recorded_payments_cover_periods_and_deposit_the_rest_as_credit() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::run_migrations(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO contracts (id, client_name, plate, plate_upper, vehicle_type, tariff_kind, monthly_amount, included_hours_per_day, date_from, date_to, status, created_at, billing_period_days, end_date)
+             VALUES ('CO1', 'Ana', 'ABC123', 'ABC123', 'car', 'none', 90000, 6, '2025-01-01', '2025-01-01', 'active', '2025-01-01T00:00:00+00:00', 30, '2025-03-02');
+             INSERT INTO shifts (id, operator_user_id, booth, opened_at, status) VALUES ('SH1', 'cashier', 'A', '2025-01-01T00:00:00+00:00', 'open');",
+        )
+        .unwrap();
+        let pay = |amount: Option<f64>| {
+            let args = RecordPaymentArgs { contract_id: "CO1".to_string(), method: "cash".to_string(), amount };
+            record_payment_on(&conn, args, Some("cashier".to_string()))
+        };
+
+        assert!(record_payment_on(
+            &conn,
+            RecordPaymentArgs { contract_id: "CO1".to_string(), method: "cash".to_string(), amount: None },
+            None,
+        )
+        .is_err());
+        let deposit = pay(Some(50000.0)).unwrap();
+        assert_eq!((deposit.date_to.as_str(), deposit.credit_balance), ("2025-01-01", 50000.0));
+        // Sin monto se cobra lo que falta del período, neto del saldo a favor.
+        let paid = pay(None).unwrap();
+        assert_eq!((paid.date_to.as_str(), paid.credit_balance), ("2025-01-31", 0.0));
+        assert_eq!(pay(None).unwrap().date_to, "2025-03-02");
+        assert!(pay(Some(1000.0)).is_err());
+
+        let kinds: Vec<(String, i64, String)> = conn
+            .prepare("SELECT p.kind, p.amount, i.kind FROM contract_payments p JOIN contract_invoices i ON i.payment_id = p.id WHERE p.shift_id = 'SH1' ORDER BY i.number")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            kinds,
+            vec![
+                ("credit".to_string(), 50000, "credit".to_string()),
+                ("period".to_string(), 40000, "period".to_string()),
+                ("period".to_string(), 90000, "period".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn payments_prorate_partial_periods_and_keep_a_credit_balance() {
+        let cop = Currency::new("COP", Some(50.0)).unwrap();
+        let m = Money::from_minor;
+        let date = |s: &str| chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+        let mut billing = BillingState {
+            date_to: date("2025-01-15"),
+            monthly_amount: m(90000),
+            billing_period_days: 30,
+            end_date: Some(date("2025-03-18")),
+            next_period_end: Some(date("2025-02-01")),
+            pending: Some((m(120000), 30, date("2025-02-01"))),
+        };
+
+        // Primer período de 17 días: 51.000; el resto queda a favor. Al llegar al corte rige
+        // el plan nuevo.
+        assert_eq!(billing.next_period(&cop), Some((date("2025-02-01"), m(51000))));
+        assert_eq!(billing.allocate(&cop, m(60000)), (m(9000), true));
+        assert_eq!(billing.date_to, date("2025-02-01"));
+        assert_eq!(billing.next_period_end, None);
+        assert_eq!(billing.monthly_amount, m(120000));
+
+        // Un abono que no completa el período solo suma saldo.
+        assert_eq!(billing.allocate(&cop, m(9000 + 50000)), (m(59000), false));
+        assert_eq!(billing.date_to, date("2025-02-01"));
+
+        // El último período termina en end_date (15 días) y se prorratea.
+        assert_eq!(billing.allocate(&cop, m(59000 + 61000)), (Money::ZERO, false));
+        assert_eq!(billing.date_to, date("2025-03-03"));
+        assert_eq!(billing.next_period(&cop), Some((date("2025-03-18"), m(60000))));
+        assert_eq!(billing.allocate(&cop, m(70000)), (m(10000), false));
+        assert_eq!(billing.next_period(&cop), None);
+    }
+}
@@ -1,8 +1,12 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use chrono_tz::Tz;
 use rusqlite::params;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use tauri::State;
 
+use crate::domains::tariff_rules::{self, TariffRule};
 use crate::id_gen;
+use crate::money::{Currency, Money};
 use crate::permissions;
 use crate::state::AppState;
 
@@ -22,17 +26,39 @@ pub struct CustomTariff {
     pub additional_hour_price: Option<f64>,
     pub additional_duration_hours: Option<i64>,
     pub additional_duration_minutes: Option<i64>,
+    /// Stays up to this many minutes exit for free.
+    pub grace_minutes: Option<i64>,
+    /// Maximum charged per 24h period of the stay.
+    pub daily_max_amount: Option<f64>,
+    /// Penalty added at exit when the ticket is lost.
+    pub lost_ticket_amount: Option<f64>,
     pub created_at: String,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct TariffForCalculation {
-    pub base_price: f64,
+    pub base_price: Money,
     pub base_duration_hours: f64,
-    pub additional_hour_price: Option<f64>,
+    pub additional_hour_price: Option<Money>,
     pub additional_period_hours: f64,
 }
 
+/// Base tariff plus its time-window rules. Minutes not covered by any rule are priced with
+/// `base` (base block + additional blocks); with no rules this is exactly the base tariff.
+/// Amounts are in minor units, also in the `vehicles.tariff_snapshot` JSON.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TariffSchedule {
+    pub base: TariffForCalculation,
+    pub rules: Vec<TariffRule>,
+    pub holidays: Vec<String>,
+    pub grace_minutes: i64,
+    pub daily_max_amount: Option<Money>,
+    pub lost_ticket_amount: Option<Money>,
+}
+
+/// Built-in rates in major units of the configured currency.
 const FALLBACK_RATES: &[(&str, f64)] = &[
     ("car", 4000.0),
     ("motorcycle", 2500.0),
@@ -48,9 +74,10 @@ const FALLBACK_ADDITIONAL: &[(&str, f64)] = &[
 ];
 
 #[allow(dead_code)]
-pub fn get_default_rate_from_db(conn: &rusqlite::Connection, vehicle_type: &str) -> Result<f64, String> {
+pub fn get_default_rate_from_db(conn: &rusqlite::Connection, vehicle_type: &str) -> Result<Money, String> {
     let normalized = vehicle_type.trim().to_lowercase();
-    let result: Option<f64> = conn
+    let currency = crate::money::currency(conn)?;
+    let result: Option<Money> = conn
         .query_row(
             "SELECT amount FROM custom_tariffs WHERE vehicle_type = ?1 AND (plate_or_ref IS NULL OR plate_or_ref = '') AND (tariff_kind IS NULL OR tariff_kind = 'regular') LIMIT 1",
             params![normalized],
@@ -58,92 +85,370 @@ pub fn get_default_rate_from_db(conn: &rusqlite::Connection, vehicle_type: &str)
         )
         .ok();
     Ok(result.unwrap_or_else(|| {
-        FALLBACK_RATES
-            .iter()
-            .find(|(t, _)| *t == normalized)
-            .map(|(_, a)| *a)
-            .unwrap_or(4000.0)
+        currency.money(
+            FALLBACK_RATES
+                .iter()
+                .find(|(t, _)| *t == normalized)
+                .map(|(_, a)| *a)
+                .unwrap_or(4000.0),
+        )
     }))
 }
 
+/// Schedule of the generic tariff (no plate) for a vehicle type and kind; built-in fallback
+/// rates when none is configured.
 pub fn get_tariff_for_calculation(
     conn: &rusqlite::Connection,
     vehicle_type: &str,
     tariff_kind: &str,
-) -> Result<TariffForCalculation, String> {
+) -> Result<TariffSchedule, String> {
     let normalized_type = vehicle_type.trim().to_lowercase();
     let normalized_kind = tariff_kind.trim().to_lowercase();
     let kind = if normalized_kind.is_empty() { "regular" } else { &normalized_kind };
 
-    let result: Option<(f64, Option<i64>, Option<i64>, Option<f64>, Option<i64>, Option<i64>)> = conn
+    let tariff_id: Option<String> = conn
         .query_row(
-            r#"SELECT amount, rate_duration_hours, rate_duration_minutes, additional_hour_price,
-                      additional_duration_hours, additional_duration_minutes
+            r#"SELECT id
                FROM custom_tariffs
                WHERE vehicle_type = ?1
                  AND (plate_or_ref IS NULL OR plate_or_ref = '')
                  AND (tariff_kind = ?2 OR (tariff_kind IS NULL AND ?2 = 'regular'))
                LIMIT 1"#,
             params![normalized_type, kind],
-            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?)),
+            |row| row.get(0),
         )
         .ok();
 
-    match result {
-        Some((base_price, dur_h, dur_m, add_price, add_dur_h, add_dur_m)) => {
-            let hours = dur_h.unwrap_or(1).max(0) as f64;
-            let minutes = dur_m.unwrap_or(0).max(0) as f64;
-            let base_duration = hours + minutes / 60.0;
-            let ad_h = add_dur_h.unwrap_or(1).max(0) as f64;
-            let ad_m = add_dur_m.unwrap_or(0).max(0) as f64;
-            let additional_period = ad_h + ad_m / 60.0;
-            Ok(TariffForCalculation {
-                base_price,
-                base_duration_hours: if base_duration > 0.0 { base_duration } else { 1.0 },
-                additional_hour_price: add_price,
-                additional_period_hours: if additional_period > 0.0 { additional_period } else { 1.0 },
-            })
+    if let Some(id) = tariff_id {
+        if let Some(schedule) = get_schedule_by_tariff_id(conn, &id)? {
+            return Ok(schedule);
         }
-        None => {
-            let base = FALLBACK_RATES
-                .iter()
-                .find(|(t, _)| *t == normalized_type)
-                .map(|(_, a)| *a)
-                .unwrap_or(4000.0);
-            let add = FALLBACK_ADDITIONAL
-                .iter()
-                .find(|(t, _)| *t == normalized_type)
-                .map(|(_, a)| *a)
-                .unwrap_or(1000.0);
-            Ok(TariffForCalculation {
-                base_price: base,
-                base_duration_hours: 1.0,
-                additional_hour_price: Some(add),
-                additional_period_hours: 1.0,
-            })
+    }
+    Ok(fallback_schedule(&normalized_type, &crate::money::currency(conn)?))
+}
+
+/// Schedule of one tariff row (generic or plate-specific). None if the row does not exist.
+pub fn get_schedule_by_tariff_id(
+    conn: &rusqlite::Connection,
+    tariff_id: &str,
+) -> Result<Option<TariffSchedule>, String> {
+    let result: Option<(Money, Option<i64>, Option<i64>, Option<Money>, Option<i64>, Option<i64>, Option<i64>, Option<Money>, Option<Money>)> = conn
+        .query_row(
+            r#"SELECT amount, rate_duration_hours, rate_duration_minutes, additional_hour_price,
+                      additional_duration_hours, additional_duration_minutes,
+                      grace_minutes, daily_max_amount, lost_ticket_amount
+               FROM custom_tariffs
+               WHERE id = ?1"#,
+            params![tariff_id],
+            |row| {
+                Ok((
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                    row.get(5)?,
+                    row.get(6)?,
+                    row.get(7)?,
+                    row.get(8)?,
+                ))
+            },
+        )
+        .ok();
+
+    let (base_price, dur_h, dur_m, add_price, add_dur_h, add_dur_m, grace_minutes, daily_max_amount, lost_ticket_amount) =
+        match result {
+            Some(r) => r,
+            None => return Ok(None),
+        };
+    let hours = dur_h.unwrap_or(1).max(0) as f64;
+    let minutes = dur_m.unwrap_or(0).max(0) as f64;
+    let base_duration = hours + minutes / 60.0;
+    let ad_h = add_dur_h.unwrap_or(1).max(0) as f64;
+    let ad_m = add_dur_m.unwrap_or(0).max(0) as f64;
+    let additional_period = ad_h + ad_m / 60.0;
+    let base = TariffForCalculation {
+        base_price,
+        base_duration_hours: if base_duration > 0.0 { base_duration } else { 1.0 },
+        additional_hour_price: add_price,
+        additional_period_hours: if additional_period > 0.0 { additional_period } else { 1.0 },
+    };
+
+    let rules = tariff_rules::load_rules_for_tariff(conn, tariff_id)?;
+    let holidays = if rules.is_empty() {
+        Vec::new()
+    } else {
+        tariff_rules::load_holiday_dates(conn)?
+    };
+    Ok(Some(TariffSchedule {
+        base,
+        rules,
+        holidays,
+        grace_minutes: grace_minutes.unwrap_or(0).max(0),
+        daily_max_amount: daily_max_amount.filter(|m| *m > Money::ZERO),
+        lost_ticket_amount: lost_ticket_amount.filter(|a| *a > Money::ZERO),
+    }))
+}
+
+fn fallback_schedule(vehicle_type: &str, currency: &Currency) -> TariffSchedule {
+    let base = FALLBACK_RATES
+        .iter()
+        .find(|(t, _)| *t == vehicle_type)
+        .map(|(_, a)| *a)
+        .unwrap_or(4000.0);
+    let add = FALLBACK_ADDITIONAL
+        .iter()
+        .find(|(t, _)| *t == vehicle_type)
+        .map(|(_, a)| *a)
+        .unwrap_or(1000.0);
+    TariffSchedule {
+        base: TariffForCalculation {
+            base_price: currency.money(base),
+            base_duration_hours: 1.0,
+            additional_hour_price: Some(currency.money(add)),
+            additional_period_hours: 1.0,
+        },
+        rules: Vec::new(),
+        holidays: Vec::new(),
+        grace_minutes: 0,
+        daily_max_amount: None,
+        lost_ticket_amount: None,
+    }
+}
+
+/// JSON snapshot of the schedule a session will be priced with, stored in
+/// `vehicles.tariff_snapshot` at entry so later tariff edits do not affect parked vehicles.
+pub fn snapshot_for_entry(
+    conn: &rusqlite::Connection,
+    tariff_id: Option<&str>,
+    vehicle_type: &str,
+    tariff_kind: &str,
+) -> Result<String, String> {
+    let schedule = match tariff_id {
+        Some(id) => get_schedule_by_tariff_id(conn, id)?,
+        None => None,
+    };
+    let schedule = match schedule {
+        Some(s) => s,
+        None => get_tariff_for_calculation(conn, vehicle_type, tariff_kind)?,
+    };
+    serde_json::to_string(&schedule).map_err(|e| e.to_string())
+}
+
+/// Schedule used to price a session: its entry snapshot; only for sessions registered before
+/// snapshots existed (no snapshot stored), the tariff resolved at entry (`tariff_id`, may be
+/// plate-specific), then the generic tariff of the type and kind. A snapshot that cannot be
+/// read is an error rather than a silent switch to the live tariff.
+pub fn schedule_for_session(
+    conn: &rusqlite::Connection,
+    vehicle_id: &str,
+    tariff_id: Option<&str>,
+    vehicle_type: &str,
+    tariff_kind: &str,
+) -> Result<TariffSchedule, String> {
+    let snapshot: Option<String> = match conn.query_row(
+        "SELECT tariff_snapshot FROM vehicles WHERE id = ?1",
+        params![vehicle_id],
+        |row| row.get(0),
+    ) {
+        Ok(snapshot) => snapshot,
+        Err(rusqlite::Error::QueryReturnedNoRows) => None,
+        Err(e) => return Err(e.to_string()),
+    };
+    if let Some(json) = snapshot {
+        return serde_json::from_str::<TariffSchedule>(&json)
+            .map_err(|e| format!("Invalid tariff snapshot for session {}: {}", vehicle_id, e));
+    }
+    if let Some(id) = tariff_id {
+        if let Some(schedule) = get_schedule_by_tariff_id(conn, id)? {
+            return Ok(schedule);
         }
     }
+    get_tariff_for_calculation(conn, vehicle_type, tariff_kind)
+}
+
+/// Itemised price of a stay. `total` is what gets charged; the discount fields are
+/// informative and already subtracted from it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CostBreakdown {
+    /// Base block(s) of the base tariff.
+    pub base_amount: Money,
+    pub additional_blocks: f64,
+    pub additional_amount: Money,
+    /// Charged by time-window rules (night, weekend, holiday).
+    pub rules_amount: Money,
+    /// Amount waived because the stay ended within the grace window.
+    pub grace_discount: Money,
+    /// Amount waived by the daily maximum.
+    pub daily_max_discount: Money,
+    pub total: Money,
 }
 
-pub fn calculate_parking_cost(tariff: &TariffForCalculation, duration_minutes: f64) -> f64 {
+pub fn calculate_parking_cost(tariff: &TariffForCalculation, duration_minutes: f64) -> Money {
+    parking_cost_breakdown(tariff, duration_minutes).total
+}
+
+/// Base tariff price split into base block and additional blocks.
+pub fn parking_cost_breakdown(tariff: &TariffForCalculation, duration_minutes: f64) -> CostBreakdown {
     let hours = (duration_minutes / 60.0).max(0.0);
     let base_price = tariff.base_price;
     if hours <= tariff.base_duration_hours {
-        return base_price;
+        return CostBreakdown {
+            base_amount: base_price,
+            total: base_price,
+            ..Default::default()
+        };
     }
     let overflow_hours = hours - tariff.base_duration_hours;
     let period_h = tariff.additional_period_hours.max(1.0 / 60.0);
     let additional_blocks = (overflow_hours / period_h).ceil().max(0.0);
     let additional_cost = match tariff.additional_hour_price {
-        Some(price) => additional_blocks * price,
-        None => additional_blocks * (tariff.base_price / tariff.base_duration_hours),
+        Some(price) => price.times(additional_blocks),
+        None => tariff.base_price.times(additional_blocks / tariff.base_duration_hours),
+    };
+    CostBreakdown {
+        base_amount: base_price,
+        additional_blocks,
+        additional_amount: additional_cost,
+        total: base_price + additional_cost,
+        ..Default::default()
+    }
+}
+
+pub fn calculate_scheduled_cost(schedule: &TariffSchedule, tz: Tz, entry: DateTime<Utc>, exit: DateTime<Utc>) -> Money {
+    scheduled_cost_breakdown(schedule, tz, entry, exit).total
+}
+
+/// Prices a stay against a schedule: walks the stay minute by minute, groups consecutive
+/// minutes by (rule, window occurrence), prices each group with its rule and prices each
+/// stretch of consecutive minutes no rule covers with the base tariff on its own (a stay
+/// 17:00 -> 07:00 around a night rule pays the base block twice). Without rules =
+/// `parking_cost_breakdown`. Rule windows are expressed in local time of `tz` (the business
+/// timezone).
+pub fn scheduled_cost_breakdown(schedule: &TariffSchedule, tz: Tz, entry: DateTime<Utc>, exit: DateTime<Utc>) -> CostBreakdown {
+    if schedule.rules.is_empty() {
+        return parking_cost_breakdown(&schedule.base, (exit - entry).num_seconds().max(0) as f64 / 60.0);
+    }
+    price_runs(schedule, &stay_runs(schedule, tz, entry, exit))
+}
+
+/// Consecutive minutes of a stay priced the same way: by one occurrence of a rule
+/// (`rule` = (index, window day)) or, with `rule` None, by the base tariff.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Run {
+    rule: Option<(usize, NaiveDate)>,
+    minutes: i64,
+}
+
+fn stay_runs(schedule: &TariffSchedule, tz: Tz, entry: DateTime<Utc>, exit: DateTime<Utc>) -> Vec<Run> {
+    let total_minutes = ((exit - entry).num_seconds().max(0) as f64 / 60.0).ceil() as i64;
+    let mut runs: Vec<Run> = Vec::new();
+    for minute in 0..total_minutes {
+        let local = (entry + chrono::Duration::minutes(minute))
+            .with_timezone(&tz)
+            .naive_local();
+        let rule = schedule
+            .rules
+            .iter()
+            .enumerate()
+            .find_map(|(idx, rule)| rule.occurrence_for(local, &schedule.holidays).map(|day| (idx, day)));
+        match runs.last_mut() {
+            Some(last) if last.rule == rule => last.minutes += 1,
+            _ => runs.push(Run { rule, minutes: 1 }),
+        }
+    }
+    runs
+}
+
+fn price_runs(schedule: &TariffSchedule, runs: &[Run]) -> CostBreakdown {
+    // Sin minutos (entrada y salida en el mismo instante) se cobra el bloque base.
+    if runs.is_empty() {
+        return parking_cost_breakdown(&schedule.base, 0.0);
+    }
+    let mut breakdown = CostBreakdown::default();
+    for run in runs {
+        match run.rule {
+            Some((idx, _)) => {
+                let cost = schedule.rules[idx].price_for_run(run.minutes);
+                breakdown.rules_amount += cost;
+                breakdown.total += cost;
+            }
+            None => {
+                let segment = parking_cost_breakdown(&schedule.base, run.minutes as f64);
+                breakdown.base_amount += segment.base_amount;
+                breakdown.additional_blocks += segment.additional_blocks;
+                breakdown.additional_amount += segment.additional_amount;
+                breakdown.total += segment.total;
+            }
+        }
+    }
+    breakdown
+}
+
+pub fn calculate_stay_cost(schedule: &TariffSchedule, tz: Tz, entry: DateTime<Utc>, exit: DateTime<Utc>) -> Money {
+    stay_cost_breakdown(schedule, tz, entry, exit).total
+}
+
+/// Full stay price: free within the grace window, otherwise the scheduled cost with each
+/// 24h period (counted from entry) capped at `daily_max_amount` when set. The stay is walked
+/// once; the cost up to each period end is priced from the same runs.
+pub fn stay_cost_breakdown(schedule: &TariffSchedule, tz: Tz, entry: DateTime<Utc>, exit: DateTime<Utc>) -> CostBreakdown {
+    let runs = if schedule.rules.is_empty() { None } else { Some(stay_runs(schedule, tz, entry, exit)) };
+    let cost_until = |minutes: f64| match &runs {
+        Some(runs) => price_runs(schedule, &runs_until(runs, minutes.ceil() as i64)),
+        None => parking_cost_breakdown(&schedule.base, minutes),
     };
-    base_price + additional_cost
+    let duration_seconds = (exit - entry).num_seconds().max(0);
+    let duration_minutes = duration_seconds as f64 / 60.0;
+    let mut breakdown = cost_until(duration_minutes);
+    if schedule.grace_minutes > 0 && duration_seconds <= schedule.grace_minutes * 60 {
+        breakdown.grace_discount = breakdown.total;
+        breakdown.total = Money::ZERO;
+        return breakdown;
+    }
+    let daily_max = match schedule.daily_max_amount {
+        Some(max) => max,
+        None => return breakdown,
+    };
+    let capped = if duration_seconds == 0 {
+        breakdown.total.min(daily_max)
+    } else {
+        let mut total = Money::ZERO;
+        let mut cost_so_far = Money::ZERO;
+        let mut period_start = 0.0;
+        while period_start < duration_minutes {
+            let period_end = (period_start + 24.0 * 60.0).min(duration_minutes);
+            // Incremental cost of this period = cost up to its end minus cost up to its start.
+            let cost_to_end = if period_end < duration_minutes { cost_until(period_end).total } else { breakdown.total };
+            total += (cost_to_end - cost_so_far).min(daily_max);
+            cost_so_far = cost_to_end;
+            period_start = period_end;
+        }
+        total
+    };
+    breakdown.daily_max_discount = (breakdown.total - capped).max(Money::ZERO);
+    breakdown.total = capped;
+    breakdown
+}
+
+/// The first `minutes` minutes of a stay's runs.
+fn runs_until(runs: &[Run], minutes: i64) -> Vec<Run> {
+    let mut left = minutes;
+    let mut prefix = Vec::new();
+    for run in runs {
+        if left <= 0 {
+            break;
+        }
+        prefix.push(Run { minutes: run.minutes.min(left), ..*run });
+        left -= run.minutes;
+    }
+    prefix
 }
 
 const VALID_RATE_UNITS: &[&str] = &["hour", "minute"];
 
-fn row_to_tariff(row: &rusqlite::Row) -> rusqlite::Result<CustomTariff> {
+fn row_to_tariff(row: &rusqlite::Row, currency: &Currency) -> rusqlite::Result<CustomTariff> {
     let plate: String = row.get("plate_or_ref")?;
     let name: Option<String> = row
         .get::<_, Option<String>>("name")
@@ -166,23 +471,29 @@ fn row_to_tariff(row: &rusqlite::Row) -> rusqlite::Result<CustomTariff> {
         .ok()
         .flatten()
         .unwrap_or_else(|| "regular".to_string());
-    let additional_hour_price: Option<f64> = row.get("additional_hour_price").ok().flatten();
+    let additional_hour_price: Option<Money> = row.get("additional_hour_price").ok().flatten();
     let additional_duration_hours: Option<i64> = row.get("additional_duration_hours").ok().flatten();
     let additional_duration_minutes: Option<i64> = row.get("additional_duration_minutes").ok().flatten();
+    let grace_minutes: Option<i64> = row.get("grace_minutes").ok().flatten();
+    let daily_max_amount: Option<Money> = row.get("daily_max_amount").ok().flatten();
+    let lost_ticket_amount: Option<Money> = row.get("lost_ticket_amount").ok().flatten();
     Ok(CustomTariff {
         id: row.get("id")?,
         vehicle_type: row.get::<_, Option<String>>("vehicle_type")?.unwrap_or_else(|| "car".to_string()),
         name,
         plate_or_ref: if plate.is_empty() { None } else { Some(plate) },
         description: row.get("description")?,
-        amount: row.get("amount")?,
+        amount: currency.major(row.get("amount")?),
         rate_unit: rate_unit.or_else(|| Some("hour".to_string())),
         rate_duration_hours: dur_h,
         rate_duration_minutes: dur_m,
         tariff_kind,
-        additional_hour_price,
+        additional_hour_price: additional_hour_price.map(|p| currency.major(p)),
         additional_duration_hours,
         additional_duration_minutes,
+        grace_minutes,
+        daily_max_amount: daily_max_amount.map(|m| currency.major(m)),
+        lost_ticket_amount: lost_ticket_amount.map(|a| currency.major(a)),
         created_at: row.get("created_at")?,
     })
 }
@@ -194,14 +505,15 @@ pub fn custom_tariffs_list(
 ) -> Result<Vec<CustomTariff>, String> {
     state.check_permission(permissions::CAJA_TRANSACTIONS_READ)?;
     let conn = state.db.get().map_err(|e| e.to_string())?;
+    let currency = crate::money::currency(&conn)?;
 
     let (sql, param): (String, Option<String>) = match search.as_deref().map(str::trim) {
         Some(s) if !s.is_empty() => (
-            "SELECT id, vehicle_type, name, plate_or_ref, description, amount, rate_unit, rate_duration_hours, rate_duration_minutes, tariff_kind, additional_hour_price, additional_duration_hours, additional_duration_minutes, created_at FROM custom_tariffs WHERE name LIKE ?1 OR plate_or_ref LIKE ?1 OR description LIKE ?1 OR vehicle_type LIKE ?1 OR tariff_kind LIKE ?1 ORDER BY tariff_kind ASC, vehicle_type ASC, COALESCE(plate_or_ref, '') ASC LIMIT 100".to_string(),
+            "SELECT id, vehicle_type, name, plate_or_ref, description, amount, rate_unit, rate_duration_hours, rate_duration_minutes, tariff_kind, additional_hour_price, additional_duration_hours, additional_duration_minutes, grace_minutes, daily_max_amount, lost_ticket_amount, created_at FROM custom_tariffs WHERE name LIKE ?1 OR plate_or_ref LIKE ?1 OR description LIKE ?1 OR vehicle_type LIKE ?1 OR tariff_kind LIKE ?1 ORDER BY tariff_kind ASC, vehicle_type ASC, COALESCE(plate_or_ref, '') ASC LIMIT 100".to_string(),
             Some(format!("%{}%", s)),
         ),
         _ => (
-            "SELECT id, vehicle_type, name, plate_or_ref, description, amount, rate_unit, rate_duration_hours, rate_duration_minutes, tariff_kind, additional_hour_price, additional_duration_hours, additional_duration_minutes, created_at FROM custom_tariffs ORDER BY tariff_kind ASC, vehicle_type ASC, COALESCE(plate_or_ref, '') ASC LIMIT 100".to_string(),
+            "SELECT id, vehicle_type, name, plate_or_ref, description, amount, rate_unit, rate_duration_hours, rate_duration_minutes, tariff_kind, additional_hour_price, additional_duration_hours, additional_duration_minutes, grace_minutes, daily_max_amount, lost_ticket_amount, created_at FROM custom_tariffs ORDER BY tariff_kind ASC, vehicle_type ASC, COALESCE(plate_or_ref, '') ASC LIMIT 100".to_string(),
             None,
         ),
     };
@@ -210,14 +522,14 @@ pub fn custom_tariffs_list(
         Some(p) => {
             let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
             let rows = stmt
-                .query_map(params![p], |row| row_to_tariff(&row))
+                .query_map(params![p], |row| row_to_tariff(row, &currency))
                 .map_err(|e| e.to_string())?;
             rows.filter_map(|r| r.ok()).collect()
         }
         None => {
             let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
             let rows = stmt
-                .query_map([], |row| row_to_tariff(&row))
+                .query_map([], |row| row_to_tariff(row, &currency))
                 .map_err(|e| e.to_string())?;
             rows.filter_map(|r| r.ok()).collect()
         }
@@ -243,6 +555,9 @@ pub(crate) struct CreateCustomTariffArgs {
     additional_hour_price: Option<f64>,
     additional_duration_hours: Option<i64>,
     additional_duration_minutes: Option<i64>,
+    grace_minutes: Option<i64>,
+    daily_max_amount: Option<f64>,
+    lost_ticket_amount: Option<f64>,
 }
 
 #[tauri::command]
@@ -295,9 +610,14 @@ pub fn custom_tariffs_create(
         .filter(|s| VALID_TARIFF_KINDS.contains(&s.as_str()))
         .unwrap_or_else(|| "regular".to_string());
     let additional_hour_price = args.additional_hour_price.filter(|p| *p >= 0.0);
+    let grace_minutes = args.grace_minutes.filter(|m| *m > 0);
+    let daily_max_amount = validate_daily_max(args.daily_max_amount)?;
+    let lost_ticket_amount = args.lost_ticket_amount.filter(|a| *a > 0.0);
 
     let name_key = name.as_deref().map(str::trim).unwrap_or("");
     let conn = state.db.get().map_err(|e| e.to_string())?;
+    let currency = crate::money::currency(&conn)?;
+    let money = |major: Option<f64>| major.map(|m| currency.money(m));
     let exists: i64 = conn
         .query_row(
             "SELECT COUNT(*) FROM custom_tariffs WHERE vehicle_type = ?1 AND COALESCE(plate_or_ref, '') = ?2 AND tariff_kind = ?3 AND COALESCE(TRIM(name), '') = ?4",
@@ -313,25 +633,47 @@ pub fn custom_tariffs_create(
     let created_at = chrono::Utc::now().to_rfc3339();
 
     conn.execute(
-        "INSERT INTO custom_tariffs (id, vehicle_type, name, plate_or_ref, description, amount, rate_unit, rate_duration_hours, rate_duration_minutes, tariff_kind, additional_hour_price, additional_duration_hours, additional_duration_minutes, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
-        params![id, vehicle_type, name, plate_key, description, amount, rate_unit, dur_h, dur_m, tariff_kind, additional_hour_price, add_dur_h, add_dur_m, created_at],
+        "INSERT INTO custom_tariffs (id, vehicle_type, name, plate_or_ref, description, amount, rate_unit, rate_duration_hours, rate_duration_minutes, tariff_kind, additional_hour_price, additional_duration_hours, additional_duration_minutes, grace_minutes, daily_max_amount, lost_ticket_amount, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)",
+        params![
+            id,
+            vehicle_type,
+            name,
+            plate_key,
+            description,
+            currency.money(amount),
+            rate_unit,
+            dur_h,
+            dur_m,
+            tariff_kind,
+            money(additional_hour_price),
+            add_dur_h,
+            add_dur_m,
+            grace_minutes,
+            money(daily_max_amount),
+            money(lost_ticket_amount),
+            created_at
+        ],
     )
     .map_err(|e| e.to_string())?;
 
+    let major = |amount: Option<f64>| money(amount).map(|m| currency.major(m));
     Ok(CustomTariff {
         id,
         vehicle_type,
         name,
         plate_or_ref,
         description,
-        amount,
+        amount: currency.major(currency.money(amount)),
         rate_unit: Some(rate_unit),
         rate_duration_hours: Some(dur_h),
         rate_duration_minutes: Some(dur_m),
         tariff_kind,
-        additional_hour_price,
+        additional_hour_price: major(additional_hour_price),
         additional_duration_hours: Some(add_dur_h),
         additional_duration_minutes: Some(add_dur_m),
+        grace_minutes,
+        daily_max_amount: major(daily_max_amount),
+        lost_ticket_amount: major(lost_ticket_amount),
         created_at,
     })
 }
@@ -352,6 +694,24 @@ pub(crate) struct UpdateCustomTariffArgs {
     additional_hour_price: Option<f64>,
     additional_duration_hours: Option<i64>,
     additional_duration_minutes: Option<i64>,
+    grace_minutes: Option<i64>,
+    /// Absent keeps the current cap, `null` clears it.
+    #[serde(default, deserialize_with = "present")]
+    daily_max_amount: Option<Option<f64>>,
+    lost_ticket_amount: Option<f64>,
+}
+
+/// Tells an absent field (None) from an explicit `null` (Some(None)).
+fn present<'de, D: Deserializer<'de>, T: Deserialize<'de>>(deserializer: D) -> Result<Option<Option<T>>, D::Error> {
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// A daily max must be a positive amount; "no cap" is None, not 0.
+fn validate_daily_max(amount: Option<f64>) -> Result<Option<f64>, String> {
+    match amount {
+        Some(m) if !m.is_finite() || m <= 0.0 => Err("Daily max must be greater than 0 (leave it empty for no cap)".to_string()),
+        other => Ok(other),
+    }
 }
 
 #[tauri::command]
@@ -362,12 +722,14 @@ pub fn custom_tariffs_update(
     state.check_permission(permissions::CAJA_TRANSACTIONS_MODIFY)?;
     let id = args.id.trim().to_string();
     let conn = state.db.get().map_err(|e| e.to_string())?;
+    let currency = crate::money::currency(&conn)?;
+    let money = |major: Option<f64>| major.map(|m| currency.money(m));
 
     let existing = conn
         .query_row(
-            "SELECT id, vehicle_type, name, plate_or_ref, description, amount, rate_unit, rate_duration_hours, rate_duration_minutes, tariff_kind, additional_hour_price, additional_duration_hours, additional_duration_minutes, created_at FROM custom_tariffs WHERE id = ?1",
+            "SELECT id, vehicle_type, name, plate_or_ref, description, amount, rate_unit, rate_duration_hours, rate_duration_minutes, tariff_kind, additional_hour_price, additional_duration_hours, additional_duration_minutes, grace_minutes, daily_max_amount, lost_ticket_amount, created_at FROM custom_tariffs WHERE id = ?1",
             params![&id],
-            |row| row_to_tariff(row),
+            |row| row_to_tariff(row, &currency),
         )
         .ok();
 
@@ -435,9 +797,33 @@ pub fn custom_tariffs_update(
                 return Err("Additional period must be at least 1 minute (hours and/or minutes)".to_string());
             }
             let new_additional_hour_price = args.additional_hour_price.or(existing_tariff.additional_hour_price);
+            // 0 clears grace / lost-ticket penalty; the daily max is cleared with an explicit null.
+            let new_grace_minutes = args.grace_minutes.or(existing_tariff.grace_minutes).filter(|m| *m > 0);
+            let new_daily_max_amount = match args.daily_max_amount {
+                Some(amount) => validate_daily_max(amount)?,
+                None => existing_tariff.daily_max_amount,
+            };
+            let new_lost_ticket_amount = args.lost_ticket_amount.or(existing_tariff.lost_ticket_amount).filter(|a| *a > 0.0);
             conn.execute(
-                "UPDATE custom_tariffs SET vehicle_type = ?1, name = ?2, plate_or_ref = ?3, description = ?4, amount = ?5, rate_unit = ?6, rate_duration_hours = ?7, rate_duration_minutes = ?8, tariff_kind = ?9, additional_hour_price = ?10, additional_duration_hours = ?11, additional_duration_minutes = ?12 WHERE id = ?13",
-                params![new_vehicle_type, new_name, new_plate_key, new_description, new_amount, new_rate_unit, new_dur_h, new_dur_m, new_tariff_kind, new_additional_hour_price, new_add_dur_h, new_add_dur_m, &id],
+                "UPDATE custom_tariffs SET vehicle_type = ?1, name = ?2, plate_or_ref = ?3, description = ?4, amount = ?5, rate_unit = ?6, rate_duration_hours = ?7, rate_duration_minutes = ?8, tariff_kind = ?9, additional_hour_price = ?10, additional_duration_hours = ?11, additional_duration_minutes = ?12, grace_minutes = ?13, daily_max_amount = ?14, lost_ticket_amount = ?15 WHERE id = ?16",
+                params![
+                    new_vehicle_type,
+                    new_name,
+                    new_plate_key,
+                    new_description,
+                    currency.money(new_amount),
+                    new_rate_unit,
+                    new_dur_h,
+                    new_dur_m,
+                    new_tariff_kind,
+                    money(new_additional_hour_price),
+                    new_add_dur_h,
+                    new_add_dur_m,
+                    new_grace_minutes,
+                    money(new_daily_max_amount),
+                    money(new_lost_ticket_amount),
+                    &id
+                ],
             )
             .map_err(|e| e.to_string())?;
         }
@@ -445,9 +831,9 @@ pub fn custom_tariffs_update(
     }
 
     conn.query_row(
-        "SELECT id, vehicle_type, name, plate_or_ref, description, amount, rate_unit, rate_duration_hours, rate_duration_minutes, tariff_kind, additional_hour_price, additional_duration_hours, additional_duration_minutes, created_at FROM custom_tariffs WHERE id = ?1",
+        "SELECT id, vehicle_type, name, plate_or_ref, description, amount, rate_unit, rate_duration_hours, rate_duration_minutes, tariff_kind, additional_hour_price, additional_duration_hours, additional_duration_minutes, grace_minutes, daily_max_amount, lost_ticket_amount, created_at FROM custom_tariffs WHERE id = ?1",
         params![&id],
-        |row| row_to_tariff(row),
+        |row| row_to_tariff(row, &currency),
     )
     .map_err(|e| e.to_string())
 }
@@ -456,6 +842,8 @@ pub fn custom_tariffs_update(
 pub fn custom_tariffs_delete(state: State<AppState>, id: String) -> Result<(), String> {
     state.check_permission(permissions::CAJA_TRANSACTIONS_MODIFY)?;
     let conn = state.db.get().map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM tariff_rules WHERE tariff_id = ?1", params![id.trim()])
+        .map_err(|e| e.to_string())?;
     let n = conn
         .execute("DELETE FROM custom_tariffs WHERE id = ?1", params![id.trim()])
         .map_err(|e| e.to_string())?;
@@ -464,3 +852,165 @@ pub fn custom_tariffs_delete(state: State<AppState>, id: String) -> Result<(), S
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// COP has no decimals: minor units = pesos.
+    fn cop(amount: i64) -> Money {
+        Money::from_minor(amount)
+    }
+
+    fn base() -> TariffForCalculation {
+        TariffForCalculation {
+            base_price: cop(4000),
+            base_duration_hours: 1.0,
+            additional_hour_price: Some(cop(1000)),
+            additional_period_hours: 1.0,
+        }
+    }
+
+    /// Base tariff with `rules`, no holidays, grace, daily max or lost-ticket penalty.
+    fn tariff_schedule(rules: Vec<TariffRule>) -> TariffSchedule {
+        TariffSchedule {
+            base: base(),
+            rules,
+            holidays: vec![],
+            grace_minutes: 0,
+            daily_max_amount: None,
+            lost_ticket_amount: None,
+        }
+    }
+
+    fn rule(start: &str, end: &str, weekdays: Vec<u8>, pricing: &str, amount: i64) -> TariffRule {
+        TariffRule {
+            id: format!("rule_{}_{}", start, end),
+            tariff_id: "t".to_string(),
+            name: None,
+            weekdays,
+            start_time: start.to_string(),
+            end_time: end.to_string(),
+            holidays: "any".to_string(),
+            pricing: pricing.to_string(),
+            amount: cop(amount),
+            block_minutes: Some(60),
+            priority: 0,
+            created_at: String::new(),
+        }
+    }
+
+    const BOGOTA: Tz = chrono_tz::America::Bogota;
+
+    /// Local Colombia time -> UTC.
+    fn at(local: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(&format!("{}-05:00", local))
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn schedule_without_rules_matches_base_tariff() {
+        let schedule = tariff_schedule(vec![]);
+        let cost = calculate_scheduled_cost(&schedule, BOGOTA, at("2025-03-03T10:00:00"), at("2025-03-03T12:30:00"));
+        assert_eq!(cost, calculate_parking_cost(&base(), 150.0));
+        assert_eq!(cost, cop(6000));
+    }
+
+    #[test]
+    fn night_flat_rate_is_added_to_day_minutes() {
+        // 17:00 -> 07:00 next day: 2h day (17-19) + night flat + 1h day (06-07); each day
+        // stretch pays the base tariff on its own.
+        let schedule = tariff_schedule(vec![rule("19:00", "06:00", vec![], "flat", 8000)]);
+        let breakdown = scheduled_cost_breakdown(&schedule, BOGOTA, at("2025-03-03T17:00:00"), at("2025-03-04T07:00:00"));
+        assert_eq!(
+            breakdown.total,
+            cop(8000) + calculate_parking_cost(&base(), 120.0) + calculate_parking_cost(&base(), 60.0)
+        );
+        assert_eq!(breakdown.total, cop(8000) + cop(5000) + cop(4000));
+        assert_eq!(breakdown.base_amount, cop(8000));
+        assert_eq!(breakdown.additional_blocks, 1.0);
+        assert_eq!(breakdown.rules_amount, cop(8000));
+
+        // Entirely inside the night window: only the rule.
+        let night = calculate_scheduled_cost(&schedule, BOGOTA, at("2025-03-03T20:00:00"), at("2025-03-04T05:00:00"));
+        assert_eq!(night, cop(8000));
+    }
+
+    #[test]
+    fn weekend_rule_applies_per_day_and_respects_holidays() {
+        // Saturday 2025-03-08 and Sunday 2025-03-09, all-day rule: two occurrences.
+        let schedule = tariff_schedule(vec![rule("00:00", "00:00", vec![6, 7], "flat", 10000)]);
+        let cost = calculate_scheduled_cost(&schedule, BOGOTA, at("2025-03-08T10:00:00"), at("2025-03-09T10:00:00"));
+        assert_eq!(cost, cop(20000));
+
+        let mut holiday_rule = rule("00:00", "00:00", vec![], "per_block", 500);
+        holiday_rule.holidays = "only".to_string();
+        let schedule = TariffSchedule {
+            holidays: vec!["2025-03-24".to_string()],
+            ..tariff_schedule(vec![holiday_rule])
+        };
+        let on_holiday = calculate_scheduled_cost(&schedule, BOGOTA, at("2025-03-24T10:00:00"), at("2025-03-24T12:30:00"));
+        assert_eq!(on_holiday, cop(1500));
+        let regular_day = calculate_scheduled_cost(&schedule, BOGOTA, at("2025-03-25T10:00:00"), at("2025-03-25T12:30:00"));
+        assert_eq!(regular_day, cop(6000));
+    }
+
+    #[test]
+    fn grace_period_and_daily_max() {
+        let schedule = TariffSchedule {
+            grace_minutes: 10,
+            daily_max_amount: Some(cop(15000)),
+            ..tariff_schedule(vec![])
+        };
+        assert_eq!(calculate_stay_cost(&schedule, BOGOTA, at("2025-03-03T10:00:00"), at("2025-03-03T10:09:00")), Money::ZERO);
+        assert_eq!(calculate_stay_cost(&schedule, BOGOTA, at("2025-03-03T10:00:00"), at("2025-03-03T10:11:00")), cop(4000));
+        // 2 days + 3h: two capped days (uncapped would be 4000 + 23 * 1000 = 27000 each) + 3 hours more.
+        let cost = calculate_stay_cost(&schedule, BOGOTA, at("2025-03-03T10:00:00"), at("2025-03-05T13:00:00"));
+        assert_eq!(cost, cop(15000) + cop(15000) + cop(3000));
+    }
+
+    #[test]
+    fn stay_breakdown_itemises_blocks_and_discounts() {
+        let schedule = TariffSchedule {
+            grace_minutes: 10,
+            daily_max_amount: Some(cop(15000)),
+            ..tariff_schedule(vec![])
+        };
+        let within_grace = stay_cost_breakdown(&schedule, BOGOTA, at("2025-03-03T10:00:00"), at("2025-03-03T10:05:00"));
+        assert_eq!(within_grace.grace_discount, cop(4000));
+        assert_eq!(within_grace.total, Money::ZERO);
+
+        // 51h: base 4000 + 50 additional blocks = 54000 uncapped, 33000 capped.
+        let long_stay = stay_cost_breakdown(&schedule, BOGOTA, at("2025-03-03T10:00:00"), at("2025-03-05T13:00:00"));
+        assert_eq!(long_stay.base_amount, cop(4000));
+        assert_eq!(long_stay.additional_blocks, 50.0);
+        assert_eq!(long_stay.additional_amount, cop(50000));
+        assert_eq!(long_stay.daily_max_discount, cop(21000));
+        assert_eq!(long_stay.total, cop(33000));
+    }
+
+    #[test]
+    fn daily_max_caps_each_day_of_a_stay_with_rules() {
+        let schedule = TariffSchedule {
+            daily_max_amount: Some(cop(15000)),
+            ..tariff_schedule(vec![rule("19:00", "06:00", vec![], "flat", 8000)])
+        };
+        // Mon 10:00 -> Thu 10:00. Uncapped: 12000 (10-19) + 3 nights + two 06-19 stretches
+        // at 16000 + 7000 (06-10) = 75000; every 24h period is worth more than the cap.
+        let capped = stay_cost_breakdown(&schedule, BOGOTA, at("2025-03-03T10:00:00"), at("2025-03-06T10:00:00"));
+        assert_eq!(capped.total, cop(45000));
+        assert_eq!(capped.daily_max_discount, cop(30000));
+        assert_eq!(capped.rules_amount, cop(24000));
+    }
+
+    #[test]
+    fn update_tells_an_absent_daily_max_from_null() {
+        let parse = |json: &str| serde_json::from_str::<UpdateCustomTariffArgs>(json).unwrap().daily_max_amount;
+        assert_eq!(parse(r#"{"id":"t"}"#), None);
+        assert_eq!(parse(r#"{"id":"t","dailyMaxAmount":null}"#), Some(None));
+        assert_eq!(parse(r#"{"id":"t","dailyMaxAmount":20000}"#), Some(Some(20000.0)));
+        assert!(validate_daily_max(Some(0.0)).is_err());
+        assert_eq!(validate_daily_max(None), Ok(None));
+    }
+}
@@ -0,0 +1,624 @@
+//! Libro de deudas: una fila por cada saldo que queda sin pagar en una salida, por placa y por
+//! la sesión que lo generó. Los abonos se suman a `paid_amount` (de la más antigua a la más
+//! reciente cuando se paga por placa) y la deuda se cierra como `settled` o `written_off`. La
+//! salida, la lista de deudores, la tesorería y los cierres de turno leen la deuda solo de aquí.
+//! Los saldos abiertos se agrupan por antigüedad (0–30, 31–60, 61–90, más de 90 días) y pueden
+//! bloquear nuevas entradas si la placa debe más de un monto o desde hace más de cierto tiempo.
+
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::db::{get_config_value, set_config_value, with_transaction};
+use crate::id_gen;
+use crate::money::{Currency, Money};
+use crate::permissions;
+use crate::state::AppState;
+
+const DEFAULT_LIST_LIMIT: u32 = 100;
+const MAX_LIST_LIMIT: u32 = 500;
+const VALID_STATUSES: &[&str] = &["open", "settled", "written_off"];
+const PAYMENT_METHODS: &[&str] = &["cash", "card", "transfer"];
+const CONFIG_KEY_BLOCK_AMOUNT: &str = "debt_entry_block_amount";
+const CONFIG_KEY_BLOCK_AGE_DAYS: &str = "debt_entry_block_age_days";
+/// Saldo abierto por antigüedad (días desde que se generó la deuda). Agregado sobre `debts`.
+pub const AGING_COLUMNS: &str = r#"
+    COALESCE(SUM(CASE WHEN julianday('now') - julianday(created_at) < 31 THEN amount - paid_amount ELSE 0 END), 0) AS days_0_30,
+    COALESCE(SUM(CASE WHEN julianday('now') - julianday(created_at) >= 31 AND julianday('now') - julianday(created_at) < 61 THEN amount - paid_amount ELSE 0 END), 0) AS days_31_60,
+    COALESCE(SUM(CASE WHEN julianday('now') - julianday(created_at) >= 61 AND julianday('now') - julianday(created_at) < 91 THEN amount - paid_amount ELSE 0 END), 0) AS days_61_90,
+    COALESCE(SUM(CASE WHEN julianday('now') - julianday(created_at) >= 91 THEN amount - paid_amount ELSE 0 END), 0) AS days_over_90
+"#;
+const DEBT_COLS: &str = "d.id, d.plate_upper, d.vehicle_id, v.ticket_code, d.amount, d.paid_amount, d.status, d.created_at, d.closed_at, d.write_off_reason, d.operator_user_id";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Debt {
+    pub id: String,
+    pub plate: String,
+    /// Sesión (fila de vehicles) cuya salida dejó este saldo pendiente.
+    pub vehicle_id: String,
+    pub ticket_code: Option<String>,
+    pub amount: f64,
+    pub paid_amount: f64,
+    pub balance: f64,
+    /// open | settled | written_off
+    pub status: String,
+    pub created_at: String,
+    pub closed_at: Option<String>,
+    pub write_off_reason: Option<String>,
+    pub operator_user_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListDebtsResult {
+    pub items: Vec<Debt>,
+    pub total: u32,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DebtAging {
+    #[serde(rename = "days0To30")]
+    pub days_0_30: f64,
+    #[serde(rename = "days31To60")]
+    pub days_31_60: f64,
+    #[serde(rename = "days61To90")]
+    pub days_61_90: f64,
+    pub days_over_90: f64,
+}
+
+/// Lee los rangos seleccionados con `AGING_COLUMNS`.
+pub fn aging_from_row(row: &rusqlite::Row, currency: &Currency) -> rusqlite::Result<DebtAging> {
+    Ok(DebtAging {
+        days_0_30: currency.major(row.get("days_0_30")?),
+        days_31_60: currency.major(row.get("days_31_60")?),
+        days_61_90: currency.major(row.get("days_61_90")?),
+        days_over_90: currency.major(row.get("days_over_90")?),
+    })
+}
+
+/// Antigüedad de todas las deudas abiertas (todas las placas).
+pub fn overall_aging(conn: &Connection) -> Result<DebtAging, String> {
+    let currency = crate::money::currency(conn)?;
+    conn.query_row(
+        &format!("SELECT {} FROM debts WHERE status = 'open'", AGING_COLUMNS),
+        [],
+        |row| aging_from_row(row, &currency),
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Límites que bloquean la entrada de placas con deuda abierta. None = sin límite.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DebtEntryLimits {
+    pub max_amount: Option<f64>,
+    pub max_age_days: Option<i64>,
+}
+
+fn get_entry_limits(conn: &Connection) -> Result<DebtEntryLimits, String> {
+    Ok(DebtEntryLimits {
+        max_amount: get_config_value(conn, CONFIG_KEY_BLOCK_AMOUNT)?
+            .and_then(|v| v.parse::<f64>().ok())
+            .filter(|v| *v > 0.0),
+        max_age_days: get_config_value(conn, CONFIG_KEY_BLOCK_AGE_DAYS)?
+            .and_then(|v| v.parse::<i64>().ok())
+            .filter(|v| *v > 0),
+    })
+}
+
+/// La llama register_entry: Err si la deuda abierta de la placa supera el monto configurado
+/// o si su deuda abierta más antigua tiene más días de los configurados.
+pub fn check_entry_debt_limits(conn: &Connection, plate_upper: &str) -> Result<(), String> {
+    if plate_upper.is_empty() {
+        return Ok(());
+    }
+    let limits = get_entry_limits(conn)?;
+    if limits.max_amount.is_none() && limits.max_age_days.is_none() {
+        return Ok(());
+    }
+    let currency = crate::money::currency(conn)?;
+    let (balance, oldest_age_days): (Money, Option<f64>) = conn
+        .query_row(
+            "SELECT COALESCE(SUM(amount - paid_amount), 0), MAX(julianday('now') - julianday(created_at)) FROM debts WHERE plate_upper = ?1 AND status = 'open'",
+            params![plate_upper],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| e.to_string())?;
+    if let Some(max) = limits.max_amount {
+        if balance > currency.money(max) {
+            return Err(format!(
+                "Ingreso bloqueado: la placa {} debe {:.2} (límite {:.2})",
+                plate_upper,
+                currency.major(balance),
+                max
+            ));
+        }
+    }
+    if let (Some(max_days), Some(age)) = (limits.max_age_days, oldest_age_days) {
+        if age.floor() as i64 > max_days {
+            return Err(format!(
+                "Ingreso bloqueado: la placa {} tiene una deuda de {} días (límite {})",
+                plate_upper,
+                age.floor() as i64,
+                max_days
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn row_to_debt(row: &rusqlite::Row, currency: &Currency) -> rusqlite::Result<Debt> {
+    let amount: Money = row.get("amount")?;
+    let paid_amount: Money = row.get("paid_amount")?;
+    Ok(Debt {
+        id: row.get("id")?,
+        plate: row.get("plate_upper")?,
+        vehicle_id: row.get("vehicle_id")?,
+        ticket_code: row.get("ticket_code")?,
+        amount: currency.major(amount),
+        paid_amount: currency.major(paid_amount),
+        balance: currency.major((amount - paid_amount).max(Money::ZERO)),
+        status: row.get("status")?,
+        created_at: row.get("created_at")?,
+        closed_at: row.get("closed_at")?,
+        write_off_reason: row.get("write_off_reason")?,
+        operator_user_id: row.get("operator_user_id")?,
+    })
+}
+
+fn get_debt(conn: &Connection, id: &str) -> Result<Debt, String> {
+    let currency = crate::money::currency(conn)?;
+    conn.query_row(
+        &format!(
+            "SELECT {} FROM debts d LEFT JOIN vehicles v ON v.id = d.vehicle_id WHERE d.id = ?1",
+            DEBT_COLS
+        ),
+        params![id],
+        |row| row_to_debt(row, &currency),
+    )
+    .map_err(|_| "Deuda no encontrada".to_string())
+}
+
+/// Saldo pendiente de la placa (deudas abiertas). Sin placa no hay saldo que arrastrar.
+pub fn open_balance_for_plate(conn: &Connection, plate_upper: &str) -> Result<Money, String> {
+    if plate_upper.is_empty() {
+        return Ok(Money::ZERO);
+    }
+    conn.query_row(
+        "SELECT COALESCE(SUM(amount - paid_amount), 0) FROM debts WHERE plate_upper = ?1 AND status = 'open'",
+        params![plate_upper],
+        |row| row.get(0),
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Registra el saldo sin pagar de una sesión. Montos <= 0 se ignoran.
+pub fn record_debt(
+    conn: &Connection,
+    plate_upper: &str,
+    vehicle_id: &str,
+    amount: Money,
+    created_at: &str,
+    operator_user_id: Option<&str>,
+) -> Result<(), String> {
+    if amount <= Money::ZERO {
+        return Ok(());
+    }
+    // La deuda no es dinero recibido: se liga al turno abierto si lo hay (la salida que la
+    // genera ya exige turno).
+    let shift_id = crate::domains::caja::open_shift_for(conn, operator_user_id)?.map(|s| s.id);
+    conn.execute(
+        "INSERT INTO debts (id, plate_upper, vehicle_id, amount, paid_amount, status, created_at, operator_user_id, shift_id) VALUES (?1, ?2, ?3, ?4, 0, 'open', ?5, ?6, ?7)",
+        params![
+            id_gen::generate_id(id_gen::PREFIX_DEBT),
+            plate_upper,
+            vehicle_id,
+            amount,
+            created_at,
+            operator_user_id,
+            shift_id
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Abona `amount` a una deuda abierta y la cierra como saldada cuando el saldo llega a cero.
+fn apply_to_debt(conn: &Connection, debt_id: &str, amount: Money, at: &str) -> Result<(), String> {
+    let n = conn
+        .execute(
+            r#"
+            UPDATE debts SET
+                paid_amount = paid_amount + ?1,
+                status = CASE WHEN amount - (paid_amount + ?1) <= 0 THEN 'settled' ELSE status END,
+                closed_at = CASE WHEN amount - (paid_amount + ?1) <= 0 THEN ?2 ELSE closed_at END
+            WHERE id = ?3 AND status = 'open'
+            "#,
+            params![amount, at, debt_id],
+        )
+        .map_err(|e| e.to_string())?;
+    if n == 0 {
+        return Err("La deuda ya no está abierta".to_string());
+    }
+    // vehicles.debt sigue el saldo de la deuda de esa sesión para que no quede desactualizado.
+    conn.execute(
+        "UPDATE vehicles SET debt = (SELECT MAX(amount - paid_amount, 0) FROM debts WHERE id = ?1) WHERE id = (SELECT vehicle_id FROM debts WHERE id = ?1)",
+        params![debt_id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Parte de un pago aplicada a una deuda.
+#[derive(Debug, Clone)]
+pub struct DebtAllocation {
+    pub debt_id: String,
+    pub vehicle_id: String,
+    pub amount: Money,
+}
+
+/// Aplica un pago a las deudas abiertas de la placa, de la más antigua a la más reciente.
+/// Devuelve lo abonado a cada deuda (en total, nunca más que el saldo abierto).
+pub fn apply_payment_oldest_first(
+    conn: &Connection,
+    plate_upper: &str,
+    amount: Money,
+    at: &str,
+) -> Result<Vec<DebtAllocation>, String> {
+    if plate_upper.is_empty() || amount <= Money::ZERO {
+        return Ok(Vec::new());
+    }
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, vehicle_id, amount - paid_amount FROM debts WHERE plate_upper = ?1 AND status = 'open' ORDER BY created_at ASC, id ASC",
+        )
+        .map_err(|e| e.to_string())?;
+    let open: Vec<(String, String, Money)> = stmt
+        .query_map(params![plate_upper], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut remaining = amount;
+    let mut allocations = Vec::new();
+    for (debt_id, vehicle_id, balance) in open {
+        if remaining <= Money::ZERO {
+            break;
+        }
+        let portion = remaining.min(balance);
+        apply_to_debt(conn, &debt_id, portion, at)?;
+        remaining -= portion;
+        allocations.push(DebtAllocation { debt_id, vehicle_id, amount: portion });
+    }
+    Ok(allocations)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DebtPaymentResult {
+    pub plate: String,
+    pub amount: f64,
+    /// Deudas que este pago dejó saldadas.
+    pub settled_debts: u32,
+    pub remaining_debt: f64,
+}
+
+fn insert_debt_payment_transaction(
+    conn: &Connection,
+    vehicle_id: &str,
+    amount: Money,
+    method: &str,
+    created_at: &str,
+    operator_user_id: Option<&str>,
+) -> Result<(), String> {
+    let shift_id = crate::domains::caja::open_shift_id(conn, operator_user_id)?;
+    conn.execute(
+        "INSERT INTO transactions (id, vehicle_id, amount, method, created_at, operator_user_id, kind, shift_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6, 'debt_payment', ?7)",
+        params![
+            id_gen::generate_id(id_gen::PREFIX_TRANSACTION),
+            vehicle_id,
+            amount,
+            method,
+            created_at,
+            operator_user_id,
+            shift_id
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Abono de una placa sin salida de vehículo: se reparte entre sus deudas abiertas (más
+/// antiguas primero) con una transacción `debt_payment` por cada deuda tocada, en el turno
+/// abierto de quien cobra.
+pub fn pay_plate_debt(
+    conn: &Connection,
+    plate: &str,
+    amount: Money,
+    method: &str,
+    operator_user_id: Option<&str>,
+) -> Result<DebtPaymentResult, String> {
+    if !PAYMENT_METHODS.contains(&method) {
+        return Err(format!("Invalid payment method: {}", method));
+    }
+    let currency = crate::money::currency(conn)?;
+    if amount <= Money::ZERO {
+        return Err("El abono debe ser mayor a 0".to_string());
+    }
+    let plate_upper = plate.trim().to_uppercase();
+    let now = chrono::Utc::now().to_rfc3339();
+    let settled_debts = with_transaction(conn, |conn| {
+        // El saldo se lee dentro de la transacción: dos abonos simultáneos no pueden superarlo.
+        let balance = open_balance_for_plate(conn, &plate_upper)?;
+        if balance <= Money::ZERO {
+            return Err("La placa no tiene deudas pendientes".to_string());
+        }
+        if amount > balance {
+            return Err(format!("El abono supera el saldo pendiente ({:.2})", currency.major(balance)));
+        }
+        let allocations = apply_payment_oldest_first(conn, &plate_upper, amount, &now)?;
+        let mut settled = 0;
+        for a in &allocations {
+            insert_debt_payment_transaction(conn, &a.vehicle_id, a.amount, method, &now, operator_user_id)?;
+            let status: String = conn
+                .query_row("SELECT status FROM debts WHERE id = ?1", params![a.debt_id], |r| r.get(0))
+                .map_err(|e| e.to_string())?;
+            if status == "settled" {
+                settled += 1;
+            }
+        }
+        Ok(settled)
+    })?;
+    Ok(DebtPaymentResult {
+        plate: plate_upper.clone(),
+        amount: currency.major(amount),
+        settled_debts,
+        remaining_debt: currency.major(open_balance_for_plate(conn, &plate_upper)?),
+    })
+}
+
+#[tauri::command]
+pub fn debts_get_entry_limits(state: State<AppState>) -> Result<DebtEntryLimits, String> {
+    state.check_permission(permissions::CAJA_DEBTORS_READ)?;
+    let conn = state.db.get().map_err(|e| e.to_string())?;
+    get_entry_limits(&conn)
+}
+
+/// Fija los límites que bloquean la entrada. None (o 0) desactiva cada límite.
+#[tauri::command]
+pub fn debts_set_entry_limits(
+    state: State<AppState>,
+    max_amount: Option<f64>,
+    max_age_days: Option<i64>,
+) -> Result<DebtEntryLimits, String> {
+    state.check_permission(permissions::CAJA_DEBT_LIMITS_MODIFY)?;
+    if max_amount.map(|v| v < 0.0).unwrap_or(false) || max_age_days.map(|v| v < 0).unwrap_or(false) {
+        return Err("Limits must be >= 0".to_string());
+    }
+    let conn = state.db.get().map_err(|e| e.to_string())?;
+    let amount = max_amount.filter(|v| *v > 0.0).map(|v| v.to_string()).unwrap_or_default();
+    let days = max_age_days.filter(|v| *v > 0).map(|v| v.to_string()).unwrap_or_default();
+    set_config_value(&conn, CONFIG_KEY_BLOCK_AMOUNT, &amount)?;
+    set_config_value(&conn, CONFIG_KEY_BLOCK_AGE_DAYS, &days)?;
+    get_entry_limits(&conn)
+}
+
+#[tauri::command]
+pub fn debts_list(
+    state: State<AppState>,
+    plate: Option<String>,
+    status: Option<String>,
+    limit: Option<u32>,
+    offset: Option<u32>,
+) -> Result<ListDebtsResult, String> {
+    state.check_permission(permissions::CAJA_DEBTORS_READ)?;
+    let plate = plate.map(|p| p.trim().to_uppercase()).filter(|p| !p.is_empty());
+    let status = status.map(|s| s.trim().to_lowercase()).filter(|s| !s.is_empty());
+    if let Some(s) = status.as_deref() {
+        if !VALID_STATUSES.contains(&s) {
+            return Err(format!("Invalid debt status: {}", s));
+        }
+    }
+    let limit = limit.unwrap_or(DEFAULT_LIST_LIMIT).min(MAX_LIST_LIMIT);
+    let offset = offset.unwrap_or(0);
+    let conn = state.db.get().map_err(|e| e.to_string())?;
+    let currency = crate::money::currency(&conn)?;
+
+    let total: u32 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM debts d WHERE (?1 IS NULL OR d.plate_upper = ?1) AND (?2 IS NULL OR d.status = ?2)",
+            params![plate, status],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM debts d LEFT JOIN vehicles v ON v.id = d.vehicle_id WHERE (?1 IS NULL OR d.plate_upper = ?1) AND (?2 IS NULL OR d.status = ?2) ORDER BY d.created_at DESC LIMIT ?3 OFFSET ?4",
+            DEBT_COLS
+        ))
+        .map_err(|e| e.to_string())?;
+    let items = stmt
+        .query_map(params![plate, status, limit, offset], |row| row_to_debt(row, &currency))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(ListDebtsResult { items, total })
+}
+
+/// Cobra un abono (total o parcial) a una deuda puntual. El pago queda como transacción
+/// `debt_payment` del turno abierto de quien cobra, y entra en la tesorería del día en que se cobra.
+fn settle_debt_on(
+    conn: &Connection,
+    debt_id: &str,
+    amount: Money,
+    method: &str,
+    operator_user_id: Option<&str>,
+) -> Result<Debt, String> {
+    if !PAYMENT_METHODS.contains(&method) {
+        return Err(format!("Invalid payment method: {}", method));
+    }
+    if amount <= Money::ZERO {
+        return Err("El abono debe ser mayor a 0".to_string());
+    }
+    let currency = crate::money::currency(conn)?;
+    let now = chrono::Utc::now().to_rfc3339();
+    with_transaction(conn, |conn| {
+        // Estado y saldo se leen dentro de la transacción: dos abonos simultáneos no pueden superarlo.
+        let debt = get_debt(conn, debt_id)?;
+        if debt.status != "open" {
+            return Err("La deuda ya no está abierta".to_string());
+        }
+        if amount > currency.money(debt.balance) {
+            return Err(format!("El abono supera el saldo pendiente ({:.2})", debt.balance));
+        }
+        apply_to_debt(conn, &debt.id, amount, &now)?;
+        insert_debt_payment_transaction(conn, &debt.vehicle_id, amount, method, &now, operator_user_id)
+    })?;
+    get_debt(conn, debt_id)
+}
+
+#[tauri::command]
+pub fn debts_settle(
+    state: State<AppState>,
+    debt_id: String,
+    amount: f64,
+    method: String,
+) -> Result<Debt, String> {
+    state.check_permission(permissions::CAJA_DEBT_PAYMENT_CREATE)?;
+    let conn = state.db.get().map_err(|e| e.to_string())?;
+    let operator = state.get_current_user_id();
+    settle_debt_on(
+        &conn,
+        debt_id.trim(),
+        crate::money::currency(&conn)?.money(amount),
+        &method.trim().to_lowercase(),
+        operator.as_deref(),
+    )
+}
+
+/// Castiga (da por incobrable) el saldo de una deuda abierta. Lo ya abonado se conserva.
+#[tauri::command]
+pub fn debts_write_off(state: State<AppState>, debt_id: String, reason: String) -> Result<Debt, String> {
+    state.check_permission(permissions::CAJA_DEBTS_WRITE_OFF)?;
+    let reason = reason.trim();
+    if reason.is_empty() {
+        return Err("Indique el motivo para castigar la deuda".to_string());
+    }
+    let conn = state.db.get().map_err(|e| e.to_string())?;
+    let n = conn
+        .execute(
+            "UPDATE debts SET status = 'written_off', closed_at = ?1, write_off_reason = ?2 WHERE id = ?3 AND status = 'open'",
+            params![chrono::Utc::now().to_rfc3339(), reason, debt_id.trim()],
+        )
+        .map_err(|e| e.to_string())?;
+    if n == 0 {
+        get_debt(&conn, debt_id.trim())?;
+        return Err("La deuda ya no está abierta".to_string());
+    }
+    get_debt(&conn, debt_id.trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cop(amount: i64) -> Money {
+        Money::from_minor(amount)
+    }
+
+    fn conn_with_debts() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::run_migrations(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO vehicles (id, ticket_code, plate, plate_upper, vehicle_type, entry_time, exit_time, status, tariff_kind) VALUES
+                ('v1', 'T1', 'ABC123', 'ABC123', 'car', '2025-01-01T00:00:00+00:00', '2025-01-01T02:00:00+00:00', 'completed', 'regular'),
+                ('v2', 'T2', 'ABC123', 'ABC123', 'car', '2025-01-02T00:00:00+00:00', '2025-01-02T02:00:00+00:00', 'completed', 'regular');",
+        )
+        .unwrap();
+        record_debt(&conn, "ABC123", "v1", cop(3000), "2025-01-01T02:00:00+00:00", None).unwrap();
+        record_debt(&conn, "ABC123", "v2", cop(2000), "2025-01-02T02:00:00+00:00", None).unwrap();
+        conn.execute_batch(
+            "INSERT INTO shifts (id, operator_user_id, booth, opened_at, status) VALUES ('SH1', 'cashier', 'B1', '2025-01-03T00:00:00+00:00', 'open');",
+        )
+        .unwrap();
+        conn
+    }
+
+    #[test]
+    fn payments_settle_oldest_debt_first() {
+        let conn = conn_with_debts();
+        let applied = apply_payment_oldest_first(&conn, "ABC123", cop(4000), "2025-01-03T00:00:00+00:00").unwrap();
+        assert_eq!(applied.len(), 2);
+        assert_eq!(applied[0].vehicle_id, "v1");
+        assert_eq!(applied[0].amount, cop(3000));
+        assert_eq!(open_balance_for_plate(&conn, "ABC123").unwrap(), cop(1000));
+        let status: String = conn
+            .query_row("SELECT status FROM debts WHERE vehicle_id = 'v1'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(status, "settled");
+
+        let applied = apply_payment_oldest_first(&conn, "ABC123", cop(5000), "2025-01-03T00:00:00+00:00").unwrap();
+        assert_eq!(applied.iter().map(|a| a.amount).sum::<Money>(), cop(1000));
+        assert_eq!(open_balance_for_plate(&conn, "ABC123").unwrap(), Money::ZERO);
+    }
+
+    #[test]
+    fn partial_settlement_records_a_transaction_and_keeps_debt_open() {
+        let conn = conn_with_debts();
+        let id: String = conn
+            .query_row("SELECT id FROM debts WHERE vehicle_id = 'v2'", [], |r| r.get(0))
+            .unwrap();
+        let debt = settle_debt_on(&conn, &id, cop(500), "card", Some("cashier")).unwrap();
+        assert_eq!(debt.status, "open");
+        assert_eq!(debt.balance, 1500.0);
+        assert!(settle_debt_on(&conn, &id, cop(1600), "cash", Some("cashier")).is_err());
+        let (paid, kind): (i64, String) = conn
+            .query_row("SELECT SUM(amount), MAX(kind) FROM transactions WHERE vehicle_id = 'v2'", [], |r| {
+                Ok((r.get(0)?, r.get(1)?))
+            })
+            .unwrap();
+        assert_eq!(paid, 500);
+        assert_eq!(kind, "debt_payment");
+        let vehicle_debt: i64 = conn
+            .query_row("SELECT debt FROM vehicles WHERE id = 'v2'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(vehicle_debt, 1500);
+
+        settle_debt_on(&conn, &id, cop(1500), "cash", Some("cashier")).unwrap();
+        let vehicle_debt: i64 = conn
+            .query_row("SELECT debt FROM vehicles WHERE id = 'v2'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(vehicle_debt, 0);
+    }
+
+    #[test]
+    fn entry_limits_block_by_amount_and_age() {
+        let conn = conn_with_debts();
+        assert!(check_entry_debt_limits(&conn, "ABC123").is_ok());
+        set_config_value(&conn, CONFIG_KEY_BLOCK_AMOUNT, "4000").unwrap();
+        assert!(check_entry_debt_limits(&conn, "ABC123").is_err());
+        set_config_value(&conn, CONFIG_KEY_BLOCK_AMOUNT, "").unwrap();
+        set_config_value(&conn, CONFIG_KEY_BLOCK_AGE_DAYS, "90").unwrap();
+        assert!(check_entry_debt_limits(&conn, "ABC123").is_err());
+        assert!(check_entry_debt_limits(&conn, "XYZ999").is_ok());
+
+        let aging = overall_aging(&conn).unwrap();
+        assert_eq!(aging.days_over_90, 5000.0);
+        assert_eq!(aging.days_0_30, 0.0);
+    }
+
+    #[test]
+    fn plate_payment_writes_debt_payment_transactions_per_debt() {
+        let conn = conn_with_debts();
+        assert!(pay_plate_debt(&conn, "abc123", cop(6000), "cash", Some("cashier")).is_err());
+        let result = pay_plate_debt(&conn, "abc123", cop(3500), "cash", Some("cashier")).unwrap();
+        assert_eq!(result.settled_debts, 1);
+        assert_eq!(result.remaining_debt, 1500.0);
+        let rows: i64 = conn
+            .query_row("SELECT COUNT(*) FROM transactions WHERE kind = 'debt_payment'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(rows, 2);
+    }
+}
@@ -0,0 +1,558 @@
+//! Contract invoices: one per recorded contract payment (billing period or deposit to the
+//! credit balance), numbered
+//! consecutively. Issuer and client data are copied when the invoice is issued so later
+//! edits to the contract or the issuer config do not change printed invoices.
+//! Amounts are tax-inclusive: the payment is the total, split into subtotal + tax.
+
+use base64::prelude::{Engine as _, BASE64_STANDARD};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::db::{get_config_value, set_config_value};
+use crate::domains::contracts::{Contract, ContractPayment, PAYMENT_KIND_CREDIT};
+use crate::id_gen;
+use crate::money;
+use crate::permissions;
+use crate::state::AppState;
+
+const CONFIG_KEY_ISSUER_NAME: &str = "invoice_issuer_name";
+const CONFIG_KEY_ISSUER_TAX_ID: &str = "invoice_issuer_tax_id";
+const CONFIG_KEY_ISSUER_ADDRESS: &str = "invoice_issuer_address";
+const CONFIG_KEY_ISSUER_PHONE: &str = "invoice_issuer_phone";
+const CONFIG_KEY_PREFIX: &str = "invoice_prefix";
+const CONFIG_KEY_TAX_RATE: &str = "invoice_tax_rate";
+const DEFAULT_PREFIX: &str = "FAC-";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InvoiceConfig {
+    pub issuer_name: String,
+    pub issuer_tax_id: String,
+    pub issuer_address: String,
+    pub issuer_phone: String,
+    /// Prepended to the consecutive number (e.g. "FAC-" -> "FAC-000001").
+    pub prefix: String,
+    /// Tax percentage included in contract amounts (e.g. 19 for IVA); 0 = no tax.
+    pub tax_rate: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Invoice {
+    pub id: String,
+    pub number: i64,
+    pub invoice_number: String,
+    pub contract_id: String,
+    pub payment_id: String,
+    pub issuer_name: String,
+    pub issuer_tax_id: String,
+    pub issuer_address: String,
+    pub issuer_phone: String,
+    pub client_name: String,
+    pub client_phone: Option<String>,
+    pub plate: String,
+    pub period_from: String,
+    pub period_to: String,
+    /// Kind of the payment: "period" or "credit" (deposit to the credit balance).
+    pub kind: String,
+    pub method: String,
+    pub currency: String,
+    pub subtotal: f64,
+    pub tax_rate: f64,
+    pub tax_amount: f64,
+    pub total: f64,
+    pub issued_at: String,
+    pub operator_user_id: Option<String>,
+}
+
+const INVOICE_COLS: &str = r#"
+    id, number, invoice_number, contract_id, payment_id,
+    issuer_name, issuer_tax_id, issuer_address, issuer_phone,
+    client_name, client_phone, plate, period_from, period_to, method, currency,
+    subtotal, tax_rate, tax_amount, total, issued_at, operator_user_id, kind
+"#;
+
+fn row_to_invoice(row: &rusqlite::Row) -> rusqlite::Result<Invoice> {
+    // Los montos están en unidades menores de la moneda con que se emitió la factura.
+    let code: String = row.get("currency")?;
+    let currency = money::Currency::new(&code, None).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, e.into())
+    })?;
+    let major = |col: &str| -> rusqlite::Result<f64> { Ok(currency.major(row.get(col)?)) };
+    Ok(Invoice {
+        id: row.get("id")?,
+        number: row.get("number")?,
+        invoice_number: row.get("invoice_number")?,
+        contract_id: row.get("contract_id")?,
+        payment_id: row.get("payment_id")?,
+        issuer_name: row.get("issuer_name")?,
+        issuer_tax_id: row.get("issuer_tax_id")?,
+        issuer_address: row.get("issuer_address")?,
+        issuer_phone: row.get("issuer_phone")?,
+        client_name: row.get("client_name")?,
+        client_phone: row.get("client_phone")?,
+        plate: row.get("plate")?,
+        period_from: row.get("period_from")?,
+        period_to: row.get("period_to")?,
+        kind: row.get("kind")?,
+        method: row.get("method")?,
+        currency: code.clone(),
+        subtotal: major("subtotal")?,
+        tax_rate: row.get("tax_rate")?,
+        tax_amount: major("tax_amount")?,
+        total: major("total")?,
+        issued_at: row.get("issued_at")?,
+        operator_user_id: row.get("operator_user_id")?,
+    })
+}
+
+fn load_config(conn: &Connection) -> Result<InvoiceConfig, String> {
+    let text = |key: &str| -> Result<String, String> { Ok(get_config_value(conn, key)?.unwrap_or_default()) };
+    Ok(InvoiceConfig {
+        issuer_name: text(CONFIG_KEY_ISSUER_NAME)?,
+        issuer_tax_id: text(CONFIG_KEY_ISSUER_TAX_ID)?,
+        issuer_address: text(CONFIG_KEY_ISSUER_ADDRESS)?,
+        issuer_phone: text(CONFIG_KEY_ISSUER_PHONE)?,
+        prefix: get_config_value(conn, CONFIG_KEY_PREFIX)?.unwrap_or_else(|| DEFAULT_PREFIX.to_string()),
+        tax_rate: get_config_value(conn, CONFIG_KEY_TAX_RATE)?
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(0.0),
+    })
+}
+
+/// Issues the invoice of a contract payment. Call inside the payment's transaction so the
+/// number is taken and the payment recorded atomically.
+pub(crate) fn issue_for_payment(
+    conn: &Connection,
+    contract: &Contract,
+    payment: &ContractPayment,
+) -> Result<Invoice, String> {
+    let config = load_config(conn)?;
+    let currency = money::currency(conn)?;
+    let total = currency.money(payment.amount);
+    let subtotal = total.times(1.0 / (1.0 + config.tax_rate / 100.0));
+    let tax_amount = total - subtotal;
+
+    let number: i64 = conn
+        .query_row("SELECT COALESCE(MAX(number), 0) + 1 FROM contract_invoices", [], |r| r.get(0))
+        .map_err(|e| e.to_string())?;
+    let invoice = Invoice {
+        id: id_gen::generate_id(id_gen::PREFIX_INVOICE),
+        number,
+        invoice_number: format!("{}{:06}", config.prefix, number),
+        contract_id: contract.id.clone(),
+        payment_id: payment.id.clone(),
+        issuer_name: config.issuer_name,
+        issuer_tax_id: config.issuer_tax_id,
+        issuer_address: config.issuer_address,
+        issuer_phone: config.issuer_phone,
+        client_name: contract.client_name.clone(),
+        client_phone: contract.client_phone.clone(),
+        plate: contract.plate.clone(),
+        period_from: payment.period_from.clone(),
+        period_to: payment.period_to.clone(),
+        kind: payment.kind.clone(),
+        method: payment.method.clone(),
+        currency: currency.code.clone(),
+        subtotal: currency.major(subtotal),
+        tax_rate: config.tax_rate,
+        tax_amount: currency.major(tax_amount),
+        total: currency.major(total),
+        issued_at: payment.created_at.clone(),
+        operator_user_id: payment.operator_user_id.clone(),
+    };
+    conn.execute(
+        &format!(
+            "INSERT INTO contract_invoices ({INVOICE_COLS}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23)"
+        ),
+        params![
+            invoice.id, invoice.number, invoice.invoice_number, invoice.contract_id, invoice.payment_id,
+            invoice.issuer_name, invoice.issuer_tax_id, invoice.issuer_address, invoice.issuer_phone,
+            invoice.client_name, invoice.client_phone, invoice.plate, invoice.period_from,
+            invoice.period_to, invoice.method, invoice.currency, subtotal, invoice.tax_rate,
+            tax_amount, total, invoice.issued_at, invoice.operator_user_id, invoice.kind,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(invoice)
+}
+
+fn get_invoice(conn: &Connection, id: &str) -> Result<Invoice, String> {
+    conn.query_row(
+        &format!("SELECT {INVOICE_COLS} FROM contract_invoices WHERE id = ?1"),
+        params![id],
+        row_to_invoice,
+    )
+    .map_err(|_| "Invoice not found".to_string())
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+fn method_label(method: &str) -> &str {
+    match method {
+        "cash" => "Efectivo",
+        "card" => "Tarjeta",
+        "transfer" => "Transferencia",
+        other => other,
+    }
+}
+
+/// Concept and period of the single invoice line.
+fn concept_line(invoice: &Invoice) -> (&'static str, String) {
+    if invoice.kind == PAYMENT_KIND_CREDIT {
+        ("Abono a saldo a favor", "-".to_string())
+    } else {
+        (
+            "Mensualidad de parqueadero",
+            format!("{} a {}", invoice.period_from, invoice.period_to),
+        )
+    }
+}
+
+/// Issue date as printed: the business date of `issued_at` (stored in UTC).
+fn issued_on(tz: chrono_tz::Tz, invoice: &Invoice) -> String {
+    crate::domains::settings::local_date(tz, &invoice.issued_at)
+        .map(|d| d.format("%Y-%m-%d").to_string())
+        .unwrap_or_else(|| invoice.issued_at.clone())
+}
+
+/// Printable invoice (self-contained HTML) for on-screen preview and printing.
+fn render_html(invoice: &Invoice, tz: chrono_tz::Tz) -> String {
+    let e = |s: &str| escape_html(s);
+    let amount = |v: f64| format!("{:.2} {}", v, e(&invoice.currency));
+    let (concept, period) = concept_line(invoice);
+    let client_phone = invoice
+        .client_phone
+        .as_deref()
+        .map(|p| format!("<div>Tel: {}</div>", e(p)))
+        .unwrap_or_default();
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="es">
+<head>
+<meta charset="utf-8">
+<title>Factura {number}</title>
+<style>
+  body {{ font-family: sans-serif; font-size: 12px; margin: 24px; }}
+  h1 {{ font-size: 18px; margin: 0 0 4px; }}
+  .header, .parties {{ display: flex; justify-content: space-between; margin-bottom: 16px; }}
+  table {{ width: 100%; border-collapse: collapse; }}
+  th, td {{ border-bottom: 1px solid #ccc; padding: 6px; text-align: left; }}
+  td.amount, th.amount {{ text-align: right; }}
+  .totals td {{ border: none; }}
+</style>
+</head>
+<body>
+<div class="header">
+  <div>
+    <h1>{issuer_name}</h1>
+    <div>NIT: {issuer_tax_id}</div>
+    <div>{issuer_address}</div>
+    <div>Tel: {issuer_phone}</div>
+  </div>
+  <div>
+    <h1>Factura {number}</h1>
+    <div>Fecha: {issued_at}</div>
+  </div>
+</div>
+<div class="parties">
+  <div>
+    <strong>Cliente</strong>
+    <div>{client_name}</div>
+    {client_phone}
+    <div>Placa: {plate}</div>
+  </div>
+</div>
+<table>
+  <tr><th>Concepto</th><th>Periodo</th><th class="amount">Valor</th></tr>
+  <tr><td>{concept}</td><td>{period}</td><td class="amount">{subtotal}</td></tr>
+</table>
+<table class="totals">
+  <tr><td class="amount">Subtotal</td><td class="amount">{subtotal}</td></tr>
+  <tr><td class="amount">Impuesto ({tax_rate}%)</td><td class="amount">{tax_amount}</td></tr>
+  <tr><td class="amount"><strong>Total</strong></td><td class="amount"><strong>{total}</strong></td></tr>
+  <tr><td class="amount">Medio de pago</td><td class="amount">{method}</td></tr>
+</table>
+</body>
+</html>
+"#,
+        number = e(&invoice.invoice_number),
+        issuer_name = e(&invoice.issuer_name),
+        issuer_tax_id = e(&invoice.issuer_tax_id),
+        issuer_address = e(&invoice.issuer_address),
+        issuer_phone = e(&invoice.issuer_phone),
+        issued_at = e(&issued_on(tz, invoice)),
+        client_name = e(&invoice.client_name),
+        client_phone = client_phone,
+        plate = e(&invoice.plate),
+        concept = e(concept),
+        period = e(&period),
+        subtotal = amount(invoice.subtotal),
+        tax_rate = invoice.tax_rate,
+        tax_amount = amount(invoice.tax_amount),
+        total = amount(invoice.total),
+        method = e(method_label(&invoice.method)),
+    )
+}
+
+/// Text for a PDF string literal: WinAnsi (Latin-1) bytes with `(`, `)` and `\` escaped;
+/// characters outside Latin-1 become `?`.
+fn pdf_text(s: &str) -> Vec<u8> {
+    let mut out = Vec::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                out.push(b'\\');
+                out.push(c as u8);
+            }
+            c if c.is_control() => out.push(b' '),
+            c if (c as u32) < 256 => out.push(c as u32 as u8),
+            _ => out.push(b'?'),
+        }
+    }
+    out
+}
+
+/// Invoice as a one-page A4 PDF with the same content as the HTML, written by hand with the
+/// built-in Helvetica fonts so no PDF library is needed.
+fn render_pdf(invoice: &Invoice, tz: chrono_tz::Tz) -> Vec<u8> {
+    let amount = |v: f64| format!("{:.2} {}", v, invoice.currency);
+    let (concept, period) = concept_line(invoice);
+    // (x, y, tamaño, negrilla, texto); y se mide desde abajo.
+    let mut lines: Vec<(i32, i32, i32, bool, String)> = vec![
+        (50, 790, 14, true, invoice.issuer_name.clone()),
+        (350, 790, 14, true, format!("Factura {}", invoice.invoice_number)),
+        (50, 772, 10, false, format!("NIT: {}", invoice.issuer_tax_id)),
+        (350, 772, 10, false, format!("Fecha: {}", issued_on(tz, invoice))),
+        (50, 758, 10, false, invoice.issuer_address.clone()),
+        (50, 744, 10, false, format!("Tel: {}", invoice.issuer_phone)),
+        (50, 710, 10, true, "Cliente".to_string()),
+        (50, 696, 10, false, invoice.client_name.clone()),
+    ];
+    let mut y = 682;
+    if let Some(phone) = invoice.client_phone.as_deref() {
+        lines.push((50, y, 10, false, format!("Tel: {}", phone)));
+        y -= 14;
+    }
+    lines.push((50, y, 10, false, format!("Placa: {}", invoice.plate)));
+    lines.extend([
+        (50, 630, 10, true, "Concepto".to_string()),
+        (250, 630, 10, true, "Periodo".to_string()),
+        (450, 630, 10, true, "Valor".to_string()),
+        (50, 612, 10, false, concept.to_string()),
+        (250, 612, 10, false, period),
+        (450, 612, 10, false, amount(invoice.subtotal)),
+        (300, 580, 10, false, "Subtotal".to_string()),
+        (450, 580, 10, false, amount(invoice.subtotal)),
+        (300, 564, 10, false, format!("Impuesto ({}%)", invoice.tax_rate)),
+        (450, 564, 10, false, amount(invoice.tax_amount)),
+        (300, 548, 10, true, "Total".to_string()),
+        (450, 548, 10, true, amount(invoice.total)),
+        (300, 532, 10, false, "Medio de pago".to_string()),
+        (450, 532, 10, false, method_label(&invoice.method).to_string()),
+    ]);
+
+    let mut content = Vec::new();
+    for (x, y, size, bold, text) in &lines {
+        let font = if *bold { "F2" } else { "F1" };
+        content.extend_from_slice(format!("BT /{} {} Tf {} {} Td (", font, size, x, y).as_bytes());
+        content.extend_from_slice(&pdf_text(text));
+        content.extend_from_slice(b") Tj ET\n");
+    }
+
+    let mut objects: Vec<Vec<u8>> = vec![
+        b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+        b"<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_vec(),
+        b"<< /Type /Page /Parent 2 0 R /MediaBox [0 0 595 842] /Resources << /Font << /F1 4 0 R /F2 5 0 R >> >> /Contents 6 0 R >>".to_vec(),
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>".to_vec(),
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>".to_vec(),
+    ];
+    let mut stream = format!("<< /Length {} >>\nstream\n", content.len()).into_bytes();
+    stream.extend_from_slice(&content);
+    stream.extend_from_slice(b"endstream");
+    objects.push(stream);
+
+    let mut pdf = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend_from_slice(format!("{} 0 obj\n", i + 1).as_bytes());
+        pdf.extend_from_slice(object);
+        pdf.extend_from_slice(b"\nendobj\n");
+    }
+    let xref_at = pdf.len();
+    pdf.extend_from_slice(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes());
+    for offset in offsets {
+        pdf.extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
+    }
+    pdf.extend_from_slice(
+        format!("trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n", objects.len() + 1, xref_at).as_bytes(),
+    );
+    pdf
+}
+
+#[tauri::command]
+pub fn invoices_list(state: State<AppState>, contract_id: Option<String>) -> Result<Vec<Invoice>, String> {
+    state.check_permission(permissions::CONTRACTS_READ)?;
+    let conn = state.db.get().map_err(|e| e.to_string())?;
+    let contract_id = contract_id.as_deref().map(str::trim).filter(|s| !s.is_empty());
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {INVOICE_COLS} FROM contract_invoices WHERE (?1 IS NULL OR contract_id = ?1) ORDER BY number DESC LIMIT 500"
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![contract_id], row_to_invoice)
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn invoices_get(state: State<AppState>, id: String) -> Result<Invoice, String> {
+    state.check_permission(permissions::CONTRACTS_READ)?;
+    let conn = state.db.get().map_err(|e| e.to_string())?;
+    get_invoice(&conn, id.trim())
+}
+
+#[tauri::command]
+pub fn invoices_render_html(state: State<AppState>, id: String) -> Result<String, String> {
+    state.check_permission(permissions::CONTRACTS_READ)?;
+    let conn = state.db.get().map_err(|e| e.to_string())?;
+    let tz = crate::domains::settings::business_tz(&conn)?;
+    Ok(render_html(&get_invoice(&conn, id.trim())?, tz))
+}
+
+/// Invoice as a PDF file, base64-encoded for the frontend to save or print.
+#[tauri::command]
+pub fn invoices_render_pdf(state: State<AppState>, id: String) -> Result<String, String> {
+    state.check_permission(permissions::CONTRACTS_READ)?;
+    let conn = state.db.get().map_err(|e| e.to_string())?;
+    let tz = crate::domains::settings::business_tz(&conn)?;
+    Ok(BASE64_STANDARD.encode(render_pdf(&get_invoice(&conn, id.trim())?, tz)))
+}
+
+#[tauri::command]
+pub fn invoices_config_get(state: State<AppState>) -> Result<InvoiceConfig, String> {
+    state.check_permission(permissions::CONTRACTS_READ)?;
+    let conn = state.db.get().map_err(|e| e.to_string())?;
+    load_config(&conn)
+}
+
+#[tauri::command]
+pub fn invoices_config_set(state: State<AppState>, payload: InvoiceConfig) -> Result<InvoiceConfig, String> {
+    state.check_permission(permissions::CONTRACTS_MODIFY)?;
+    if !payload.tax_rate.is_finite() || payload.tax_rate < 0.0 || payload.tax_rate >= 100.0 {
+        return Err("Tax rate must be between 0 and 100".to_string());
+    }
+    let conn = state.db.get().map_err(|e| e.to_string())?;
+    set_config_value(&conn, CONFIG_KEY_ISSUER_NAME, payload.issuer_name.trim())?;
+    set_config_value(&conn, CONFIG_KEY_ISSUER_TAX_ID, payload.issuer_tax_id.trim())?;
+    set_config_value(&conn, CONFIG_KEY_ISSUER_ADDRESS, payload.issuer_address.trim())?;
+    set_config_value(&conn, CONFIG_KEY_ISSUER_PHONE, payload.issuer_phone.trim())?;
+    set_config_value(&conn, CONFIG_KEY_PREFIX, payload.prefix.trim())?;
+    set_config_value(&conn, CONFIG_KEY_TAX_RATE, &payload.tax_rate.to_string())?;
+    load_config(&conn)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contract() -> Contract {
+        Contract {
+            id: "CO1".to_string(),
+            client_name: "Ana <Pérez>".to_string(),
+            client_phone: None,
+            plate: "ABC123".to_string(),
+            plate_upper: "ABC123".to_string(),
+            vehicle_type: "car".to_string(),
+            tariff_kind: "none".to_string(),
+            monthly_amount: 119000.0,
+            included_hours_per_day: 6.0,
+            date_from: "2025-01-01".to_string(),
+            date_to: "2025-01-31".to_string(),
+            status: "active".to_string(),
+            created_at: "2025-01-01T00:00:00+00:00".to_string(),
+            notes: None,
+            extra_charge_first: None,
+            extra_charge_repeat: None,
+            extra_interval: None,
+            is_in_arrears: false,
+            billing_period_days: 30,
+            cancelled_at: None,
+            cancellation_reason: None,
+            last_payment_date: None,
+            extra_charge_per_interval: None,
+            end_date: None,
+            auto_renew: false,
+            plates: vec!["ABC123".to_string()],
+            max_simultaneous_vehicles: 1,
+            credit_balance: 0.0,
+            next_period_end: None,
+            pending_monthly_amount: None,
+            pending_billing_period_days: None,
+            pending_effective_date: None,
+        }
+    }
+
+    fn payment(id: &str, from: &str, to: &str) -> ContractPayment {
+        ContractPayment {
+            id: id.to_string(),
+            contract_id: "CO1".to_string(),
+            amount: 119000.0,
+            method: "cash".to_string(),
+            period_from: from.to_string(),
+            period_to: to.to_string(),
+            kind: "period".to_string(),
+            operator_user_id: None,
+            created_at: "2025-01-31T15:00:00+00:00".to_string(),
+        }
+    }
+
+    #[test]
+    fn invoices_are_numbered_consecutively_with_tax_split() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::run_migrations(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO contracts (id, client_name, plate, plate_upper, vehicle_type, tariff_kind, monthly_amount, included_hours_per_day, date_from, date_to, status, created_at)
+             VALUES ('CO1', 'Ana', 'ABC123', 'ABC123', 'car', 'none', 119000, 6, '2025-01-01', '2025-03-02', 'active', '2025-01-01T00:00:00+00:00');
+             INSERT INTO contract_payments (id, contract_id, amount, method, period_from, period_to, created_at)
+             VALUES ('P1', 'CO1', 119000, 'cash', '2025-01-01', '2025-01-31', '2025-01-01T15:00:00+00:00'),
+                    ('P2', 'CO1', 119000, 'cash', '2025-01-31', '2025-03-02', '2025-01-31T15:00:00+00:00');",
+        )
+        .unwrap();
+        set_config_value(&conn, CONFIG_KEY_TAX_RATE, "19").unwrap();
+
+        let first = issue_for_payment(&conn, &contract(), &payment("P1", "2025-01-01", "2025-01-31")).unwrap();
+        let second = issue_for_payment(&conn, &contract(), &payment("P2", "2025-01-31", "2025-03-02")).unwrap();
+        assert_eq!((first.number, second.number), (1, 2));
+        assert_eq!(second.invoice_number, "FAC-000002");
+        assert_eq!((second.subtotal, second.tax_amount, second.total), (100000.0, 19000.0, 119000.0));
+
+        let html = render_html(&get_invoice(&conn, &second.id).unwrap(), chrono_tz::America::Bogota);
+        assert!(html.contains("FAC-000002"));
+        assert!(html.contains("Ana &lt;Pérez&gt;"));
+
+        // Emitida a las 02:00 UTC: en Bogotá todavía es el día anterior.
+        let late = Invoice { issued_at: "2025-02-01T02:00:00+00:00".to_string(), ..second };
+        assert_eq!(issued_on(chrono_tz::America::Bogota, &late), "2025-01-31");
+        assert!(render_html(&late, chrono_tz::America::Bogota).contains("Fecha: 2025-01-31"));
+
+        let pdf = render_pdf(&late, chrono_tz::America::Bogota);
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text.starts_with("%PDF-1.4") && text.ends_with("%%EOF\n"));
+        assert!(text.contains("(Factura FAC-000002)") && text.contains("(Fecha: 2025-01-31)"));
+        // Latin-1 en WinAnsi y paréntesis escapados.
+        assert!(pdf.windows(11).any(|w| w == b"Ana <P\xe9rez>"));
+        assert_eq!(pdf_text("a(b)\\ €"), b"a\\(b\\)\\\\ ?".to_vec());
+        let xref_at: usize = text.trim_end().lines().rev().nth(1).unwrap().parse().unwrap();
+        assert!(pdf[xref_at..].starts_with(b"xref"));
+    }
+}
@@ -1,41 +1,48 @@
 //! Daily metrics from persistent store (SQLite). Revenue and transaction count
-//! come from table `transactions` so metrics are coherent with Caja (till).
+//! come from table `transactions` so metrics are coherent with Caja (till); contract
+//! payments (`contract_payments`) are added to total revenue as a separate line.
 //!
 //! Hour-by-hour metrics (arrivals, occupancy, exits). All three count only completed turns (vehicles that have exited).
 //! - Arrivals: hour of entry (when vehicles arrived, demand for spaces).
 //! - Occupancy: vehicles that were occupying a spot during each hour (busiest hours).
 //! - Exits (peak_hours): hour of checkout/payment (when spaces free up).
 //!
-//! All hourly aggregations use the app timezone (Colombia, UTC-5) so labels match local time.
+//! Days and hourly aggregations use the business timezone (see `settings`) so labels match local time.
+//!
+//! Occupancy rates are percentages of the configured lot capacity (see `capacity`); 0 when no capacity is set.
 
-use chrono::{DateTime, FixedOffset, NaiveDate, Timelike, TimeZone, Utc};
+use chrono::{DateTime, Datelike, NaiveDate, Timelike, TimeZone, Utc};
+use chrono_tz::Tz;
 use rusqlite::params;
 use serde::Serialize;
 use tauri::State;
 
+use crate::domains::settings;
+use crate::money::Money;
 use crate::permissions;
 use crate::state::AppState;
 
-fn utc_to_local_hour(utc_iso: &str) -> Option<u8> {
-    let dt = DateTime::parse_from_rfc3339(utc_iso).ok()?.with_timezone(&Utc);
-    let colombia = FixedOffset::west_opt(5 * 3600)?;
-    let local = dt.with_timezone(&colombia);
-    Some(local.hour() as u8)
+fn utc_to_local(tz: Tz, utc_iso: &str) -> Option<DateTime<Tz>> {
+    Some(DateTime::parse_from_rfc3339(utc_iso).ok()?.with_timezone(&tz))
+}
+
+fn utc_to_local_hour(tz: Tz, utc_iso: &str) -> Option<u8> {
+    utc_to_local(tz, utc_iso).map(|local| local.hour() as u8)
 }
 
 fn local_date_hour_to_utc_range(
+    tz: Tz,
     date: NaiveDate,
     hour: u8,
 ) -> Option<(chrono::DateTime<Utc>, chrono::DateTime<Utc>)> {
-    let colombia = FixedOffset::west_opt(5 * 3600)?;
     let local_start = date.and_hms_opt(hour.into(), 0, 0)?;
     let local_end = if hour == 23 {
         date.succ_opt()?.and_hms_opt(0, 0, 0)?
     } else {
         date.and_hms_opt((hour + 1).into(), 0, 0)?
     };
-    let utc_start = colombia.from_local_datetime(&local_start).single()?.with_timezone(&Utc);
-    let utc_end = colombia.from_local_datetime(&local_end).single()?.with_timezone(&Utc);
+    let utc_start = tz.from_local_datetime(&local_start).earliest()?.with_timezone(&Utc);
+    let utc_end = tz.from_local_datetime(&local_end).earliest()?.with_timezone(&Utc);
     Some((utc_start, utc_end))
 }
 
@@ -45,6 +52,8 @@ pub struct PeakHourSlot {
     pub hour_label: String,
     pub hour_start: u8,
     pub count: u32,
+    /// Only set by occupancy_by_hour: average occupancy as % of lot capacity.
+    pub occupancy_rate: Option<f64>,
 }
 
 fn hour_label(hour_start: u8) -> String {
@@ -55,29 +64,16 @@ fn hour_label(hour_start: u8) -> String {
     )
 }
 
-fn date_prefix(s: &str) -> &str {
-    let s = s.trim();
-    if s.len() >= 10 {
-        &s[..10]
-    } else {
-        s
-    }
-}
-
+/// UTC bounds [from, to) of the business days date_from..=date_to (default: today in `tz`).
 fn date_bounds(
+    tz: Tz,
     date_from: Option<&str>,
     date_to: Option<&str>,
-    today: &str,
 ) -> Result<(String, String, chrono::NaiveDate, chrono::NaiveDate), String> {
-    let from = date_prefix(date_from.unwrap_or(today));
-    let to = date_prefix(date_to.unwrap_or(today));
-    let from_bound = format!("{}T00:00:00.000Z", from);
-    let to_date = chrono::NaiveDate::parse_from_str(to, "%Y-%m-%d").map_err(|e| e.to_string())?;
-    let to_next = to_date
-        .succ_opt()
-        .map(|d| format!("{}T00:00:00.000Z", d))
-        .unwrap_or_else(|| format!("{}T23:59:59.999Z", to));
-    let from_date = chrono::NaiveDate::parse_from_str(from, "%Y-%m-%d").map_err(|e| e.to_string())?;
+    let today = settings::today_in(tz);
+    let from_date = date_from.map(settings::parse_date).transpose()?.unwrap_or(today);
+    let to_date = date_to.map(settings::parse_date).transpose()?.unwrap_or(today);
+    let (from_bound, to_next) = settings::range_bounds_utc(tz, from_date, to_date);
     Ok((from_bound, to_next, from_date, to_date))
 }
 
@@ -89,6 +85,7 @@ fn build_24_slots(counts_by_hour: &std::collections::HashMap<u8, u32>) -> Vec<Pe
                 hour_label: hour_label(hour_start),
                 hour_start,
                 count,
+                occupancy_rate: None,
             }
         })
         .collect()
@@ -110,7 +107,9 @@ pub struct DailyMetrics {
     pub total_vehicles: u32,
     pub active_vehicles: u32,
     pub occupancy_rate: f64,
+    /// Parking revenue plus contract payments.
     pub total_revenue: f64,
+    pub contract_revenue: f64,
     pub average_ticket: f64,
     pub average_stay_minutes: f64,
     pub turnover_rate: f64,
@@ -124,14 +123,17 @@ pub fn metricas_get_daily(
 ) -> Result<DailyMetrics, String> {
     state.check_permission(permissions::METRICAS_DASHBOARD_READ)?;
     let conn = state.db.get().map_err(|e| e.to_string())?;
+    daily_metrics_on(&conn, date)
+}
 
-    let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
-    let day_filter = date
+fn daily_metrics_on(conn: &rusqlite::Connection, date: Option<String>) -> Result<DailyMetrics, String> {
+    let tz = settings::business_tz(conn)?;
+    let day = date
         .as_deref()
-        .map(date_prefix)
-        .filter(|s| s.len() >= 10)
-        .unwrap_or_else(|| today.as_str());
-    let day_prefix = format!("{}%", day_filter);
+        .and_then(|s| settings::parse_date(s).ok())
+        .unwrap_or_else(|| settings::today_in(tz));
+    let (day_start, day_end) = settings::day_bounds_utc(tz, day);
+    let currency = crate::money::currency(conn)?;
 
     let active_vehicles: u32 = if date.is_none() {
         conn.query_row(
@@ -144,20 +146,20 @@ pub fn metricas_get_daily(
         0
     };
 
-    let (completed_today, total_revenue, sum_stay_minutes): (u32, f64, f64) = conn
+    let (completed_today, parking_revenue, sum_stay_minutes): (u32, Money, f64) = conn
         .query_row(
             r#"
             SELECT
-                COUNT(*),
+                COUNT(CASE WHEN t.kind != 'refund' THEN 1 END),
                 COALESCE(SUM(t.amount), 0),
-                COALESCE(SUM(
+                COALESCE(SUM(CASE WHEN t.kind != 'refund' THEN
                     (julianday(v.exit_time) - julianday(v.entry_time)) * 24 * 60
-                ), 0)
+                END), 0)
             FROM vehicles v
             INNER JOIN transactions t ON t.vehicle_id = v.id
-            WHERE t.created_at LIKE ?1
+            WHERE t.created_at >= ?1 AND t.created_at < ?2
             "#,
-            params![&day_prefix],
+            params![&day_start, &day_end],
             |row| Ok((row.get::<_, i64>(0)? as u32, row.get(1)?, row.get(2)?)),
         )
         .map_err(|e| e.to_string())?;
@@ -168,8 +170,17 @@ pub fn metricas_get_daily(
         active_vehicles + completed_today
     };
 
+    let contract_revenue: Money = conn
+        .query_row(
+            "SELECT COALESCE(SUM(amount), 0) FROM contract_payments WHERE created_at >= ?1 AND created_at < ?2",
+            params![&day_start, &day_end],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    let total_revenue = currency.major(parking_revenue + contract_revenue);
+
     let average_ticket = if completed_today > 0 {
-        total_revenue / (completed_today as f64)
+        currency.major(parking_revenue) / (completed_today as f64)
     } else {
         0.0
     };
@@ -180,7 +191,13 @@ pub fn metricas_get_daily(
         0.0
     };
 
-    let occupancy_rate = 0.0;
+    let total_capacity = crate::domains::capacity::total_capacity(conn)?;
+    let occupancy_rate = if date.is_none() {
+        crate::domains::capacity::occupancy_rate(active_vehicles as f64, total_capacity)
+    } else {
+        let average_occupied = average_occupied_between(conn, &day_start, &day_end)?;
+        crate::domains::capacity::occupancy_rate(average_occupied, total_capacity)
+    };
 
     let turnover_rate = if date.is_some() || active_vehicles == 0 {
         0.0
@@ -199,16 +216,16 @@ pub fn metricas_get_daily(
             SELECT v.vehicle_type, COALESCE(SUM(t.amount), 0), COUNT(*)
             FROM vehicles v
             INNER JOIN transactions t ON t.vehicle_id = v.id
-            WHERE t.created_at LIKE ?1
+            WHERE t.created_at >= ?1 AND t.created_at < ?2
             GROUP BY LOWER(TRIM(v.vehicle_type))
             "#,
         )
         .map_err(|e| e.to_string())?;
     let rows = stmt
-        .query_map(params![&day_prefix], |row| {
+        .query_map(params![&day_start, &day_end], |row| {
             Ok((
                 row.get::<_, String>(0)?.trim().to_lowercase(),
-                row.get::<_, f64>(1)?,
+                currency.major(row.get::<_, Money>(1)?),
                 row.get::<_, i64>(2)? as u32,
             ))
         })
@@ -239,6 +256,7 @@ pub fn metricas_get_daily(
         active_vehicles,
         occupancy_rate,
         total_revenue,
+        contract_revenue: currency.major(contract_revenue),
         average_ticket,
         average_stay_minutes,
         turnover_rate,
@@ -246,8 +264,35 @@ pub fn metricas_get_daily(
     })
 }
 
+/// Average number of vehicles parked during [from, to): sum of each stay's overlap with the
+/// window divided by the window length. Active vehicles count until now.
+fn average_occupied_between(conn: &rusqlite::Connection, from: &str, to: &str) -> Result<f64, String> {
+    let now = chrono::Utc::now().to_rfc3339();
+    let overlap_minutes: f64 = conn
+        .query_row(
+            r#"
+            SELECT COALESCE(SUM(MAX(0,
+                (julianday(MIN(COALESCE(exit_time, ?3), ?2)) - julianday(MAX(entry_time, ?1))) * 24 * 60
+            )), 0)
+            FROM vehicles
+            WHERE entry_time < ?2 AND (exit_time IS NULL OR exit_time > ?1) AND status != 'removed'
+            "#,
+            params![from, to, now],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    let window_minutes = (DateTime::parse_from_rfc3339(to).map_err(|e| e.to_string())?
+        - DateTime::parse_from_rfc3339(from).map_err(|e| e.to_string())?)
+    .num_seconds() as f64
+        / 60.0;
+    if window_minutes <= 0.0 {
+        return Ok(0.0);
+    }
+    Ok(overlap_minutes / window_minutes)
+}
+
 /// Exits by hour: count of checkouts/payments per hour (when spaces free up).
-/// Only completed turns. Hour is in app timezone (Colombia).
+/// Only completed turns. Hour is in the business timezone.
 #[tauri::command]
 pub fn metricas_get_peak_hours(
     state: State<AppState>,
@@ -257,9 +302,9 @@ pub fn metricas_get_peak_hours(
     state.check_permission(permissions::METRICAS_DASHBOARD_READ)?;
     let conn = state.db.get().map_err(|e| e.to_string())?;
 
-    let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
+    let tz = settings::business_tz(&conn)?;
     let (from_bound, to_next, _, _) =
-        date_bounds(date_from.as_deref(), date_to.as_deref(), &today)?;
+        date_bounds(tz, date_from.as_deref(), date_to.as_deref())?;
 
     let mut stmt = conn
         .prepare(
@@ -273,7 +318,7 @@ pub fn metricas_get_peak_hours(
     let mut counts_by_hour: std::collections::HashMap<u8, u32> = (0..24).map(|h| (h, 0)).collect();
     while let Some(row) = rows.next().map_err(|e| e.to_string())? {
         let created_at: String = row.get(0).map_err(|e| e.to_string())?;
-        if let Some(h) = utc_to_local_hour(&created_at) {
+        if let Some(h) = utc_to_local_hour(tz, &created_at) {
             if h < 24 {
                 *counts_by_hour.get_mut(&h).unwrap() += 1;
             }
@@ -284,7 +329,7 @@ pub fn metricas_get_peak_hours(
 }
 
 /// Arrivals by hour: count of vehicles that entered per hour (demand for spaces).
-/// Only completed turns. Hour is in app timezone (Colombia).
+/// Only completed turns. Hour is in the business timezone.
 #[tauri::command]
 pub fn metricas_get_arrivals_by_hour(
     state: State<AppState>,
@@ -294,9 +339,9 @@ pub fn metricas_get_arrivals_by_hour(
     state.check_permission(permissions::METRICAS_DASHBOARD_READ)?;
     let conn = state.db.get().map_err(|e| e.to_string())?;
 
-    let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
+    let tz = settings::business_tz(&conn)?;
     let (from_bound, to_next, _, _) =
-        date_bounds(date_from.as_deref(), date_to.as_deref(), &today)?;
+        date_bounds(tz, date_from.as_deref(), date_to.as_deref())?;
 
     let mut stmt = conn
         .prepare(
@@ -313,7 +358,7 @@ pub fn metricas_get_arrivals_by_hour(
     let mut counts_by_hour: std::collections::HashMap<u8, u32> = (0..24).map(|h| (h, 0)).collect();
     while let Some(row) = rows.next().map_err(|e| e.to_string())? {
         let entry_time: String = row.get(0).map_err(|e| e.to_string())?;
-        if let Some(h) = utc_to_local_hour(&entry_time) {
+        if let Some(h) = utc_to_local_hour(tz, &entry_time) {
             if h < 24 {
                 *counts_by_hour.get_mut(&h).unwrap() += 1;
             }
@@ -324,7 +369,8 @@ pub fn metricas_get_arrivals_by_hour(
 }
 
 /// Occupancy by hour: average number of vehicles occupying a spot during each hour (busiest hours).
-/// Only completed turns. Hour buckets are in app timezone (Colombia).
+/// Only completed turns. Hour buckets are in the business timezone.
+/// Each slot also carries the average as a percentage of total lot capacity.
 #[tauri::command]
 pub fn metricas_get_occupancy_by_hour(
     state: State<AppState>,
@@ -334,9 +380,9 @@ pub fn metricas_get_occupancy_by_hour(
     state.check_permission(permissions::METRICAS_DASHBOARD_READ)?;
     let conn = state.db.get().map_err(|e| e.to_string())?;
 
-    let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
+    let tz = settings::business_tz(&conn)?;
     let (from_bound, to_next, from_date, to_date) =
-        date_bounds(date_from.as_deref(), date_to.as_deref(), &today)?;
+        date_bounds(tz, date_from.as_deref(), date_to.as_deref())?;
 
     let mut stmt = conn
         .prepare(
@@ -366,7 +412,7 @@ pub fn metricas_get_occupancy_by_hour(
     let mut d = from_date;
     while d <= to_date {
         for hour in 0u8..24 {
-            let (utc_start, utc_end) = match local_date_hour_to_utc_range(d, hour) {
+            let (utc_start, utc_end) = match local_date_hour_to_utc_range(tz, d, hour) {
                 Some(pair) => pair,
                 None => continue,
             };
@@ -387,11 +433,23 @@ pub fn metricas_get_occupancy_by_hour(
     }
 
     let counts_by_hour: std::collections::HashMap<u8, u32> = sum_by_hour
+        .iter()
+        .map(|(h, sum)| (*h, (*sum as f64 / num_days).round() as u32))
+        .collect();
+
+    let total_capacity = crate::domains::capacity::total_capacity(&conn)?;
+    let slots = build_24_slots(&counts_by_hour)
         .into_iter()
-        .map(|(h, sum)| (h, (sum as f64 / num_days).round() as u32))
+        .map(|slot| {
+            let average = *sum_by_hour.get(&slot.hour_start).unwrap_or(&0) as f64 / num_days;
+            PeakHourSlot {
+                occupancy_rate: Some(crate::domains::capacity::occupancy_rate(average, total_capacity)),
+                ..slot
+            }
+        })
         .collect();
 
-    Ok(build_24_slots(&counts_by_hour))
+    Ok(slots)
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -413,7 +471,8 @@ fn hour_in_period(hour: u8, period: &str) -> bool {
 }
 
 /// Heatmap: count of completed vehicles by day of week and vehicle type.
-/// Counts vehicles whose turn ended (exit_time) in the date range; day of week and period come from exit_time.
+/// Counts vehicles whose turn ended (exit_time) in the date range; day of week and period come from exit_time
+/// in the business timezone.
 /// Optional period filter: morning (6–12), midday (12–15), afternoon (15–19), night (19–6).
 /// Vehicle types are distinct values from the vehicles table (not a fixed list).
 #[tauri::command]
@@ -426,23 +485,18 @@ pub fn metricas_get_heatmap_day_vehicle(
     state.check_permission(permissions::METRICAS_DASHBOARD_READ)?;
     let conn = state.db.get().map_err(|e| e.to_string())?;
 
-    let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
+    let tz = settings::business_tz(&conn)?;
     let (from_bound, to_next, _, _) =
-        date_bounds(date_from.as_deref(), date_to.as_deref(), &today)?;
+        date_bounds(tz, date_from.as_deref(), date_to.as_deref())?;
 
     let period_key = period.as_deref().unwrap_or("");
 
     let mut stmt = conn
         .prepare(
             r#"
-            SELECT
-                CAST(COALESCE(strftime('%w', substr(exit_time, 1, 10)), '0') AS INTEGER) as dow,
-                CAST(substr(exit_time, 12, 2) AS INTEGER) as h,
-                LOWER(vehicle_type) as vehicle_type,
-                COUNT(*) as cnt
+            SELECT exit_time, LOWER(vehicle_type)
             FROM vehicles
-            WHERE status = 'completed' AND exit_time IS NOT NULL AND length(exit_time) >= 13 AND exit_time >= ?1 AND exit_time < ?2
-            GROUP BY dow, h, LOWER(vehicle_type)
+            WHERE status = 'completed' AND exit_time IS NOT NULL AND exit_time >= ?1 AND exit_time < ?2
             "#,
         )
         .map_err(|e| e.to_string())?;
@@ -452,14 +506,16 @@ pub fn metricas_get_heatmap_day_vehicle(
 
     let mut acc: std::collections::HashMap<(u8, String), u32> = std::collections::HashMap::new();
     while let Some(row) = rows.next().map_err(|e| e.to_string())? {
-        let dow: u8 = row.get::<_, i64>(0).map_err(|e| e.to_string())? as u8;
-        let h: u8 = row.get::<_, i64>(1).map_err(|e| e.to_string())? as u8;
-        let vt: String = row.get(2).map_err(|e| e.to_string())?;
-        let cnt: u32 = row.get::<_, i64>(3).map_err(|e| e.to_string())? as u32;
-        if !hour_in_period(h, period_key) {
+        let exit_time: String = row.get(0).map_err(|e| e.to_string())?;
+        let vt: String = row.get(1).map_err(|e| e.to_string())?;
+        let Some(local) = utc_to_local(tz, &exit_time) else {
+            continue;
+        };
+        if !hour_in_period(local.hour() as u8, period_key) {
             continue;
         }
-        *acc.entry((dow, vt)).or_insert(0) += cnt;
+        let dow = local.weekday().num_days_from_sunday() as u8;
+        *acc.entry((dow, vt)).or_insert(0) += 1;
     }
 
     let out: Vec<HeatmapDayVehicleRow> = acc
@@ -473,3 +529,34 @@ pub fn metricas_get_heatmap_day_vehicle(
 
     Ok(out)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn contract_payments_add_to_total_revenue_as_their_own_line() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        crate::db::run_migrations(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO vehicles (id, ticket_code, plate, plate_upper, vehicle_type, entry_time, exit_time, status, tariff_kind) VALUES
+                ('v1', 'T1', 'ABC123', 'ABC123', 'car', '2025-01-05T08:00:00+00:00', '2025-01-05T10:00:00+00:00', 'completed', 'regular');
+             INSERT INTO transactions (id, vehicle_id, amount, method, created_at) VALUES
+                ('tx1', 'v1', 6000, 'cash', '2025-01-05T10:00:00+00:00');
+             INSERT INTO contracts (id, client_name, plate, plate_upper, vehicle_type, tariff_kind, monthly_amount, included_hours_per_day, date_from, date_to, status, created_at)
+             VALUES ('CO1', 'A', 'AAA111', 'AAA111', 'car', 'none', 100000, 6, '2025-01-01', '2025-01-01', 'active', '2025-01-01T00:00:00+00:00');
+             INSERT INTO contract_payments (id, contract_id, amount, method, period_from, period_to, created_at) VALUES
+                ('P1', 'CO1', 100000, 'transfer', '2025-01-01', '2025-01-31', '2025-01-05T12:00:00+00:00'),
+                ('P2', 'CO1', 30000, 'cash', '2025-02-01', '2025-02-09', '2025-01-06T12:00:00+00:00');",
+        )
+        .unwrap();
+
+        let metrics = daily_metrics_on(&conn, Some("2025-01-05".to_string())).unwrap();
+        assert_eq!(metrics.contract_revenue, 100000.0);
+        assert_eq!(metrics.total_revenue, 106000.0);
+        // El ticket promedio y el desglose por tipo son solo de parqueo.
+        assert_eq!(metrics.average_ticket, 6000.0);
+        let cars = metrics.revenue_by_vehicle_type.iter().find(|r| r.vehicle_type == "car").unwrap();
+        assert_eq!(cars.revenue, 6000.0);
+    }
+}
@@ -1,10 +1,17 @@
 pub mod vehiculos;
 pub mod custom_tariffs;
+pub mod tariff_rules;
 pub mod contracts;
+pub mod invoices;
+pub mod vouchers;
 pub mod barcodes;
+pub mod capacity;
 pub mod caja;
+pub mod debts;
 pub mod metricas;
 pub mod reportes;
+pub mod settings;
+pub mod spaces;
 pub mod roles;
 pub mod backup;
 pub mod first_run;
@@ -1,12 +1,14 @@
 //! Report export: predefined types (transactions, completed vehicles, shift closures,
-//! transactions+vehicle). Data via JOINs; configurable columns and filters; CSV export.
+//! transactions+vehicle, debtors / debt aging, voucher redemptions / merchant billing, price overrides, refunds). Data via JOINs;
+//! configurable columns and filters; CSV export.
 
 use rusqlite::params;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use tauri::State;
-use chrono::{NaiveDate, TimeDelta};
 
+use crate::domains::settings;
+use crate::money::Money;
 use crate::permissions;
 use crate::state::AppState;
 
@@ -19,6 +21,11 @@ pub enum ReportType {
     TransactionsWithVehicle,
     VehicleExits,
     Debtors,
+    DebtAging,
+    VoucherRedemptions,
+    MerchantBilling,
+    Overrides,
+    Refunds,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -116,6 +123,69 @@ fn debtors_columns() -> Vec<ColumnDef> {
     ]
 }
 
+fn debt_aging_columns() -> Vec<ColumnDef> {
+    vec![
+        ColumnDef { key: "plate".into(), label: "Plate".into() },
+        ColumnDef { key: "total_debt".into(), label: "Total debt".into() },
+        ColumnDef { key: "days_0_30".into(), label: "0–30 days".into() },
+        ColumnDef { key: "days_31_60".into(), label: "31–60 days".into() },
+        ColumnDef { key: "days_61_90".into(), label: "61–90 days".into() },
+        ColumnDef { key: "days_over_90".into(), label: "90+ days".into() },
+        ColumnDef { key: "oldest_debt_at".into(), label: "Oldest debt".into() },
+    ]
+}
+
+fn voucher_redemptions_columns() -> Vec<ColumnDef> {
+    vec![
+        ColumnDef { key: "redeemed_at".into(), label: "Redeemed at".into() },
+        ColumnDef { key: "merchant".into(), label: "Merchant".into() },
+        ColumnDef { key: "voucher_code".into(), label: "Voucher".into() },
+        ColumnDef { key: "voucher_kind".into(), label: "Voucher kind".into() },
+        ColumnDef { key: "ticket_code".into(), label: "Ticket".into() },
+        ColumnDef { key: "plate".into(), label: "Plate".into() },
+        ColumnDef { key: "discount_amount".into(), label: "Discount".into() },
+        ColumnDef { key: "operator_user_id".into(), label: "Operator".into() },
+    ]
+}
+
+fn merchant_billing_columns() -> Vec<ColumnDef> {
+    vec![
+        ColumnDef { key: "merchant".into(), label: "Merchant".into() },
+        ColumnDef { key: "redemptions".into(), label: "Redemptions".into() },
+        ColumnDef { key: "total_discount".into(), label: "Total to bill".into() },
+        ColumnDef { key: "first_redeemed_at".into(), label: "First redemption".into() },
+        ColumnDef { key: "last_redeemed_at".into(), label: "Last redemption".into() },
+    ]
+}
+
+fn overrides_columns() -> Vec<ColumnDef> {
+    vec![
+        ColumnDef { key: "transaction_id".into(), label: "Transaction ID".into() },
+        ColumnDef { key: "created_at".into(), label: "Created at".into() },
+        ColumnDef { key: "ticket_code".into(), label: "Ticket".into() },
+        ColumnDef { key: "plate".into(), label: "Plate".into() },
+        ColumnDef { key: "computed_amount".into(), label: "Computed amount".into() },
+        ColumnDef { key: "override_amount".into(), label: "Override amount".into() },
+        ColumnDef { key: "difference".into(), label: "Difference".into() },
+        ColumnDef { key: "override_reason".into(), label: "Reason".into() },
+        ColumnDef { key: "operator_user_id".into(), label: "Operator".into() },
+    ]
+}
+
+fn refunds_columns() -> Vec<ColumnDef> {
+    vec![
+        ColumnDef { key: "transaction_id".into(), label: "Transaction ID".into() },
+        ColumnDef { key: "created_at".into(), label: "Created at".into() },
+        ColumnDef { key: "ticket_code".into(), label: "Ticket".into() },
+        ColumnDef { key: "plate".into(), label: "Plate".into() },
+        ColumnDef { key: "method".into(), label: "Payment method".into() },
+        ColumnDef { key: "refunded_amount".into(), label: "Refunded amount".into() },
+        ColumnDef { key: "refund_of".into(), label: "Original transaction".into() },
+        ColumnDef { key: "refund_reason".into(), label: "Reason".into() },
+        ColumnDef { key: "operator_user_id".into(), label: "Operator".into() },
+    ]
+}
+
 fn all_columns_for_type(report_type: &ReportType) -> Vec<ColumnDef> {
     match report_type {
         ReportType::Transactions => transactions_columns(),
@@ -124,6 +194,11 @@ fn all_columns_for_type(report_type: &ReportType) -> Vec<ColumnDef> {
         ReportType::TransactionsWithVehicle => transactions_with_vehicle_columns(),
         ReportType::VehicleExits => vehicle_exits_columns(),
         ReportType::Debtors => debtors_columns(),
+        ReportType::DebtAging => debt_aging_columns(),
+        ReportType::VoucherRedemptions => voucher_redemptions_columns(),
+        ReportType::MerchantBilling => merchant_billing_columns(),
+        ReportType::Overrides => overrides_columns(),
+        ReportType::Refunds => refunds_columns(),
     }
 }
 
@@ -145,28 +220,17 @@ pub struct ReportFilters {
     pub date_to: String,
     pub payment_method: Option<String>,
     pub vehicle_type: Option<String>,
+    /// Voucher reports only.
+    pub merchant: Option<String>,
 }
 
-fn normalize_date_prefix(s: &str) -> String {
-    let s = s.trim();
-    if s.len() >= 10 {
-        format!("{}%", &s[..10])
-    } else {
-        format!("{}%", s)
-    }
-}
-
-/// Exclusive end for date range: next day at 00:00:00 UTC (ISO format).
-/// Stored timestamps are ISO (e.g. 2026-01-02T12:00:00.000Z); using "YYYY-MM-DD 23:59:59"
-/// would sort before "T" and exclude that day. So we use "YYYY-MM-DD+1T00:00:00.000Z".
-fn date_to_end(date_to: &str) -> String {
-    let s = date_to.trim();
-    let day = if s.len() >= 10 { &s[..10] } else { s };
-    NaiveDate::parse_from_str(day, "%Y-%m-%d")
-        .ok()
-        .and_then(|d| d.checked_add_signed(TimeDelta::days(1)))
-        .map(|d| format!("{}T00:00:00.000Z", d))
-        .unwrap_or_else(|| format!("{} 23:59:59.999", day))
+/// Half-open UTC range [start, end) for the business days date_from..=date_to, so a day
+/// follows the business timezone (see `settings`). Stored timestamps are rfc3339 UTC.
+fn date_range(conn: &rusqlite::Connection, date_from: &str, date_to: &str) -> Result<(String, String), String> {
+    let tz = settings::business_tz(conn)?;
+    let from = settings::parse_date(date_from)?;
+    let to = settings::parse_date(date_to)?;
+    Ok(settings::range_bounds_utc(tz, from, to))
 }
 
 fn run_transactions(
@@ -176,9 +240,9 @@ fn run_transactions(
     payment_method: Option<&str>,
     columns: &[ColumnDef],
 ) -> Result<Vec<HashMap<String, serde_json::Value>>, String> {
-    let from_prefix = normalize_date_prefix(date_from);
-    let to_end = date_to_end(date_to);
+    let (from_start, to_end) = date_range(conn, date_from, date_to)?;
     let keys: Vec<String> = columns.iter().map(|c| c.key.clone()).collect();
+    let currency = crate::money::currency(conn)?;
 
     let mut list = Vec::new();
     if let Some(m) = payment_method {
@@ -187,7 +251,7 @@ fn run_transactions(
             let mut stmt = conn
                 .prepare("SELECT t.id, t.vehicle_id, t.amount, t.method, t.created_at, COALESCE(u.display_name, t.operator_user_id) FROM transactions t LEFT JOIN users u ON t.operator_user_id = u.id WHERE t.created_at >= ?1 AND t.created_at < ?2 AND LOWER(t.method) = ?3 ORDER BY t.created_at ASC")
                 .map_err(|e| e.to_string())?;
-            let rows = stmt.query_map(params![from_prefix, to_end, m], |row| {
+            let rows = stmt.query_map(params![from_start, to_end, m], |row| {
                 let mut map = HashMap::new();
                 if keys.contains(&"id".to_string()) {
                     map.insert("id".into(), serde_json::json!(row.get::<_, String>(0)?));
@@ -196,7 +260,7 @@ fn run_transactions(
                     map.insert("vehicle_id".into(), serde_json::json!(row.get::<_, String>(1)?));
                 }
                 if keys.contains(&"amount".to_string()) {
-                    map.insert("amount".into(), serde_json::json!(row.get::<_, f64>(2)?));
+                    map.insert("amount".into(), serde_json::json!(currency.major(row.get(2)?)));
                 }
                 if keys.contains(&"method".to_string()) {
                     map.insert("method".into(), serde_json::json!(row.get::<_, String>(3)?));
@@ -218,7 +282,7 @@ fn run_transactions(
     let mut stmt = conn
         .prepare("SELECT t.id, t.vehicle_id, t.amount, t.method, t.created_at, COALESCE(u.display_name, t.operator_user_id) FROM transactions t LEFT JOIN users u ON t.operator_user_id = u.id WHERE t.created_at >= ?1 AND t.created_at < ?2 ORDER BY t.created_at ASC")
         .map_err(|e| e.to_string())?;
-    let rows = stmt.query_map(params![from_prefix, to_end], |row| {
+    let rows = stmt.query_map(params![from_start, to_end], |row| {
         let mut map = HashMap::new();
         if keys.contains(&"id".to_string()) {
             map.insert("id".into(), serde_json::json!(row.get::<_, String>(0)?));
@@ -227,7 +291,7 @@ fn run_transactions(
             map.insert("vehicle_id".into(), serde_json::json!(row.get::<_, String>(1)?));
         }
         if keys.contains(&"amount".to_string()) {
-            map.insert("amount".into(), serde_json::json!(row.get::<_, f64>(2)?));
+            map.insert("amount".into(), serde_json::json!(currency.major(row.get(2)?)));
         }
         if keys.contains(&"method".to_string()) {
             map.insert("method".into(), serde_json::json!(row.get::<_, String>(3)?));
@@ -253,9 +317,9 @@ fn run_completed_vehicles(
     vehicle_type: Option<&str>,
     columns: &[ColumnDef],
 ) -> Result<Vec<HashMap<String, serde_json::Value>>, String> {
-    let from_prefix = normalize_date_prefix(date_from);
-    let to_end = date_to_end(date_to);
+    let (from_start, to_end) = date_range(conn, date_from, date_to)?;
     let keys: Vec<String> = columns.iter().map(|c| c.key.clone()).collect();
+    let currency = crate::money::currency(conn)?;
 
     let mut list = Vec::new();
     if let Some(vt) = vehicle_type {
@@ -264,7 +328,7 @@ fn run_completed_vehicles(
             let mut stmt = conn
                 .prepare("SELECT v.id, v.ticket_code, v.plate, v.vehicle_type, v.entry_time, v.exit_time, v.total_amount, v.debt, COALESCE(u.display_name, v.operator_user_id) FROM vehicles v LEFT JOIN users u ON v.operator_user_id = u.id WHERE v.status = 'completed' AND v.exit_time IS NOT NULL AND v.exit_time >= ?1 AND v.exit_time < ?2 AND LOWER(v.vehicle_type) = ?3 ORDER BY v.exit_time ASC")
                 .map_err(|e| e.to_string())?;
-            let rows = stmt.query_map(params![from_prefix, to_end, vt], |row| {
+            let rows = stmt.query_map(params![from_start, to_end, vt], |row| {
                 let mut map = HashMap::new();
                 if keys.contains(&"id".to_string()) {
                     map.insert("id".into(), serde_json::json!(row.get::<_, String>(0)?));
@@ -285,10 +349,10 @@ fn run_completed_vehicles(
                     map.insert("exit_time".into(), serde_json::json!(row.get::<_, Option<String>>(5)?));
                 }
                 if keys.contains(&"total_amount".to_string()) {
-                    map.insert("total_amount".into(), serde_json::json!(row.get::<_, Option<f64>>(6)?));
+                    map.insert("total_amount".into(), serde_json::json!(row.get::<_, Option<Money>>(6)?.map(|m| currency.major(m))));
                 }
                 if keys.contains(&"debt".to_string()) {
-                    map.insert("debt".into(), serde_json::json!(row.get::<_, Option<f64>>(7)?));
+                    map.insert("debt".into(), serde_json::json!(row.get::<_, Option<Money>>(7)?.map(|m| currency.major(m))));
                 }
                 if keys.contains(&"operator_user_id".to_string()) {
                     map.insert("operator_user_id".into(), serde_json::json!(row.get::<_, Option<String>>(8)?));
@@ -304,7 +368,7 @@ fn run_completed_vehicles(
     let mut stmt = conn
         .prepare("SELECT v.id, v.ticket_code, v.plate, v.vehicle_type, v.entry_time, v.exit_time, v.total_amount, v.debt, COALESCE(u.display_name, v.operator_user_id) FROM vehicles v LEFT JOIN users u ON v.operator_user_id = u.id WHERE v.status = 'completed' AND v.exit_time IS NOT NULL AND v.exit_time >= ?1 AND v.exit_time < ?2 ORDER BY v.exit_time ASC")
         .map_err(|e| e.to_string())?;
-    let rows = stmt.query_map(params![from_prefix, to_end], |row| {
+    let rows = stmt.query_map(params![from_start, to_end], |row| {
         let mut map = HashMap::new();
         if keys.contains(&"id".to_string()) {
             map.insert("id".into(), serde_json::json!(row.get::<_, String>(0)?));
@@ -325,10 +389,10 @@ fn run_completed_vehicles(
             map.insert("exit_time".into(), serde_json::json!(row.get::<_, Option<String>>(5)?));
         }
         if keys.contains(&"total_amount".to_string()) {
-            map.insert("total_amount".into(), serde_json::json!(row.get::<_, Option<f64>>(6)?));
+            map.insert("total_amount".into(), serde_json::json!(row.get::<_, Option<Money>>(6)?.map(|m| currency.major(m))));
         }
         if keys.contains(&"debt".to_string()) {
-            map.insert("debt".into(), serde_json::json!(row.get::<_, Option<f64>>(7)?));
+            map.insert("debt".into(), serde_json::json!(row.get::<_, Option<Money>>(7)?.map(|m| currency.major(m))));
         }
         if keys.contains(&"operator_user_id".to_string()) {
             map.insert("operator_user_id".into(), serde_json::json!(row.get::<_, Option<String>>(8)?));
@@ -348,9 +412,9 @@ fn run_vehicle_exits(
     vehicle_type: Option<&str>,
     columns: &[ColumnDef],
 ) -> Result<Vec<HashMap<String, serde_json::Value>>, String> {
-    let from_prefix = normalize_date_prefix(date_from);
-    let to_end = date_to_end(date_to);
+    let (from_start, to_end) = date_range(conn, date_from, date_to)?;
     let keys: Vec<String> = columns.iter().map(|c| c.key.clone()).collect();
+    let currency = crate::money::currency(conn)?;
 
     let mut list = Vec::new();
     if let Some(vt) = vehicle_type {
@@ -359,7 +423,7 @@ fn run_vehicle_exits(
             let mut stmt = conn
                 .prepare("SELECT v.id, v.ticket_code, v.plate, v.vehicle_type, v.entry_time, v.exit_time, v.status, v.total_amount, v.debt, COALESCE(u.display_name, v.operator_user_id) FROM vehicles v LEFT JOIN users u ON v.operator_user_id = u.id WHERE v.status IN ('completed', 'removed') AND v.exit_time IS NOT NULL AND v.exit_time >= ?1 AND v.exit_time < ?2 AND LOWER(v.vehicle_type) = ?3 ORDER BY v.exit_time ASC")
                 .map_err(|e| e.to_string())?;
-            let rows = stmt.query_map(params![from_prefix, to_end, vt], |row| {
+            let rows = stmt.query_map(params![from_start, to_end, vt], |row| {
                 let mut map = HashMap::new();
                 if keys.contains(&"id".to_string()) {
                     map.insert("id".into(), serde_json::json!(row.get::<_, String>(0)?));
@@ -383,10 +447,10 @@ fn run_vehicle_exits(
                     map.insert("status".into(), serde_json::json!(row.get::<_, String>(6)?));
                 }
                 if keys.contains(&"total_amount".to_string()) {
-                    map.insert("total_amount".into(), serde_json::json!(row.get::<_, Option<f64>>(7)?));
+                    map.insert("total_amount".into(), serde_json::json!(row.get::<_, Option<Money>>(7)?.map(|m| currency.major(m))));
                 }
                 if keys.contains(&"debt".to_string()) {
-                    map.insert("debt".into(), serde_json::json!(row.get::<_, Option<f64>>(8)?));
+                    map.insert("debt".into(), serde_json::json!(row.get::<_, Option<Money>>(8)?.map(|m| currency.major(m))));
                 }
                 if keys.contains(&"operator_user_id".to_string()) {
                     map.insert("operator_user_id".into(), serde_json::json!(row.get::<_, Option<String>>(9)?));
@@ -402,7 +466,7 @@ fn run_vehicle_exits(
     let mut stmt = conn
         .prepare("SELECT v.id, v.ticket_code, v.plate, v.vehicle_type, v.entry_time, v.exit_time, v.status, v.total_amount, v.debt, COALESCE(u.display_name, v.operator_user_id) FROM vehicles v LEFT JOIN users u ON v.operator_user_id = u.id WHERE v.status IN ('completed', 'removed') AND v.exit_time IS NOT NULL AND v.exit_time >= ?1 AND v.exit_time < ?2 ORDER BY v.exit_time ASC")
         .map_err(|e| e.to_string())?;
-    let rows = stmt.query_map(params![from_prefix, to_end], |row| {
+    let rows = stmt.query_map(params![from_start, to_end], |row| {
         let mut map = HashMap::new();
         if keys.contains(&"id".to_string()) {
             map.insert("id".into(), serde_json::json!(row.get::<_, String>(0)?));
@@ -426,10 +490,10 @@ fn run_vehicle_exits(
             map.insert("status".into(), serde_json::json!(row.get::<_, String>(6)?));
         }
         if keys.contains(&"total_amount".to_string()) {
-            map.insert("total_amount".into(), serde_json::json!(row.get::<_, Option<f64>>(7)?));
+            map.insert("total_amount".into(), serde_json::json!(row.get::<_, Option<Money>>(7)?.map(|m| currency.major(m))));
         }
         if keys.contains(&"debt".to_string()) {
-            map.insert("debt".into(), serde_json::json!(row.get::<_, Option<f64>>(8)?));
+            map.insert("debt".into(), serde_json::json!(row.get::<_, Option<Money>>(8)?.map(|m| currency.major(m))));
         }
         if keys.contains(&"operator_user_id".to_string()) {
             map.insert("operator_user_id".into(), serde_json::json!(row.get::<_, Option<String>>(9)?));
@@ -448,14 +512,14 @@ fn run_shift_closures(
     date_to: &str,
     columns: &[ColumnDef],
 ) -> Result<Vec<HashMap<String, serde_json::Value>>, String> {
-    let from_prefix = normalize_date_prefix(date_from);
-    let to_end = date_to_end(date_to);
+    let (from_start, to_end) = date_range(conn, date_from, date_to)?;
 
     let sql = "SELECT s.id, s.closed_at, s.expected_total, s.cash_total, s.card_total, s.transfer_total, s.arqueo_cash, s.discrepancy, s.total_transactions, s.notes, COALESCE(u.display_name, s.operator_user_id) FROM shift_closures s LEFT JOIN users u ON s.operator_user_id = u.id WHERE s.closed_at >= ?1 AND s.closed_at < ?2 ORDER BY s.closed_at ASC";
     let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
     let keys: Vec<String> = columns.iter().map(|c| c.key.clone()).collect();
+    let currency = crate::money::currency(conn)?;
     let rows = stmt
-        .query_map(params![from_prefix, to_end], |row| {
+        .query_map(params![from_start, to_end], |row| {
             let mut map = HashMap::new();
             if keys.contains(&"id".to_string()) {
                 map.insert("id".into(), serde_json::json!(row.get::<_, String>(0)?));
@@ -464,22 +528,22 @@ fn run_shift_closures(
                 map.insert("closed_at".into(), serde_json::json!(row.get::<_, String>(1)?));
             }
             if keys.contains(&"expected_total".to_string()) {
-                map.insert("expected_total".into(), serde_json::json!(row.get::<_, f64>(2)?));
+                map.insert("expected_total".into(), serde_json::json!(currency.major(row.get(2)?)));
             }
             if keys.contains(&"cash_total".to_string()) {
-                map.insert("cash_total".into(), serde_json::json!(row.get::<_, f64>(3)?));
+                map.insert("cash_total".into(), serde_json::json!(currency.major(row.get(3)?)));
             }
             if keys.contains(&"card_total".to_string()) {
-                map.insert("card_total".into(), serde_json::json!(row.get::<_, f64>(4)?));
+                map.insert("card_total".into(), serde_json::json!(currency.major(row.get(4)?)));
             }
             if keys.contains(&"transfer_total".to_string()) {
-                map.insert("transfer_total".into(), serde_json::json!(row.get::<_, f64>(5)?));
+                map.insert("transfer_total".into(), serde_json::json!(currency.major(row.get(5)?)));
             }
             if keys.contains(&"arqueo_cash".to_string()) {
-                map.insert("arqueo_cash".into(), serde_json::json!(row.get::<_, Option<f64>>(6)?));
+                map.insert("arqueo_cash".into(), serde_json::json!(row.get::<_, Option<Money>>(6)?.map(|m| currency.major(m))));
             }
             if keys.contains(&"discrepancy".to_string()) {
-                map.insert("discrepancy".into(), serde_json::json!(row.get::<_, f64>(7)?));
+                map.insert("discrepancy".into(), serde_json::json!(currency.major(row.get(7)?)));
             }
             if keys.contains(&"total_transactions".to_string()) {
                 map.insert("total_transactions".into(), serde_json::json!(row.get::<_, i64>(8)?));
@@ -509,9 +573,9 @@ fn run_transactions_with_vehicle(
     vehicle_type: Option<&str>,
     columns: &[ColumnDef],
 ) -> Result<Vec<HashMap<String, serde_json::Value>>, String> {
-    let from_prefix = normalize_date_prefix(date_from);
-    let to_end = date_to_end(date_to);
+    let (from_start, to_end) = date_range(conn, date_from, date_to)?;
     let keys: Vec<String> = columns.iter().map(|c| c.key.clone()).collect();
+    let currency = crate::money::currency(conn)?;
 
     let base_sql = "SELECT t.id AS transaction_id, t.created_at, t.amount, t.method, COALESCE(u.display_name, t.operator_user_id), v.id AS vehicle_id, v.ticket_code, v.plate, v.vehicle_type, v.entry_time, v.exit_time FROM transactions t INNER JOIN vehicles v ON v.id = t.vehicle_id LEFT JOIN users u ON t.operator_user_id = u.id WHERE t.created_at >= ?1 AND t.created_at < ?2";
     let map_row = |row: &rusqlite::Row| -> Result<HashMap<String, serde_json::Value>, rusqlite::Error> {
@@ -523,7 +587,7 @@ fn run_transactions_with_vehicle(
             map.insert("created_at".into(), serde_json::json!(row.get::<_, String>(1)?));
         }
         if keys.contains(&"amount".to_string()) {
-            map.insert("amount".into(), serde_json::json!(row.get::<_, f64>(2)?));
+            map.insert("amount".into(), serde_json::json!(currency.major(row.get(2)?)));
         }
         if keys.contains(&"method".to_string()) {
             map.insert("method".into(), serde_json::json!(row.get::<_, String>(3)?));
@@ -562,7 +626,7 @@ fn run_transactions_with_vehicle(
             let v = v.to_lowercase();
             let sql = format!("{} AND LOWER(t.method) = ?3 AND LOWER(v.vehicle_type) = ?4 ORDER BY t.created_at ASC", base_sql);
             let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
-            let rows = stmt.query_map(params![from_prefix, to_end, m, v], |row| map_row(&row)).map_err(|e| e.to_string())?;
+            let rows = stmt.query_map(params![from_start, to_end, m, v], |row| map_row(&row)).map_err(|e| e.to_string())?;
             for row in rows {
                 list.push(row.map_err(|e| e.to_string())?);
             }
@@ -571,7 +635,7 @@ fn run_transactions_with_vehicle(
             let m = m.to_lowercase();
             let sql = format!("{} AND LOWER(t.method) = ?3 ORDER BY t.created_at ASC", base_sql);
             let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
-            let rows = stmt.query_map(params![from_prefix, to_end, m], |row| map_row(&row)).map_err(|e| e.to_string())?;
+            let rows = stmt.query_map(params![from_start, to_end, m], |row| map_row(&row)).map_err(|e| e.to_string())?;
             for row in rows {
                 list.push(row.map_err(|e| e.to_string())?);
             }
@@ -580,7 +644,7 @@ fn run_transactions_with_vehicle(
             let v = v.to_lowercase();
             let sql = format!("{} AND LOWER(v.vehicle_type) = ?3 ORDER BY t.created_at ASC", base_sql);
             let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
-            let rows = stmt.query_map(params![from_prefix, to_end, v], |row| map_row(&row)).map_err(|e| e.to_string())?;
+            let rows = stmt.query_map(params![from_start, to_end, v], |row| map_row(&row)).map_err(|e| e.to_string())?;
             for row in rows {
                 list.push(row.map_err(|e| e.to_string())?);
             }
@@ -588,7 +652,7 @@ fn run_transactions_with_vehicle(
         (None, None) => {
             let sql = format!("{} ORDER BY t.created_at ASC", base_sql);
             let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
-            let rows = stmt.query_map(params![from_prefix, to_end], |row| map_row(&row)).map_err(|e| e.to_string())?;
+            let rows = stmt.query_map(params![from_start, to_end], |row| map_row(&row)).map_err(|e| e.to_string())?;
             for row in rows {
                 list.push(row.map_err(|e| e.to_string())?);
             }
@@ -602,13 +666,14 @@ fn run_debtors(
     columns: &[ColumnDef],
 ) -> Result<Vec<HashMap<String, serde_json::Value>>, String> {
     let keys: Vec<String> = columns.iter().map(|c| c.key.clone()).collect();
+    let currency = crate::money::currency(conn)?;
     let sql = r#"
         SELECT plate_upper AS plate,
-               SUM(COALESCE(debt, 0)) AS total_debt,
-               MIN(exit_time) AS oldest_exit_time,
+               SUM(amount - paid_amount) AS total_debt,
+               MIN(created_at) AS oldest_exit_time,
                COUNT(*) AS sessions_with_debt
-        FROM vehicles
-        WHERE plate_upper IS NOT NULL AND plate_upper != '' AND COALESCE(debt, 0) > 0
+        FROM debts
+        WHERE plate_upper != '' AND status = 'open'
         GROUP BY plate_upper
         ORDER BY total_debt DESC
     "#;
@@ -620,7 +685,7 @@ fn run_debtors(
                 map.insert("plate".into(), serde_json::json!(row.get::<_, String>(0)?));
             }
             if keys.contains(&"total_debt".to_string()) {
-                map.insert("total_debt".into(), serde_json::json!(row.get::<_, f64>(1)?));
+                map.insert("total_debt".into(), serde_json::json!(currency.major(row.get(1)?)));
             }
             if keys.contains(&"oldest_exit_time".to_string()) {
                 map.insert("oldest_exit_time".into(), serde_json::json!(row.get::<_, Option<String>>(2)?));
@@ -638,6 +703,290 @@ fn run_debtors(
     Ok(list)
 }
 
+/// Saldo abierto por placa repartido por antigüedad (0–30, 31–60, 61–90, 90+ días).
+fn run_debt_aging(
+    conn: &rusqlite::Connection,
+    columns: &[ColumnDef],
+) -> Result<Vec<HashMap<String, serde_json::Value>>, String> {
+    let keys: Vec<String> = columns.iter().map(|c| c.key.clone()).collect();
+    let currency = crate::money::currency(conn)?;
+    let sql = format!(
+        r#"
+        SELECT plate_upper AS plate,
+               SUM(amount - paid_amount) AS total_debt,
+               MIN(created_at) AS oldest_debt_at,
+               {}
+        FROM debts
+        WHERE plate_upper != '' AND status = 'open'
+        GROUP BY plate_upper
+        ORDER BY days_over_90 DESC, total_debt DESC
+        "#,
+        crate::domains::debts::AGING_COLUMNS
+    );
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            let aging = crate::domains::debts::aging_from_row(row, &currency)?;
+            let mut map = HashMap::new();
+            if keys.contains(&"plate".to_string()) {
+                map.insert("plate".into(), serde_json::json!(row.get::<_, String>("plate")?));
+            }
+            if keys.contains(&"total_debt".to_string()) {
+                map.insert("total_debt".into(), serde_json::json!(currency.major(row.get("total_debt")?)));
+            }
+            if keys.contains(&"days_0_30".to_string()) {
+                map.insert("days_0_30".into(), serde_json::json!(aging.days_0_30));
+            }
+            if keys.contains(&"days_31_60".to_string()) {
+                map.insert("days_31_60".into(), serde_json::json!(aging.days_31_60));
+            }
+            if keys.contains(&"days_61_90".to_string()) {
+                map.insert("days_61_90".into(), serde_json::json!(aging.days_61_90));
+            }
+            if keys.contains(&"days_over_90".to_string()) {
+                map.insert("days_over_90".into(), serde_json::json!(aging.days_over_90));
+            }
+            if keys.contains(&"oldest_debt_at".to_string()) {
+                map.insert("oldest_debt_at".into(), serde_json::json!(row.get::<_, Option<String>>("oldest_debt_at")?));
+            }
+            Ok(map)
+        })
+        .map_err(|e| e.to_string())?;
+    let mut list = Vec::new();
+    for row in rows {
+        list.push(row.map_err(|e| e.to_string())?);
+    }
+    Ok(list)
+}
+
+fn run_voucher_redemptions(
+    conn: &rusqlite::Connection,
+    date_from: &str,
+    date_to: &str,
+    merchant: Option<&str>,
+    columns: &[ColumnDef],
+) -> Result<Vec<HashMap<String, serde_json::Value>>, String> {
+    let (from_start, to_end) = date_range(conn, date_from, date_to)?;
+    let merchant = merchant.map(str::trim).filter(|m| !m.is_empty());
+    let keys: Vec<String> = columns.iter().map(|c| c.key.clone()).collect();
+    let currency = crate::money::currency(conn)?;
+    let sql = r#"
+        SELECT r.redeemed_at, r.merchant, vo.code, vo.kind, v.ticket_code, v.plate, r.discount_amount,
+               COALESCE(u.display_name, r.operator_user_id)
+        FROM voucher_redemptions r
+        JOIN vouchers vo ON vo.id = r.voucher_id
+        JOIN vehicles v ON v.id = r.vehicle_id
+        LEFT JOIN users u ON r.operator_user_id = u.id
+        WHERE r.redeemed_at >= ?1 AND r.redeemed_at < ?2 AND (?3 IS NULL OR r.merchant = ?3)
+        ORDER BY r.merchant ASC, r.redeemed_at ASC
+    "#;
+    let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![from_start, to_end, merchant], |row| {
+            let mut map = HashMap::new();
+            if keys.contains(&"redeemed_at".to_string()) {
+                map.insert("redeemed_at".into(), serde_json::json!(row.get::<_, String>(0)?));
+            }
+            if keys.contains(&"merchant".to_string()) {
+                map.insert("merchant".into(), serde_json::json!(row.get::<_, String>(1)?));
+            }
+            if keys.contains(&"voucher_code".to_string()) {
+                map.insert("voucher_code".into(), serde_json::json!(row.get::<_, String>(2)?));
+            }
+            if keys.contains(&"voucher_kind".to_string()) {
+                map.insert("voucher_kind".into(), serde_json::json!(row.get::<_, String>(3)?));
+            }
+            if keys.contains(&"ticket_code".to_string()) {
+                map.insert("ticket_code".into(), serde_json::json!(row.get::<_, String>(4)?));
+            }
+            if keys.contains(&"plate".to_string()) {
+                map.insert("plate".into(), serde_json::json!(row.get::<_, String>(5)?));
+            }
+            if keys.contains(&"discount_amount".to_string()) {
+                map.insert("discount_amount".into(), serde_json::json!(currency.major(row.get(6)?)));
+            }
+            if keys.contains(&"operator_user_id".to_string()) {
+                map.insert("operator_user_id".into(), serde_json::json!(row.get::<_, Option<String>>(7)?));
+            }
+            Ok(map)
+        })
+        .map_err(|e| e.to_string())?;
+    let mut list = Vec::new();
+    for row in rows {
+        list.push(row.map_err(|e| e.to_string())?);
+    }
+    Ok(list)
+}
+
+/// One row per merchant: what to bill them for the validations redeemed in the period.
+fn run_merchant_billing(
+    conn: &rusqlite::Connection,
+    date_from: &str,
+    date_to: &str,
+    merchant: Option<&str>,
+    columns: &[ColumnDef],
+) -> Result<Vec<HashMap<String, serde_json::Value>>, String> {
+    let (from_start, to_end) = date_range(conn, date_from, date_to)?;
+    let merchant = merchant.map(str::trim).filter(|m| !m.is_empty());
+    let keys: Vec<String> = columns.iter().map(|c| c.key.clone()).collect();
+    let currency = crate::money::currency(conn)?;
+    let sql = r#"
+        SELECT merchant, COUNT(*), COALESCE(SUM(discount_amount), 0), MIN(redeemed_at), MAX(redeemed_at)
+        FROM voucher_redemptions
+        WHERE redeemed_at >= ?1 AND redeemed_at < ?2 AND (?3 IS NULL OR merchant = ?3)
+        GROUP BY merchant
+        ORDER BY merchant ASC
+    "#;
+    let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![from_start, to_end, merchant], |row| {
+            let mut map = HashMap::new();
+            if keys.contains(&"merchant".to_string()) {
+                map.insert("merchant".into(), serde_json::json!(row.get::<_, String>(0)?));
+            }
+            if keys.contains(&"redemptions".to_string()) {
+                map.insert("redemptions".into(), serde_json::json!(row.get::<_, i64>(1)?));
+            }
+            if keys.contains(&"total_discount".to_string()) {
+                map.insert("total_discount".into(), serde_json::json!(currency.major(row.get(2)?)));
+            }
+            if keys.contains(&"first_redeemed_at".to_string()) {
+                map.insert("first_redeemed_at".into(), serde_json::json!(row.get::<_, String>(3)?));
+            }
+            if keys.contains(&"last_redeemed_at".to_string()) {
+                map.insert("last_redeemed_at".into(), serde_json::json!(row.get::<_, String>(4)?));
+            }
+            Ok(map)
+        })
+        .map_err(|e| e.to_string())?;
+    let mut list = Vec::new();
+    for row in rows {
+        list.push(row.map_err(|e| e.to_string())?);
+    }
+    Ok(list)
+}
+
+/// Exits whose price was typed by the operator, with the computed price it replaced.
+fn run_overrides(
+    conn: &rusqlite::Connection,
+    date_from: &str,
+    date_to: &str,
+    columns: &[ColumnDef],
+) -> Result<Vec<HashMap<String, serde_json::Value>>, String> {
+    let (from_start, to_end) = date_range(conn, date_from, date_to)?;
+    let keys: Vec<String> = columns.iter().map(|c| c.key.clone()).collect();
+    let currency = crate::money::currency(conn)?;
+    let sql = r#"
+        SELECT t.id, t.created_at, v.ticket_code, v.plate, t.computed_amount, t.override_amount, t.override_reason,
+               COALESCE(u.display_name, t.operator_user_id)
+        FROM transactions t
+        JOIN vehicles v ON v.id = t.vehicle_id
+        LEFT JOIN users u ON t.operator_user_id = u.id
+        WHERE t.override_amount IS NOT NULL AND t.created_at >= ?1 AND t.created_at < ?2
+        ORDER BY t.created_at ASC
+    "#;
+    let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![from_start, to_end], |row| {
+            let computed: Option<Money> = row.get(4)?;
+            let overridden: Money = row.get(5)?;
+            let mut map = HashMap::new();
+            if keys.contains(&"transaction_id".to_string()) {
+                map.insert("transaction_id".into(), serde_json::json!(row.get::<_, String>(0)?));
+            }
+            if keys.contains(&"created_at".to_string()) {
+                map.insert("created_at".into(), serde_json::json!(row.get::<_, String>(1)?));
+            }
+            if keys.contains(&"ticket_code".to_string()) {
+                map.insert("ticket_code".into(), serde_json::json!(row.get::<_, String>(2)?));
+            }
+            if keys.contains(&"plate".to_string()) {
+                map.insert("plate".into(), serde_json::json!(row.get::<_, String>(3)?));
+            }
+            if keys.contains(&"computed_amount".to_string()) {
+                map.insert("computed_amount".into(), serde_json::json!(computed.map(|c| currency.major(c))));
+            }
+            if keys.contains(&"override_amount".to_string()) {
+                map.insert("override_amount".into(), serde_json::json!(currency.major(overridden)));
+            }
+            if keys.contains(&"difference".to_string()) {
+                map.insert("difference".into(), serde_json::json!(computed.map(|c| currency.major(overridden - c))));
+            }
+            if keys.contains(&"override_reason".to_string()) {
+                map.insert("override_reason".into(), serde_json::json!(row.get::<_, Option<String>>(6)?));
+            }
+            if keys.contains(&"operator_user_id".to_string()) {
+                map.insert("operator_user_id".into(), serde_json::json!(row.get::<_, Option<String>>(7)?));
+            }
+            Ok(map)
+        })
+        .map_err(|e| e.to_string())?;
+    let mut list = Vec::new();
+    for row in rows {
+        list.push(row.map_err(|e| e.to_string())?);
+    }
+    Ok(list)
+}
+
+fn run_refunds(
+    conn: &rusqlite::Connection,
+    date_from: &str,
+    date_to: &str,
+    columns: &[ColumnDef],
+) -> Result<Vec<HashMap<String, serde_json::Value>>, String> {
+    let (from_start, to_end) = date_range(conn, date_from, date_to)?;
+    let keys: Vec<String> = columns.iter().map(|c| c.key.clone()).collect();
+    let currency = crate::money::currency(conn)?;
+    let sql = r#"
+        SELECT t.id, t.created_at, v.ticket_code, v.plate, t.method, -t.amount, t.refund_of, t.refund_reason,
+               COALESCE(u.display_name, t.operator_user_id)
+        FROM transactions t
+        JOIN vehicles v ON v.id = t.vehicle_id
+        LEFT JOIN users u ON t.operator_user_id = u.id
+        WHERE t.kind = 'refund' AND t.created_at >= ?1 AND t.created_at < ?2
+        ORDER BY t.created_at ASC
+    "#;
+    let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![from_start, to_end], |row| {
+            let mut map = HashMap::new();
+            if keys.contains(&"transaction_id".to_string()) {
+                map.insert("transaction_id".into(), serde_json::json!(row.get::<_, String>(0)?));
+            }
+            if keys.contains(&"created_at".to_string()) {
+                map.insert("created_at".into(), serde_json::json!(row.get::<_, String>(1)?));
+            }
+            if keys.contains(&"ticket_code".to_string()) {
+                map.insert("ticket_code".into(), serde_json::json!(row.get::<_, String>(2)?));
+            }
+            if keys.contains(&"plate".to_string()) {
+                map.insert("plate".into(), serde_json::json!(row.get::<_, String>(3)?));
+            }
+            if keys.contains(&"method".to_string()) {
+                map.insert("method".into(), serde_json::json!(row.get::<_, String>(4)?));
+            }
+            if keys.contains(&"refunded_amount".to_string()) {
+                map.insert("refunded_amount".into(), serde_json::json!(currency.major(row.get(5)?)));
+            }
+            if keys.contains(&"refund_of".to_string()) {
+                map.insert("refund_of".into(), serde_json::json!(row.get::<_, Option<String>>(6)?));
+            }
+            if keys.contains(&"refund_reason".to_string()) {
+                map.insert("refund_reason".into(), serde_json::json!(row.get::<_, Option<String>>(7)?));
+            }
+            if keys.contains(&"operator_user_id".to_string()) {
+                map.insert("operator_user_id".into(), serde_json::json!(row.get::<_, Option<String>>(8)?));
+            }
+            Ok(map)
+        })
+        .map_err(|e| e.to_string())?;
+    let mut list = Vec::new();
+    for row in rows {
+        list.push(row.map_err(|e| e.to_string())?);
+    }
+    Ok(list)
+}
+
 #[tauri::command]
 pub fn reportes_get_column_definitions(report_type: ReportType) -> Vec<ColumnDef> {
     all_columns_for_type(&report_type)
@@ -651,9 +1000,12 @@ pub fn reportes_fetch(
     selected_columns: Option<Vec<String>>,
 ) -> Result<ReportData, String> {
     state.check_permission(permissions::METRICAS_REPORTS_EXPORT)?;
-    if report_type == ReportType::Debtors {
+    if matches!(report_type, ReportType::Debtors | ReportType::DebtAging) {
         state.check_permission(permissions::CAJA_DEBTORS_READ)?;
     }
+    if matches!(report_type, ReportType::VoucherRedemptions | ReportType::MerchantBilling) {
+        state.check_permission(permissions::VOUCHERS_READ)?;
+    }
     let conn = state.db.get().map_err(|e| e.to_string())?;
 
     let all = all_columns_for_type(&report_type);
@@ -702,6 +1054,23 @@ pub fn reportes_fetch(
             &columns,
         )?,
         ReportType::Debtors => run_debtors(&conn, &columns)?,
+        ReportType::DebtAging => run_debt_aging(&conn, &columns)?,
+        ReportType::VoucherRedemptions => run_voucher_redemptions(
+            &conn,
+            &filters.date_from,
+            &filters.date_to,
+            filters.merchant.as_deref(),
+            &columns,
+        )?,
+        ReportType::MerchantBilling => run_merchant_billing(
+            &conn,
+            &filters.date_from,
+            &filters.date_to,
+            filters.merchant.as_deref(),
+            &columns,
+        )?,
+        ReportType::Overrides => run_overrides(&conn, &filters.date_from, &filters.date_to, &columns)?,
+        ReportType::Refunds => run_refunds(&conn, &filters.date_from, &filters.date_to, &columns)?,
     };
 
     Ok(ReportData { columns, rows })
@@ -737,9 +1106,12 @@ pub fn reportes_write_csv(
     path: String,
 ) -> Result<(), String> {
     state.check_permission(permissions::METRICAS_REPORTS_EXPORT)?;
-    if report_type == ReportType::Debtors {
+    if matches!(report_type, ReportType::Debtors | ReportType::DebtAging) {
         state.check_permission(permissions::CAJA_DEBTORS_READ)?;
     }
+    if matches!(report_type, ReportType::VoucherRedemptions | ReportType::MerchantBilling) {
+        state.check_permission(permissions::VOUCHERS_READ)?;
+    }
     let conn = state.db.get().map_err(|e| e.to_string())?;
 
     let all = all_columns_for_type(&report_type);
@@ -788,6 +1160,23 @@ pub fn reportes_write_csv(
             &columns,
         )?,
         ReportType::Debtors => run_debtors(&conn, &columns)?,
+        ReportType::DebtAging => run_debt_aging(&conn, &columns)?,
+        ReportType::VoucherRedemptions => run_voucher_redemptions(
+            &conn,
+            &filters.date_from,
+            &filters.date_to,
+            filters.merchant.as_deref(),
+            &columns,
+        )?,
+        ReportType::MerchantBilling => run_merchant_billing(
+            &conn,
+            &filters.date_from,
+            &filters.date_to,
+            filters.merchant.as_deref(),
+            &columns,
+        )?,
+        ReportType::Overrides => run_overrides(&conn, &filters.date_from, &filters.date_to, &columns)?,
+        ReportType::Refunds => run_refunds(&conn, &filters.date_from, &filters.date_to, &columns)?,
     };
 
     let header: String = columns.iter().map(|c| c.label.as_str()).collect::<Vec<_>>().join(",");
@@ -799,3 +1188,90 @@ pub fn reportes_write_csv(
     std::fs::write(&path, content).map_err(|e| e.to_string())?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use rusqlite::Connection;
+
+    fn migrated_conn() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::run_migrations(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO vehicles (id, ticket_code, plate, plate_upper, vehicle_type, entry_time, exit_time, status, tariff_kind) VALUES
+                ('v1', 'T1', 'AAA111', 'AAA111', 'car', '2025-01-05T13:00:00+00:00', '2025-01-05T15:00:00+00:00', 'completed', 'regular'),
+                ('v2', 'T2', 'BBB222', 'BBB222', 'car', '2025-01-05T13:00:00+00:00', '2025-01-06T03:00:00+00:00', 'completed', 'regular');",
+        )
+        .unwrap();
+        conn
+    }
+
+    /// Mediodía del día de negocio de hace `days` días, en UTC.
+    fn business_days_ago(conn: &Connection, days: i64) -> String {
+        let tz = settings::business_tz(conn).unwrap();
+        let day = settings::today_in(tz) - chrono::Duration::days(days);
+        tz.from_local_datetime(&day.and_hms_opt(12, 0, 0).unwrap())
+            .unwrap()
+            .with_timezone(&chrono::Utc)
+            .to_rfc3339()
+    }
+
+    #[test]
+    fn overrides_show_the_computed_and_charged_amounts_with_the_reason() {
+        let conn = migrated_conn();
+        // El segundo cobro es a las 22:00 del 5 de enero en Bogotá (03:00 UTC del 6).
+        conn.execute_batch(
+            "INSERT INTO transactions (id, vehicle_id, amount, method, created_at, computed_amount, override_amount, override_reason) VALUES
+                ('tx1', 'v1', 3000, 'cash', '2025-01-05T15:00:00+00:00', 8000, 3000, 'cliente frecuente'),
+                ('tx2', 'v2', 9000, 'card', '2025-01-06T03:00:00+00:00', NULL, NULL, NULL),
+                ('tx3', 'v2', 0, 'cash', '2025-01-06T03:00:00+00:00', 5000, 0, 'cortesía');",
+        )
+        .unwrap();
+
+        let rows = run_overrides(&conn, "2025-01-05", "2025-01-05", &overrides_columns()).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0]["transaction_id"], serde_json::json!("tx1"));
+        assert_eq!(rows[0]["computed_amount"], serde_json::json!(8000.0));
+        assert_eq!(rows[0]["override_amount"], serde_json::json!(3000.0));
+        assert_eq!(rows[0]["difference"], serde_json::json!(-5000.0));
+        assert_eq!(rows[0]["override_reason"], serde_json::json!("cliente frecuente"));
+        assert_eq!(rows[1]["transaction_id"], serde_json::json!("tx3"));
+        assert_eq!(rows[1]["override_amount"], serde_json::json!(0.0));
+        assert_eq!(rows[1]["override_reason"], serde_json::json!("cortesía"));
+        assert!(run_overrides(&conn, "2025-01-06", "2025-01-06", &overrides_columns()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn debt_aging_puts_each_open_balance_in_its_bucket() {
+        let conn = migrated_conn();
+        let debts = [
+            ("d1", "AAA111", "v1", 10000, 0, "open", 5),
+            ("d2", "AAA111", "v1", 3000, 1000, "open", 100),
+            ("d3", "BBB222", "v2", 5000, 0, "open", 40),
+            ("d4", "BBB222", "v2", 7000, 0, "open", 70),
+            ("d5", "BBB222", "v2", 4000, 4000, "settled", 200),
+        ];
+        for (id, plate, vehicle, amount, paid, status, days) in debts {
+            conn.execute(
+                "INSERT INTO debts (id, plate_upper, vehicle_id, amount, paid_amount, status, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![id, plate, vehicle, amount, paid, status, business_days_ago(&conn, days)],
+            )
+            .unwrap();
+        }
+
+        let rows = run_debt_aging(&conn, &debt_aging_columns()).unwrap();
+        assert_eq!(rows.len(), 2);
+        // Primero la placa con saldo de más de 90 días.
+        assert_eq!(rows[0]["plate"], serde_json::json!("AAA111"));
+        assert_eq!(rows[0]["total_debt"], serde_json::json!(12000.0));
+        assert_eq!(rows[0]["days_0_30"], serde_json::json!(10000.0));
+        assert_eq!(rows[0]["days_31_60"], serde_json::json!(0.0));
+        assert_eq!(rows[0]["days_over_90"], serde_json::json!(2000.0));
+        assert_eq!(rows[1]["plate"], serde_json::json!("BBB222"));
+        assert_eq!(rows[1]["total_debt"], serde_json::json!(12000.0));
+        assert_eq!(rows[1]["days_31_60"], serde_json::json!(5000.0));
+        assert_eq!(rows[1]["days_61_90"], serde_json::json!(7000.0));
+        assert_eq!(rows[1]["days_over_90"], serde_json::json!(0.0));
+    }
+}
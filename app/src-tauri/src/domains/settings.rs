@@ -0,0 +1,233 @@
+//! Business settings shared by every domain: the business timezone (IANA name, persisted in
+//! drive_config as `business_timezone`) and the currency with its rounding increment (see
+//! `money`). Every "day" boundary — treasury, shifts, reports, metrics, contract arrears — is
+//! computed in this zone; timestamps are still stored in UTC (rfc3339).
+
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use chrono_tz::Tz;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::db::{get_config_value, set_config_value};
+use crate::money::{self, Currency};
+use crate::permissions;
+use crate::state::AppState;
+
+const CONFIG_KEY_TIMEZONE: &str = "business_timezone";
+
+/// Parses an IANA zone name (e.g. "America/Bogota").
+pub fn parse_timezone(name: &str) -> Result<Tz, String> {
+    name.trim()
+        .parse::<Tz>()
+        .map_err(|_| format!("Invalid timezone: {}", name.trim()))
+}
+
+/// Configured business timezone; America/Bogota when unset or invalid.
+pub fn business_tz(conn: &Connection) -> Result<Tz, String> {
+    let configured = get_config_value(conn, CONFIG_KEY_TIMEZONE)?
+        .filter(|s| !s.trim().is_empty())
+        .and_then(|s| parse_timezone(&s).ok());
+    Ok(configured.unwrap_or(chrono_tz::America::Bogota))
+}
+
+/// Current business date in `tz`.
+pub fn today_in(tz: Tz) -> NaiveDate {
+    Utc::now().with_timezone(&tz).date_naive()
+}
+
+/// Business date of a stored UTC timestamp.
+pub fn local_date(tz: Tz, utc_iso: &str) -> Option<NaiveDate> {
+    DateTime::parse_from_rfc3339(utc_iso)
+        .ok()
+        .map(|dt| dt.with_timezone(&tz).date_naive())
+}
+
+/// UTC instant of local midnight starting `date`. On a DST gap the first valid instant is used.
+fn local_midnight_utc(tz: Tz, date: NaiveDate) -> DateTime<Utc> {
+    let midnight = date.and_hms_opt(0, 0, 0).unwrap_or_default();
+    tz.from_local_datetime(&midnight)
+        .earliest()
+        .or_else(|| tz.from_local_datetime(&(midnight + chrono::Duration::hours(1))).earliest())
+        .map(|dt| dt.with_timezone(&Utc))
+        .unwrap_or_else(|| Utc.from_utc_datetime(&midnight))
+}
+
+fn utc_bound(dt: DateTime<Utc>) -> String {
+    dt.to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+}
+
+/// Half-open UTC range [start, end) covering the business days `from..=to`, as rfc3339 strings
+/// comparable with stored timestamps.
+pub fn range_bounds_utc(tz: Tz, from: NaiveDate, to: NaiveDate) -> (String, String) {
+    let end_day = to.succ_opt().unwrap_or(to);
+    (
+        utc_bound(local_midnight_utc(tz, from)),
+        utc_bound(local_midnight_utc(tz, end_day)),
+    )
+}
+
+/// Half-open UTC range [start, end) of one business day.
+pub fn day_bounds_utc(tz: Tz, date: NaiveDate) -> (String, String) {
+    range_bounds_utc(tz, date, date)
+}
+
+/// Parses a "YYYY-MM-DD" date (longer values are cut to the date part).
+pub fn parse_date(s: &str) -> Result<NaiveDate, String> {
+    let s = s.trim();
+    let s = if s.len() >= 10 { &s[..10] } else { s };
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn settings_get_business_timezone(state: State<AppState>) -> Result<String, String> {
+    state.check_permission(permissions::SETTINGS_READ)?;
+    let conn = state.db.get().map_err(|e| e.to_string())?;
+    Ok(business_tz(&conn)?.name().to_string())
+}
+
+#[tauri::command]
+pub fn settings_set_business_timezone(state: State<AppState>, timezone: String) -> Result<String, String> {
+    state.check_permission(permissions::SETTINGS_MODIFY)?;
+    let tz = parse_timezone(&timezone)?;
+    let conn = state.db.get().map_err(|e| e.to_string())?;
+    set_config_value(&conn, CONFIG_KEY_TIMEZONE, tz.name())?;
+    Ok(tz.name().to_string())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurrencySettings {
+    pub code: String,
+    pub decimals: u32,
+    /// Charges are rounded to the nearest multiple of this amount (major units).
+    pub rounding_increment: f64,
+}
+
+impl From<&Currency> for CurrencySettings {
+    fn from(c: &Currency) -> Self {
+        CurrencySettings {
+            code: c.code.clone(),
+            decimals: c.decimals,
+            rounding_increment: c.rounding_increment_major(),
+        }
+    }
+}
+
+#[tauri::command]
+pub fn settings_get_currency(state: State<AppState>) -> Result<CurrencySettings, String> {
+    state.check_permission(permissions::SETTINGS_READ)?;
+    let conn = state.db.get().map_err(|e| e.to_string())?;
+    Ok(CurrencySettings::from(&money::currency(&conn)?))
+}
+
+#[tauri::command]
+pub fn settings_set_currency(
+    state: State<AppState>,
+    code: String,
+    rounding_increment: Option<f64>,
+) -> Result<CurrencySettings, String> {
+    state.check_permission(permissions::SETTINGS_MODIFY)?;
+    let conn = state.db.get().map_err(|e| e.to_string())?;
+    set_currency_on(&conn, &code, rounding_increment)
+}
+
+/// Saves the currency. The code can only change while nothing has been charged or owed yet:
+/// stored amounts are in the current currency and are not converted. Tariff amounts keep their
+/// value in major units when the number of decimals changes. The rounding increment can change
+/// at any time.
+fn set_currency_on(
+    conn: &Connection,
+    code: &str,
+    rounding_increment: Option<f64>,
+) -> Result<CurrencySettings, String> {
+    let currency = Currency::new(code, rounding_increment)?;
+    let current = money::currency(conn)?;
+    if currency.code != current.code {
+        let in_use: i64 = conn
+            .query_row(
+                "SELECT (SELECT COUNT(*) FROM transactions) + (SELECT COUNT(*) FROM contracts) + (SELECT COUNT(*) FROM debts)",
+                [],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+        if in_use > 0 {
+            return Err("The currency cannot be changed once transactions, contracts or debts exist".to_string());
+        }
+    }
+    crate::db::with_transaction(conn, |conn| {
+        if currency.decimals != current.decimals {
+            let factor = currency.minor_per_major() as f64 / current.minor_per_major() as f64;
+            rescale_tariff_amounts(conn, factor)?;
+        }
+        set_config_value(conn, money::CONFIG_KEY_CURRENCY, &currency.code)?;
+        set_config_value(
+            conn,
+            money::CONFIG_KEY_ROUNDING_INCREMENT,
+            &currency.rounding_increment_major().to_string(),
+        )
+    })?;
+    Ok(CurrencySettings::from(&currency))
+}
+
+/// Multiplies the minor-unit amounts of tariffs and tariff rules by `factor`.
+fn rescale_tariff_amounts(conn: &Connection, factor: f64) -> Result<(), String> {
+    conn.execute(
+        r#"UPDATE custom_tariffs SET
+               amount = CAST(ROUND(amount * ?1) AS INTEGER),
+               additional_hour_price = CAST(ROUND(additional_hour_price * ?1) AS INTEGER),
+               daily_max_amount = CAST(ROUND(daily_max_amount * ?1) AS INTEGER),
+               lost_ticket_amount = CAST(ROUND(lost_ticket_amount * ?1) AS INTEGER)"#,
+        [factor],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute("UPDATE tariff_rules SET amount = CAST(ROUND(amount * ?1) AS INTEGER)", [factor])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn day_bounds_follow_the_business_timezone() {
+        let date = NaiveDate::from_ymd_opt(2025, 1, 5).unwrap();
+        let (start, end) = day_bounds_utc(chrono_tz::America::Bogota, date);
+        assert_eq!(start, "2025-01-05T05:00:00.000Z");
+        assert_eq!(end, "2025-01-06T05:00:00.000Z");
+
+        // Día de cambio de horario: 23 horas.
+        let dst = NaiveDate::from_ymd_opt(2025, 3, 30).unwrap();
+        let (start, end) = day_bounds_utc(chrono_tz::Europe::Madrid, dst);
+        assert_eq!(start, "2025-03-29T23:00:00.000Z");
+        assert_eq!(end, "2025-03-30T22:00:00.000Z");
+
+        assert!(parse_timezone("Mars/Olympus").is_err());
+    }
+
+    #[test]
+    fn currency_code_is_locked_once_money_was_recorded() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::run_migrations(&conn).unwrap();
+        let tariff_total = |conn: &Connection| -> i64 {
+            conn.query_row("SELECT SUM(amount) FROM custom_tariffs", [], |row| row.get(0)).unwrap()
+        };
+        let cop_total = tariff_total(&conn);
+        assert_eq!(set_currency_on(&conn, "usd", Some(0.05)).unwrap().code, "USD");
+        // Las tarifas conservan su valor en unidades mayores: 3000 COP -> 3000.00 USD.
+        assert_eq!(tariff_total(&conn), cop_total * 100);
+        assert_eq!(set_currency_on(&conn, "COP", Some(50.0)).unwrap().code, "COP");
+        assert_eq!(tariff_total(&conn), cop_total);
+
+        conn.execute(
+            "INSERT INTO debts (id, plate_upper, vehicle_id, amount, paid_amount, status, created_at) VALUES ('d1', 'ABC123', 'v1', 5000, 0, 'open', '2025-01-01T00:00:00+00:00')",
+            [],
+        )
+        .unwrap();
+        assert!(set_currency_on(&conn, "USD", None).is_err());
+        let same = set_currency_on(&conn, "cop", Some(100.0)).unwrap();
+        assert_eq!(same.code, "COP");
+        assert_eq!(money::currency(&conn).unwrap().rounding_increment_major(), 100.0);
+    }
+}
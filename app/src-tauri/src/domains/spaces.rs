@@ -0,0 +1,409 @@
+//! Individual parking spaces (code, zone, allowed vehicle types, status).
+//! A space is occupied by at most one active vehicle (`spaces.vehicle_id`); the vehicle keeps
+//! `vehicles.space_id` as the record of where it was parked. Entry assigns (explicit code or
+//! first free compatible space), exit/removal releases.
+
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::db::with_transaction;
+use crate::domains::capacity::VEHICLE_TYPES;
+use crate::id_gen;
+use crate::permissions;
+use crate::state::AppState;
+
+const STATUS_AVAILABLE: &str = "available";
+const STATUS_OCCUPIED: &str = "occupied";
+const STATUS_OUT_OF_SERVICE: &str = "out_of_service";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Space {
+    pub id: String,
+    pub code: String,
+    pub zone: String,
+    pub allowed_vehicle_types: Vec<String>,
+    /// available | occupied | out_of_service
+    pub status: String,
+    pub vehicle_id: Option<String>,
+    pub created_at: String,
+    pub updated_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LotMapVehicle {
+    pub id: String,
+    pub ticket_code: String,
+    pub plate: String,
+    pub vehicle_type: String,
+    pub entry_time: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LotMapEntry {
+    pub space: Space,
+    pub vehicle: Option<LotMapVehicle>,
+}
+
+const SPACE_COLS: &str = "id, code, zone, allowed_vehicle_types, status, vehicle_id, created_at, updated_at";
+
+fn row_to_space(row: &rusqlite::Row) -> rusqlite::Result<Space> {
+    let allowed: String = row.get("allowed_vehicle_types")?;
+    Ok(Space {
+        id: row.get("id")?,
+        code: row.get("code")?,
+        zone: row.get("zone")?,
+        allowed_vehicle_types: allowed
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(String::from)
+            .collect(),
+        status: row.get("status")?,
+        vehicle_id: row.get("vehicle_id")?,
+        created_at: row.get("created_at")?,
+        updated_at: row.get("updated_at")?,
+    })
+}
+
+fn normalize_allowed_types(types: Option<&[String]>) -> Result<String, String> {
+    let list: Vec<String> = match types {
+        Some(list) if !list.is_empty() => list.iter().map(|t| t.trim().to_lowercase()).collect(),
+        _ => VEHICLE_TYPES.iter().map(|t| t.to_string()).collect(),
+    };
+    for t in &list {
+        if !VEHICLE_TYPES.contains(&t.as_str()) {
+            return Err(format!("Invalid vehicle type: {}", t));
+        }
+    }
+    Ok(list.join(","))
+}
+
+fn find_space_by_code(conn: &Connection, code: &str) -> Option<Space> {
+    conn.query_row(
+        &format!("SELECT {} FROM spaces WHERE code = ?1", SPACE_COLS),
+        params![code.trim().to_uppercase()],
+        row_to_space,
+    )
+    .ok()
+}
+
+fn get_space(conn: &Connection, id: &str) -> Result<Space, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM spaces WHERE id = ?1", SPACE_COLS),
+        params![id],
+        row_to_space,
+    )
+    .map_err(|_| "Space not found".to_string())
+}
+
+fn space_accepts(space: &Space, vehicle_type: &str) -> bool {
+    space.allowed_vehicle_types.iter().any(|t| t == vehicle_type)
+}
+
+/// Picks the space for a new entry. With a requested code the space must exist, be available
+/// and accept the vehicle type; without one, the first free compatible space (by zone, code)
+/// is used. Ok(None) when the lot has no free space modelled for this type.
+pub fn pick_space_for_entry(
+    conn: &Connection,
+    vehicle_type: &str,
+    requested_code: Option<&str>,
+) -> Result<Option<Space>, String> {
+    if let Some(code) = requested_code.map(str::trim).filter(|s| !s.is_empty()) {
+        let space = find_space_by_code(conn, code).ok_or_else(|| format!("El espacio {} no existe", code))?;
+        if space.status != STATUS_AVAILABLE {
+            return Err(format!("El espacio {} no está disponible", space.code));
+        }
+        if !space_accepts(&space, vehicle_type) {
+            return Err(format!("El espacio {} no admite este tipo de vehículo", space.code));
+        }
+        return Ok(Some(space));
+    }
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM spaces WHERE status = 'available' ORDER BY zone, code",
+            SPACE_COLS
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt.query_map([], row_to_space).map_err(|e| e.to_string())?;
+    for row in rows {
+        let space = row.map_err(|e| e.to_string())?;
+        if space_accepts(&space, vehicle_type) {
+            return Ok(Some(space));
+        }
+    }
+    Ok(None)
+}
+
+/// Marks the space occupied by the vehicle. Fails if someone else took it in the meantime.
+pub fn occupy_space(conn: &Connection, space_id: &str, vehicle_id: &str) -> Result<(), String> {
+    let now = chrono::Utc::now().to_rfc3339();
+    let n = conn
+        .execute(
+            "UPDATE spaces SET status = 'occupied', vehicle_id = ?1, updated_at = ?2 WHERE id = ?3 AND status = 'available'",
+            params![vehicle_id, now, space_id],
+        )
+        .map_err(|e| e.to_string())?;
+    if n == 0 {
+        return Err("El espacio ya no está disponible".to_string());
+    }
+    Ok(())
+}
+
+/// Frees whatever space the vehicle is occupying (no-op when it has none).
+pub fn release_space_for_vehicle(conn: &Connection, vehicle_id: &str) -> Result<(), String> {
+    let now = chrono::Utc::now().to_rfc3339();
+    conn.execute(
+        "UPDATE spaces SET status = 'available', vehicle_id = NULL, updated_at = ?1 WHERE vehicle_id = ?2",
+        params![now, vehicle_id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn spaces_get_lot_map(state: State<AppState>, zone: Option<String>) -> Result<Vec<LotMapEntry>, String> {
+    state.check_permission(permissions::SPACES_READ)?;
+    let conn = state.db.get().map_err(|e| e.to_string())?;
+    let zone = zone.as_deref().map(str::trim).filter(|s| !s.is_empty());
+    let mut stmt = conn
+        .prepare(
+            r#"
+            SELECT s.id, s.code, s.zone, s.allowed_vehicle_types, s.status, s.vehicle_id, s.created_at, s.updated_at,
+                   v.ticket_code, v.plate, v.vehicle_type, v.entry_time
+            FROM spaces s
+            LEFT JOIN vehicles v ON v.id = s.vehicle_id AND v.status = 'active'
+            WHERE (?1 IS NULL OR s.zone = ?1)
+            ORDER BY s.zone, s.code
+            "#,
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![zone], |row| {
+            let space = row_to_space(row)?;
+            let ticket_code: Option<String> = row.get("ticket_code")?;
+            let vehicle = match (space.vehicle_id.clone(), ticket_code) {
+                (Some(id), Some(ticket_code)) => Some(LotMapVehicle {
+                    id,
+                    ticket_code,
+                    plate: row.get("plate")?,
+                    vehicle_type: row.get("vehicle_type")?,
+                    entry_time: row.get("entry_time")?,
+                }),
+                _ => None,
+            };
+            Ok(LotMapEntry { space, vehicle })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateSpaceArgs {
+    pub code: String,
+    pub zone: Option<String>,
+    pub allowed_vehicle_types: Option<Vec<String>>,
+}
+
+#[tauri::command]
+pub fn spaces_create(state: State<AppState>, args: CreateSpaceArgs) -> Result<Space, String> {
+    state.check_permission(permissions::SPACES_MODIFY)?;
+    let code = args.code.trim().to_uppercase();
+    if code.is_empty() {
+        return Err("Space code is required".to_string());
+    }
+    let zone = args.zone.as_deref().map(str::trim).unwrap_or("").to_string();
+    let allowed = normalize_allowed_types(args.allowed_vehicle_types.as_deref())?;
+    let conn = state.db.get().map_err(|e| e.to_string())?;
+    if find_space_by_code(&conn, &code).is_some() {
+        return Err(format!("A space with code {} already exists", code));
+    }
+    let id = id_gen::generate_id(id_gen::PREFIX_SPACE);
+    let now = chrono::Utc::now().to_rfc3339();
+    conn.execute(
+        "INSERT INTO spaces (id, code, zone, allowed_vehicle_types, status, vehicle_id, created_at) VALUES (?1, ?2, ?3, ?4, 'available', NULL, ?5)",
+        params![id, code, zone, allowed, now],
+    )
+    .map_err(|e| e.to_string())?;
+    get_space(&conn, &id)
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateSpaceArgs {
+    pub id: String,
+    pub zone: Option<String>,
+    pub allowed_vehicle_types: Option<Vec<String>>,
+    /// Only available <-> out_of_service can be set by hand; occupancy follows entries/exits.
+    pub status: Option<String>,
+}
+
+#[tauri::command]
+pub fn spaces_update(state: State<AppState>, args: UpdateSpaceArgs) -> Result<Space, String> {
+    state.check_permission(permissions::SPACES_MODIFY)?;
+    let conn = state.db.get().map_err(|e| e.to_string())?;
+    let existing = get_space(&conn, args.id.trim())?;
+    let zone = args
+        .zone
+        .as_deref()
+        .map(|z| z.trim().to_string())
+        .unwrap_or(existing.zone.clone());
+    let allowed = match args.allowed_vehicle_types.as_deref() {
+        Some(list) => normalize_allowed_types(Some(list))?,
+        None => existing.allowed_vehicle_types.join(","),
+    };
+    let status = match args.status.as_deref().map(str::trim) {
+        None => existing.status.clone(),
+        Some(s) if s == STATUS_AVAILABLE || s == STATUS_OUT_OF_SERVICE => {
+            if existing.status == STATUS_OCCUPIED {
+                return Err("Cannot change the status of an occupied space".to_string());
+            }
+            s.to_string()
+        }
+        Some(s) => return Err(format!("Invalid space status: {}", s)),
+    };
+    let now = chrono::Utc::now().to_rfc3339();
+    conn.execute(
+        "UPDATE spaces SET zone = ?1, allowed_vehicle_types = ?2, status = ?3, updated_at = ?4 WHERE id = ?5",
+        params![zone, allowed, status, now, existing.id],
+    )
+    .map_err(|e| e.to_string())?;
+    get_space(&conn, &existing.id)
+}
+
+#[tauri::command]
+pub fn spaces_delete(state: State<AppState>, id: String) -> Result<(), String> {
+    state.check_permission(permissions::SPACES_MODIFY)?;
+    let conn = state.db.get().map_err(|e| e.to_string())?;
+    let space = get_space(&conn, id.trim())?;
+    if space.status == STATUS_OCCUPIED {
+        return Err("Cannot delete an occupied space".to_string());
+    }
+    conn.execute("DELETE FROM spaces WHERE id = ?1", params![space.id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Moves an active vehicle to another free space (frees the current one, if any). Moving it
+/// to the space it already occupies is a no-op.
+fn move_vehicle_on(conn: &Connection, ticket_code: &str, target_space_code: &str) -> Result<Space, String> {
+    with_transaction(conn, |conn| {
+        let (vehicle_id, vehicle_type): (String, String) = conn
+            .query_row(
+                "SELECT id, vehicle_type FROM vehicles WHERE ticket_code = ?1 AND status = 'active'",
+                params![ticket_code.trim()],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .map_err(|_| "Vehicle not found or not active".to_string())?;
+        if let Some(current) = find_space_by_code(conn, target_space_code)
+            .filter(|space| space.vehicle_id.as_deref() == Some(vehicle_id.as_str()))
+        {
+            return Ok(current);
+        }
+        let target = pick_space_for_entry(conn, &vehicle_type, Some(target_space_code))?
+            .ok_or_else(|| "Space not found".to_string())?;
+        release_space_for_vehicle(conn, &vehicle_id)?;
+        occupy_space(conn, &target.id, &vehicle_id)?;
+        conn.execute(
+            "UPDATE vehicles SET space_id = ?1 WHERE id = ?2",
+            params![target.id, vehicle_id],
+        )
+        .map_err(|e| e.to_string())?;
+        get_space(conn, &target.id)
+    })
+}
+
+#[tauri::command]
+pub fn spaces_move_vehicle(
+    state: State<AppState>,
+    ticket_code: String,
+    target_space_code: String,
+) -> Result<Space, String> {
+    state.check_permission(permissions::VEHICULOS_ENTRIES_MODIFY)?;
+    let conn = state.db.get().map_err(|e| e.to_string())?;
+    move_vehicle_on(&conn, &ticket_code, &target_space_code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn migrated_conn() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::run_migrations(&conn).unwrap();
+        conn
+    }
+
+    fn add_space(conn: &Connection, code: &str, zone: &str, allowed: &str) {
+        conn.execute(
+            "INSERT INTO spaces (id, code, zone, allowed_vehicle_types, status, created_at) VALUES (?1, ?1, ?2, ?3, 'available', 'x')",
+            params![code, zone, allowed],
+        )
+        .unwrap();
+    }
+
+    fn park(conn: &Connection, id: &str, vehicle_type: &str, space_id: Option<&str>) {
+        conn.execute(
+            "INSERT INTO vehicles (id, ticket_code, plate, plate_upper, vehicle_type, entry_time, status, space_id) VALUES (?1, ?1, ?1, ?1, ?2, 'x', 'active', ?3)",
+            params![id, vehicle_type, space_id],
+        )
+        .unwrap();
+        if let Some(sid) = space_id {
+            occupy_space(conn, sid, id).unwrap();
+        }
+    }
+
+    #[test]
+    fn entry_assigns_the_first_free_compatible_space_and_exit_releases_it() {
+        let conn = migrated_conn();
+        add_space(&conn, "B1", "B", "car");
+        add_space(&conn, "A1", "A", "motorcycle");
+        add_space(&conn, "A2", "A", "car");
+
+        let picked = pick_space_for_entry(&conn, "car", None).unwrap().unwrap();
+        assert_eq!(picked.code, "A2");
+        assert!(pick_space_for_entry(&conn, "car", Some("A1")).unwrap_err().contains("no admite"));
+        assert!(pick_space_for_entry(&conn, "car", Some("Z9")).unwrap_err().contains("no existe"));
+        assert!(pick_space_for_entry(&conn, "truck", None).unwrap().is_none());
+
+        park(&conn, "v1", "car", Some("A2"));
+        assert!(occupy_space(&conn, "A2", "v2").is_err());
+        assert!(pick_space_for_entry(&conn, "car", Some("a2")).unwrap_err().contains("no está disponible"));
+        assert_eq!(pick_space_for_entry(&conn, "car", None).unwrap().unwrap().code, "B1");
+
+        release_space_for_vehicle(&conn, "v1").unwrap();
+        let released = get_space(&conn, "A2").unwrap();
+        assert_eq!(released.status, STATUS_AVAILABLE);
+        assert_eq!(released.vehicle_id, None);
+    }
+
+    #[test]
+    fn moving_frees_the_old_space_and_moving_in_place_is_a_no_op() {
+        let conn = migrated_conn();
+        add_space(&conn, "A1", "A", "car");
+        add_space(&conn, "A2", "A", "car");
+        park(&conn, "v1", "car", Some("A1"));
+
+        let same = move_vehicle_on(&conn, "v1", "A1").unwrap();
+        assert_eq!(same.vehicle_id.as_deref(), Some("v1"));
+        assert_eq!(same.status, STATUS_OCCUPIED);
+
+        let moved = move_vehicle_on(&conn, "v1", "a2").unwrap();
+        assert_eq!(moved.code, "A2");
+        assert_eq!(moved.vehicle_id.as_deref(), Some("v1"));
+        assert_eq!(get_space(&conn, "A1").unwrap().status, STATUS_AVAILABLE);
+        let space_id: Option<String> = conn
+            .query_row("SELECT space_id FROM vehicles WHERE id = 'v1'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(space_id.as_deref(), Some("A2"));
+
+        park(&conn, "v2", "car", Some("A1"));
+        assert!(move_vehicle_on(&conn, "v2", "A2").is_err());
+        assert_eq!(get_space(&conn, "A1").unwrap().vehicle_id.as_deref(), Some("v2"));
+        assert!(move_vehicle_on(&conn, "nope", "A1").is_err());
+    }
+}
@@ -0,0 +1,412 @@
+//! Time-of-day / day-of-week rules attached to a custom tariff, plus the holiday calendar.
+//! A rule covers a local-time window (start..end, wrapping past midnight when end <= start)
+//! on some weekdays, optionally only or never on holidays. Exit pricing splits the stay
+//! across these windows (see `custom_tariffs::calculate_scheduled_cost`).
+
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::id_gen;
+use crate::money::{Currency, Money};
+use crate::permissions;
+use crate::state::AppState;
+
+const VALID_HOLIDAY_MODES: &[&str] = &["any", "only", "exclude"];
+const VALID_PRICING: &[&str] = &["flat", "per_block"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TariffRule {
+    pub id: String,
+    pub tariff_id: String,
+    pub name: Option<String>,
+    /// ISO weekdays (1 = Monday .. 7 = Sunday). Empty = every day.
+    pub weekdays: Vec<u8>,
+    /// Local time "HH:MM". When end_time <= start_time the window wraps past midnight
+    /// (equal = whole day).
+    pub start_time: String,
+    pub end_time: String,
+    /// any | only | exclude
+    pub holidays: String,
+    /// flat: `amount` once per window occurrence; per_block: `amount` per started `block_minutes`.
+    pub pricing: String,
+    pub amount: Money,
+    pub block_minutes: Option<i64>,
+    /// Highest priority wins when windows overlap.
+    pub priority: i64,
+    pub created_at: String,
+}
+
+/// A rule as returned to the frontend: same fields, `amount` in major units.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TariffRuleEntry {
+    pub id: String,
+    pub tariff_id: String,
+    pub name: Option<String>,
+    pub weekdays: Vec<u8>,
+    pub start_time: String,
+    pub end_time: String,
+    pub holidays: String,
+    pub pricing: String,
+    pub amount: f64,
+    pub block_minutes: Option<i64>,
+    pub priority: i64,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Holiday {
+    pub date: String,
+    pub name: Option<String>,
+}
+
+fn parse_hhmm(s: &str) -> Option<u32> {
+    let t = NaiveTime::parse_from_str(s.trim(), "%H:%M").ok()?;
+    Some(t.hour() * 60 + t.minute())
+}
+
+impl TariffRule {
+    /// If the local minute falls inside this rule's window, returns the local date the window
+    /// started on (the "occurrence"); a 22:00–06:00 window at 02:00 belongs to the previous day.
+    pub fn occurrence_for(&self, local: NaiveDateTime, holidays: &[String]) -> Option<NaiveDate> {
+        let start = parse_hhmm(&self.start_time)?;
+        let end = parse_hhmm(&self.end_time)?;
+        let minute = local.hour() * 60 + local.minute();
+        let date = local.date();
+        let anchor = if start < end {
+            if minute >= start && minute < end {
+                date
+            } else {
+                return None;
+            }
+        } else if minute >= start {
+            date
+        } else if minute < end {
+            date.pred_opt()?
+        } else {
+            return None;
+        };
+        let weekday = anchor.weekday().number_from_monday() as u8;
+        if !self.weekdays.is_empty() && !self.weekdays.contains(&weekday) {
+            return None;
+        }
+        if self.holidays == "any" {
+            return Some(anchor);
+        }
+        let anchor_key = anchor.format("%Y-%m-%d").to_string();
+        let is_holiday = holidays.contains(&anchor_key);
+        match self.holidays.as_str() {
+            "only" if !is_holiday => None,
+            "exclude" if is_holiday => None,
+            _ => Some(anchor),
+        }
+    }
+
+    /// Price of one contiguous run of `minutes` inside one occurrence of this window.
+    pub fn price_for_run(&self, minutes: i64) -> Money {
+        match self.pricing.as_str() {
+            "per_block" => {
+                let block = self.block_minutes.unwrap_or(60).max(1);
+                Money::from_minor(self.amount.minor() * ((minutes + block - 1) / block))
+            }
+            _ => self.amount,
+        }
+    }
+
+    fn into_entry(self, currency: &Currency) -> TariffRuleEntry {
+        TariffRuleEntry {
+            id: self.id,
+            tariff_id: self.tariff_id,
+            name: self.name,
+            weekdays: self.weekdays,
+            start_time: self.start_time,
+            end_time: self.end_time,
+            holidays: self.holidays,
+            pricing: self.pricing,
+            amount: currency.major(self.amount),
+            block_minutes: self.block_minutes,
+            priority: self.priority,
+            created_at: self.created_at,
+        }
+    }
+}
+
+const RULE_COLS: &str = "id, tariff_id, name, weekdays, start_time, end_time, holidays, pricing, amount, block_minutes, priority, created_at";
+
+fn row_to_rule(row: &rusqlite::Row) -> rusqlite::Result<TariffRule> {
+    let weekdays: String = row.get("weekdays")?;
+    Ok(TariffRule {
+        id: row.get("id")?,
+        tariff_id: row.get("tariff_id")?,
+        name: row.get("name")?,
+        weekdays: weekdays
+            .split(',')
+            .filter_map(|d| d.trim().parse::<u8>().ok())
+            .filter(|d| (1..=7).contains(d))
+            .collect(),
+        start_time: row.get("start_time")?,
+        end_time: row.get("end_time")?,
+        holidays: row.get("holidays")?,
+        pricing: row.get("pricing")?,
+        amount: row.get("amount")?,
+        block_minutes: row.get("block_minutes")?,
+        priority: row.get("priority")?,
+        created_at: row.get("created_at")?,
+    })
+}
+
+/// Rules of a tariff ordered by priority (desc), then creation (first wins on ties).
+pub fn load_rules_for_tariff(conn: &Connection, tariff_id: &str) -> Result<Vec<TariffRule>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM tariff_rules WHERE tariff_id = ?1 ORDER BY priority DESC, created_at ASC",
+            RULE_COLS
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt.query_map(params![tariff_id], row_to_rule).map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+pub fn load_holiday_dates(conn: &Connection) -> Result<Vec<String>, String> {
+    let mut stmt = conn
+        .prepare("SELECT date FROM holidays ORDER BY date")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt.query_map([], |row| row.get::<_, String>(0)).map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+fn normalize_weekdays(weekdays: Option<&[u8]>) -> Result<String, String> {
+    let list = weekdays.unwrap_or(&[]);
+    if list.iter().any(|d| !(1..=7).contains(d)) {
+        return Err("Weekdays must be between 1 (Monday) and 7 (Sunday)".to_string());
+    }
+    Ok(list.iter().map(|d| d.to_string()).collect::<Vec<_>>().join(","))
+}
+
+fn validate_rule_fields(
+    start_time: &str,
+    end_time: &str,
+    holidays: &str,
+    pricing: &str,
+    amount: f64,
+    block_minutes: Option<i64>,
+) -> Result<(), String> {
+    if parse_hhmm(start_time).is_none() || parse_hhmm(end_time).is_none() {
+        return Err("Start and end time must be HH:MM".to_string());
+    }
+    if !VALID_HOLIDAY_MODES.contains(&holidays) {
+        return Err(format!("Invalid holidays mode: {}", holidays));
+    }
+    if !VALID_PRICING.contains(&pricing) {
+        return Err(format!("Invalid pricing: {}", pricing));
+    }
+    if amount < 0.0 {
+        return Err("Amount must be non-negative".to_string());
+    }
+    if pricing == "per_block" && block_minutes.map(|b| b <= 0).unwrap_or(true) {
+        return Err("Per-block rules need block minutes > 0".to_string());
+    }
+    Ok(())
+}
+
+fn get_rule(conn: &Connection, id: &str) -> Result<TariffRule, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM tariff_rules WHERE id = ?1", RULE_COLS),
+        params![id],
+        row_to_rule,
+    )
+    .map_err(|_| "Tariff rule not found".to_string())
+}
+
+#[tauri::command]
+pub fn tariff_rules_list(state: State<AppState>, tariff_id: String) -> Result<Vec<TariffRuleEntry>, String> {
+    state.check_permission(permissions::CAJA_TRANSACTIONS_READ)?;
+    let conn = state.db.get().map_err(|e| e.to_string())?;
+    let currency = crate::money::currency(&conn)?;
+    Ok(load_rules_for_tariff(&conn, tariff_id.trim())?
+        .into_iter()
+        .map(|rule| rule.into_entry(&currency))
+        .collect())
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CreateTariffRuleArgs {
+    tariff_id: String,
+    name: Option<String>,
+    weekdays: Option<Vec<u8>>,
+    start_time: String,
+    end_time: String,
+    holidays: Option<String>,
+    pricing: String,
+    amount: f64,
+    block_minutes: Option<i64>,
+    priority: Option<i64>,
+}
+
+#[tauri::command]
+pub fn tariff_rules_create(state: State<AppState>, args: CreateTariffRuleArgs) -> Result<TariffRuleEntry, String> {
+    state.check_permission(permissions::CAJA_TRANSACTIONS_CREATE)?;
+    let holidays = args.holidays.as_deref().map(str::trim).unwrap_or("any").to_lowercase();
+    let pricing = args.pricing.trim().to_lowercase();
+    validate_rule_fields(&args.start_time, &args.end_time, &holidays, &pricing, args.amount, args.block_minutes)?;
+    let weekdays = normalize_weekdays(args.weekdays.as_deref())?;
+    let conn = state.db.get().map_err(|e| e.to_string())?;
+    let currency = crate::money::currency(&conn)?;
+    let tariff_exists: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM custom_tariffs WHERE id = ?1",
+            params![args.tariff_id.trim()],
+            |r| r.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    if tariff_exists == 0 {
+        return Err("Custom tariff not found".to_string());
+    }
+    let id = id_gen::generate_id(id_gen::PREFIX_TARIFF_RULE);
+    let created_at = chrono::Utc::now().to_rfc3339();
+    let name = args.name.as_deref().map(str::trim).filter(|s| !s.is_empty());
+    conn.execute(
+        &format!(
+            "INSERT INTO tariff_rules ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            RULE_COLS
+        ),
+        params![
+            id,
+            args.tariff_id.trim(),
+            name,
+            weekdays,
+            args.start_time.trim(),
+            args.end_time.trim(),
+            holidays,
+            pricing,
+            currency.money(args.amount),
+            args.block_minutes,
+            args.priority.unwrap_or(0),
+            created_at
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(get_rule(&conn, &id)?.into_entry(&currency))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct UpdateTariffRuleArgs {
+    id: String,
+    name: Option<String>,
+    weekdays: Option<Vec<u8>>,
+    start_time: Option<String>,
+    end_time: Option<String>,
+    holidays: Option<String>,
+    pricing: Option<String>,
+    amount: Option<f64>,
+    block_minutes: Option<i64>,
+    priority: Option<i64>,
+}
+
+#[tauri::command]
+pub fn tariff_rules_update(state: State<AppState>, args: UpdateTariffRuleArgs) -> Result<TariffRuleEntry, String> {
+    state.check_permission(permissions::CAJA_TRANSACTIONS_MODIFY)?;
+    let conn = state.db.get().map_err(|e| e.to_string())?;
+    let currency = crate::money::currency(&conn)?;
+    let existing = get_rule(&conn, args.id.trim())?;
+    let start_time = args.start_time.unwrap_or(existing.start_time);
+    let end_time = args.end_time.unwrap_or(existing.end_time);
+    let holidays = args.holidays.map(|h| h.trim().to_lowercase()).unwrap_or(existing.holidays);
+    let pricing = args.pricing.map(|p| p.trim().to_lowercase()).unwrap_or(existing.pricing);
+    let amount = args.amount.unwrap_or(currency.major(existing.amount));
+    let block_minutes = args.block_minutes.or(existing.block_minutes);
+    validate_rule_fields(&start_time, &end_time, &holidays, &pricing, amount, block_minutes)?;
+    let weekdays = normalize_weekdays(Some(args.weekdays.as_deref().unwrap_or(&existing.weekdays)))?;
+    let name = args
+        .name
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+        .or(existing.name);
+    conn.execute(
+        "UPDATE tariff_rules SET name = ?1, weekdays = ?2, start_time = ?3, end_time = ?4, holidays = ?5, pricing = ?6, amount = ?7, block_minutes = ?8, priority = ?9 WHERE id = ?10",
+        params![
+            name,
+            weekdays,
+            start_time.trim(),
+            end_time.trim(),
+            holidays,
+            pricing,
+            currency.money(amount),
+            block_minutes,
+            args.priority.unwrap_or(existing.priority),
+            existing.id
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(get_rule(&conn, &existing.id)?.into_entry(&currency))
+}
+
+#[tauri::command]
+pub fn tariff_rules_delete(state: State<AppState>, id: String) -> Result<(), String> {
+    state.check_permission(permissions::CAJA_TRANSACTIONS_MODIFY)?;
+    let conn = state.db.get().map_err(|e| e.to_string())?;
+    let n = conn
+        .execute("DELETE FROM tariff_rules WHERE id = ?1", params![id.trim()])
+        .map_err(|e| e.to_string())?;
+    if n == 0 {
+        return Err("Tariff rule not found".to_string());
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn holidays_list(state: State<AppState>, year: Option<i32>) -> Result<Vec<Holiday>, String> {
+    state.check_permission(permissions::CAJA_TRANSACTIONS_READ)?;
+    let conn = state.db.get().map_err(|e| e.to_string())?;
+    let prefix = year.map(|y| format!("{}-%", y)).unwrap_or_else(|| "%".to_string());
+    let mut stmt = conn
+        .prepare("SELECT date, name FROM holidays WHERE date LIKE ?1 ORDER BY date")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![prefix], |row| {
+            Ok(Holiday {
+                date: row.get(0)?,
+                name: row.get(1)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn holidays_set(state: State<AppState>, date: String, name: Option<String>) -> Result<Holiday, String> {
+    state.check_permission(permissions::CAJA_TRANSACTIONS_MODIFY)?;
+    let date = NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+        .map_err(|_| "Date must be YYYY-MM-DD".to_string())?
+        .format("%Y-%m-%d")
+        .to_string();
+    let name = name.as_deref().map(str::trim).filter(|s| !s.is_empty()).map(String::from);
+    let conn = state.db.get().map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO holidays (date, name) VALUES (?1, ?2) ON CONFLICT(date) DO UPDATE SET name = ?2",
+        params![date, name],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(Holiday { date, name })
+}
+
+#[tauri::command]
+pub fn holidays_delete(state: State<AppState>, date: String) -> Result<(), String> {
+    state.check_permission(permissions::CAJA_TRANSACTIONS_MODIFY)?;
+    let conn = state.db.get().map_err(|e| e.to_string())?;
+    let n = conn
+        .execute("DELETE FROM holidays WHERE date = ?1", params![date.trim()])
+        .map_err(|e| e.to_string())?;
+    if n == 0 {
+        return Err("Holiday not found".to_string());
+    }
+    Ok(())
+}
@@ -2,7 +2,9 @@ use rusqlite::params;
 use serde::{Deserialize, Serialize};
 use tauri::State;
 
+use crate::db::with_transaction;
 use crate::id_gen;
+use crate::money::{Currency, Money};
 use crate::permissions;
 use crate::state::AppState;
 
@@ -45,7 +47,18 @@ fn status_from_str(s: &str) -> VehicleStatus {
     }
 }
 
-fn row_to_vehicle(row: &rusqlite::Row) -> Result<Vehicle, rusqlite::Error> {
+/// Columnas de `vehicles` para `row_to_vehicle`. En un turno activo `debt` es el saldo abierto
+/// de la placa en el libro de deudas; en uno cerrado, la deuda que dejó esa salida.
+const VEHICLE_COLUMNS: &str = "id, ticket_code, plate, vehicle_type, observations, entry_time, exit_time, status, total_amount, \
+    CASE WHEN status = 'active' AND COALESCE(plate_upper, '') != '' \
+        THEN (SELECT COALESCE(SUM(d.amount - d.paid_amount), 0) FROM debts d WHERE d.plate_upper = vehicles.plate_upper AND d.status = 'open') \
+        ELSE debt END AS debt, \
+    special_rate, tariff_kind, tariff_id, operator_user_id, space_id";
+
+fn row_to_vehicle(row: &rusqlite::Row, currency: &Currency) -> Result<Vehicle, rusqlite::Error> {
+    let major = |col: &str| -> Result<Option<f64>, rusqlite::Error> {
+        Ok(row.get::<_, Option<Money>>(col)?.map(|m| currency.major(m)))
+    };
     Ok(Vehicle {
         id: row.get("id")?,
         ticket_code: row.get("ticket_code")?,
@@ -55,12 +68,13 @@ fn row_to_vehicle(row: &rusqlite::Row) -> Result<Vehicle, rusqlite::Error> {
         entry_time: row.get("entry_time")?,
         exit_time: row.get("exit_time")?,
         status: status_from_str(&row.get::<_, String>("status")?),
-        total_amount: row.get("total_amount")?,
-        debt: row.get("debt")?,
-        special_rate: row.get("special_rate")?,
+        total_amount: major("total_amount")?,
+        debt: major("debt")?,
+        special_rate: major("special_rate")?,
         tariff_kind: row.get::<_, Option<String>>("tariff_kind")?.unwrap_or_else(|| "regular".to_string()),
         tariff_id: row.get("tariff_id").ok().flatten(),
         operator_user_id: row.get("operator_user_id").ok().flatten(),
+        space_id: row.get("space_id").ok().flatten(),
     })
 }
 
@@ -81,6 +95,7 @@ pub struct Vehicle {
     pub tariff_kind: String,
     pub tariff_id: Option<String>,
     pub operator_user_id: Option<String>,
+    pub space_id: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -121,6 +136,7 @@ pub fn vehiculos_list_vehicles(
     let limit = limit.unwrap_or(DEFAULT_LIST_LIMIT).min(MAX_LIST_LIMIT);
     let offset = offset.unwrap_or(0);
     let conn = state.db.get().map_err(|e| e.to_string())?;
+    let currency = crate::money::currency(&conn)?;
 
     let (count_sql, list_sql) = match status.as_deref() {
         Some("active") | Some("completed") | Some("removed") => (
@@ -129,13 +145,13 @@ pub fn vehiculos_list_vehicles(
                 status.as_deref().unwrap_or("active")
             ),
             format!(
-                "SELECT id, ticket_code, plate, vehicle_type, observations, entry_time, exit_time, status, total_amount, debt, special_rate, tariff_kind, tariff_id, operator_user_id FROM vehicles WHERE status = '{}' ORDER BY entry_time DESC LIMIT ?1 OFFSET ?2",
+                "SELECT {VEHICLE_COLUMNS} FROM vehicles WHERE status = '{}' ORDER BY entry_time DESC LIMIT ?1 OFFSET ?2",
                 status.as_deref().unwrap_or("active")
             ),
         ),
         _ => (
             "SELECT COUNT(*) FROM vehicles".to_string(),
-            "SELECT id, ticket_code, plate, vehicle_type, observations, entry_time, exit_time, status, total_amount, debt, special_rate, tariff_kind, tariff_id, operator_user_id FROM vehicles ORDER BY entry_time DESC LIMIT ?1 OFFSET ?2".to_string(),
+            format!("SELECT {VEHICLE_COLUMNS} FROM vehicles ORDER BY entry_time DESC LIMIT ?1 OFFSET ?2"),
         ),
     };
 
@@ -145,7 +161,7 @@ pub fn vehiculos_list_vehicles(
 
     let mut stmt = conn.prepare(&list_sql).map_err(|e| e.to_string())?;
     let rows = stmt
-        .query_map(rusqlite::params![limit, offset], |row| row_to_vehicle(row))
+        .query_map(rusqlite::params![limit, offset], |row| row_to_vehicle(row, &currency))
         .map_err(|e| e.to_string())?;
     let items: Vec<Vehicle> = rows.filter_map(|r| r.ok()).collect();
 
@@ -169,16 +185,17 @@ pub fn vehiculos_list_vehicles_by_date(
     let limit = limit.unwrap_or(DEFAULT_LIST_LIMIT).min(MAX_LIST_LIMIT);
     let offset = offset.unwrap_or(0);
     let conn = state.db.get().map_err(|e| e.to_string())?;
+    let currency = crate::money::currency(&conn)?;
 
     let count_sql = "SELECT COUNT(*) FROM vehicles WHERE (substr(entry_time, 1, 10) = ?1) OR (exit_time IS NOT NULL AND substr(exit_time, 1, 10) = ?2)";
     let total: u32 = conn
         .query_row(count_sql, params![date_prefix, date_prefix], |row| row.get(0))
         .map_err(|e| e.to_string())?;
 
-    let list_sql = "SELECT id, ticket_code, plate, vehicle_type, observations, entry_time, exit_time, status, total_amount, debt, special_rate, tariff_kind, tariff_id, operator_user_id FROM vehicles WHERE (substr(entry_time, 1, 10) = ?1) OR (exit_time IS NOT NULL AND substr(exit_time, 1, 10) = ?2) ORDER BY entry_time DESC LIMIT ?3 OFFSET ?4";
-    let mut stmt = conn.prepare(list_sql).map_err(|e| e.to_string())?;
+    let list_sql = format!("SELECT {VEHICLE_COLUMNS} FROM vehicles WHERE (substr(entry_time, 1, 10) = ?1) OR (exit_time IS NOT NULL AND substr(exit_time, 1, 10) = ?2) ORDER BY entry_time DESC LIMIT ?3 OFFSET ?4");
+    let mut stmt = conn.prepare(&list_sql).map_err(|e| e.to_string())?;
     let rows = stmt
-        .query_map(params![date_prefix, date_prefix, limit, offset], |row| row_to_vehicle(row))
+        .query_map(params![date_prefix, date_prefix, limit, offset], |row| row_to_vehicle(row, &currency))
         .map_err(|e| e.to_string())?;
     let items: Vec<Vehicle> = rows.filter_map(|r| r.ok()).collect();
 
@@ -189,14 +206,14 @@ pub fn vehiculos_list_vehicles_by_date(
 pub fn vehiculos_get_total_debt(state: State<AppState>) -> Result<f64, String> {
     state.check_permission(permissions::CAJA_DEBTORS_READ)?;
     let conn = state.db.get().map_err(|e| e.to_string())?;
-    let total: f64 = conn
+    let total: Money = conn
         .query_row(
-            "SELECT COALESCE(SUM(COALESCE(debt, 0)), 0) FROM vehicles WHERE COALESCE(debt, 0) > 0",
+            "SELECT COALESCE(SUM(amount - paid_amount), 0) FROM debts WHERE status = 'open'",
             [],
             |row| row.get(0),
         )
         .map_err(|e| e.to_string())?;
-    Ok(total)
+    Ok(crate::money::currency(&conn)?.major(total))
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -206,6 +223,7 @@ pub struct DebtorEntry {
     pub total_debt: f64,
     pub sessions_with_debt: u32,
     pub oldest_exit_time: Option<String>,
+    pub aging: crate::domains::debts::DebtAging,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -213,6 +231,8 @@ pub struct DebtorEntry {
 pub struct ListDebtorsResult {
     pub items: Vec<DebtorEntry>,
     pub total: u32,
+    /// Aging of all open debt, not only the current page.
+    pub aging: crate::domains::debts::DebtAging,
 }
 
 #[tauri::command]
@@ -225,13 +245,14 @@ pub fn vehiculos_list_debtors(
     let limit = limit.unwrap_or(DEFAULT_LIST_LIMIT).min(MAX_LIST_LIMIT);
     let offset = offset.unwrap_or(0);
     let conn = state.db.get().map_err(|e| e.to_string())?;
+    let currency = crate::money::currency(&conn)?;
 
     let total: u32 = conn
         .query_row(
             r#"
             SELECT COUNT(*) FROM (
-                SELECT plate_upper FROM vehicles
-                WHERE plate_upper IS NOT NULL AND plate_upper != '' AND COALESCE(debt, 0) > 0
+                SELECT plate_upper FROM debts
+                WHERE plate_upper != '' AND status = 'open'
                 GROUP BY plate_upper
             )
             "#,
@@ -241,33 +262,37 @@ pub fn vehiculos_list_debtors(
         .map_err(|e| e.to_string())?;
 
     let mut stmt = conn
-        .prepare(
+        .prepare(&format!(
             r#"
             SELECT plate_upper AS plate,
-                   SUM(COALESCE(debt, 0)) AS total_debt,
+                   SUM(amount - paid_amount) AS total_debt,
                    COUNT(*) AS sessions_with_debt,
-                   MIN(exit_time) AS oldest_exit_time
-            FROM vehicles
-            WHERE plate_upper IS NOT NULL AND plate_upper != '' AND COALESCE(debt, 0) > 0
+                   MIN(created_at) AS oldest_exit_time,
+                   {}
+            FROM debts
+            WHERE plate_upper != '' AND status = 'open'
             GROUP BY plate_upper
             ORDER BY total_debt DESC
             LIMIT ?1 OFFSET ?2
             "#,
-        )
+            crate::domains::debts::AGING_COLUMNS
+        ))
         .map_err(|e| e.to_string())?;
     let rows = stmt
         .query_map(rusqlite::params![limit, offset], |row| {
             Ok(DebtorEntry {
                 plate: row.get("plate")?,
-                total_debt: row.get("total_debt")?,
+                total_debt: currency.major(row.get("total_debt")?),
                 sessions_with_debt: row.get::<_, i64>("sessions_with_debt")? as u32,
                 oldest_exit_time: row.get("oldest_exit_time")?,
+                aging: crate::domains::debts::aging_from_row(row, &currency)?,
             })
         })
         .map_err(|e| e.to_string())?;
     let items: Vec<DebtorEntry> = rows.filter_map(|r| r.ok()).collect();
+    let aging = crate::domains::debts::overall_aging(&conn)?;
 
-    Ok(ListDebtorsResult { items, total })
+    Ok(ListDebtorsResult { items, total, aging })
 }
 
 #[tauri::command]
@@ -275,14 +300,8 @@ pub fn vehiculos_get_plate_debt(state: State<AppState>, plate: String) -> Result
     state.check_permission(permissions::VEHICULOS_ENTRIES_READ)?;
     let conn = state.db.get().map_err(|e| e.to_string())?;
     let key = normalize_plate_for_index(&plate);
-    let debt: f64 = conn
-        .query_row(
-            "SELECT COALESCE(SUM(COALESCE(debt, 0)), 0) FROM vehicles WHERE plate_upper = ?1 AND COALESCE(debt, 0) > 0",
-            params![key],
-            |row| row.get(0),
-        )
-        .map_err(|e| e.to_string())?;
-    Ok(debt)
+    let balance = crate::domains::debts::open_balance_for_plate(&conn, &key)?;
+    Ok(crate::money::currency(&conn)?.major(balance))
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -319,14 +338,18 @@ pub fn vehiculos_get_debt_detail_by_plate(
     state.check_permission(permissions::CAJA_DEBTORS_READ)?;
     let conn = state.db.get().map_err(|e| e.to_string())?;
     let key = normalize_plate_for_index(&plate);
+    let currency = crate::money::currency(&conn)?;
 
     let mut stmt = conn
         .prepare(
             r#"
-            SELECT id, ticket_code, entry_time, exit_time, COALESCE(debt, 0) AS debt, total_amount
-            FROM vehicles
-            WHERE plate_upper = ?1 AND COALESCE(debt, 0) > 0
-            ORDER BY entry_time DESC
+            SELECT v.id, v.ticket_code, v.entry_time, v.exit_time,
+                   SUM(d.amount - d.paid_amount) AS debt, v.total_amount
+            FROM debts d
+            JOIN vehicles v ON v.id = d.vehicle_id
+            WHERE d.plate_upper = ?1 AND d.status = 'open'
+            GROUP BY v.id
+            ORDER BY v.entry_time DESC
             "#,
         )
         .map_err(|e| e.to_string())?;
@@ -337,8 +360,8 @@ pub fn vehiculos_get_debt_detail_by_plate(
                 ticket_code: row.get("ticket_code")?,
                 entry_time: row.get("entry_time")?,
                 exit_time: row.get("exit_time")?,
-                debt: row.get::<_, f64>("debt")?,
-                total_amount: row.get("total_amount")?,
+                debt: currency.major(row.get("debt")?),
+                total_amount: row.get::<_, Option<Money>>("total_amount")?.map(|m| currency.major(m)),
             })
         })
         .map_err(|e| e.to_string())?;
@@ -363,7 +386,7 @@ pub fn vehiculos_get_debt_detail_by_plate(
             .query_map(rusqlite::params_from_iter(vehicle_ids.iter()), |row| {
                 Ok(DebtTransactionEntry {
                     created_at: row.get("created_at")?,
-                    amount: row.get("amount")?,
+                    amount: currency.major(row.get("amount")?),
                     method: row.get("method")?,
                 })
             })
@@ -387,6 +410,7 @@ fn vehicle_type_has_plate(v: &VehicleType) -> bool {
 pub struct RegisterEntryResult {
     pub vehicle: Vehicle,
     pub contract_arrears_warning: Option<String>,
+    pub capacity_warning: Option<String>,
 }
 
 #[tauri::command]
@@ -397,8 +421,45 @@ pub fn vehiculos_register_entry(
     observations: Option<String>,
     ticket_code: Option<String>,
     tariff_kind: Option<String>,
+    space_code: Option<String>,
 ) -> Result<RegisterEntryResult, String> {
     state.check_permission(permissions::VEHICULOS_ENTRIES_CREATE)?;
+    let conn = state.db.get().map_err(|e| e.to_string())?;
+    let request = EntryRequest {
+        plate,
+        vehicle_type,
+        observations,
+        ticket_code,
+        tariff_kind,
+        space_code,
+    };
+    register_entry_on(&conn, request, state.get_current_user_id())
+}
+
+/// Datos de un ingreso tal como llegan del comando.
+struct EntryRequest {
+    plate: String,
+    vehicle_type: VehicleType,
+    observations: Option<String>,
+    ticket_code: Option<String>,
+    tariff_kind: Option<String>,
+    space_code: Option<String>,
+}
+
+/// Registra el ingreso: validaciones, ocupación del espacio e INSERT en una sola transacción.
+fn register_entry_on(
+    conn: &rusqlite::Connection,
+    request: EntryRequest,
+    operator_user_id: Option<String>,
+) -> Result<RegisterEntryResult, String> {
+    let EntryRequest {
+        plate,
+        vehicle_type,
+        observations,
+        ticket_code,
+        tariff_kind,
+        space_code,
+    } = request;
     let code = ticket_code.unwrap_or_else(|| {
         format!(
             "TK{}",
@@ -418,7 +479,6 @@ pub fn vehiculos_register_entry(
         .map(str::to_lowercase)
         .filter(|s| ["regular", "employee", "student"].contains(&s.as_str()))
         .unwrap_or_else(|| "regular".to_string());
-    let operator_user_id = state.get_current_user_id();
 
     let plate_trimmed = plate.trim();
     if vehicle_type_has_plate(&vehicle_type) && plate_trimmed.is_empty() {
@@ -433,102 +493,126 @@ pub fn vehiculos_register_entry(
     let id = id_gen::generate_id(id_gen::PREFIX_VEHICLE);
     let entry_time = chrono::Utc::now().to_rfc3339();
 
-    let conn = state.db.get().map_err(|e| e.to_string())?;
-
-    // No puede haber otro registro activo con el mismo código de barras (tarjeta en uso).
-    let ticket_in_use: Option<i64> = conn
-        .query_row(
-            "SELECT 1 FROM vehicles WHERE ticket_code = ?1 AND status = 'active' LIMIT 1",
-            params![&code],
-            |row| row.get(0),
-        )
-        .ok();
-    if ticket_in_use.is_some() {
-        return Err("Ese ticket o código de barras ya está en uso. Debe cerrar el turno anterior antes de reutilizar la tarjeta.".to_string());
-    }
-
-    // Para tipos con placa: una placa = un solo tipo de vehículo (no puede ser moto y auto a la vez).
-    if vehicle_type_has_plate(&vehicle_type) && !plate_upper.is_empty() {
-        let plate_in_use: Option<i64> = conn
+    // Lecturas y validaciones dentro de la misma transacción que el INSERT: dos ingresos
+    // simultáneos no pueden pasar ambos las verificaciones de ticket, placa, deuda y capacidad.
+    let (space_id, capacity_warning, debt, tariff_id) = with_transaction(conn, |conn| {
+        // No puede haber otro registro activo con el mismo código de barras (tarjeta en uso).
+        let ticket_in_use: Option<i64> = conn
             .query_row(
-                "SELECT 1 FROM vehicles WHERE plate_upper = ?1 AND status = 'active' LIMIT 1",
-                params![&plate_upper],
+                "SELECT 1 FROM vehicles WHERE ticket_code = ?1 AND status = 'active' LIMIT 1",
+                params![&code],
                 |row| row.get(0),
             )
             .ok();
-        if plate_in_use.is_some() {
-            return Err("Esa placa ya tiene un vehículo activo en el estacionamiento. Una placa solo puede estar asociada a un vehículo a la vez.".to_string());
+        if ticket_in_use.is_some() {
+            return Err("Ese ticket o código de barras ya está en uso. Debe cerrar el turno anterior antes de reutilizar la tarjeta.".to_string());
         }
-        let existing_type: Option<String> = conn
-            .query_row(
-                "SELECT vehicle_type FROM vehicles WHERE plate_upper = ?1 ORDER BY entry_time DESC LIMIT 1",
-                params![&plate_upper],
-                |row| row.get(0),
-            )
-            .ok();
-        if let Some(ref existing) = existing_type {
-            let existing_enum = vehicle_type_from_str(existing);
-            if existing_enum != vehicle_type {
-                let tipo_str = match &vehicle_type {
-                    VehicleType::Car => "auto",
-                    VehicleType::Motorcycle => "moto",
-                    VehicleType::Truck => "camión",
-                    VehicleType::Bicycle => "bicicleta",
-                };
-                let existing_str = match existing_enum {
-                    VehicleType::Car => "auto",
-                    VehicleType::Motorcycle => "moto",
-                    VehicleType::Truck => "camión",
-                    VehicleType::Bicycle => "bicicleta",
-                };
-                return Err(format!(
-                    "Esa placa ya fue registrada como {}. Una placa corresponde a un solo tipo de vehículo; no se puede registrar un {} con la misma placa.",
-                    existing_str, tipo_str
-                ));
+
+        // Para tipos con placa: una placa = un solo tipo de vehículo (no puede ser moto y auto a la vez).
+        if vehicle_type_has_plate(&vehicle_type) && !plate_upper.is_empty() {
+            let plate_in_use: Option<i64> = conn
+                .query_row(
+                    "SELECT 1 FROM vehicles WHERE plate_upper = ?1 AND status = 'active' LIMIT 1",
+                    params![&plate_upper],
+                    |row| row.get(0),
+                )
+                .ok();
+            if plate_in_use.is_some() {
+                return Err("Esa placa ya tiene un vehículo activo en el estacionamiento. Una placa solo puede estar asociada a un vehículo a la vez.".to_string());
+            }
+            let existing_type: Option<String> = conn
+                .query_row(
+                    "SELECT vehicle_type FROM vehicles WHERE plate_upper = ?1 ORDER BY entry_time DESC LIMIT 1",
+                    params![&plate_upper],
+                    |row| row.get(0),
+                )
+                .ok();
+            if let Some(ref existing) = existing_type {
+                let existing_enum = vehicle_type_from_str(existing);
+                if existing_enum != vehicle_type {
+                    let tipo_str = match &vehicle_type {
+                        VehicleType::Car => "auto",
+                        VehicleType::Motorcycle => "moto",
+                        VehicleType::Truck => "camión",
+                        VehicleType::Bicycle => "bicicleta",
+                    };
+                    let existing_str = match existing_enum {
+                        VehicleType::Car => "auto",
+                        VehicleType::Motorcycle => "moto",
+                        VehicleType::Truck => "camión",
+                        VehicleType::Bicycle => "bicicleta",
+                    };
+                    return Err(format!(
+                        "Esa placa ya fue registrada como {}. Una placa corresponde a un solo tipo de vehículo; no se puede registrar un {} con la misma placa.",
+                        existing_str, tipo_str
+                    ));
+                }
             }
         }
-    }
 
-    let debt: f64 = if !plate_upper.is_empty() {
-        conn.query_row(
-            "SELECT COALESCE(SUM(COALESCE(debt, 0)), 0) FROM vehicles WHERE plate_upper = ?1 AND COALESCE(debt, 0) > 0",
-            params![&plate_upper],
-            |row| row.get(0),
-        )
-        .unwrap_or(0.0)
-    } else {
-        0.0
-    };
+        // Límites de deuda configurados (monto o antigüedad): bloquean el ingreso de la placa.
+        crate::domains::debts::check_entry_debt_limits(conn, &plate_upper)?;
 
-    let tariff_id = resolve_tariff_id_for_entry(
-        &conn,
-        vehicle_type_to_str(&vehicle_type),
-        &tariff_kind_val,
-        &plate_upper,
-    );
+        // Saldo pendiente de la placa: se informa en el ingreso pero vive solo en el libro de deudas.
+        let debt = crate::domains::debts::open_balance_for_plate(conn, &plate_upper)?;
 
-    conn.execute(
-        "INSERT INTO vehicles (id, ticket_code, plate, plate_upper, vehicle_type, observations, entry_time, exit_time, status, total_amount, debt, special_rate, tariff_kind, tariff_id, operator_user_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, NULL, 'active', NULL, ?8, NULL, ?9, ?10, ?11)",
-        params![
-            id,
-            code,
-            plate_upper,
-            plate_upper.clone(),
+        let tariff_id = resolve_tariff_id_for_entry(
+            conn,
             vehicle_type_to_str(&vehicle_type),
-            observations,
-            entry_time,
-            if debt > 0.0 { debt } else { 0.0_f64 },
-            tariff_kind_val,
-            tariff_id,
-            operator_user_id,
-        ],
-    )
-    .map_err(|e| e.to_string())?;
+            &tariff_kind_val,
+            &plate_upper,
+        );
+        // Se congela la tarifa vigente al ingreso: editarla después no cambia el cobro de este turno.
+        let tariff_snapshot = crate::domains::custom_tariffs::snapshot_for_entry(
+            conn,
+            tariff_id.as_deref(),
+            vehicle_type_to_str(&vehicle_type),
+            &tariff_kind_val,
+        )?;
+
+        // Espacio: el solicitado (debe estar libre y admitir el tipo) o el primero libre compatible.
+        let space_id = crate::domains::spaces::pick_space_for_entry(
+            conn,
+            vehicle_type_to_str(&vehicle_type),
+            space_code.as_deref(),
+        )?
+        .map(|s| s.id);
+        // Capacidad del tipo y de la zona del espacio: en modo "reject" bloquea la entrada, en "warn" solo avisa.
+        let capacity_warning = crate::domains::capacity::check_entry_capacity(
+            conn,
+            vehicle_type_to_str(&vehicle_type),
+            space_id.as_deref(),
+        )?;
+        if let Some(ref sid) = space_id {
+            crate::domains::spaces::occupy_space(conn, sid, &id)?;
+        }
+
+        conn.execute(
+            "INSERT INTO vehicles (id, ticket_code, plate, plate_upper, vehicle_type, observations, entry_time, exit_time, status, total_amount, debt, special_rate, tariff_kind, tariff_id, operator_user_id, space_id, tariff_snapshot) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, NULL, 'active', NULL, ?8, NULL, ?9, ?10, ?11, ?12, ?13)",
+            params![
+                id,
+                code,
+                plate_upper,
+                plate_upper.clone(),
+                vehicle_type_to_str(&vehicle_type),
+                observations,
+                entry_time,
+                Money::ZERO,
+                tariff_kind_val,
+                tariff_id,
+                operator_user_id,
+                space_id,
+                tariff_snapshot,
+            ],
+        )
+        .map_err(|e| e.to_string())?;
 
-    let _ = crate::domains::barcodes::ensure_barcode_exists_for_ticket(&*conn, &code);
+        let _ = crate::domains::barcodes::ensure_barcode_exists_for_ticket(conn, &code);
+        Ok((space_id, capacity_warning, debt, tariff_id))
+    })?;
 
     let contract_arrears_warning = if !plate_upper.is_empty() {
-        crate::domains::contracts::find_any_contract_for_plate(&conn, &plate_upper)
+        crate::domains::contracts::find_any_contract_for_plate(conn, &plate_upper)
             .filter(|c| c.is_in_arrears)
             .map(|c| format!("Contrato en mora: {}", c.client_name))
     } else {
@@ -545,13 +629,14 @@ pub fn vehiculos_register_entry(
         exit_time: None,
         status: VehicleStatus::Active,
         total_amount: None,
-        debt: if debt > 0.0 { Some(debt) } else { None },
+        debt: if debt > Money::ZERO { Some(crate::money::currency(conn)?.major(debt)) } else { None },
         special_rate: None,
         tariff_kind: tariff_kind_val,
         tariff_id,
         operator_user_id,
+        space_id,
     };
-    Ok(RegisterEntryResult { vehicle, contract_arrears_warning })
+    Ok(RegisterEntryResult { vehicle, contract_arrears_warning, capacity_warning })
 }
 
 fn resolve_tariff_id_for_entry(
@@ -580,16 +665,400 @@ fn resolve_tariff_id_for_entry(
     .ok()
 }
 
+/// Desglose del cobro de salida. Lo devuelve `vehiculos_quote_exit` y lo usa
+/// `vehiculos_process_exit`, así la cotización siempre coincide con lo cobrado.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExitQuote {
+    pub vehicle_id: String,
+    pub ticket_code: String,
+    pub plate: String,
+    pub entry_time: String,
+    pub exit_time: String,
+    pub duration_minutes: f64,
+    /// "tariff", "contract" or "custom" (price typed by the operator).
+    pub pricing_mode: String,
+    pub base_amount: f64,
+    pub additional_blocks: f64,
+    pub additional_amount: f64,
+    pub rules_amount: f64,
+    /// Minutes covered by the plate's active contract; None when there is no contract.
+    pub contract_included_minutes: Option<f64>,
+    pub grace_discount: f64,
+    pub daily_max_discount: f64,
+    /// Merchant voucher applied at exit, if any.
+    pub voucher_code: Option<String>,
+    pub voucher_discount: f64,
+    pub lost_ticket_amount: f64,
+    /// Rounding to the currency's increment (see `money`); parking_cost already includes it.
+    pub rounding_adjustment: f64,
+    pub parking_cost: f64,
+    pub carried_debt: f64,
+    pub total: f64,
+}
+
+/// Cobro por exceso sobre las horas incluidas del contrato: (bloques, monto).
+fn contract_overstay_cost(
+    conn: &rusqlite::Connection,
+    currency: &Currency,
+    vehicle: &Vehicle,
+    contract: &crate::domains::contracts::Contract,
+    duration_minutes: f64,
+    included_minutes: f64,
+) -> Result<(f64, Money), String> {
+    if duration_minutes <= included_minutes + 1.0 {
+        return Ok((0.0, Money::ZERO));
+    }
+    if let (Some(rate), Some(interval)) = (
+        contract.extra_charge_per_interval.or(contract.extra_charge_repeat),
+        contract.extra_interval,
+    ) {
+        if interval > 0 {
+            let extra_minutes = duration_minutes - included_minutes;
+            let blocks = (extra_minutes / interval as f64).ceil();
+            return Ok((blocks, currency.money(rate).times(blocks)));
+        }
+        return Ok((0.0, Money::ZERO));
+    }
+    if contract.tariff_kind.is_empty() {
+        return Ok((0.0, Money::ZERO));
+    }
+    let tariff = crate::domains::custom_tariffs::get_tariff_for_calculation(
+        conn,
+        vehicle_type_to_str(&vehicle.vehicle_type),
+        &contract.tariff_kind,
+    )?
+    .base;
+    let overstay_minutes = duration_minutes - included_minutes;
+    let overstay_hours = overstay_minutes / 60.0;
+    let period_h = tariff.additional_period_hours.max(1.0 / 60.0);
+    let blocks = (overstay_hours / period_h).ceil().max(0.0);
+    let additional_rate = tariff.additional_hour_price.unwrap_or(tariff.base_price);
+    Ok((blocks, additional_rate.times(blocks)))
+}
+
+/// Busca el vale digitado en la salida y verifica que sea canjeable en la fecha local de salida.
+fn find_voucher_for_exit(
+    conn: &rusqlite::Connection,
+    voucher_code: Option<&str>,
+    exit_time: &str,
+) -> Result<Option<crate::domains::vouchers::Voucher>, String> {
+    let code = match voucher_code.map(str::trim).filter(|s| !s.is_empty()) {
+        Some(c) => c,
+        None => return Ok(None),
+    };
+    let local_date = chrono::DateTime::parse_from_rfc3339(exit_time)
+        .map_err(|e| e.to_string())?
+        .with_timezone(&crate::domains::settings::business_tz(conn)?)
+        .date_naive();
+    crate::domains::vouchers::find_redeemable(conn, code, local_date).map(Some)
+}
+
+/// Cobro de una salida en unidades mínimas, junto con su desglose para la interfaz.
+struct ExitCharge {
+    quote: ExitQuote,
+    parking_cost: Money,
+    carried_debt: Money,
+    voucher_discount: Money,
+}
+
+/// Calcula el cobro de un turno activo como si saliera en `exit_time`. No escribe nada.
+fn compute_exit_quote(
+    conn: &rusqlite::Connection,
+    vehicle: &Vehicle,
+    exit_time: &str,
+    custom_parking_cost: Option<Money>,
+    lost_ticket: bool,
+    voucher: Option<&crate::domains::vouchers::Voucher>,
+) -> Result<ExitQuote, String> {
+    compute_exit_charge(conn, vehicle, exit_time, custom_parking_cost, lost_ticket, voucher).map(|c| c.quote)
+}
+
+fn compute_exit_charge(
+    conn: &rusqlite::Connection,
+    vehicle: &Vehicle,
+    exit_time: &str,
+    custom_parking_cost: Option<Money>,
+    lost_ticket: bool,
+    voucher: Option<&crate::domains::vouchers::Voucher>,
+) -> Result<ExitCharge, String> {
+    let currency = crate::money::currency(conn)?;
+    let entry_ts = chrono::DateTime::parse_from_rfc3339(&vehicle.entry_time)
+        .map_err(|e| e.to_string())?
+        .with_timezone(&chrono::Utc);
+    let exit_ts = chrono::DateTime::parse_from_rfc3339(exit_time)
+        .map_err(|e| e.to_string())?
+        .with_timezone(&chrono::Utc);
+    let duration_minutes = (exit_ts - entry_ts).num_seconds().max(0) as f64 / 60.0;
+    let custom_cost = custom_parking_cost.filter(|c| *c >= Money::ZERO);
+    if custom_cost.is_some() && voucher.is_some() {
+        return Err("No se puede aplicar un vale a un precio digitado manualmente".to_string());
+    }
+    let free_minutes = voucher.map(|v| v.free_minutes()).unwrap_or(0.0);
+
+    let mut breakdown = crate::domains::custom_tariffs::CostBreakdown::default();
+    let mut contract_included_minutes = None;
+    // Costo del turno con los minutos gratis del vale ya descontados.
+    let mut cost_with_free_minutes = None;
+    let pricing_mode = if let Some(c) = custom_cost {
+        breakdown.total = c;
+        "custom"
+    } else {
+        let contract = if !vehicle.plate.is_empty() {
+            let plate_key = normalize_plate_for_index(&vehicle.plate);
+            crate::domains::contracts::find_active_contract_for_plate(conn, &plate_key)
+        } else {
+            None
+        };
+        // Con el cupo de vehículos simultáneos lleno, el turno se cobra con la tarifa normal.
+        let contract = match contract {
+            Some(c) if crate::domains::contracts::has_free_slot(conn, &c, &vehicle.id, &vehicle.entry_time)? => Some(c),
+            _ => None,
+        };
+
+        if let Some(contract) = contract {
+            let included_minutes = contract.included_hours_per_day * 60.0;
+            contract_included_minutes = Some(included_minutes);
+            let (blocks, amount) =
+                contract_overstay_cost(conn, &currency, vehicle, &contract, duration_minutes, included_minutes)?;
+            breakdown.additional_blocks = blocks;
+            breakdown.additional_amount = amount;
+            breakdown.total = amount;
+            if free_minutes > 0.0 {
+                let (_, reduced) = contract_overstay_cost(
+                    conn,
+                    &currency,
+                    vehicle,
+                    &contract,
+                    duration_minutes,
+                    included_minutes + free_minutes,
+                )?;
+                cost_with_free_minutes = Some(reduced);
+            }
+            "contract"
+        } else {
+            let schedule = crate::domains::custom_tariffs::schedule_for_session(
+                conn,
+                &vehicle.id,
+                vehicle.tariff_id.as_deref(),
+                vehicle_type_to_str(&vehicle.vehicle_type),
+                &vehicle.tariff_kind,
+            )?;
+            let tz = crate::domains::settings::business_tz(conn)?;
+            breakdown = crate::domains::custom_tariffs::stay_cost_breakdown(&schedule, tz, entry_ts, exit_ts);
+            if free_minutes > 0.0 {
+                // Los minutos gratis se descuentan del final del turno.
+                let reduced_exit = (exit_ts - chrono::Duration::seconds((free_minutes * 60.0) as i64)).max(entry_ts);
+                cost_with_free_minutes =
+                    Some(crate::domains::custom_tariffs::calculate_stay_cost(&schedule, tz, entry_ts, reduced_exit));
+            }
+            "tariff"
+        }
+    };
+
+    let voucher_discount = match (voucher, cost_with_free_minutes) {
+        (Some(_), Some(reduced)) => (breakdown.total - reduced).max(Money::ZERO),
+        (Some(v), None) => v.amount_discount(&currency, breakdown.total),
+        (None, _) => Money::ZERO,
+    };
+
+    // Ticket perdido: se suma la penalidad de la tarifa (no aplica si el precio fue digitado a mano).
+    let lost_ticket_amount = if lost_ticket && custom_cost.is_none() {
+        crate::domains::custom_tariffs::schedule_for_session(
+            conn,
+            &vehicle.id,
+            vehicle.tariff_id.as_deref(),
+            vehicle_type_to_str(&vehicle.vehicle_type),
+            &vehicle.tariff_kind,
+        )?
+        .lost_ticket_amount
+        .unwrap_or(Money::ZERO)
+    } else {
+        Money::ZERO
+    };
+    // El cobro calculado se redondea al incremento de la moneda; el precio digitado queda tal cual.
+    let unrounded_cost = breakdown.total - voucher_discount + lost_ticket_amount;
+    let parking_cost = if custom_cost.is_some() {
+        unrounded_cost
+    } else {
+        currency.round_charge(unrounded_cost)
+    };
+    let rounding_adjustment = parking_cost - unrounded_cost;
+    let carried_debt =
+        crate::domains::debts::open_balance_for_plate(conn, &normalize_plate_for_index(&vehicle.plate))?;
+
+    let major = |m: Money| currency.major(m);
+    let quote = ExitQuote {
+        vehicle_id: vehicle.id.clone(),
+        ticket_code: vehicle.ticket_code.clone(),
+        plate: vehicle.plate.clone(),
+        entry_time: vehicle.entry_time.clone(),
+        exit_time: exit_time.to_string(),
+        duration_minutes,
+        pricing_mode: pricing_mode.to_string(),
+        base_amount: major(breakdown.base_amount),
+        additional_blocks: breakdown.additional_blocks,
+        additional_amount: major(breakdown.additional_amount),
+        rules_amount: major(breakdown.rules_amount),
+        contract_included_minutes,
+        grace_discount: major(breakdown.grace_discount),
+        daily_max_discount: major(breakdown.daily_max_discount),
+        voucher_code: voucher.map(|v| v.code.clone()),
+        voucher_discount: major(voucher_discount),
+        lost_ticket_amount: major(lost_ticket_amount),
+        rounding_adjustment: major(rounding_adjustment),
+        parking_cost: major(parking_cost),
+        carried_debt: major(carried_debt),
+        total: major(parking_cost + carried_debt),
+    };
+    Ok(ExitCharge { quote, parking_cost, carried_debt, voucher_discount })
+}
+
+/// Cotiza la salida de un ticket activo sin cerrarlo. `at_time` (RFC 3339) permite
+/// cotizar a una hora futura; por defecto es ahora.
+#[tauri::command]
+pub fn vehiculos_quote_exit(
+    state: State<AppState>,
+    ticket_code: String,
+    at_time: Option<String>,
+    lost_ticket: Option<bool>,
+    voucher_code: Option<String>,
+) -> Result<ExitQuote, String> {
+    state.check_permission(permissions::VEHICULOS_ENTRIES_READ)?;
+    let conn = state.db.get().map_err(|e| e.to_string())?;
+    let currency = crate::money::currency(&conn)?;
+
+    let vehicle: Vehicle = conn
+        .query_row(
+            &format!("SELECT {VEHICLE_COLUMNS} FROM vehicles WHERE ticket_code = ?1 AND status = 'active'"),
+            params![ticket_code.trim()],
+            |row| row_to_vehicle(row, &currency),
+        )
+        .map_err(|_| "Vehicle not found or already completed".to_string())?;
+
+    let exit_time = match at_time.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+        Some(t) => chrono::DateTime::parse_from_rfc3339(t)
+            .map_err(|_| "Invalid at_time; expected RFC 3339".to_string())?
+            .with_timezone(&chrono::Utc)
+            .to_rfc3339(),
+        None => chrono::Utc::now().to_rfc3339(),
+    };
+    let voucher = find_voucher_for_exit(&conn, voucher_code.as_deref(), &exit_time)?;
+    compute_exit_quote(&conn, &vehicle, &exit_time, None, lost_ticket.unwrap_or(false), voucher.as_ref())
+}
+
+/// Pago de una salida con un medio: `{ method, amount }`. Varios permiten pago mixto
+/// (p. ej. parte efectivo, parte tarjeta).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Tender {
+    pub method: String,
+    pub amount: f64,
+}
+
+/// Un `Tender` en unidades mínimas de la moneda: así se reparte, se guarda y se devuelve.
+#[derive(Debug, Clone)]
+struct Payment {
+    method: String,
+    amount: Money,
+}
+
+impl Payment {
+    fn to_tender(&self, currency: &Currency) -> Tender {
+        Tender { method: self.method.clone(), amount: currency.major(self.amount) }
+    }
+}
+
+/// Resultado de la salida: el vehículo (mismos campos de siempre) más el cambio a devolver
+/// y lo registrado por cada medio de pago.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProcessExitResult {
+    #[serde(flatten)]
+    pub vehicle: Vehicle,
+    pub change_due: f64,
+    pub tenders: Vec<Tender>,
+    /// Saldo abierto de la placa en el libro de deudas después de esta salida.
+    pub outstanding_debt: f64,
+}
+
+const TENDER_METHODS: &[&str] = &["cash", "card", "transfer", "contract"];
+
+/// Reparte el total entre los medios entregados. El excedente solo se devuelve como cambio
+/// en efectivo; devuelve (pagos aplicados, cambio, deuda restante).
+fn apply_tenders(tenders: &[Payment], total_due: Money) -> Result<(Vec<Payment>, Money, Money), String> {
+    for t in tenders {
+        if !TENDER_METHODS.contains(&t.method.as_str()) {
+            return Err(format!("Medio de pago inválido: {}", t.method));
+        }
+        if t.amount <= Money::ZERO {
+            return Err("Cada pago debe tener un monto mayor a 0".to_string());
+        }
+    }
+    let tendered: Money = tenders.iter().map(|t| t.amount).sum();
+    let cash: Money = tenders.iter().filter(|t| t.method == "cash").map(|t| t.amount).sum();
+    let change_due = (tendered - total_due).max(Money::ZERO);
+    if change_due > cash {
+        return Err("El cambio solo se puede dar en efectivo: los pagos no en efectivo superan el total".to_string());
+    }
+    let mut change_left = change_due;
+    let mut applied = Vec::with_capacity(tenders.len());
+    for t in tenders {
+        let mut amount = t.amount;
+        if t.method == "cash" && change_left > Money::ZERO {
+            let used = change_left.min(amount);
+            amount -= used;
+            change_left -= used;
+        }
+        if amount > Money::ZERO {
+            applied.push(Payment { method: t.method.clone(), amount });
+        }
+    }
+    let remaining = (total_due - (tendered - change_due)).max(Money::ZERO);
+    Ok((applied, change_due, remaining))
+}
+
+/// Precio digitado a mano: permiso propio (`check_override`) y motivo obligatorio, que queda
+/// en la transacción. Devuelve el precio y el motivo normalizados.
+fn validate_price_override(
+    custom_parking_cost: Option<f64>,
+    override_reason: Option<String>,
+    check_override: impl FnOnce() -> Result<(), String>,
+) -> Result<(Option<f64>, Option<String>), String> {
+    let price_override = custom_parking_cost.filter(|c| *c >= 0.0);
+    let override_reason = override_reason
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(String::from);
+    if price_override.is_some() {
+        check_override()?;
+        if override_reason.is_none() {
+            return Err("Debe indicar el motivo del cambio de precio".to_string());
+        }
+    }
+    Ok((price_override, override_reason))
+}
+
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub fn vehiculos_process_exit(
     state: State<AppState>,
     ticket_code: String,
     partial_payment: Option<f64>,
     payment_method: Option<String>,
     custom_parking_cost: Option<f64>,
-) -> Result<Vehicle, String> {
+    lost_ticket: Option<bool>,
+    voucher_code: Option<String>,
+    override_reason: Option<String>,
+    tenders: Option<Vec<Tender>>,
+) -> Result<ProcessExitResult, String> {
     state.check_permission(permissions::CAJA_TRANSACTIONS_CREATE)?;
 
+    let (price_override, override_reason) = validate_price_override(custom_parking_cost, override_reason, || {
+        state.check_permission(permissions::CAJA_PRICE_OVERRIDE)
+    })?;
+
     let method = payment_method.as_deref().unwrap_or("cash").to_lowercase();
     let method = if ["cash", "card", "transfer", "contract", "debt"].contains(&method.as_str()) {
         method
@@ -597,126 +1066,200 @@ pub fn vehiculos_process_exit(
         "cash".to_string()
     };
 
-    if method == "debt" {
+    // Con `tenders` se ignoran payment_method y partial_payment.
+    let tenders = tenders.unwrap_or_default();
+    if tenders.is_empty() && method == "debt" {
         state.check_permission(permissions::CAJA_DEBT_PAYMENT_CREATE)?;
     }
 
     let conn = state.db.get().map_err(|e| e.to_string())?;
-    let operator_user_id = state.get_current_user_id();
+    // Montos entregados en unidades mínimas de la moneda, para que cambio y deuda no arrastren decimales.
+    let currency = crate::money::currency(&conn)?;
+    let tenders: Vec<Payment> = tenders
+        .into_iter()
+        .map(|t| Payment {
+            method: t.method.trim().to_lowercase(),
+            amount: currency.money(t.amount),
+        })
+        .collect();
+    let request = ExitRequest {
+        partial_payment: partial_payment.map(|p| currency.money(p)),
+        method,
+        price_override: price_override.map(|p| currency.money(p)),
+        override_reason,
+        lost_ticket: lost_ticket.unwrap_or(false),
+        voucher_code,
+        tenders,
+    };
+    process_exit_on(&conn, ticket_code.trim(), request, state.get_current_user_id())
+}
 
-    let vehicle: Vehicle = conn
-        .query_row(
-            "SELECT id, ticket_code, plate, vehicle_type, observations, entry_time, exit_time, status, total_amount, debt, special_rate, tariff_kind, tariff_id, operator_user_id FROM vehicles WHERE ticket_code = ?1 AND status = 'active'",
-            params![ticket_code.trim()],
-            |row| row_to_vehicle(row),
-        )
-        .map_err(|_| "Vehicle not found or already completed".to_string())?;
+/// Salida ya validada (permisos, medio de pago normalizado) lista para cobrar.
+struct ExitRequest {
+    partial_payment: Option<Money>,
+    method: String,
+    price_override: Option<Money>,
+    override_reason: Option<String>,
+    lost_ticket: bool,
+    voucher_code: Option<String>,
+    tenders: Vec<Payment>,
+}
+
+/// Cobra y cierra el turno activo del ticket. La lectura del turno, el cálculo del cobro (con la
+/// deuda arrastrada de la placa), el turno de caja y todas las escrituras (canje del vale, cierre
+/// del vehículo, espacio, saldo de deudas anteriores y transacciones) van en una sola transacción,
+/// para que un abono o una salida concurrente de la misma placa no cobre dos veces la deuda anterior.
+fn process_exit_on(
+    conn: &rusqlite::Connection,
+    ticket_code: &str,
+    request: ExitRequest,
+    operator_user_id: Option<String>,
+) -> Result<ProcessExitResult, String> {
+    let ExitRequest {
+        partial_payment,
+        method,
+        price_override,
+        override_reason,
+        lost_ticket,
+        voucher_code,
+        tenders,
+    } = request;
+
+    if partial_payment.is_some_and(|p| p < Money::ZERO) {
+        return Err("El abono parcial no puede ser negativo".to_string());
+    }
 
+    let currency = crate::money::currency(conn)?;
     let exit_time = chrono::Utc::now().to_rfc3339();
-    let parking_cost = match custom_parking_cost {
-        Some(c) if c >= 0.0 => c,
-        _ => {
-            let entry_ts = chrono::DateTime::parse_from_rfc3339(&vehicle.entry_time)
-                .map_err(|e| e.to_string())?
-                .with_timezone(&chrono::Utc);
-            let exit_ts = chrono::DateTime::parse_from_rfc3339(&exit_time)
-                .map_err(|e| e.to_string())?
-                .with_timezone(&chrono::Utc);
-            let duration_minutes = (exit_ts - entry_ts).num_seconds().max(0) as f64 / 60.0;
-
-            let has_active_contract = if !vehicle.plate.is_empty() {
-                let plate_key = normalize_plate_for_index(&vehicle.plate);
-                crate::domains::contracts::find_active_contract_for_plate(&conn, &plate_key).is_some()
+    with_transaction(conn, |conn| {
+        let vehicle: Vehicle = conn
+            .query_row(
+                &format!("SELECT {VEHICLE_COLUMNS} FROM vehicles WHERE ticket_code = ?1 AND status = 'active'"),
+                params![ticket_code],
+                |row| row_to_vehicle(row, &currency),
+            )
+            .map_err(|_| "Vehicle not found or already completed".to_string())?;
+
+        let voucher = find_voucher_for_exit(conn, voucher_code.as_deref(), &exit_time)?;
+        let charge = compute_exit_charge(
+            conn,
+            &vehicle,
+            &exit_time,
+            price_override,
+            lost_ticket,
+            voucher.as_ref(),
+        )?;
+        // Precio que se habría cobrado sin el cambio manual, para la auditoría.
+        let computed_amount = if price_override.is_some() {
+            Some(compute_exit_charge(conn, &vehicle, &exit_time, None, lost_ticket, None)?.parking_cost)
+        } else {
+            None
+        };
+        let parking_cost = charge.parking_cost;
+        let debt = charge.carried_debt;
+        let total_with_debt = parking_cost + debt;
+
+        let (applied, change_due, new_debt) = if !tenders.is_empty() {
+            apply_tenders(&tenders, total_with_debt)?
+        } else if method == "debt" {
+            (Vec::new(), Money::ZERO, total_with_debt)
+        } else {
+            let (final_amount, new_debt) = match partial_payment {
+                Some(p) if p < total_with_debt => (p, total_with_debt - p),
+                _ => (total_with_debt, Money::ZERO),
+            };
+            let applied = if final_amount > Money::ZERO {
+                vec![Payment { method: method.clone(), amount: final_amount }]
             } else {
-                false
+                Vec::new()
             };
+            (applied, Money::ZERO, new_debt)
+        };
+        let final_amount: Money = applied.iter().map(|t| t.amount).sum();
+        // Lo cobrado salda primero las deudas anteriores de la placa (más antiguas primero);
+        // lo que falte del turno actual queda como una deuda nueva en el libro.
+        let paid_to_previous = final_amount.min(debt);
+        let session_debt = (parking_cost - (final_amount - paid_to_previous)).max(Money::ZERO);
+        let plate_key = normalize_plate_for_index(&vehicle.plate);
 
-            if has_active_contract {
-                let plate_key = normalize_plate_for_index(&vehicle.plate);
-                let contract = crate::domains::contracts::find_active_contract_for_plate(&conn, &plate_key).unwrap();
-                let included_minutes = contract.included_hours_per_day * 60.0;
-
-                if duration_minutes <= included_minutes + 1.0 {
-                    0.0
-                } else if let (Some(rate), Some(interval)) = (
-                    contract.extra_charge_per_interval.or(contract.extra_charge_repeat),
-                    contract.extra_interval,
-                ) {
-                    if interval > 0 {
-                        let extra_minutes = duration_minutes - included_minutes;
-                        (extra_minutes / interval as f64).ceil() * rate
-                    } else {
-                        0.0
-                    }
-                } else if !contract.tariff_kind.is_empty() {
-                    let tariff = crate::domains::custom_tariffs::get_tariff_for_calculation(
-                        &conn,
-                        vehicle_type_to_str(&vehicle.vehicle_type),
-                        &contract.tariff_kind,
-                    )?;
-                    let overstay_minutes = duration_minutes - included_minutes;
-                    let overstay_hours = overstay_minutes / 60.0;
-                    let period_h = tariff.additional_period_hours.max(1.0 / 60.0);
-                    let additional_blocks = (overstay_hours / period_h).ceil().max(0.0);
-                    let additional_rate = tariff.additional_hour_price.unwrap_or(tariff.base_price);
-                    additional_blocks * additional_rate
-                } else {
-                    0.0
-                }
-            } else {
-                let tariff = crate::domains::custom_tariffs::get_tariff_for_calculation(
-                    &conn,
-                    vehicle_type_to_str(&vehicle.vehicle_type),
-                    &vehicle.tariff_kind,
-                )?;
-                crate::domains::custom_tariffs::calculate_parking_cost(&tariff, duration_minutes)
-            }
+        let shift_id = crate::domains::caja::open_shift_id(conn, operator_user_id.as_deref())?;
+
+        // Un vale que no descuenta nada (p. ej. minutos gratis dentro del bloque base) no se
+        // consume.
+        if let Some(v) = voucher.as_ref().filter(|_| charge.voucher_discount > Money::ZERO) {
+            crate::domains::vouchers::record_redemption(
+                conn,
+                v,
+                &vehicle.id,
+                charge.voucher_discount,
+                &exit_time,
+                operator_user_id.as_deref(),
+            )?;
         }
-    };
-    let debt = vehicle.debt.unwrap_or(0.0);
-    let total_with_debt = parking_cost + debt;
 
-    let (final_amount, new_debt) = if method == "debt" {
-        (0.0, total_with_debt)
-    } else {
-        match partial_payment {
-            Some(p) if p < total_with_debt => (p, total_with_debt - p),
-            _ => (total_with_debt, 0.0),
+        let n = conn
+            .execute(
+                "UPDATE vehicles SET exit_time = ?1, status = 'completed', total_amount = ?2, debt = ?3, exit_shift_id = ?5, paid_to_previous = ?6 WHERE id = ?4 AND status = 'active'",
+                params![exit_time, final_amount, session_debt, vehicle.id, shift_id, paid_to_previous],
+            )
+            .map_err(|e| e.to_string())?;
+        if n == 0 {
+            return Err("Vehicle not found or already completed".to_string());
         }
-    };
-
-    conn.execute(
-        "UPDATE vehicles SET exit_time = ?1, status = 'completed', total_amount = ?2, debt = ?3 WHERE id = ?4",
-        params![exit_time, final_amount, new_debt, vehicle.id],
-    )
-    .map_err(|e| e.to_string())?;
 
-    if new_debt == 0.0 && !vehicle.plate.is_empty() {
-        let plate_key = normalize_plate_for_index(&vehicle.plate);
-        conn.execute(
-            "UPDATE vehicles SET debt = 0 WHERE plate_upper = ?1 AND id != ?2 AND COALESCE(debt, 0) > 0",
-            params![&plate_key, &vehicle.id],
-        )
-        .map_err(|e| e.to_string())?;
-    }
-
-    if final_amount > 0.0 {
-        let tx_id = id_gen::generate_id(id_gen::PREFIX_TRANSACTION);
-        conn.execute(
-            "INSERT INTO transactions (id, vehicle_id, amount, method, created_at, operator_user_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-            params![tx_id, vehicle.id, final_amount, method, exit_time, operator_user_id],
-        )
-        .map_err(|e| e.to_string())?;
-    }
+        crate::domains::spaces::release_space_for_vehicle(conn, &vehicle.id)?;
+
+        crate::domains::debts::apply_payment_oldest_first(conn, &plate_key, paid_to_previous, &exit_time)?;
+        crate::domains::debts::record_debt(
+            conn,
+            &plate_key,
+            &vehicle.id,
+            session_debt,
+            &exit_time,
+            operator_user_id.as_deref(),
+        )?;
+
+        // Una transacción por medio de pago. Con precio manual cobrado en caja se registra aunque
+        // sea 0, para no perder la auditoría (que va solo en la primera fila); fiado no deja fila.
+        let mut rows: Vec<Payment> = applied.clone();
+        if rows.is_empty() && price_override.is_some() && method != "debt" {
+            rows.push(Payment { method: method.clone(), amount: Money::ZERO });
+        }
+        for (i, tender) in rows.iter().enumerate() {
+            let audit = i == 0 && price_override.is_some();
+            let tx_id = id_gen::generate_id(id_gen::PREFIX_TRANSACTION);
+            conn.execute(
+                "INSERT INTO transactions (id, vehicle_id, amount, method, created_at, operator_user_id, computed_amount, override_amount, override_reason, shift_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                params![
+                    tx_id,
+                    vehicle.id,
+                    tender.amount,
+                    tender.method,
+                    exit_time,
+                    operator_user_id,
+                    if audit { computed_amount } else { None },
+                    if audit { price_override } else { None },
+                    if audit { override_reason.as_deref() } else { None },
+                    shift_id
+                ],
+            )
+            .map_err(|e| e.to_string())?;
+        }
 
-    let updated = Vehicle {
-        exit_time: Some(exit_time),
-        status: VehicleStatus::Completed,
-        total_amount: Some(final_amount),
-        debt: if new_debt > 0.0 { Some(new_debt) } else { None },
-        ..vehicle
-    };
-    Ok(updated)
+        let updated = Vehicle {
+            exit_time: Some(exit_time.clone()),
+            status: VehicleStatus::Completed,
+            total_amount: Some(currency.major(final_amount)),
+            debt: if session_debt > Money::ZERO { Some(currency.major(session_debt)) } else { None },
+            ..vehicle
+        };
+        Ok(ProcessExitResult {
+            vehicle: updated,
+            change_due: currency.major(change_due),
+            tenders: applied.iter().map(|t| t.to_tender(&currency)).collect(),
+            outstanding_debt: currency.major(new_debt),
+        })
+    })
 }
 
 #[tauri::command]
@@ -734,36 +1277,56 @@ pub fn vehiculos_remove_from_parking(
         _ => {}
     }
     let conn = state.db.get().map_err(|e| e.to_string())?;
+    let currency = crate::money::currency(&conn)?;
     let vehicle: Vehicle = if let Some(id) = by_id {
         conn.query_row(
-            "SELECT id, ticket_code, plate, vehicle_type, observations, entry_time, exit_time, status, total_amount, debt, special_rate, tariff_kind, tariff_id, operator_user_id FROM vehicles WHERE id = ?1 AND status = 'active'",
+            &format!("SELECT {VEHICLE_COLUMNS} FROM vehicles WHERE id = ?1 AND status = 'active'"),
             params![id],
-            |row| row_to_vehicle(row),
+            |row| row_to_vehicle(row, &currency),
         )
         .map_err(|_| "Vehicle not found or not active".to_string())?
     } else {
         let ticket = by_ticket.unwrap();
         conn.query_row(
-            "SELECT id, ticket_code, plate, vehicle_type, observations, entry_time, exit_time, status, total_amount, debt, special_rate, tariff_kind, tariff_id, operator_user_id FROM vehicles WHERE ticket_code = ?1 AND status = 'active'",
+            &format!("SELECT {VEHICLE_COLUMNS} FROM vehicles WHERE ticket_code = ?1 AND status = 'active'"),
             params![ticket],
-            |row| row_to_vehicle(row),
+            |row| row_to_vehicle(row, &currency),
         )
         .map_err(|_| "Vehicle not found or not active".to_string())?
     };
+    remove_from_parking_on(&conn, vehicle, state.get_current_user_id())
+}
+
+/// Saca el vehículo sin cobro: cierre como 'removed', liberación del espacio y transacción
+/// de trazabilidad, todo o nada.
+fn remove_from_parking_on(
+    conn: &rusqlite::Connection,
+    vehicle: Vehicle,
+    operator_user_id: Option<String>,
+) -> Result<Vehicle, String> {
     let exit_time = chrono::Utc::now().to_rfc3339();
-    conn.execute(
-        "UPDATE vehicles SET exit_time = ?1, status = 'removed', total_amount = NULL, debt = 0 WHERE id = ?2",
-        params![exit_time, vehicle.id],
-    )
-    .map_err(|e| e.to_string())?;
-    // Registrar la acción de remoción en transactions para trazabilidad
-    let tx_id = id_gen::generate_id(id_gen::PREFIX_TRANSACTION);
-    let operator_user_id = state.get_current_user_id();
-    conn.execute(
-        "INSERT INTO transactions (id, vehicle_id, amount, method, created_at, operator_user_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-        params![tx_id, vehicle.id, 0.0, "removed", exit_time, operator_user_id],
-    )
-    .map_err(|e| e.to_string())?;
+    with_transaction(conn, |conn| {
+        // Retirar no mueve dinero: el turno solo se registra si hay uno abierto.
+        let shift_id = crate::domains::caja::open_shift_for(conn, operator_user_id.as_deref())?.map(|s| s.id);
+        let n = conn
+            .execute(
+                "UPDATE vehicles SET exit_time = ?1, status = 'removed', total_amount = NULL, debt = 0, exit_shift_id = ?3 WHERE id = ?2 AND status = 'active'",
+                params![exit_time, vehicle.id, shift_id],
+            )
+            .map_err(|e| e.to_string())?;
+        if n == 0 {
+            return Err("Vehicle not found or not active".to_string());
+        }
+        crate::domains::spaces::release_space_for_vehicle(conn, &vehicle.id)?;
+        // Registrar la acción de remoción en transactions para trazabilidad
+        let tx_id = id_gen::generate_id(id_gen::PREFIX_TRANSACTION);
+        conn.execute(
+            "INSERT INTO transactions (id, vehicle_id, amount, method, created_at, operator_user_id, shift_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![tx_id, vehicle.id, Money::ZERO, "removed", exit_time, operator_user_id, shift_id],
+        )
+        .map_err(|e| e.to_string())?;
+        Ok(())
+    })?;
     let updated = Vehicle {
         exit_time: Some(exit_time),
         status: VehicleStatus::Removed,
@@ -774,6 +1337,224 @@ pub fn vehiculos_remove_from_parking(
     Ok(updated)
 }
 
+/// Devolución de una salida: transacciones negativas por medio de pago, cada una ligada al
+/// cobro original.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RefundResult {
+    pub vehicle: Vehicle,
+    pub refunded: f64,
+    pub refunds: Vec<Tender>,
+    pub reopened: bool,
+    pub capacity_warning: Option<String>,
+}
+
+/// Medios que movieron dinero y por tanto se pueden devolver.
+const REFUNDABLE_METHODS: &[&str] = &["cash", "card", "transfer"];
+
+/// Devuelve todo o parte de lo cobrado por la estadía en una salida. Con `reopen` (solo
+/// devolución total) la sesión vuelve a quedar activa, ocupa de nuevo un espacio y se borra la
+/// deuda que generó.
+#[tauri::command]
+pub fn vehiculos_refund_exit(
+    state: State<AppState>,
+    vehicle_id: String,
+    reason: String,
+    amount: Option<f64>,
+    reopen: Option<bool>,
+) -> Result<RefundResult, String> {
+    state.check_permission(permissions::CAJA_REFUNDS_CREATE)?;
+    let reason = reason.trim();
+    if reason.is_empty() {
+        return Err("Debe indicar el motivo de la devolución".to_string());
+    }
+    let conn = state.db.get().map_err(|e| e.to_string())?;
+    let currency = crate::money::currency(&conn)?;
+    refund_exit_on(
+        &conn,
+        vehicle_id.trim(),
+        amount.map(|a| currency.money(a)),
+        reason,
+        reopen.unwrap_or(false),
+        state.get_current_user_id(),
+    )
+}
+
+/// Reparte la devolución sobre los cobros de la salida, del más reciente al más antiguo. Lo que
+/// la salida abonó a deudas anteriores (`paid_to_previous`) queda fuera del monto devolvible:
+/// esas deudas siguen saldadas. El canje de vales no se revierte. Lo devolvible se calcula dentro
+/// de la transacción de escritura: dos devoluciones simultáneas no pueden superar lo cobrado.
+fn refund_exit_on(
+    conn: &rusqlite::Connection,
+    vehicle_id: &str,
+    amount: Option<Money>,
+    reason: &str,
+    reopen: bool,
+    operator_user_id: Option<String>,
+) -> Result<RefundResult, String> {
+    let currency = crate::money::currency(conn)?;
+    let refunded_at = chrono::Utc::now().to_rfc3339();
+    with_transaction(conn, |conn| {
+        let (vehicle, paid_to_previous): (Vehicle, Money) = conn
+            .query_row(
+                &format!("SELECT {VEHICLE_COLUMNS}, paid_to_previous FROM vehicles WHERE id = ?1 AND status = 'completed'"),
+                params![vehicle_id],
+                |row| Ok((row_to_vehicle(row, &currency)?, row.get("paid_to_previous")?)),
+            )
+            .map_err(|_| "Solo se pueden devolver salidas completadas".to_string())?;
+
+        // Cobros de la salida con lo que aún se puede devolver de cada uno.
+        let mut stmt = conn
+            .prepare(
+                r#"
+                SELECT t.id, LOWER(t.method),
+                       t.amount + COALESCE((SELECT SUM(r.amount) FROM transactions r WHERE r.refund_of = t.id), 0)
+                FROM transactions t
+                WHERE t.vehicle_id = ?1 AND t.kind = 'parking' AND t.amount > 0
+                ORDER BY t.created_at DESC, t.rowid DESC
+                "#,
+            )
+            .map_err(|e| e.to_string())?;
+        let charges: Vec<(String, String, Money)> = stmt
+            .query_map(params![vehicle_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .map_err(|e| e.to_string())?
+            .collect::<Result<Vec<(String, String, Money)>, _>>()
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .filter(|(_, method, available)| REFUNDABLE_METHODS.contains(&method.as_str()) && *available > Money::ZERO)
+            .collect();
+        // Lo ya devuelto se descuenta primero de la parte de la estadía.
+        let refundable = (charges.iter().map(|(_, _, available)| *available).sum::<Money>() - paid_to_previous)
+            .max(Money::ZERO);
+
+        let amount = amount.unwrap_or(refundable);
+        if amount <= Money::ZERO {
+            return Err("El monto a devolver debe ser mayor a 0".to_string());
+        }
+        if amount > refundable {
+            return Err(format!("El monto a devolver supera lo cobrado ({:.2})", currency.major(refundable)));
+        }
+        let plate_key = normalize_plate_for_index(&vehicle.plate);
+        if reopen {
+            if amount < refundable {
+                return Err("Para reabrir la sesión se debe devolver todo lo cobrado".to_string());
+            }
+            if !plate_key.is_empty() {
+                let active: i64 = conn
+                    .query_row(
+                        "SELECT COUNT(*) FROM vehicles WHERE plate_upper = ?1 AND status = 'active'",
+                        params![&plate_key],
+                        |row| row.get(0),
+                    )
+                    .map_err(|e| e.to_string())?;
+                if active > 0 {
+                    return Err(format!("Ya hay un vehículo activo con la placa {}", vehicle.plate));
+                }
+            }
+            let paid_debts: i64 = conn
+                .query_row(
+                    "SELECT COUNT(*) FROM debts WHERE vehicle_id = ?1 AND (status != 'open' OR paid_amount > 0)",
+                    params![vehicle_id],
+                    |row| row.get(0),
+                )
+                .map_err(|e| e.to_string())?;
+            if paid_debts > 0 {
+                return Err("No se puede reabrir: la deuda de la sesión ya tiene abonos".to_string());
+            }
+        }
+
+        let shift_id = crate::domains::caja::open_shift_id(conn, operator_user_id.as_deref())?;
+
+        let mut left = amount;
+        let mut refunds: Vec<Payment> = Vec::new();
+        for (charge_id, method, available) in &charges {
+            let take = left.min(*available);
+            if take <= Money::ZERO {
+                break;
+            }
+            conn.execute(
+                "INSERT INTO transactions (id, vehicle_id, amount, method, created_at, operator_user_id, kind, refund_of, refund_reason, shift_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6, 'refund', ?7, ?8, ?9)",
+                params![
+                    id_gen::generate_id(id_gen::PREFIX_TRANSACTION),
+                    vehicle_id,
+                    -take,
+                    method,
+                    refunded_at,
+                    operator_user_id,
+                    charge_id,
+                    reason,
+                    shift_id
+                ],
+            )
+            .map_err(|e| e.to_string())?;
+            match refunds.iter_mut().find(|t| &t.method == method) {
+                Some(t) => t.amount += take,
+                None => refunds.push(Payment { method: method.clone(), amount: take }),
+            }
+            left -= take;
+        }
+        if !reopen {
+            conn.execute(
+                "UPDATE vehicles SET total_amount = MAX(COALESCE(total_amount, 0) - ?1, 0) WHERE id = ?2",
+                params![amount, vehicle_id],
+            )
+            .map_err(|e| e.to_string())?;
+            let total_amount =
+                vehicle.total_amount.map(|t| currency.major((currency.money(t) - amount).max(Money::ZERO)));
+            return Ok(RefundResult {
+                vehicle: Vehicle { total_amount, ..vehicle },
+                refunded: currency.major(amount),
+                refunds: refunds.iter().map(|t| t.to_tender(&currency)).collect(),
+                reopened: false,
+                capacity_warning: None,
+            });
+        }
+
+        // La sesión reabierta vuelve a contar contra la capacidad y a ocupar un espacio: el
+        // que tenía si sigue libre o, si no, el primero libre compatible.
+        let vehicle_type = vehicle_type_to_str(&vehicle.vehicle_type);
+        let previous_space = vehicle
+            .space_id
+            .as_deref()
+            .filter(|sid| crate::domains::spaces::occupy_space(conn, sid, vehicle_id).is_ok())
+            .map(str::to_string);
+        let space_id = match previous_space {
+            Some(sid) => Some(sid),
+            None => match crate::domains::spaces::pick_space_for_entry(conn, vehicle_type, None)? {
+                Some(space) => {
+                    crate::domains::spaces::occupy_space(conn, &space.id, vehicle_id)?;
+                    Some(space.id)
+                }
+                None => None,
+            },
+        };
+        let capacity_warning =
+            crate::domains::capacity::check_entry_capacity(conn, vehicle_type, space_id.as_deref())?;
+
+        conn.execute("DELETE FROM debts WHERE vehicle_id = ?1", params![vehicle_id])
+            .map_err(|e| e.to_string())?;
+        conn.execute(
+            "UPDATE vehicles SET status = 'active', exit_time = NULL, total_amount = NULL, debt = 0, exit_shift_id = NULL, paid_to_previous = 0, space_id = ?2 WHERE id = ?1 AND status = 'completed'",
+            params![vehicle_id, space_id],
+        )
+        .map_err(|e| e.to_string())?;
+        Ok(RefundResult {
+            vehicle: Vehicle {
+                exit_time: None,
+                status: VehicleStatus::Active,
+                total_amount: None,
+                debt: None,
+                space_id,
+                ..vehicle
+            },
+            refunded: currency.major(amount),
+            refunds: refunds.iter().map(|t| t.to_tender(&currency)).collect(),
+            reopened: true,
+            capacity_warning,
+        })
+    })
+}
+
 #[tauri::command]
 pub fn vehiculos_find_by_ticket(
     state: State<AppState>,
@@ -781,10 +1562,11 @@ pub fn vehiculos_find_by_ticket(
 ) -> Result<Option<Vehicle>, String> {
     state.check_permission(permissions::VEHICULOS_ENTRIES_READ)?;
     let conn = state.db.get().map_err(|e| e.to_string())?;
+    let currency = crate::money::currency(&conn)?;
     let result = conn.query_row(
-        "SELECT id, ticket_code, plate, vehicle_type, observations, entry_time, exit_time, status, total_amount, debt, special_rate, tariff_kind, tariff_id, operator_user_id FROM vehicles WHERE ticket_code = ?1 AND status = 'active' LIMIT 1",
+        &format!("SELECT {VEHICLE_COLUMNS} FROM vehicles WHERE ticket_code = ?1 AND status = 'active' LIMIT 1"),
         params![ticket_code.trim()],
-        |row| row_to_vehicle(row),
+        |row| row_to_vehicle(row, &currency),
     );
     match result {
         Ok(v) => Ok(Some(v)),
@@ -800,11 +1582,12 @@ pub fn vehiculos_find_by_plate(
 ) -> Result<Option<Vehicle>, String> {
     state.check_permission(permissions::VEHICULOS_ENTRIES_READ)?;
     let conn = state.db.get().map_err(|e| e.to_string())?;
+    let currency = crate::money::currency(&conn)?;
     let key = normalize_plate_for_index(&plate);
     let result = conn.query_row(
-        "SELECT id, ticket_code, plate, vehicle_type, observations, entry_time, exit_time, status, total_amount, debt, special_rate, tariff_kind, tariff_id, operator_user_id FROM vehicles WHERE plate_upper = ?1 AND status = 'active' LIMIT 1",
+        &format!("SELECT {VEHICLE_COLUMNS} FROM vehicles WHERE plate_upper = ?1 AND status = 'active' LIMIT 1"),
         params![key],
-        |row| row_to_vehicle(row),
+        |row| row_to_vehicle(row, &currency),
     );
     match result {
         Ok(v) => Ok(Some(v)),
@@ -821,14 +1604,15 @@ pub fn vehiculos_get_vehicles_by_plate(
 ) -> Result<Vec<Vehicle>, String> {
     state.check_permission(permissions::VEHICULOS_ENTRIES_READ)?;
     let conn = state.db.get().map_err(|e| e.to_string())?;
+    let currency = crate::money::currency(&conn)?;
     let key = normalize_plate_for_index(&plate);
     let mut stmt = conn
         .prepare(
-            "SELECT id, ticket_code, plate, vehicle_type, observations, entry_time, exit_time, status, total_amount, debt, special_rate, tariff_kind, tariff_id, operator_user_id FROM vehicles WHERE plate_upper = ?1 ORDER BY entry_time DESC",
+            &format!("SELECT {VEHICLE_COLUMNS} FROM vehicles WHERE plate_upper = ?1 ORDER BY entry_time DESC"),
         )
         .map_err(|e| e.to_string())?;
     let rows = stmt
-        .query_map(params![key], |row| row_to_vehicle(row))
+        .query_map(params![key], |row| row_to_vehicle(row, &currency))
         .map_err(|e| e.to_string())?;
     let list: Vec<Vehicle> = rows.filter_map(|r| r.ok()).collect();
     Ok(list)
@@ -865,34 +1649,40 @@ pub fn vehiculos_search_vehicles_by_plate_prefix(
 ) -> Result<Vec<Vehicle>, String> {
     state.check_permission(permissions::VEHICULOS_ENTRIES_READ)?;
     let conn = state.db.get().map_err(|e| e.to_string())?;
+    let currency = crate::money::currency(&conn)?;
     let key = normalize_plate_for_index(&plate_prefix);
     let pattern = like_escape_prefix(&key);
     let mut stmt = conn
         .prepare(
-            "SELECT id, ticket_code, plate, vehicle_type, observations, entry_time, exit_time, status, total_amount, debt, special_rate, tariff_kind, tariff_id, operator_user_id FROM vehicles WHERE plate_upper LIKE ?1 ESCAPE '\\' ORDER BY entry_time DESC",
+            &format!("SELECT {VEHICLE_COLUMNS} FROM vehicles WHERE plate_upper LIKE ?1 ESCAPE '\\' ORDER BY entry_time DESC"),
         )
         .map_err(|e| e.to_string())?;
     let rows = stmt
-        .query_map(params![pattern], |row| row_to_vehicle(row))
+        .query_map(params![pattern], |row| row_to_vehicle(row, &currency))
         .map_err(|e| e.to_string())?;
     let list: Vec<Vehicle> = rows.filter_map(|r| r.ok()).collect();
     Ok(list)
 }
 
-/// Elimina un vehículo y sus transacciones. El cliente usa esto para quitar el dato que considera erróneo.
+/// Elimina un vehículo con sus transacciones y deudas. El cliente usa esto para quitar el dato que considera erróneo.
 #[tauri::command]
 pub fn vehiculos_delete_vehicle(state: State<AppState>, vehicle_id: String) -> Result<(), String> {
     state.check_permission(permissions::VEHICULOS_ENTRIES_DELETE)?;
     let conn = state.db.get().map_err(|e| e.to_string())?;
-    conn.execute("DELETE FROM transactions WHERE vehicle_id = ?1", params![&vehicle_id])
-        .map_err(|e| e.to_string())?;
-    let n = conn
-        .execute("DELETE FROM vehicles WHERE id = ?1", params![&vehicle_id])
-        .map_err(|e| e.to_string())?;
-    if n == 0 {
-        return Err("Vehículo no encontrado".to_string());
-    }
-    Ok(())
+    with_transaction(&conn, |conn| {
+        crate::domains::spaces::release_space_for_vehicle(conn, &vehicle_id)?;
+        conn.execute("DELETE FROM transactions WHERE vehicle_id = ?1", params![&vehicle_id])
+            .map_err(|e| e.to_string())?;
+        conn.execute("DELETE FROM debts WHERE vehicle_id = ?1", params![&vehicle_id])
+            .map_err(|e| e.to_string())?;
+        let n = conn
+            .execute("DELETE FROM vehicles WHERE id = ?1", params![&vehicle_id])
+            .map_err(|e| e.to_string())?;
+        if n == 0 {
+            return Err("Vehículo no encontrado".to_string());
+        }
+        Ok(())
+    })
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -907,6 +1697,7 @@ pub struct PlateConflict {
 pub fn vehiculos_get_plate_conflicts(state: State<AppState>) -> Result<Vec<PlateConflict>, String> {
     state.check_permission(permissions::VEHICULOS_ENTRIES_READ)?;
     let conn = state.db.get().map_err(|e| e.to_string())?;
+    let currency = crate::money::currency(&conn)?;
     // Placas (no vacías) que tienen más de un vehicle_type distinto
     let mut plates: Vec<String> = Vec::new();
     let mut stmt = conn
@@ -927,11 +1718,11 @@ pub fn vehiculos_get_plate_conflicts(state: State<AppState>) -> Result<Vec<Plate
     for plate in plates {
         let mut stmt = conn
             .prepare(
-                "SELECT id, ticket_code, plate, vehicle_type, observations, entry_time, exit_time, status, total_amount, debt, special_rate, tariff_kind, tariff_id, operator_user_id FROM vehicles WHERE plate_upper = ?1 ORDER BY entry_time DESC",
+                &format!("SELECT {VEHICLE_COLUMNS} FROM vehicles WHERE plate_upper = ?1 ORDER BY entry_time DESC"),
             )
             .map_err(|e| e.to_string())?;
         let rows = stmt
-            .query_map(params![&plate], |row| row_to_vehicle(row))
+            .query_map(params![&plate], |row| row_to_vehicle(row, &currency))
             .map_err(|e| e.to_string())?;
         let vehicles: Vec<Vehicle> = rows.filter_map(|r| r.ok()).collect();
         if !vehicles.is_empty() {
@@ -953,7 +1744,17 @@ pub fn vehiculos_resolve_plate_conflict(
 ) -> Result<(), String> {
     state.check_permission(permissions::VEHICULOS_ENTRIES_DELETE)?;
     let conn = state.db.get().map_err(|e| e.to_string())?;
-    let key = normalize_plate_for_index(&plate);
+    resolve_plate_conflict_on(&conn, &plate, &keep_vehicle_id)
+}
+
+/// Deja solo `keep_vehicle_id` para la placa; borra los demás registros y sus transacciones
+/// en una sola transacción.
+fn resolve_plate_conflict_on(
+    conn: &rusqlite::Connection,
+    plate: &str,
+    keep_vehicle_id: &str,
+) -> Result<(), String> {
+    let key = normalize_plate_for_index(plate);
     let mut stmt = conn
         .prepare("SELECT id FROM vehicles WHERE plate_upper = ?1")
         .map_err(|e| e.to_string())?;
@@ -962,17 +1763,388 @@ pub fn vehiculos_resolve_plate_conflict(
         .map_err(|e| e.to_string())?
         .filter_map(|r| r.ok())
         .collect();
-    if !ids.contains(&keep_vehicle_id) {
+    if !ids.iter().any(|id| id == keep_vehicle_id) {
         return Err("El id a mantener no corresponde a esa placa".to_string());
     }
-    for id in ids {
-        if id == keep_vehicle_id {
-            continue;
+    with_transaction(conn, |conn| {
+        for id in ids.iter().filter(|id| id.as_str() != keep_vehicle_id) {
+            crate::domains::spaces::release_space_for_vehicle(conn, id)?;
+            conn.execute("DELETE FROM transactions WHERE vehicle_id = ?1", params![id])
+                .map_err(|e| e.to_string())?;
+            conn.execute("DELETE FROM debts WHERE vehicle_id = ?1", params![id])
+                .map_err(|e| e.to_string())?;
+            conn.execute("DELETE FROM vehicles WHERE id = ?1", params![id])
+                .map_err(|e| e.to_string())?;
         }
-        conn.execute("DELETE FROM transactions WHERE vehicle_id = ?1", params![&id])
-            .map_err(|e| e.to_string())?;
-        conn.execute("DELETE FROM vehicles WHERE id = ?1", params![&id])
-            .map_err(|e| e.to_string())?;
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+
+    fn cop(amount: i64) -> Money {
+        Money::from_minor(amount)
+    }
+
+    fn tender(method: &str, amount: i64) -> Payment {
+        Payment { method: method.to_string(), amount: cop(amount) }
+    }
+
+    const CASHIER: &str = "cashier";
+
+    /// Migrated database with an open shift for `CASHIER`, so exits and refunds can take money.
+    fn migrated_conn() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::run_migrations(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO shifts (id, operator_user_id, booth, opened_at, status) VALUES ('SH1', ?1, 'B1', '2025-01-01T00:00:00+00:00', 'open')",
+            params![CASHIER],
+        )
+        .unwrap();
+        conn
+    }
+
+    fn cashier() -> Option<String> {
+        Some(CASHIER.to_string())
+    }
+
+    /// Makes every `op` on `table` fail, simulating a crash between two writes.
+    fn inject_failure(conn: &Connection, op: &str, table: &str) {
+        conn.execute_batch(&format!(
+            "CREATE TRIGGER fail_{op}_{table} BEFORE {op} ON {table} BEGIN SELECT RAISE(ABORT, 'injected failure'); END;"
+        ))
+        .unwrap();
+    }
+
+    fn entry(conn: &Connection, plate: &str, ticket: &str, space_code: Option<&str>) -> Result<Vehicle, String> {
+        let request = EntryRequest {
+            plate: plate.to_string(),
+            vehicle_type: VehicleType::Car,
+            observations: None,
+            ticket_code: Some(ticket.to_string()),
+            tariff_kind: None,
+            space_code: space_code.map(String::from),
+        };
+        register_entry_on(conn, request, None).map(|r| r.vehicle)
+    }
+
+    fn cash_exit() -> ExitRequest {
+        ExitRequest {
+            partial_payment: None,
+            method: "cash".to_string(),
+            price_override: None,
+            override_reason: None,
+            lost_ticket: false,
+            voucher_code: None,
+            tenders: Vec::new(),
+        }
+    }
+
+    fn add_voucher(conn: &Connection, code: &str, kind: &str, value: f64) {
+        conn.execute(
+            "INSERT INTO vouchers (id, code, merchant, kind, value, created_at) VALUES (?1, ?1, 'Tienda', ?2, ?3, 'x')",
+            params![code, kind, value],
+        )
+        .unwrap();
+    }
+
+    fn count(conn: &Connection, sql: &str) -> i64 {
+        conn.query_row(sql, [], |r| r.get(0)).unwrap()
+    }
+
+    fn add_space(conn: &Connection) {
+        conn.execute(
+            "INSERT INTO spaces (id, code, zone, allowed_vehicle_types, status, created_at, updated_at) VALUES ('sp1', 'A1', '', 'car', 'available', 'x', 'x')",
+            [],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn entry_is_rolled_back_when_insert_fails() {
+        let conn = migrated_conn();
+        add_space(&conn);
+        inject_failure(&conn, "INSERT", "vehicles");
+        assert!(entry(&conn, "ABC123", "T1", Some("A1")).is_err());
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM spaces WHERE status = 'available' AND vehicle_id IS NULL"), 1);
+    }
+
+    #[test]
+    fn exit_is_rolled_back_when_payment_insert_fails() {
+        let conn = migrated_conn();
+        add_space(&conn);
+        conn.execute(
+            "INSERT INTO vehicles (id, ticket_code, plate, plate_upper, vehicle_type, entry_time, exit_time, status, debt, tariff_kind) VALUES ('old', 'T0', 'ABC123', 'ABC123', 'car', '2025-01-01T00:00:00+00:00', '2025-01-01T02:00:00+00:00', 'completed', 3000, 'regular')",
+            [],
+        )
+        .unwrap();
+        crate::domains::debts::record_debt(&conn, "ABC123", "old", cop(3000), "2025-01-01T02:00:00+00:00", None).unwrap();
+        entry(&conn, "ABC123", "T1", Some("A1")).unwrap();
+        inject_failure(&conn, "INSERT", "transactions");
+
+        assert!(process_exit_on(&conn, "T1", cash_exit(), cashier()).is_err());
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM vehicles WHERE ticket_code = 'T1' AND status = 'active'"), 1);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM spaces WHERE status = 'occupied'"), 1);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM debts WHERE vehicle_id = 'old' AND status = 'open' AND paid_amount = 0"), 1);
+
+        conn.execute_batch("DROP TRIGGER fail_INSERT_transactions;").unwrap();
+        let result = process_exit_on(&conn, "T1", cash_exit(), cashier()).unwrap();
+        assert!(matches!(result.vehicle.status, VehicleStatus::Completed));
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM transactions"), 1);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM debts WHERE vehicle_id = 'old' AND status = 'settled'"), 1);
+    }
+
+    #[test]
+    fn price_override_needs_its_permission_and_a_reason() {
+        let denied = || Err("Permission denied: 'caja:price_override:create' required".to_string());
+        let allowed = || Ok(());
+        assert!(validate_price_override(Some(3000.0), Some("cliente frecuente".to_string()), denied)
+            .unwrap_err()
+            .contains("Permission denied"));
+        assert_eq!(
+            validate_price_override(Some(3000.0), Some("   ".to_string()), allowed).unwrap_err(),
+            "Debe indicar el motivo del cambio de precio"
+        );
+        assert_eq!(
+            validate_price_override(Some(3000.0), Some(" cliente frecuente ".to_string()), allowed).unwrap(),
+            (Some(3000.0), Some("cliente frecuente".to_string()))
+        );
+        // Sin precio manual no se pide permiso ni motivo.
+        assert_eq!(validate_price_override(None, None, denied).unwrap(), (None, None));
+        assert!(!permissions::operator_permissions().contains(&permissions::CAJA_PRICE_OVERRIDE));
+        assert!(permissions::admin_permissions().contains(&permissions::CAJA_PRICE_OVERRIDE));
+
+        let conn = migrated_conn();
+        entry(&conn, "ABC123", "T1", None).unwrap();
+        let overridden = ExitRequest {
+            price_override: Some(cop(3000)),
+            override_reason: Some("cliente frecuente".to_string()),
+            ..cash_exit()
+        };
+        process_exit_on(&conn, "T1", overridden, cashier()).unwrap();
+        let (amount, computed, override_amount, reason): (i64, i64, i64, String) = conn
+            .query_row(
+                "SELECT amount, computed_amount, override_amount, override_reason FROM transactions",
+                [],
+                |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?)),
+            )
+            .unwrap();
+        assert_eq!((amount, override_amount, reason.as_str()), (3000, 3000, "cliente frecuente"));
+        assert!(computed > 0);
+    }
+
+    #[test]
+    fn negative_partial_payments_are_rejected_and_debt_exits_write_no_empty_charge() {
+        let conn = migrated_conn();
+        entry(&conn, "ABC123", "T1", None).unwrap();
+        let negative = ExitRequest { partial_payment: Some(cop(-500)), ..cash_exit() };
+        assert!(process_exit_on(&conn, "T1", negative, cashier()).is_err());
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM vehicles WHERE status = 'active'"), 1);
+
+        let on_credit = ExitRequest {
+            method: "debt".to_string(),
+            price_override: Some(cop(3000)),
+            override_reason: Some("cliente frecuente".to_string()),
+            ..cash_exit()
+        };
+        let result = process_exit_on(&conn, "T1", on_credit, cashier()).unwrap();
+        assert_eq!(result.outstanding_debt, 3000.0);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM transactions"), 0);
+    }
+
+    #[test]
+    fn debt_is_carried_through_the_ledger_without_double_counting() {
+        let conn = migrated_conn();
+        entry(&conn, "ABC123", "T1", None).unwrap();
+        let on_credit = ExitRequest {
+            method: "debt".to_string(),
+            price_override: Some(cop(5000)),
+            ..cash_exit()
+        };
+        process_exit_on(&conn, "T1", on_credit, cashier()).unwrap();
+
+        let second = entry(&conn, "ABC123", "T2", None).unwrap();
+        assert_eq!(second.debt, Some(5000.0));
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM vehicles WHERE ticket_code = 'T2' AND debt = 0"), 1);
+
+        let partial = ExitRequest {
+            partial_payment: Some(cop(2000)),
+            price_override: Some(cop(4000)),
+            ..cash_exit()
+        };
+        let result = process_exit_on(&conn, "T2", partial, cashier()).unwrap();
+        assert_eq!(result.vehicle.debt, Some(4000.0));
+        assert_eq!(result.outstanding_debt, 7000.0);
+        assert_eq!(crate::domains::debts::open_balance_for_plate(&conn, "ABC123").unwrap(), cop(7000));
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM debts WHERE status = 'open'"), 2);
+    }
+
+    #[test]
+    fn refund_links_to_the_charge_and_reopens_only_on_full_refund() {
+        let conn = migrated_conn();
+        let vehicle = entry(&conn, "ABC123", "T1", None).unwrap();
+        let paid = ExitRequest {
+            price_override: Some(cop(6000)),
+            tenders: vec![tender("card", 2000), tender("cash", 4000)],
+            ..cash_exit()
+        };
+        process_exit_on(&conn, "T1", paid, cashier()).unwrap();
+
+        assert!(refund_exit_on(&conn, &vehicle.id, Some(cop(7000)), "cobro doble", false, cashier()).is_err());
+        assert!(refund_exit_on(&conn, &vehicle.id, Some(cop(1000)), "cobro doble", true, cashier()).is_err());
+        let partial = refund_exit_on(&conn, &vehicle.id, Some(cop(1000)), "cobro doble", false, cashier()).unwrap();
+        assert!(!partial.reopened);
+        assert_eq!(partial.vehicle.total_amount, Some(5000.0));
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM transactions WHERE kind = 'refund' AND refund_of IS NOT NULL AND amount = -1000"), 1);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM vehicles WHERE ticket_code = 'T1' AND total_amount = 5000"), 1);
+
+        let full = refund_exit_on(&conn, &vehicle.id, None, "tarifa equivocada", true, cashier()).unwrap();
+        assert_eq!(full.refunded, 5000.0);
+        assert!(full.reopened);
+        let net: i64 = conn
+            .query_row("SELECT SUM(amount) FROM transactions WHERE vehicle_id = ?1", params![&vehicle.id], |r| r.get(0))
+            .unwrap();
+        assert_eq!(net, 0);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM vehicles WHERE ticket_code = 'T1' AND status = 'active' AND exit_time IS NULL"), 1);
+    }
+
+    #[test]
+    fn refund_keeps_prior_debt_settled_and_reopen_reoccupies_the_space() {
+        let conn = migrated_conn();
+        add_space(&conn);
+        conn.execute_batch(
+            "INSERT INTO vehicles (id, ticket_code, plate, plate_upper, vehicle_type, entry_time, status, tariff_kind) VALUES
+                ('old', 'T0', 'ABC123', 'ABC123', 'car', '2025-01-01T00:00:00+00:00', 'completed', 'regular');
+             INSERT INTO debts (id, plate_upper, vehicle_id, amount, paid_amount, status, created_at) VALUES
+                ('d1', 'ABC123', 'old', 2000, 0, 'open', '2025-01-01T01:00:00+00:00');",
+        )
+        .unwrap();
+        let vehicle = entry(&conn, "ABC123", "T1", Some("A1")).unwrap();
+        let paid = ExitRequest {
+            price_override: Some(cop(6000)),
+            tenders: vec![tender("cash", 8000)],
+            ..cash_exit()
+        };
+        process_exit_on(&conn, "T1", paid, cashier()).unwrap();
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM spaces WHERE status = 'available'"), 1);
+
+        assert!(refund_exit_on(&conn, &vehicle.id, Some(cop(7000)), "cobro doble", false, cashier()).is_err());
+        let full = refund_exit_on(&conn, &vehicle.id, None, "tarifa equivocada", true, cashier()).unwrap();
+        assert_eq!(full.refunded, 6000.0);
+        assert_eq!(full.vehicle.space_id.as_deref(), Some("sp1"));
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM debts WHERE id = 'd1' AND status = 'settled'"), 1);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM spaces WHERE id = 'sp1' AND status = 'occupied'"), 1);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM vehicles WHERE id = 'old' OR status = 'active'"), 2);
+    }
+
+    #[test]
+    fn exit_price_uses_the_tariff_snapshot_taken_at_entry() {
+        let conn = migrated_conn();
+        let vehicle = entry(&conn, "ABC123", "T1", None).unwrap();
+        let exit_time = (chrono::DateTime::parse_from_rfc3339(&vehicle.entry_time).unwrap()
+            + chrono::Duration::hours(3))
+        .to_rfc3339();
+        let before = compute_exit_quote(&conn, &vehicle, &exit_time, None, false, None).unwrap();
+
+        conn.execute("UPDATE custom_tariffs SET amount = amount * 10, additional_hour_price = COALESCE(additional_hour_price, 0) * 10", [])
+            .unwrap();
+        let after = compute_exit_quote(&conn, &vehicle, &exit_time, None, false, None).unwrap();
+        assert!(before.total > 0.0);
+        assert_eq!(after.total, before.total);
+
+        conn.execute("UPDATE vehicles SET tariff_snapshot = '{broken' WHERE id = ?1", params![&vehicle.id])
+            .unwrap();
+        assert!(compute_exit_quote(&conn, &vehicle, &exit_time, None, false, None).is_err());
+    }
+
+    #[test]
+    fn vouchers_discount_an_amount_or_reprice_the_stay_without_free_minutes() {
+        let conn = migrated_conn();
+        let vehicle = entry(&conn, "ABC123", "T1", None).unwrap();
+        let entry_ts = chrono::DateTime::parse_from_rfc3339(&vehicle.entry_time).unwrap();
+        let exit_time = (entry_ts + chrono::Duration::hours(3)).to_rfc3339();
+        add_voucher(&conn, "MITAD", "percentage", 50.0);
+        add_voucher(&conn, "MIL", "fixed", 1000.0);
+        add_voucher(&conn, "DOSHORAS", "free_minutes", 120.0);
+        let quote = |exit: &str, code: Option<&str>| {
+            let voucher = find_voucher_for_exit(&conn, code, exit).unwrap();
+            compute_exit_quote(&conn, &vehicle, exit, None, false, voucher.as_ref()).unwrap()
+        };
+
+        let full = quote(&exit_time, None);
+        let half = quote(&exit_time, Some("mitad"));
+        assert_eq!(half.voucher_discount, full.parking_cost / 2.0);
+        assert_eq!(half.parking_cost, full.parking_cost - half.voucher_discount);
+        assert_eq!(quote(&exit_time, Some("MIL")).voucher_discount, 1000.0);
+
+        // Minutos gratis: se cobra como si hubiera salido 2 horas antes.
+        let shorter = quote(&(entry_ts + chrono::Duration::hours(1)).to_rfc3339(), None);
+        let free = quote(&exit_time, Some("DOSHORAS"));
+        assert_eq!(free.parking_cost, shorter.parking_cost);
+        assert_eq!(free.voucher_discount, full.parking_cost - shorter.parking_cost);
+        assert!(free.voucher_discount > 0.0);
+    }
+
+    #[test]
+    fn a_voucher_that_discounts_nothing_is_not_redeemed() {
+        let conn = migrated_conn();
+        entry(&conn, "ABC123", "T1", None).unwrap();
+        add_voucher(&conn, "GRATIS", "free_minutes", 30.0);
+        let request = ExitRequest { voucher_code: Some("GRATIS".to_string()), ..cash_exit() };
+        process_exit_on(&conn, "T1", request, cashier()).unwrap();
+        assert_eq!(count(&conn, "SELECT uses_count FROM vouchers WHERE code = 'GRATIS'"), 0);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM voucher_redemptions"), 0);
+    }
+
+    #[test]
+    fn removal_is_rolled_back_when_trace_insert_fails() {
+        let conn = migrated_conn();
+        add_space(&conn);
+        let vehicle = entry(&conn, "ABC123", "T1", Some("A1")).unwrap();
+        inject_failure(&conn, "INSERT", "transactions");
+        assert!(remove_from_parking_on(&conn, vehicle, None).is_err());
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM vehicles WHERE status = 'active'"), 1);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM spaces WHERE status = 'occupied'"), 1);
+    }
+
+    #[test]
+    fn plate_conflict_resolution_is_rolled_back_when_delete_fails() {
+        let conn = migrated_conn();
+        conn.execute_batch(
+            "INSERT INTO vehicles (id, ticket_code, plate, plate_upper, vehicle_type, entry_time, status, tariff_kind) VALUES
+                ('keep', 'T1', 'ABC123', 'ABC123', 'car', '2025-01-01T00:00:00+00:00', 'completed', 'regular'),
+                ('dup', 'T2', 'ABC123', 'ABC123', 'car', '2025-01-02T00:00:00+00:00', 'completed', 'regular');
+             INSERT INTO transactions (id, vehicle_id, amount, method, created_at) VALUES ('tx1', 'dup', 5000, 'cash', '2025-01-02T01:00:00+00:00');",
+        )
+        .unwrap();
+        inject_failure(&conn, "DELETE", "vehicles");
+        assert!(resolve_plate_conflict_on(&conn, "abc123", "keep").is_err());
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM transactions WHERE vehicle_id = 'dup'"), 1);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM vehicles"), 2);
+    }
+
+    #[test]
+    fn split_tenders_give_change_from_cash_only() {
+        let (applied, change, debt) =
+            apply_tenders(&[tender("card", 6000), tender("cash", 5000)], cop(10000)).unwrap();
+        assert_eq!(change, cop(1000));
+        assert_eq!(debt, Money::ZERO);
+        assert_eq!(applied.len(), 2);
+        assert_eq!(applied[1].amount, cop(4000));
+
+        assert!(apply_tenders(&[tender("card", 12000), tender("cash", 1000)], cop(10000)).is_err());
+    }
+
+    #[test]
+    fn short_tenders_leave_debt() {
+        let (applied, change, debt) =
+            apply_tenders(&[tender("transfer", 3000), tender("cash", 2000)], cop(8000)).unwrap();
+        assert_eq!(change, Money::ZERO);
+        assert_eq!(debt, cop(3000));
+        assert_eq!(applied.iter().map(|t| t.amount).sum::<Money>(), cop(5000));
+        assert!(apply_tenders(&[tender("debt", 1000)], cop(1000)).is_err());
     }
-    Ok(())
 }
@@ -0,0 +1,403 @@
+//! Merchant validation vouchers: codes handed out by nearby shops that discount the parking
+//! cost at exit (percentage, fixed amount or free minutes), with optional expiry and usage
+//! limit. Each redemption is recorded with its discount so merchants can be billed (see
+//! the voucher report types in `reportes.rs`).
+
+use chrono::NaiveDate;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::id_gen;
+use crate::money::{Currency, Money};
+use crate::permissions;
+use crate::state::AppState;
+
+const VALID_KINDS: &[&str] = &["percentage", "fixed", "free_minutes"];
+const VOUCHER_COLS: &str =
+    "id, code, merchant, kind, value, expires_on, max_uses, uses_count, active, created_at";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Voucher {
+    pub id: String,
+    pub code: String,
+    pub merchant: String,
+    /// percentage | fixed | free_minutes
+    pub kind: String,
+    /// Percent (0–100), amount (major units), or minutes depending on `kind`.
+    pub value: f64,
+    /// Last valid day (YYYY-MM-DD, local). None = no expiry.
+    pub expires_on: Option<String>,
+    /// None = unlimited.
+    pub max_uses: Option<i64>,
+    pub uses_count: i64,
+    pub active: bool,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VoucherRedemption {
+    pub id: String,
+    pub voucher_id: String,
+    pub voucher_code: String,
+    pub vehicle_id: String,
+    pub ticket_code: String,
+    pub merchant: String,
+    pub discount_amount: f64,
+    pub redeemed_at: String,
+    pub operator_user_id: Option<String>,
+}
+
+impl Voucher {
+    /// Discount for percentage / fixed vouchers over `cost` (never above it).
+    /// Free-minutes vouchers are priced by the exit flow, which re-prices the shorter stay.
+    pub fn amount_discount(&self, currency: &Currency, cost: Money) -> Money {
+        let discount = match self.kind.as_str() {
+            "percentage" => cost.times(self.value.min(100.0) / 100.0),
+            "fixed" => currency.money(self.value),
+            _ => Money::ZERO,
+        };
+        discount.clamp(Money::ZERO, cost.max(Money::ZERO))
+    }
+
+    pub fn free_minutes(&self) -> f64 {
+        if self.kind == "free_minutes" {
+            self.value.max(0.0)
+        } else {
+            0.0
+        }
+    }
+}
+
+fn normalize_code(code: &str) -> String {
+    code.trim().to_uppercase()
+}
+
+fn row_to_voucher(row: &rusqlite::Row) -> rusqlite::Result<Voucher> {
+    Ok(Voucher {
+        id: row.get("id")?,
+        code: row.get("code")?,
+        merchant: row.get("merchant")?,
+        kind: row.get("kind")?,
+        value: row.get("value")?,
+        expires_on: row.get("expires_on")?,
+        max_uses: row.get("max_uses")?,
+        uses_count: row.get("uses_count")?,
+        active: row.get::<_, i64>("active")? != 0,
+        created_at: row.get("created_at")?,
+    })
+}
+
+fn get_voucher(conn: &Connection, id: &str) -> Result<Voucher, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM vouchers WHERE id = ?1", VOUCHER_COLS),
+        params![id],
+        row_to_voucher,
+    )
+    .map_err(|_| "Voucher not found".to_string())
+}
+
+/// Looks up a voucher by code and checks it can be used on `local_date`
+/// (active, not expired, uses left). Used by the exit quote and by process_exit.
+pub fn find_redeemable(conn: &Connection, code: &str, local_date: NaiveDate) -> Result<Voucher, String> {
+    let voucher = conn
+        .query_row(
+            &format!("SELECT {} FROM vouchers WHERE code = ?1", VOUCHER_COLS),
+            params![normalize_code(code)],
+            row_to_voucher,
+        )
+        .map_err(|_| "Vale no encontrado".to_string())?;
+    if !voucher.active {
+        return Err("El vale está desactivado".to_string());
+    }
+    if let Some(expires_on) = voucher.expires_on.as_deref() {
+        let last_day = NaiveDate::parse_from_str(expires_on, "%Y-%m-%d").map_err(|e| e.to_string())?;
+        if local_date > last_day {
+            return Err(format!("El vale venció el {}", expires_on));
+        }
+    }
+    if let Some(max) = voucher.max_uses {
+        if voucher.uses_count >= max {
+            return Err("El vale ya alcanzó su límite de usos".to_string());
+        }
+    }
+    Ok(voucher)
+}
+
+/// Records a redemption and consumes one use. Fails if the voucher ran out of uses meanwhile.
+pub fn record_redemption(
+    conn: &Connection,
+    voucher: &Voucher,
+    vehicle_id: &str,
+    discount_amount: Money,
+    redeemed_at: &str,
+    operator_user_id: Option<&str>,
+) -> Result<(), String> {
+    let n = conn
+        .execute(
+            "UPDATE vouchers SET uses_count = uses_count + 1 WHERE id = ?1 AND (max_uses IS NULL OR uses_count < max_uses)",
+            params![voucher.id],
+        )
+        .map_err(|e| e.to_string())?;
+    if n == 0 {
+        return Err("El vale ya alcanzó su límite de usos".to_string());
+    }
+    conn.execute(
+        "INSERT INTO voucher_redemptions (id, voucher_id, vehicle_id, merchant, discount_amount, redeemed_at, operator_user_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            id_gen::generate_id(id_gen::PREFIX_VOUCHER_REDEMPTION),
+            voucher.id,
+            vehicle_id,
+            voucher.merchant,
+            discount_amount,
+            redeemed_at,
+            operator_user_id
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn validate_voucher_fields(kind: &str, value: f64, expires_on: Option<&str>, max_uses: Option<i64>) -> Result<(), String> {
+    if !VALID_KINDS.contains(&kind) {
+        return Err(format!("Invalid voucher kind: {}", kind));
+    }
+    if value < 0.0 || (kind == "percentage" && value > 100.0) {
+        return Err("Voucher value must be >= 0 (percentage up to 100)".to_string());
+    }
+    if let Some(d) = expires_on {
+        NaiveDate::parse_from_str(d, "%Y-%m-%d").map_err(|_| "Expiry must be YYYY-MM-DD".to_string())?;
+    }
+    if max_uses.map(|m| m <= 0).unwrap_or(false) {
+        return Err("Max uses must be > 0".to_string());
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn vouchers_list(
+    state: State<AppState>,
+    merchant: Option<String>,
+    include_inactive: Option<bool>,
+) -> Result<Vec<Voucher>, String> {
+    state.check_permission(permissions::VOUCHERS_READ)?;
+    let conn = state.db.get().map_err(|e| e.to_string())?;
+    let merchant = merchant.as_deref().map(str::trim).filter(|s| !s.is_empty());
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM vouchers WHERE (?1 IS NULL OR merchant = ?1) AND (?2 = 1 OR active = 1) ORDER BY merchant, code",
+            VOUCHER_COLS
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![merchant, include_inactive.unwrap_or(false) as i64], row_to_voucher)
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CreateVoucherArgs {
+    code: String,
+    merchant: String,
+    kind: String,
+    value: f64,
+    expires_on: Option<String>,
+    max_uses: Option<i64>,
+}
+
+#[tauri::command]
+pub fn vouchers_create(state: State<AppState>, args: CreateVoucherArgs) -> Result<Voucher, String> {
+    state.check_permission(permissions::VOUCHERS_MODIFY)?;
+    let code = normalize_code(&args.code);
+    if code.is_empty() {
+        return Err("Voucher code is required".to_string());
+    }
+    let merchant = args.merchant.trim();
+    if merchant.is_empty() {
+        return Err("Merchant is required".to_string());
+    }
+    let kind = args.kind.trim().to_lowercase();
+    let expires_on = args.expires_on.as_deref().map(str::trim).filter(|s| !s.is_empty());
+    validate_voucher_fields(&kind, args.value, expires_on, args.max_uses)?;
+    let conn = state.db.get().map_err(|e| e.to_string())?;
+    let exists: i64 = conn
+        .query_row("SELECT COUNT(*) FROM vouchers WHERE code = ?1", params![code], |r| r.get(0))
+        .map_err(|e| e.to_string())?;
+    if exists > 0 {
+        return Err("A voucher with this code already exists".to_string());
+    }
+    let id = id_gen::generate_id(id_gen::PREFIX_VOUCHER);
+    conn.execute(
+        &format!(
+            "INSERT INTO vouchers ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, 0, 1, ?8)",
+            VOUCHER_COLS
+        ),
+        params![
+            id,
+            code,
+            merchant,
+            kind,
+            args.value,
+            expires_on,
+            args.max_uses,
+            chrono::Utc::now().to_rfc3339()
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    get_voucher(&conn, &id)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct UpdateVoucherArgs {
+    id: String,
+    merchant: Option<String>,
+    kind: Option<String>,
+    value: Option<f64>,
+    /// Empty string clears the expiry.
+    expires_on: Option<String>,
+    /// 0 clears the limit.
+    max_uses: Option<i64>,
+    active: Option<bool>,
+}
+
+#[tauri::command]
+pub fn vouchers_update(state: State<AppState>, args: UpdateVoucherArgs) -> Result<Voucher, String> {
+    state.check_permission(permissions::VOUCHERS_MODIFY)?;
+    let conn = state.db.get().map_err(|e| e.to_string())?;
+    let existing = get_voucher(&conn, args.id.trim())?;
+    let merchant = args
+        .merchant
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+        .unwrap_or(existing.merchant);
+    let kind = args.kind.map(|k| k.trim().to_lowercase()).unwrap_or(existing.kind);
+    let value = args.value.unwrap_or(existing.value);
+    let expires_on = match args.expires_on {
+        Some(d) if d.trim().is_empty() => None,
+        Some(d) => Some(d.trim().to_string()),
+        None => existing.expires_on,
+    };
+    let max_uses = match args.max_uses {
+        Some(0) => None,
+        Some(m) => Some(m),
+        None => existing.max_uses,
+    };
+    validate_voucher_fields(&kind, value, expires_on.as_deref(), max_uses)?;
+    conn.execute(
+        "UPDATE vouchers SET merchant = ?1, kind = ?2, value = ?3, expires_on = ?4, max_uses = ?5, active = ?6 WHERE id = ?7",
+        params![
+            merchant,
+            kind,
+            value,
+            expires_on,
+            max_uses,
+            args.active.unwrap_or(existing.active) as i64,
+            existing.id
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    get_voucher(&conn, &existing.id)
+}
+
+/// Deletes a voucher that was never redeemed; redeemed vouchers must be deactivated instead
+/// so their redemptions can still be billed.
+#[tauri::command]
+pub fn vouchers_delete(state: State<AppState>, id: String) -> Result<(), String> {
+    state.check_permission(permissions::VOUCHERS_MODIFY)?;
+    let conn = state.db.get().map_err(|e| e.to_string())?;
+    let redemptions: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM voucher_redemptions WHERE voucher_id = ?1",
+            params![id.trim()],
+            |r| r.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    if redemptions > 0 {
+        return Err("Voucher has redemptions; deactivate it instead".to_string());
+    }
+    let n = conn
+        .execute("DELETE FROM vouchers WHERE id = ?1", params![id.trim()])
+        .map_err(|e| e.to_string())?;
+    if n == 0 {
+        return Err("Voucher not found".to_string());
+    }
+    Ok(())
+}
+
+/// Redemptions of a voucher (by id), newest first.
+#[tauri::command]
+pub fn vouchers_list_redemptions(state: State<AppState>, voucher_id: String) -> Result<Vec<VoucherRedemption>, String> {
+    state.check_permission(permissions::VOUCHERS_READ)?;
+    let conn = state.db.get().map_err(|e| e.to_string())?;
+    let currency = crate::money::currency(&conn)?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT r.id, r.voucher_id, vo.code, r.vehicle_id, v.ticket_code, r.merchant, r.discount_amount, r.redeemed_at, r.operator_user_id
+             FROM voucher_redemptions r
+             JOIN vouchers vo ON vo.id = r.voucher_id
+             JOIN vehicles v ON v.id = r.vehicle_id
+             WHERE r.voucher_id = ?1
+             ORDER BY r.redeemed_at DESC",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![voucher_id.trim()], |row| {
+            Ok(VoucherRedemption {
+                id: row.get(0)?,
+                voucher_id: row.get(1)?,
+                voucher_code: row.get(2)?,
+                vehicle_id: row.get(3)?,
+                ticket_code: row.get(4)?,
+                merchant: row.get(5)?,
+                discount_amount: currency.major(row.get(6)?),
+                redeemed_at: row.get(7)?,
+                operator_user_id: row.get(8)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn voucher(kind: &str, value: f64) -> Voucher {
+        Voucher {
+            id: "V1".to_string(),
+            code: "V1".to_string(),
+            merchant: "Tienda".to_string(),
+            kind: kind.to_string(),
+            value,
+            expires_on: None,
+            max_uses: None,
+            uses_count: 0,
+            active: true,
+            created_at: "x".to_string(),
+        }
+    }
+
+    #[test]
+    fn amount_discount_never_exceeds_the_cost() {
+        let cop = Currency::new("COP", None).unwrap();
+        let discount = |kind: &str, value: f64, cost: i64| {
+            voucher(kind, value).amount_discount(&cop, Money::from_minor(cost)).minor()
+        };
+        assert_eq!(discount("percentage", 25.0, 8000), 2000);
+        assert_eq!(discount("percentage", 150.0, 8000), 8000);
+        assert_eq!(discount("fixed", 3000.0, 8000), 3000);
+        assert_eq!(discount("fixed", 3000.0, 2000), 2000);
+        assert_eq!(discount("free_minutes", 60.0, 8000), 0);
+
+        let usd = Currency::new("USD", None).unwrap();
+        assert_eq!(voucher("fixed", 2.5).amount_discount(&usd, Money::from_minor(1000)), Money::from_minor(250));
+        assert_eq!(voucher("free_minutes", 60.0).free_minutes(), 60.0);
+        assert_eq!(voucher("fixed", 60.0).free_minutes(), 0.0);
+    }
+}
@@ -7,10 +7,19 @@ pub const ID_LENGTH: usize = 25;
 pub const PREFIX_VEHICLE: &str = "VH";
 pub const PREFIX_TRANSACTION: &str = "TX";
 pub const PREFIX_SHIFT_CLOSURE: &str = "SC";
+pub const PREFIX_SHIFT: &str = "SH";
 pub const PREFIX_CUSTOM_TARIFF: &str = "CT";
 pub const PREFIX_USER: &str = "US";
 pub const PREFIX_BARCODE: &str = "BC";
 pub const PREFIX_CONTRACT: &str = "CO";
+pub const PREFIX_SPACE: &str = "SP";
+pub const PREFIX_TARIFF_RULE: &str = "TR";
+pub const PREFIX_VOUCHER: &str = "VO";
+pub const PREFIX_VOUCHER_REDEMPTION: &str = "VR";
+pub const PREFIX_DEBT: &str = "DT";
+pub const PREFIX_CASH_MOVEMENT: &str = "CM";
+pub const PREFIX_INVOICE: &str = "IN";
+pub const PREFIX_CONTRACT_HISTORY: &str = "CH";
 #[allow(dead_code)]
 pub const PREFIX_ROLE: &str = "RL";
 #[allow(dead_code)]
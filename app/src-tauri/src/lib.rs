@@ -4,6 +4,7 @@ mod db;
 mod dev;
 mod domains;
 mod id_gen;
+mod money;
 mod permissions;
 mod scanner;
 mod state;
@@ -46,19 +47,67 @@ use domains::{
         barcodes_get_by_id,
         barcodes_list,
     },
-    caja::{caja_close_shift, caja_get_debug, caja_get_treasury, caja_list_shift_closures},
+    caja::{
+        caja_approve_shift_closure,
+        caja_close_shift,
+        caja_get_current_shift,
+        caja_get_debug,
+        caja_get_discrepancy_tolerance,
+        caja_get_treasury,
+        caja_list_cash_movements,
+        caja_list_shift_closures,
+        caja_open_shift,
+        caja_pay_debt,
+        caja_record_cash_movement,
+        caja_set_discrepancy_tolerance,
+    },
+    debts::{
+        debts_get_entry_limits,
+        debts_list,
+        debts_set_entry_limits,
+        debts_settle,
+        debts_write_off,
+    },
+    capacity::{
+        capacity_delete_zone,
+        capacity_get_status,
+        capacity_set_enforcement,
+        capacity_set_zone,
+    },
     contracts::{
+        contracts_cancel_plan_change,
         contracts_create,
         contracts_delete,
         contracts_get_any_by_plate,
         contracts_get_by_plate,
+        contracts_get_history,
         contracts_list,
         contracts_list_payments,
+        contracts_quote_payment,
         contracts_record_payment,
+        contracts_run_status_job,
         contracts_suggest_monthly,
         contracts_update,
+        spawn_contract_status_scheduler,
     },
     custom_tariffs::{custom_tariffs_create, custom_tariffs_list, custom_tariffs_update, custom_tariffs_delete},
+    tariff_rules::{
+        holidays_delete,
+        holidays_list,
+        holidays_set,
+        tariff_rules_create,
+        tariff_rules_delete,
+        tariff_rules_list,
+        tariff_rules_update,
+    },
+    invoices::{
+        invoices_config_get,
+        invoices_config_set,
+        invoices_get,
+        invoices_list,
+        invoices_render_html,
+        invoices_render_pdf,
+    },
     metricas::{
         metricas_get_arrivals_by_hour,
         metricas_get_daily,
@@ -67,6 +116,13 @@ use domains::{
         metricas_get_peak_hours,
     },
     reportes::{reportes_fetch, reportes_get_column_definitions, reportes_write_csv},
+    settings::{
+        settings_get_business_timezone,
+        settings_get_currency,
+        settings_set_business_timezone,
+        settings_set_currency,
+    },
+    spaces::{spaces_create, spaces_delete, spaces_get_lot_map, spaces_move_vehicle, spaces_update},
     roles::{
         auth_get_session,
         auth_login,
@@ -97,11 +153,20 @@ use domains::{
         vehiculos_list_vehicles,
         vehiculos_list_vehicles_by_date,
         vehiculos_process_exit,
+        vehiculos_quote_exit,
+        vehiculos_refund_exit,
         vehiculos_register_entry,
         vehiculos_remove_from_parking,
         vehiculos_resolve_plate_conflict,
         vehiculos_search_vehicles_by_plate_prefix,
     },
+    vouchers::{
+        vouchers_create,
+        vouchers_delete,
+        vouchers_list,
+        vouchers_list_redemptions,
+        vouchers_update,
+    },
 };
 
 fn load_dotenv() {
@@ -197,6 +262,7 @@ pub fn run() {
             log::info!("Database pool initialized and app state managed");
             scanner::spawn_barcode_listener(app.handle().clone());
             spawn_backup_scheduler(app.handle().clone());
+            spawn_contract_status_scheduler(app.handle().clone());
             log::info!("Setup completed successfully");
             Ok(())
         })
@@ -218,20 +284,44 @@ pub fn run() {
             vehiculos_list_vehicles_by_date,
             vehiculos_register_entry,
             vehiculos_process_exit,
+            vehiculos_refund_exit,
+            vehiculos_quote_exit,
             vehiculos_remove_from_parking,
+            vouchers_list,
+            vouchers_create,
+            vouchers_update,
+            vouchers_delete,
+            vouchers_list_redemptions,
             custom_tariffs_list,
             custom_tariffs_create,
             custom_tariffs_update,
             custom_tariffs_delete,
+            tariff_rules_list,
+            tariff_rules_create,
+            tariff_rules_update,
+            tariff_rules_delete,
+            holidays_list,
+            holidays_set,
+            holidays_delete,
             contracts_list,
             contracts_create,
             contracts_update,
             contracts_delete,
+            contracts_cancel_plan_change,
             contracts_get_by_plate,
             contracts_get_any_by_plate,
             contracts_suggest_monthly,
             contracts_record_payment,
+            contracts_quote_payment,
             contracts_list_payments,
+            contracts_get_history,
+            contracts_run_status_job,
+            invoices_list,
+            invoices_get,
+            invoices_render_html,
+            invoices_render_pdf,
+            invoices_config_get,
+            invoices_config_set,
             barcodes_list,
             barcodes_get_by_id,
             barcodes_get_by_code,
@@ -251,8 +341,30 @@ pub fn run() {
             vehiculos_resolve_plate_conflict,
             caja_get_treasury,
             caja_get_debug,
+            caja_open_shift,
+            caja_get_current_shift,
             caja_close_shift,
             caja_list_shift_closures,
+            caja_pay_debt,
+            caja_record_cash_movement,
+            caja_list_cash_movements,
+            caja_approve_shift_closure,
+            caja_get_discrepancy_tolerance,
+            caja_set_discrepancy_tolerance,
+            debts_list,
+            debts_get_entry_limits,
+            debts_set_entry_limits,
+            debts_settle,
+            debts_write_off,
+            capacity_get_status,
+            capacity_set_zone,
+            capacity_delete_zone,
+            capacity_set_enforcement,
+            spaces_get_lot_map,
+            spaces_create,
+            spaces_update,
+            spaces_delete,
+            spaces_move_vehicle,
             metricas_get_daily,
             metricas_get_peak_hours,
             metricas_get_arrivals_by_hour,
@@ -261,6 +373,10 @@ pub fn run() {
             reportes_get_column_definitions,
             reportes_fetch,
             reportes_write_csv,
+            settings_get_business_timezone,
+            settings_set_business_timezone,
+            settings_get_currency,
+            settings_set_currency,
             auth_login,
             auth_logout,
             auth_get_session,
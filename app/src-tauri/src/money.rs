@@ -0,0 +1,218 @@
+//! Money in integer minor units (centavos, cents) and the configured business currency.
+//! Amounts are stored as INTEGER minor units in SQLite (migration 54) and carried as `Money`
+//! through tariffs, debts, caja and contracts; API structs keep major units (`Currency::major`)
+//! and inputs are converted once with `Currency::money`. Charges are rounded to the configured
+//! increment (e.g. 50 COP) with `Currency::round_charge`.
+
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::ops::{Add, AddAssign, Neg, Sub, SubAssign};
+
+use crate::db::get_config_value;
+
+pub const CONFIG_KEY_CURRENCY: &str = "currency_code";
+/// Rounding increment for charges, in major units ("50" = nearest 50 COP).
+pub const CONFIG_KEY_ROUNDING_INCREMENT: &str = "currency_rounding_increment";
+
+pub const DEFAULT_CURRENCY: &str = "COP";
+
+/// ISO 4217 code and number of decimals (minor unit exponent).
+pub const SUPPORTED_CURRENCIES: &[(&str, u32)] = &[
+    ("COP", 0),
+    ("CLP", 0),
+    ("USD", 2),
+    ("EUR", 2),
+    ("MXN", 2),
+    ("PEN", 2),
+    ("ARS", 2),
+    ("BRL", 2),
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Money(i64);
+
+impl Money {
+    pub const ZERO: Money = Money(0);
+
+    pub fn from_minor(minor: i64) -> Self {
+        Money(minor)
+    }
+
+    pub fn minor(self) -> i64 {
+        self.0
+    }
+
+    pub fn abs(self) -> Money {
+        Money(self.0.abs())
+    }
+
+    /// Scaled amount (blocks, days / period, percentages), rounded half away from zero to a minor unit.
+    pub fn times(self, factor: f64) -> Money {
+        Money((self.0 as f64 * factor).round() as i64)
+    }
+}
+
+impl Add for Money {
+    type Output = Money;
+    fn add(self, rhs: Money) -> Money {
+        Money(self.0 + rhs.0)
+    }
+}
+
+impl Sub for Money {
+    type Output = Money;
+    fn sub(self, rhs: Money) -> Money {
+        Money(self.0 - rhs.0)
+    }
+}
+
+impl AddAssign for Money {
+    fn add_assign(&mut self, rhs: Money) {
+        self.0 += rhs.0;
+    }
+}
+
+impl SubAssign for Money {
+    fn sub_assign(&mut self, rhs: Money) {
+        self.0 -= rhs.0;
+    }
+}
+
+impl Neg for Money {
+    type Output = Money;
+    fn neg(self) -> Money {
+        Money(-self.0)
+    }
+}
+
+impl ToSql for Money {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.0))
+    }
+}
+
+/// INTEGER minor units; REAL is accepted (rounded) for aggregates such as `SUM` over mixed rows.
+impl FromSql for Money {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        match value {
+            ValueRef::Integer(i) => Ok(Money(i)),
+            ValueRef::Real(f) => Ok(Money(f.round() as i64)),
+            _ => Err(FromSqlError::InvalidType),
+        }
+    }
+}
+
+impl std::iter::Sum for Money {
+    fn sum<I: Iterator<Item = Money>>(iter: I) -> Money {
+        iter.fold(Money::ZERO, |acc, m| acc + m)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Currency {
+    pub code: String,
+    pub decimals: u32,
+    /// Charges are rounded to the nearest multiple of this (minor units, >= 1).
+    pub rounding_increment: Money,
+}
+
+impl Currency {
+    /// Validates the code and converts the increment (major units; None/0 = no rounding beyond minor units).
+    pub fn new(code: &str, rounding_increment: Option<f64>) -> Result<Self, String> {
+        let code = code.trim().to_uppercase();
+        let decimals = SUPPORTED_CURRENCIES
+            .iter()
+            .find(|(c, _)| *c == code)
+            .map(|(_, d)| *d)
+            .ok_or_else(|| format!("Unsupported currency: {}", code))?;
+        let increment = rounding_increment.unwrap_or(0.0);
+        if !increment.is_finite() || increment < 0.0 {
+            return Err("Rounding increment must be >= 0".to_string());
+        }
+        let mut currency = Currency { code, decimals, rounding_increment: Money(1) };
+        let minor = currency.money(increment).minor();
+        if (increment * currency.scale() - minor as f64).abs() > 1e-6 {
+            return Err(format!(
+                "Rounding increment must be a multiple of the minor unit of {}",
+                currency.code
+            ));
+        }
+        currency.rounding_increment = Money(minor.max(1));
+        Ok(currency)
+    }
+
+    fn scale(&self) -> f64 {
+        10_f64.powi(self.decimals as i32)
+    }
+
+    /// Major units -> minor units (half away from zero).
+    pub fn money(&self, major: f64) -> Money {
+        Money((major * self.scale()).round() as i64)
+    }
+
+    pub fn major(&self, money: Money) -> f64 {
+        money.minor() as f64 / self.scale()
+    }
+
+    /// Nearest multiple of the rounding increment; halves round up.
+    pub fn round_charge(&self, money: Money) -> Money {
+        let inc = self.rounding_increment.minor().max(1);
+        let rem = money.minor().rem_euclid(inc);
+        let down = money.minor() - rem;
+        if rem * 2 >= inc {
+            Money(down + inc)
+        } else {
+            Money(down)
+        }
+    }
+
+    /// Minor units per major unit (100 for USD, 1 for COP).
+    pub fn minor_per_major(&self) -> i64 {
+        10_i64.pow(self.decimals)
+    }
+
+    pub fn rounding_increment_major(&self) -> f64 {
+        self.major(self.rounding_increment)
+    }
+}
+
+/// Business currency from drive_config; COP without extra rounding when unset.
+pub fn currency(conn: &Connection) -> Result<Currency, String> {
+    let code = get_config_value(conn, CONFIG_KEY_CURRENCY)?
+        .filter(|s| !s.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_CURRENCY.to_string());
+    let increment = get_config_value(conn, CONFIG_KEY_ROUNDING_INCREMENT)?
+        .and_then(|s| s.trim().parse::<f64>().ok());
+    Currency::new(&code, increment).or_else(|_| Currency::new(DEFAULT_CURRENCY, None))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn charges_round_to_the_configured_increment() {
+        let cop = Currency::new("cop", Some(50.0)).unwrap();
+        let charge = |c: &Currency, major: f64| c.major(c.round_charge(c.money(major)));
+        assert_eq!(charge(&cop, 4333.33), 4350.0);
+        assert_eq!(charge(&cop, 4324.4), 4300.0);
+        assert_eq!(charge(&cop, 4325.0), 4350.0);
+        assert_eq!(cop.money(4333.33), Money::from_minor(4333));
+
+        let usd = Currency::new("USD", Some(0.05)).unwrap();
+        assert_eq!(usd.money(12.346).minor(), 1235);
+        assert_eq!(charge(&usd, 12.32), 12.3);
+        assert_eq!(charge(&usd, 12.33), 12.35);
+        assert_eq!(usd.minor_per_major(), 100);
+        assert_eq!(Money::from_minor(1000).times(1.5), Money::from_minor(1500));
+        assert_eq!(Money::from_minor(333).times(0.5), Money::from_minor(167));
+
+        let total: Money = [0.1, 0.2].iter().map(|a| usd.money(*a)).sum();
+        assert_eq!(usd.major(total), 0.3);
+
+        assert!(Currency::new("XXX", None).is_err());
+        assert!(Currency::new("COP", Some(0.5)).is_err());
+    }
+}
@@ -19,6 +19,7 @@ pub const CAJA_TRANSACTIONS_READ: &str = "caja:transactions:read";
 pub const CAJA_TRANSACTIONS_CREATE: &str = "caja:transactions:create";
 pub const CAJA_TRANSACTIONS_MODIFY: &str = "caja:transactions:modify";
 pub const CAJA_SHIFT_CLOSE: &str = "caja:shift:close";
+pub const CAJA_SHIFT_OPEN: &str = "caja:shift:open";
 
 pub const METRICAS_DASHBOARD_READ: &str = "metricas:dashboard:read";
 pub const METRICAS_REPORTS_EXPORT: &str = "metricas:reports:export";
@@ -48,6 +49,25 @@ pub const CONTRACTS_DELETE: &str = "contracts:delete";
 pub const CONTRACTS_PAYMENT_CREATE: &str = "contracts:payment:create";
 
 pub const CAJA_DEBT_PAYMENT_CREATE: &str = "caja:debt_payment:create";
+pub const CAJA_PRICE_OVERRIDE: &str = "caja:price_override:create";
+pub const CAJA_DEBTS_WRITE_OFF: &str = "caja:debts:write_off";
+pub const CAJA_DEBT_LIMITS_MODIFY: &str = "caja:debt_limits:modify";
+pub const CAJA_CASH_MOVEMENTS_CREATE: &str = "caja:cash_movements:create";
+pub const CAJA_CASH_WITHDRAW: &str = "caja:cash_movements:withdraw";
+pub const CAJA_SHIFT_APPROVE: &str = "caja:shift:approve";
+pub const CAJA_REFUNDS_CREATE: &str = "caja:refunds:create";
+
+pub const CAPACITY_READ: &str = "capacity:status:read";
+pub const CAPACITY_MODIFY: &str = "capacity:config:modify";
+
+pub const SETTINGS_READ: &str = "settings:business:read";
+pub const SETTINGS_MODIFY: &str = "settings:business:modify";
+
+pub const SPACES_READ: &str = "spaces:map:read";
+pub const SPACES_MODIFY: &str = "spaces:config:modify";
+
+pub const VOUCHERS_READ: &str = "vouchers:codes:read";
+pub const VOUCHERS_MODIFY: &str = "vouchers:codes:modify";
 
 pub const DEV_CONSOLE_ACCESS: &str = "dev:console:access";
 
@@ -64,6 +84,7 @@ pub fn all_permissions() -> Vec<&'static str> {
         CAJA_TRANSACTIONS_CREATE,
         CAJA_TRANSACTIONS_MODIFY,
         CAJA_SHIFT_CLOSE,
+        CAJA_SHIFT_OPEN,
         METRICAS_DASHBOARD_READ,
         METRICAS_REPORTS_EXPORT,
         ROLES_USERS_READ,
@@ -87,6 +108,21 @@ pub fn all_permissions() -> Vec<&'static str> {
         CONTRACTS_DELETE,
         CONTRACTS_PAYMENT_CREATE,
         CAJA_DEBT_PAYMENT_CREATE,
+        CAJA_PRICE_OVERRIDE,
+        CAJA_DEBTS_WRITE_OFF,
+        CAJA_DEBT_LIMITS_MODIFY,
+        CAJA_CASH_MOVEMENTS_CREATE,
+        CAJA_CASH_WITHDRAW,
+        CAJA_SHIFT_APPROVE,
+        CAJA_REFUNDS_CREATE,
+        CAPACITY_READ,
+        CAPACITY_MODIFY,
+        SETTINGS_READ,
+        SETTINGS_MODIFY,
+        SPACES_READ,
+        SPACES_MODIFY,
+        VOUCHERS_READ,
+        VOUCHERS_MODIFY,
         DEV_CONSOLE_ACCESS,
     ]
 }
@@ -109,8 +145,13 @@ pub fn operator_permissions() -> Vec<&'static str> {
         CAJA_TRANSACTIONS_CREATE,
         CAJA_TRANSACTIONS_MODIFY,
         CAJA_SHIFT_CLOSE,
+        CAJA_SHIFT_OPEN,
+        CAJA_CASH_MOVEMENTS_CREATE,
         METRICAS_DASHBOARD_READ,
         CONTRACTS_READ,
+        CAPACITY_READ,
+        SETTINGS_READ,
+        SPACES_READ,
     ]
 }
 
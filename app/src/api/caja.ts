@@ -1,17 +1,71 @@
 import { invokeTauri } from "@/lib/tauriInvoke";
-import type { TreasuryData, ShiftClosure } from "@/types/parking";
+import type {
+  CashMovement,
+  CashMovementKind,
+  DebtPaymentResult,
+  DenominationCount,
+  Shift,
+  TreasuryData,
+  ShiftClosure,
+} from "@/types/parking";
 
 export function getTreasury(date?: string): Promise<TreasuryData> {
   return invokeTauri<TreasuryData>("caja_get_treasury", date ? { date } : {});
 }
 
+export function openShift(args: { booth: string; openingFloat?: number }): Promise<Shift> {
+  return invokeTauri<Shift>("caja_open_shift", args);
+}
+
+export function getCurrentShift(): Promise<Shift | null> {
+  return invokeTauri<Shift | null>("caja_get_current_shift");
+}
+
 export function listShiftClosures(args: { limit: number }): Promise<ShiftClosure[]> {
   return invokeTauri<ShiftClosure[]>("caja_list_shift_closures", args);
 }
 
 export function closeShift(args: {
   arqueoCash?: number;
+  denominations?: DenominationCount[];
   notes?: string;
 }): Promise<ShiftClosure> {
   return invokeTauri<ShiftClosure>("caja_close_shift", args);
 }
+
+export function approveShiftClosure(args: {
+  closureId: string;
+  note?: string;
+}): Promise<ShiftClosure> {
+  return invokeTauri<ShiftClosure>("caja_approve_shift_closure", args);
+}
+
+/** `shiftId` is required for drop/withdrawal (the open shift the cash leaves); other kinds use the caller's shift. */
+export function recordCashMovement(args: {
+  kind: CashMovementKind;
+  amount: number;
+  reason?: string;
+  shiftId?: string;
+}): Promise<CashMovement> {
+  return invokeTauri<CashMovement>("caja_record_cash_movement", args);
+}
+
+export function listCashMovements(args: { shiftId?: string } = {}): Promise<CashMovement[]> {
+  return invokeTauri<CashMovement[]>("caja_list_cash_movements", args);
+}
+
+export function getDiscrepancyTolerance(): Promise<number | null> {
+  return invokeTauri<number | null>("caja_get_discrepancy_tolerance");
+}
+
+export function setDiscrepancyTolerance(args: { tolerance: number | null }): Promise<number | null> {
+  return invokeTauri<number | null>("caja_set_discrepancy_tolerance", args);
+}
+
+export function payDebt(args: {
+  plate: string;
+  amount: number;
+  method: "cash" | "card" | "transfer";
+}): Promise<DebtPaymentResult> {
+  return invokeTauri<DebtPaymentResult>("caja_pay_debt", args);
+}
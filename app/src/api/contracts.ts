@@ -1,5 +1,14 @@
 import { invokeTauri } from "@/lib/tauriInvoke";
-import type { Contract, ContractPayment, TariffKind } from "@/types/parking";
+import type {
+  Contract,
+  ContractHistoryEntry,
+  ContractInvoice,
+  ContractPayment,
+  ContractPaymentQuote,
+  ContractStatusJobSummary,
+  InvoiceConfig,
+  TariffKind,
+} from "@/types/parking";
 
 export function listContracts(args: {
   status?: string | null;
@@ -12,6 +21,9 @@ export function createContract(args: {
   clientName: string;
   clientPhone?: string | null;
   plate: string;
+  /** Additional plates sharing the contract. */
+  plates?: string[];
+  maxSimultaneousVehicles?: number | null;
   vehicleType: string;
   tariffKind?: TariffKind | null;
   monthlyAmount?: number | null;
@@ -22,6 +34,9 @@ export function createContract(args: {
   extraInterval?: number | null;
   billingPeriodDays?: number;
   endDate?: string | null;
+  autoRenew?: boolean;
+  /** Ends the first (prorated) period early, e.g. on the 1st of next month. The first period is due from dateFrom until the first payment. */
+  firstPeriodEnd?: string | null;
 }): Promise<Contract> {
   return invokeTauri<Contract>("contracts_create", { args });
 }
@@ -39,12 +54,22 @@ export function updateContract(args: {
   extraInterval?: number | null;
   billingPeriodDays?: number;
   endDate?: string | null;
+  autoRenew?: boolean;
+  /** Full set of plates. */
+  plates?: string[] | null;
+  maxSimultaneousVehicles?: number | null;
 }): Promise<Contract> {
   return invokeTauri<Contract>("contracts_update", { args });
 }
 
-export function deleteContract(id: string): Promise<void> {
-  return invokeTauri("contracts_delete", { id });
+/** Cancels the contract; payments and invoices are kept. */
+export function deleteContract(id: string, reason?: string | null): Promise<void> {
+  return invokeTauri("contracts_delete", { id, reason: reason ?? null });
+}
+
+/** Drops the plan change scheduled for the next billing cut. */
+export function cancelContractPlanChange(id: string): Promise<void> {
+  return invokeTauri("contracts_cancel_plan_change", { id });
 }
 
 export function getContractByPlate(plate: string): Promise<Contract | null> {
@@ -67,10 +92,49 @@ export function recordContractPayment(args: {
   return invokeTauri<Contract>("contracts_record_payment", { args });
 }
 
+/** Next period due (prorated when shorter) net of the credit balance; null once the end date is reached. */
+export function quoteContractPayment(contractId: string): Promise<ContractPaymentQuote | null> {
+  return invokeTauri<ContractPaymentQuote | null>("contracts_quote_payment", { contractId });
+}
+
 export function listContractPayments(contractId: string): Promise<ContractPayment[]> {
   return invokeTauri<ContractPayment[]>("contracts_list_payments", { contractId });
 }
 
+export function getContractHistory(contractId: string): Promise<ContractHistoryEntry[]> {
+  return invokeTauri<ContractHistoryEntry[]>("contracts_get_history", { contractId });
+}
+
+export function runContractStatusJob(): Promise<ContractStatusJobSummary> {
+  return invokeTauri<ContractStatusJobSummary>("contracts_run_status_job");
+}
+
 export function getContractAnyByPlate(plate: string): Promise<import("@/types/parking").Contract | null> {
   return invokeTauri<import("@/types/parking").Contract | null>("contracts_get_any_by_plate", { plate });
 }
+
+export function listContractInvoices(contractId?: string | null): Promise<ContractInvoice[]> {
+  return invokeTauri<ContractInvoice[]>("invoices_list", { contractId: contractId ?? null });
+}
+
+export function getContractInvoice(id: string): Promise<ContractInvoice> {
+  return invokeTauri<ContractInvoice>("invoices_get", { id });
+}
+
+/** Printable HTML of the invoice (preview and print from the webview). */
+export function renderContractInvoiceHtml(id: string): Promise<string> {
+  return invokeTauri<string>("invoices_render_html", { id });
+}
+
+/** Invoice as a PDF file, base64-encoded. */
+export function renderContractInvoicePdf(id: string): Promise<string> {
+  return invokeTauri<string>("invoices_render_pdf", { id });
+}
+
+export function getInvoiceConfig(): Promise<InvoiceConfig> {
+  return invokeTauri<InvoiceConfig>("invoices_config_get", {});
+}
+
+export function setInvoiceConfig(payload: InvoiceConfig): Promise<InvoiceConfig> {
+  return invokeTauri<InvoiceConfig>("invoices_config_set", { payload });
+}
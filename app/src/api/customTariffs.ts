@@ -18,6 +18,9 @@ export function createCustomTariff(args: {
   additionalHourPrice?: number | null;
   additionalDurationHours?: number | null;
   additionalDurationMinutes?: number | null;
+  graceMinutes?: number | null;
+  dailyMaxAmount?: number | null;
+  lostTicketAmount?: number | null;
 }): Promise<CustomTariff> {
   return invokeTauri<CustomTariff>("custom_tariffs_create", { args });
 }
@@ -36,6 +39,10 @@ export function updateCustomTariff(args: {
   additionalHourPrice?: number | null;
   additionalDurationHours?: number | null;
   additionalDurationMinutes?: number | null;
+  graceMinutes?: number | null;
+  /** Omit to keep the current cap; null clears it. Must be > 0 when set. */
+  dailyMaxAmount?: number | null;
+  lostTicketAmount?: number | null;
 }): Promise<CustomTariff> {
   return invokeTauri<CustomTariff>("custom_tariffs_update", { args });
 }
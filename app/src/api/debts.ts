@@ -0,0 +1,31 @@
+import { invokeTauri } from "@/lib/tauriInvoke";
+import type { Debt, DebtEntryLimits, DebtStatus, ListDebtsResult } from "@/types/parking";
+
+export function listDebts(args?: {
+  plate?: string | null;
+  status?: DebtStatus | null;
+  limit?: number | null;
+  offset?: number | null;
+}): Promise<ListDebtsResult> {
+  return invokeTauri<ListDebtsResult>("debts_list", args ?? {});
+}
+
+export function settleDebt(args: {
+  debtId: string;
+  amount: number;
+  method: "cash" | "card" | "transfer";
+}): Promise<Debt> {
+  return invokeTauri<Debt>("debts_settle", args);
+}
+
+export function writeOffDebt(args: { debtId: string; reason: string }): Promise<Debt> {
+  return invokeTauri<Debt>("debts_write_off", args);
+}
+
+export function getDebtEntryLimits(): Promise<DebtEntryLimits> {
+  return invokeTauri<DebtEntryLimits>("debts_get_entry_limits");
+}
+
+export function setDebtEntryLimits(args: DebtEntryLimits): Promise<DebtEntryLimits> {
+  return invokeTauri<DebtEntryLimits>("debts_set_entry_limits", args);
+}
@@ -4,8 +4,11 @@ export * from "./backup";
 export * from "./barcodes";
 export * from "./caja";
 export * from "./customTariffs";
+export * from "./debts";
 export * from "./dev";
 export * from "./metricas";
 export * from "./reportes";
 export * from "./roles";
+export * from "./settings";
 export * from "./vehiculos";
+export * from "./vouchers";
@@ -0,0 +1,26 @@
+import { invokeTauri } from "@/lib/tauriInvoke";
+
+/** Business timezone (IANA name, e.g. "America/Bogota"); day boundaries use it. */
+export function getBusinessTimezone(): Promise<string> {
+  return invokeTauri<string>("settings_get_business_timezone", {});
+}
+
+export function setBusinessTimezone(timezone: string): Promise<string> {
+  return invokeTauri<string>("settings_set_business_timezone", { timezone });
+}
+
+export interface CurrencySettings {
+  code: string;
+  decimals: number;
+  /** Charges are rounded to the nearest multiple of this amount. */
+  roundingIncrement: number;
+}
+
+export function getCurrency(): Promise<CurrencySettings> {
+  return invokeTauri<CurrencySettings>("settings_get_currency", {});
+}
+
+/** The code can only change before any transaction, contract or debt exists. */
+export function setCurrency(code: string, roundingIncrement?: number | null): Promise<CurrencySettings> {
+  return invokeTauri<CurrencySettings>("settings_set_currency", { code, roundingIncrement });
+}
@@ -21,6 +21,7 @@ export interface VehicleBackend {
   tariffKind: TariffKind;
   tariffId?: string | null;
   operatorUserId?: string | null;
+  spaceId?: string | null;
 }
 
 export interface ListVehiclesResponse {
@@ -52,6 +53,7 @@ export function listVehiclesByDate(args: {
 export interface RegisterEntryResult {
   vehicle: VehicleBackend;
   contractArrearsWarning?: string | null;
+  capacityWarning?: string | null;
 }
 
 export function registerEntry(args: {
@@ -60,17 +62,86 @@ export function registerEntry(args: {
   observations?: string | null;
   ticketCode?: string | null;
   tariffKind?: TariffKind | null;
+  spaceCode?: string | null;
 }): Promise<RegisterEntryResult> {
   return invokeTauri<RegisterEntryResult>("vehiculos_register_entry", args);
 }
 
+export interface ExitTender {
+  method: "cash" | "card" | "transfer" | "contract";
+  amount: number;
+}
+
+export interface ProcessExitResult extends VehicleBackend {
+  changeDue: number;
+  tenders: ExitTender[];
+  outstandingDebt: number;
+}
+
 export function processExit(args: {
   ticketCode: string;
   partialPayment?: number | null;
   paymentMethod?: string | null;
   customParkingCost?: number | null;
-}): Promise<VehicleBackend> {
-  return invokeTauri<VehicleBackend>("vehiculos_process_exit", args);
+  lostTicket?: boolean | null;
+  voucherCode?: string | null;
+  overrideReason?: string | null;
+  /** Split payment; when set, paymentMethod and partialPayment are ignored. */
+  tenders?: ExitTender[] | null;
+}): Promise<ProcessExitResult> {
+  return invokeTauri<ProcessExitResult>("vehiculos_process_exit", args);
+}
+
+export interface RefundResult {
+  vehicle: VehicleBackend;
+  refunded: number;
+  refunds: ExitTender[];
+  reopened: boolean;
+  capacityWarning?: string | null;
+}
+
+/** Refunds all (default) or part of a completed exit; `reopen` requires a full refund. */
+export function refundExit(args: {
+  vehicleId: string;
+  reason: string;
+  amount?: number | null;
+  reopen?: boolean | null;
+}): Promise<RefundResult> {
+  return invokeTauri<RefundResult>("vehiculos_refund_exit", args);
+}
+
+export interface ExitQuote {
+  vehicleId: string;
+  ticketCode: string;
+  plate: string;
+  entryTime: string;
+  exitTime: string;
+  durationMinutes: number;
+  pricingMode: "tariff" | "contract" | "custom";
+  baseAmount: number;
+  additionalBlocks: number;
+  additionalAmount: number;
+  rulesAmount: number;
+  contractIncludedMinutes: number | null;
+  graceDiscount: number;
+  dailyMaxDiscount: number;
+  voucherCode: string | null;
+  voucherDiscount: number;
+  lostTicketAmount: number;
+  /** Rounding to the currency increment, already included in parkingCost. */
+  roundingAdjustment: number;
+  parkingCost: number;
+  carriedDebt: number;
+  total: number;
+}
+
+export function quoteExit(args: {
+  ticketCode: string;
+  atTime?: string | null;
+  lostTicket?: boolean | null;
+  voucherCode?: string | null;
+}): Promise<ExitQuote> {
+  return invokeTauri<ExitQuote>("vehiculos_quote_exit", args);
 }
 
 export function removeVehicleFromParking(args: {
@@ -0,0 +1,40 @@
+import { invokeTauri } from "@/lib/tauriInvoke";
+import type { Voucher, VoucherKind, VoucherRedemption } from "@/types/parking";
+
+export function listVouchers(args?: {
+  merchant?: string | null;
+  includeInactive?: boolean | null;
+}): Promise<Voucher[]> {
+  return invokeTauri<Voucher[]>("vouchers_list", args ?? {});
+}
+
+export function createVoucher(args: {
+  code: string;
+  merchant: string;
+  kind: VoucherKind;
+  value: number;
+  expiresOn?: string | null;
+  maxUses?: number | null;
+}): Promise<Voucher> {
+  return invokeTauri<Voucher>("vouchers_create", { args });
+}
+
+export function updateVoucher(args: {
+  id: string;
+  merchant?: string | null;
+  kind?: VoucherKind | null;
+  value?: number | null;
+  expiresOn?: string | null;
+  maxUses?: number | null;
+  active?: boolean | null;
+}): Promise<Voucher> {
+  return invokeTauri<Voucher>("vouchers_update", { args });
+}
+
+export function deleteVoucher(id: string): Promise<void> {
+  return invokeTauri("vouchers_delete", { id });
+}
+
+export function listVoucherRedemptions(voucherId: string): Promise<VoucherRedemption[]> {
+  return invokeTauri<VoucherRedemption[]>("vouchers_list_redemptions", { voucherId });
+}
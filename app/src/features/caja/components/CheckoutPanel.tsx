@@ -33,7 +33,8 @@ interface CheckoutPanelProps {
   onCheckout: (
     partialPayment?: number,
     paymentMethod?: PaymentMethod,
-    customParkingCost?: number
+    customParkingCost?: number,
+    overrideReason?: string
   ) => void;
   onCancel: () => void;
 }
@@ -48,6 +49,7 @@ export const CheckoutPanel = ({
   const { getTariffForKind } = useDefaultRates();
   const { hasPermission } = useMyPermissions();
   const canDebtPayment = hasPermission("caja:debt_payment:create");
+  const canOverridePrice = hasPermission("caja:price_override:create");
 
   const vehicleTariffKind = (vehicle.tariffKind || "regular") as TariffKind;
   const defaultTariff = getTariffForKind(vehicle.vehicleType, vehicleTariffKind);
@@ -70,6 +72,7 @@ export const CheckoutPanel = ({
   const [elapsed, setElapsed] = useState({ hours: 0, minutes: 0, seconds: 0 });
   const [paymentMethod, setPaymentMethod] = useState<PaymentMethod>("cash");
   const [partialAmount, setPartialAmount] = useState<string>("");
+  const [overrideReason, setOverrideReason] = useState<string>("");
   const [showPartial, setShowPartial] = useState(false);
   const [selectedCustomTariff, setSelectedCustomTariff] = useState<SelectedTariffForCheckout | null>(null);
   const [customTariffSelectorOpen, setCustomTariffSelectorOpen] = useState(false);
@@ -150,14 +153,19 @@ export const CheckoutPanel = ({
 
   const timeParkedLabel = `${elapsed.hours}h ${elapsed.minutes}min`;
 
+  // The backend prices the default rate itself; only a custom rate is sent as a price override.
+  const isOverride = selectedCustomTariff != null;
+  const overrideReasonMissing = isOverride && overrideReason.trim() === "";
+
   const handleCheckout = () => {
-    const costToSend = parkingCost;
+    const costToSend = isOverride ? parkingCost : undefined;
+    const reason = isOverride ? overrideReason.trim() : undefined;
     if (paymentMethod === "debt") {
-      onCheckout(0, "debt", costToSend);
+      onCheckout(0, "debt", costToSend, reason);
     } else if (showPartial && partialAmount) {
-      onCheckout(parseFloat(partialAmount), paymentMethod, costToSend);
+      onCheckout(parseFloat(partialAmount), paymentMethod, costToSend, reason);
     } else {
-      onCheckout(undefined, paymentMethod, costToSend);
+      onCheckout(undefined, paymentMethod, costToSend, reason);
     }
   };
 
@@ -238,16 +246,29 @@ export const CheckoutPanel = ({
           >
             {t("checkout.useDefaultRate")}
           </Button>
-          <Button
-            type="button"
-            variant={selectedCustomTariff !== null ? "default" : "outline"}
-            size="sm"
-            onClick={() => setCustomTariffSelectorOpen(true)}
-          >
-            <Tag className="h-3.5 w-3.5 mr-1" />
-            {t("checkout.useCustomRate")}
-          </Button>
+          {canOverridePrice && (
+            <Button
+              type="button"
+              variant={selectedCustomTariff !== null ? "default" : "outline"}
+              size="sm"
+              onClick={() => setCustomTariffSelectorOpen(true)}
+            >
+              <Tag className="h-3.5 w-3.5 mr-1" />
+              {t("checkout.useCustomRate")}
+            </Button>
+          )}
         </div>
+        {isOverride && (
+          <div className="space-y-1">
+            <Label htmlFor="override-reason">{t("checkout.overrideReason")}</Label>
+            <Input
+              id="override-reason"
+              value={overrideReason}
+              onChange={(e) => setOverrideReason(e.target.value)}
+              placeholder={t("checkout.overrideReasonPlaceholder")}
+            />
+          </div>
+        )}
       </div>
 
       <CustomTariffSelector
@@ -401,7 +422,13 @@ export const CheckoutPanel = ({
         <Button type="button" variant="outline" onClick={onCancel} className="flex-1">
           {t("common.cancel")}
         </Button>
-        <Button type="button" variant="coco" onClick={handleCheckout} className="flex-1">
+        <Button
+          type="button"
+          variant="coco"
+          onClick={handleCheckout}
+          disabled={overrideReasonMissing}
+          className="flex-1"
+        >
           {paymentMethod === "debt" ? t("checkout.registerDebt") : t("checkout.charge")}
         </Button>
       </div>
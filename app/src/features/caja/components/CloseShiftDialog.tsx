@@ -76,6 +76,12 @@ export const CloseShiftDialog = ({
             <div className="mt-1 text-xs text-muted-foreground">
               {treasury.totalTransactions} {t("till.totalTransactions").toLowerCase()}
             </div>
+            {treasury.contractPaymentsTotal > 0 && (
+              <div className="mt-2 flex justify-between text-muted-foreground">
+                <span>{t("till.contractPayments")}</span>
+                <span className="font-medium">${treasury.contractPaymentsTotal.toFixed(2)}</span>
+              </div>
+            )}
             {treasury.debtTotal > 0 && (
               <div className="mt-2 flex justify-between text-destructive text-sm font-medium border-t border-border pt-2">
                 <span>{t("till.debtTotal")}</span>
@@ -159,7 +159,7 @@ export const ContractsPage = () => {
   });
 
   const deleteMutation = useMutation({
-    mutationFn: deleteContract,
+    mutationFn: (id: string) => deleteContract(id),
     onSuccess: () => {
       queryClient.invalidateQueries({ queryKey: ["contracts"] });
       setDeleteId(null);
@@ -195,14 +195,16 @@ export const VehiculosPage = () => {
   const handleCheckout = (
     partialPayment?: number,
     paymentMethod?: "cash" | "card" | "transfer" | "contract" | "debt",
-    customParkingCost?: number
+    customParkingCost?: number,
+    overrideReason?: string
   ) => {
     if (selectedVehicle) {
       processExit(
         selectedVehicle.ticketCode,
         partialPayment,
         paymentMethod,
-        customParkingCost
+        customParkingCost,
+        overrideReason
       );
       setSelectedVehicle(null);
       setViewMode("search");
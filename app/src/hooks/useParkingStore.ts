@@ -44,15 +44,24 @@ function normalizeTreasuryData(raw: unknown): TreasuryData | null {
   if (hasCamel) {
     const pb = r.paymentBreakdown as Record<string, unknown> | undefined;
     return {
+      shiftId: typeof r.shiftId === 'string' ? r.shiftId : null,
       expectedCash: Number(r.expectedCash) || 0,
-      actualCash: Number(r.actualCash) || 0,
-      discrepancy: Number(r.discrepancy) || 0,
+      actualCash: typeof r.actualCash === 'number' ? r.actualCash : null,
+      discrepancy: typeof r.discrepancy === 'number' ? r.discrepancy : null,
       totalTransactions: Number(r.totalTransactions) || 0,
       paymentBreakdown: {
         cash: Number(pb?.cash) || 0,
         card: Number(pb?.card) || 0,
         transfer: Number(pb?.transfer) || 0,
       },
+      debtPaymentsTotal: Number(r.debtPaymentsTotal) || 0,
+      contractPaymentsTotal: Number(r.contractPaymentsTotal) || 0,
+      refundsTotal: Number(r.refundsTotal) || 0,
+      openingFloat: Number(r.openingFloat) || 0,
+      cashDrops: Number(r.cashDrops) || 0,
+      cashExpenses: Number(r.cashExpenses) || 0,
+      cashDeposits: Number(r.cashDeposits) || 0,
+      expectedDrawerCash: Number(r.expectedDrawerCash) || 0,
       debtTotal: Number(r.debtTotal) || 0,
       vehiclesAttended: Number(r.vehiclesAttended) || 0,
       vehiclesWithDebt: Number(r.vehiclesWithDebt) || 0,
@@ -62,15 +71,24 @@ function normalizeTreasuryData(raw: unknown): TreasuryData | null {
   if (hasSnake) {
     const pb = r.payment_breakdown as Record<string, unknown> | undefined;
     return {
+      shiftId: typeof r.shift_id === 'string' ? r.shift_id : null,
       expectedCash: Number(r.expected_cash) || 0,
-      actualCash: Number(r.actual_cash) || 0,
-      discrepancy: Number(r.discrepancy) || 0,
+      actualCash: typeof r.actual_cash === 'number' ? r.actual_cash : null,
+      discrepancy: typeof r.discrepancy === 'number' ? r.discrepancy : null,
       totalTransactions: Number(r.total_transactions) || 0,
       paymentBreakdown: {
         cash: Number(pb?.cash) || 0,
         card: Number(pb?.card) || 0,
         transfer: Number(pb?.transfer) || 0,
       },
+      debtPaymentsTotal: Number(r.debt_payments_total) || 0,
+      contractPaymentsTotal: Number(r.contract_payments_total) || 0,
+      refundsTotal: Number(r.refunds_total) || 0,
+      openingFloat: Number(r.opening_float) || 0,
+      cashDrops: Number(r.cash_drops) || 0,
+      cashExpenses: Number(r.cash_expenses) || 0,
+      cashDeposits: Number(r.cash_deposits) || 0,
+      expectedDrawerCash: Number(r.expected_drawer_cash) || 0,
       debtTotal: Number(r.debt_total) || 0,
       vehiclesAttended: Number(r.vehicles_attended) || 0,
       vehiclesWithDebt: Number(r.vehicles_with_debt) || 0,
@@ -156,11 +174,20 @@ export const useParkingStore = () => {
     revenueByVehicleType: [],
   };
   const ZERO_TREASURY: TreasuryData = {
+    shiftId: null,
     expectedCash: 0,
-    actualCash: 0,
-    discrepancy: 0,
+    actualCash: null,
+    discrepancy: null,
     totalTransactions: 0,
     paymentBreakdown: { cash: 0, card: 0, transfer: 0 },
+    debtPaymentsTotal: 0,
+    contractPaymentsTotal: 0,
+    refundsTotal: 0,
+    openingFloat: 0,
+    cashDrops: 0,
+    cashExpenses: 0,
+    cashDeposits: 0,
+    expectedDrawerCash: 0,
     debtTotal: 0,
     vehiclesAttended: 0,
     vehiclesWithDebt: 0,
@@ -248,12 +275,14 @@ export const useParkingStore = () => {
       payPartial?: number;
       paymentMethod?: string;
       customParkingCost?: number;
+      overrideReason?: string;
     }) => {
       const v = await apiVehiculos.processExit({
         ticketCode: args.ticketCode,
         partialPayment: args.payPartial ?? null,
         paymentMethod: args.paymentMethod ?? null,
         customParkingCost: args.customParkingCost ?? null,
+        overrideReason: args.overrideReason ?? null,
       });
       return vehicleFromBackend(v);
     },
@@ -375,7 +404,8 @@ export const useParkingStore = () => {
       ticketCode: string,
       payPartial?: number,
       paymentMethod?: string,
-      customParkingCost?: number
+      customParkingCost?: number,
+      overrideReason?: string
     ) => {
       if (tauri) {
         processExitMutation.mutate({
@@ -383,6 +413,7 @@ export const useParkingStore = () => {
           payPartial,
           paymentMethod,
           customParkingCost,
+          overrideReason,
         });
         return null;
       }
@@ -515,15 +546,24 @@ export const useParkingStore = () => {
       : (treasuryData ?? (() => {
           const revenue = completedVehicles.reduce((sum, v) => sum + (v.totalAmount ?? 0), 0);
           return {
+            shiftId: null,
             expectedCash: revenue,
-            actualCash: revenue,
-            discrepancy: 0,
+            actualCash: null,
+            discrepancy: null,
             totalTransactions: completedVehicles.length,
             paymentBreakdown: {
               cash: revenue,
               card: 0,
               transfer: 0,
             },
+            debtPaymentsTotal: 0,
+            contractPaymentsTotal: 0,
+            refundsTotal: 0,
+            openingFloat: 0,
+            cashDrops: 0,
+            cashExpenses: 0,
+            cashDeposits: 0,
+            expectedDrawerCash: revenue,
             debtTotal: 0,
             vehiclesAttended: completedVehicles.length,
             vehiclesWithDebt: 0,
@@ -263,6 +263,7 @@ export const en: Translations = {
     notesPlaceholder: "Optional",
     submitCloseShift: "Close shift",
     noClosuresYet: "No closures recorded yet.",
+    contractPayments: "Contract payments",
     debtTotal: "Total debt",
     vehiclesAttended: "Vehicles attended",
     vehiclesWithDebt: "With debt",
@@ -555,6 +556,8 @@ export const en: Translations = {
     bicycle: "Bicycle",
     useDefaultRate: "Default rate",
     useCustomRate: "Use custom rate",
+    overrideReason: "Price override reason",
+    overrideReasonPlaceholder: "Required when using a custom rate",
     regularTariff: "Regular",
     employeeTariff: "Employee",
     studentTariff: "Student",
@@ -675,9 +678,9 @@ export const en: Translations = {
     day: "day",
     created: "Contract created successfully.",
     updated: "Contract updated.",
-    deleted: "Contract deleted.",
-    confirmDelete: "Delete this contract?",
-    confirmDeleteDescription: "This action cannot be undone. The contract will be permanently deleted.",
+    deleted: "Contract cancelled.",
+    confirmDelete: "Cancel this contract?",
+    confirmDeleteDescription: "The contract will be cancelled. Its payments and invoices are kept in the history.",
     statusArrears: "In arrears",
     chargeSectionTitle: "Contract payments",
     chargeSectionDesc: "Contracts due or overdue that require payment.",
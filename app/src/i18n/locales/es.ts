@@ -263,6 +263,7 @@ export const es: Translations = {
     notesPlaceholder: "Opcional",
     submitCloseShift: "Cerrar turno",
     noClosuresYet: "Aún no hay cierres registrados.",
+    contractPayments: "Pagos de contratos",
     debtTotal: "Total en deuda",
     vehiclesAttended: "Vehículos atendidos",
     vehiclesWithDebt: "Con deuda",
@@ -555,6 +556,8 @@ export const es: Translations = {
     bicycle: "Bicicleta",
     useDefaultRate: "Tarifa por defecto",
     useCustomRate: "Usar tarifa personalizada",
+    overrideReason: "Motivo del cambio de precio",
+    overrideReasonPlaceholder: "Obligatorio al usar una tarifa personalizada",
     regularTariff: "Regular",
     employeeTariff: "Empleado",
     studentTariff: "Estudiante",
@@ -675,9 +678,9 @@ export const es: Translations = {
     day: "día",
     created: "Contrato creado exitosamente.",
     updated: "Contrato actualizado.",
-    deleted: "Contrato eliminado.",
-    confirmDelete: "¿Eliminar este contrato?",
-    confirmDeleteDescription: "Esta acción no se puede deshacer. El contrato será eliminado permanentemente.",
+    deleted: "Contrato cancelado.",
+    confirmDelete: "¿Cancelar este contrato?",
+    confirmDeleteDescription: "El contrato quedará cancelado. Sus pagos y facturas se conservan en el historial.",
     statusArrears: "En mora",
     chargeSectionTitle: "Cobros de contratos",
     chargeSectionDesc: "Contratos próximos a vencer o en mora que requieren pago.",
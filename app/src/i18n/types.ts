@@ -264,6 +264,7 @@ export interface Translations {
     notesPlaceholder: string;
     submitCloseShift: string;
     noClosuresYet: string;
+    contractPayments: string;
     debtTotal: string;
     vehiclesAttended: string;
     vehiclesWithDebt: string;
@@ -526,6 +527,8 @@ export interface Translations {
     bicycle: string;
     useDefaultRate: string;
     useCustomRate: string;
+    overrideReason: string;
+    overrideReasonPlaceholder: string;
     regularTariff: string;
     employeeTariff: string;
     studentTariff: string;
@@ -39,7 +39,9 @@ export interface DailyMetrics {
   totalVehicles: number;
   activeVehicles: number;
   occupancyRate: number;
+  /** Parking revenue plus contract payments. */
   totalRevenue: number;
+  contractRevenue?: number;
   averageTicket: number;
   averageStayMinutes: number;
   turnoverRate: number;
@@ -50,6 +52,8 @@ export interface PeakHourSlot {
   hourLabel: string;
   hourStart: number;
   count: number;
+  /** Average occupancy as % of lot capacity (occupancy by hour only). */
+  occupancyRate?: number | null;
 }
 
 export interface HeatmapDayVehicleRow {
@@ -67,15 +71,34 @@ export type DayPeriodFilter =
 
 /** Tesorería: una sola fuente, tabla transactions. */
 export interface TreasuryData {
+  /** Operator's open shift; null for historical dates or without an open shift. */
+  shiftId: string | null;
   expectedCash: number;
-  actualCash: number;
-  discrepancy: number;
+  /** Cash counted in the scope's shift closures; null until a closure records a count. */
+  actualCash: number | null;
+  /** Counted minus expected drawer cash from those closures; null without a count. */
+  discrepancy: number | null;
   totalTransactions: number;
   paymentBreakdown: {
     cash: number;
     card: number;
     transfer: number;
   };
+  /** Debt payments collected without an exit (already included in paymentBreakdown). */
+  debtPaymentsTotal: number;
+  /** Contract payments (already included in paymentBreakdown). */
+  contractPaymentsTotal: number;
+  /** Refunds (already deducted from paymentBreakdown). */
+  refundsTotal: number;
+  openingFloat: number;
+  /** Cash dropped to the safe or withdrawn from the drawer. */
+  cashDrops: number;
+  /** Petty cash expenses paid from the drawer. */
+  cashExpenses: number;
+  /** Non-payment cash added to the drawer (change, top-ups). */
+  cashDeposits: number;
+  /** Opening float + deposits + cash collected - drops/withdrawals - expenses. */
+  expectedDrawerCash: number;
   debtTotal: number;
   vehiclesAttended: number;
   vehiclesWithDebt: number;
@@ -95,10 +118,56 @@ export interface ShiftClosure {
   totalTransactions: number;
   notes: string | null;
   operatorUserId?: string | null;
+  debtPaymentsTotal: number;
+  contractPaymentsTotal: number;
+  refundsTotal: number;
   debtTotal: number;
   vehiclesAttended: number;
   vehiclesWithDebt: number;
   vehiclesRemoved: number;
+  openingFloat: number;
+  cashDrops: number;
+  cashExpenses: number;
+  cashDeposits: number;
+  expectedDrawerCash: number;
+  denominations: DenominationCount[];
+  status: ShiftClosureStatus;
+  approvedBy: string | null;
+  approvedAt: string | null;
+  approvalNote: string | null;
+  /** Null for closures recorded before explicit shifts. */
+  shiftId: string | null;
+  booth: string | null;
+  openedAt: string | null;
+}
+
+export interface Shift {
+  id: string;
+  operatorUserId: string | null;
+  booth: string;
+  openedAt: string;
+  closedAt: string | null;
+  status: 'open' | 'closed';
+  closureId: string | null;
+}
+
+export type ShiftClosureStatus = 'closed' | 'pending_approval' | 'approved';
+
+export interface DenominationCount {
+  denomination: number;
+  quantity: number;
+}
+
+export type CashMovementKind = 'opening_float' | 'deposit' | 'drop' | 'withdrawal' | 'expense';
+
+export interface CashMovement {
+  id: string;
+  kind: CashMovementKind;
+  amount: number;
+  reason: string | null;
+  createdAt: string;
+  operatorUserId: string | null;
+  shiftId: string | null;
 }
 
 export type UserRole = 'operator' | 'admin' | 'developer';
@@ -156,6 +225,9 @@ export interface CustomTariff {
   additionalHourPrice?: number | null;
   additionalDurationHours?: number | null;
   additionalDurationMinutes?: number | null;
+  graceMinutes?: number | null;
+  dailyMaxAmount?: number | null;
+  lostTicketAmount?: number | null;
   createdAt: string;
 }
 
@@ -184,6 +256,53 @@ export interface Contract {
   lastPaymentDate?: string | null;
   extraChargePerInterval?: number | null;
   endDate?: string | null;
+  autoRenew: boolean;
+  /** Every plate covered by the contract; `plate` is the main one. */
+  plates: string[];
+  /** How many of `plates` may be parked at the same time under the contract. */
+  maxSimultaneousVehicles: number;
+  /** Amount paid but not yet applied to a period (partial payments). */
+  creditBalance: number;
+  /** End of the next period when shorter than a full one (prorated first period). */
+  nextPeriodEnd?: string | null;
+  /** Plan change scheduled for the period starting on pendingEffectiveDate. */
+  pendingMonthlyAmount?: number | null;
+  pendingBillingPeriodDays?: number | null;
+  pendingEffectiveDate?: string | null;
+}
+
+/** Next period of a contract and what is left to pay for it after the credit balance. */
+export interface ContractPaymentQuote {
+  contractId: string;
+  periodFrom: string;
+  periodTo: string;
+  periodAmount: number;
+  prorated: boolean;
+  creditBalance: number;
+  amountDue: number;
+}
+
+/** Contract history entry (status change or edit); changedBy is null when made by the status job. */
+export interface ContractHistoryEntry {
+  id: string;
+  contractId: string;
+  fromStatus?: string | null;
+  toStatus: string;
+  event: string;
+  reason?: string | null;
+  changedAt: string;
+  changedBy?: string | null;
+  /** Contract version after this change (1 = as created). */
+  version: number;
+  /** Edited fields as { field: { old, new } }; null for status changes. */
+  changes?: Record<string, { old: unknown; new: unknown }> | null;
+}
+
+export interface ContractStatusJobSummary {
+  arrears: number;
+  expired: number;
+  renewed: number;
+  reactivated: number;
 }
 
 export interface ContractPayment {
@@ -193,13 +312,55 @@ export interface ContractPayment {
   method: string;
   periodFrom: string;
   periodTo: string;
+  /** "credit" = deposit to the credit balance, covers no period. */
+  kind: ContractPaymentKind;
   createdAt: string;
   operatorUserId?: string | null;
 }
 
+export type ContractPaymentKind = "period" | "credit";
+
+/** Invoice issued for one contract payment (billing period); amounts are tax-inclusive. */
+export interface ContractInvoice {
+  id: string;
+  number: number;
+  invoiceNumber: string;
+  contractId: string;
+  paymentId: string;
+  issuerName: string;
+  issuerTaxId: string;
+  issuerAddress: string;
+  issuerPhone: string;
+  clientName: string;
+  clientPhone: string | null;
+  plate: string;
+  periodFrom: string;
+  periodTo: string;
+  kind: ContractPaymentKind;
+  method: string;
+  currency: string;
+  subtotal: number;
+  taxRate: number;
+  taxAmount: number;
+  total: number;
+  issuedAt: string;
+  operatorUserId: string | null;
+}
+
+export interface InvoiceConfig {
+  issuerName: string;
+  issuerTaxId: string;
+  issuerAddress: string;
+  issuerPhone: string;
+  prefix: string;
+  /** Tax percentage included in contract amounts (e.g. 19). */
+  taxRate: number;
+}
+
 export interface RegisterEntryResult {
   vehicle: Vehicle;
   contractArrearsWarning?: string | null;
+  capacityWarning?: string | null;
 }
 
 export type PaymentMethod = 'cash' | 'card' | 'transfer' | 'contract' | 'debt';
@@ -219,18 +380,34 @@ export interface Barcode {
   createdAt: string;
 }
 
-/** Debtor row: plate, total debt, sessions count, oldest exit time. */
+/** Open debt split by age in days. */
+export interface DebtAging {
+  days0To30: number;
+  days31To60: number;
+  days61To90: number;
+  daysOver90: number;
+}
+
+/** Debtor row: plate, total debt, sessions count, oldest exit time, aging buckets. */
 export interface DebtorEntry {
   plate: string;
   totalDebt: number;
   sessionsWithDebt: number;
   oldestExitTime: string | null;
+  aging: DebtAging;
 }
 
-/** Paginated list of debtors. */
+/** Paginated list of debtors, with the aging of all open debt. */
 export interface ListDebtorsResult {
   items: DebtorEntry[];
   total: number;
+  aging: DebtAging;
+}
+
+/** Entry is blocked for plates above these limits; null = no limit. */
+export interface DebtEntryLimits {
+  maxAmount: number | null;
+  maxAgeDays: number | null;
 }
 
 /** Session with debt for debt-detail-by-plate drill-down. */
@@ -256,6 +433,36 @@ export interface DebtDetailByPlateResult {
   transactions: DebtTransactionEntry[];
 }
 
+export type DebtStatus = "open" | "settled" | "written_off";
+
+/** Debt ledger entry: unpaid balance left by one session, with payments applied. */
+export interface Debt {
+  id: string;
+  plate: string;
+  vehicleId: string;
+  ticketCode: string | null;
+  amount: number;
+  paidAmount: number;
+  balance: number;
+  status: DebtStatus;
+  createdAt: string;
+  closedAt: string | null;
+  writeOffReason: string | null;
+  operatorUserId: string | null;
+}
+
+export interface ListDebtsResult {
+  items: Debt[];
+  total: number;
+}
+
+export interface DebtPaymentResult {
+  plate: string;
+  amount: number;
+  settledDebts: number;
+  remainingDebt: number;
+}
+
 /** Datos del registro que falló por conflicto de placa; para que el cliente elija eliminar el erróneo y reintentar. */
 export interface PendingRegisterConflict {
   plate: string;
@@ -270,7 +477,12 @@ export type ReportTypeKey =
   | 'shift_closures'
   | 'transactions_with_vehicle'
   | 'vehicle_exits'
-  | 'debtors';
+  | 'debtors'
+  | 'debt_aging'
+  | 'voucher_redemptions'
+  | 'merchant_billing'
+  | 'overrides'
+  | 'refunds';
 
 export interface ReportColumnDef {
   key: string;
@@ -282,9 +494,37 @@ export interface ReportFilters {
   dateTo: string;
   paymentMethod?: string | null;
   vehicleType?: string | null;
+  merchant?: string | null;
 }
 
 export interface ReportData {
   columns: ReportColumnDef[];
   rows: Record<string, string | number | null>[];
 }
+
+export type VoucherKind = "percentage" | "fixed" | "free_minutes";
+
+export interface Voucher {
+  id: string;
+  code: string;
+  merchant: string;
+  kind: VoucherKind;
+  value: number;
+  expiresOn: string | null;
+  maxUses: number | null;
+  usesCount: number;
+  active: boolean;
+  createdAt: string;
+}
+
+export interface VoucherRedemption {
+  id: string;
+  voucherId: string;
+  voucherCode: string;
+  vehicleId: string;
+  ticketCode: string;
+  merchant: string;
+  discountAmount: number;
+  redeemedAt: string;
+  operatorUserId: string | null;
+}